@@ -0,0 +1,158 @@
+use std::sync::Arc;
+use napi::{Error, Result};
+use napi_derive::napi;
+use web3_resolver::models::{AddressTag, ResolvedDomainData};
+use web3_resolver::{DomainResolverBuilder, Web3DomainResolver};
+
+fn resolution_error(error: anyhow::Error) -> Error {
+    Error::from_reason(format!("{:#}", error))
+}
+
+/// Options of `createResolver`, unset options keep the defaults of the preset.
+#[napi(object)]
+pub struct ResolverOptions {
+    /// `gateway` or `wallet`, see `DomainResolverBuilder::gateway_defaults` and `wallet_defaults`
+    pub preset: Option<String>,
+    /// Time-to-live of cached results, `0` disables the cache
+    pub cache_ttl_seconds: Option<u32>,
+    pub everscale_endpoint: Option<String>,
+    pub ud_base_url: Option<String>,
+    pub ethereum_endpoint: Option<String>,
+}
+
+/// Resolution target of a domain.
+#[napi(object)]
+pub struct Target {
+    /// `domain-string`, `onchain-data`, `onchain-contract-data` or `dns-records`
+    pub kind: String,
+    /// Address, onchain content, or comma separated IP addresses of `dns-records`
+    pub value: String,
+    /// Content type of onchain contract content
+    pub content_type: Option<String>,
+    /// Address tag, e.g. `ipfs` or `web2-url`
+    pub tag: String,
+    /// Resolver the target came from
+    pub source: String,
+}
+
+impl Target {
+    fn new(data: &ResolvedDomainData, tag: &AddressTag, source: &str) -> Self {
+        Self {
+            kind: data.kind().to_string(),
+            value: data.value_string(),
+            content_type: data.content_type().map(|content_type| content_type.to_string()),
+            tag: tag.as_str().to_string(),
+            source: source.to_string(),
+        }
+    }
+}
+
+/// Raw record of a domain.
+#[napi(object)]
+pub struct Record {
+    /// Record key, e.g. `1003`, `crypto.ETH.address` or `contenthash`
+    pub key: String,
+    /// Raw value: hex encoded BOC of Evername cells, JSON of Unstoppable Domains records, hex or text of ENS records
+    pub raw: String,
+    /// Decoded value, unset if the record could not be decoded
+    pub value: Option<String>,
+}
+
+/// `Web3DomainResolver` with its caches, shared by all calls.
+#[napi]
+pub struct Resolver {
+    inner: Arc<Web3DomainResolver>,
+}
+
+/// Builds the resolver.
+///
+/// ```ts
+/// const resolver = await createResolver({ preset: 'gateway' });
+/// const target = await resolver.resolve('maksimzubov.ever');
+/// ```
+#[napi]
+pub async fn create_resolver(options: Option<ResolverOptions>) -> Result<Resolver> {
+    let options = options.unwrap_or(ResolverOptions {
+        preset: None,
+        cache_ttl_seconds: None,
+        everscale_endpoint: None,
+        ud_base_url: None,
+        ethereum_endpoint: None,
+    });
+    let builder = match options.preset.as_deref() {
+        None => DomainResolverBuilder::default(),
+        Some("gateway") => DomainResolverBuilder::gateway_defaults(),
+        Some("wallet") => DomainResolverBuilder::wallet_defaults(),
+        Some(preset) => return Err(Error::from_reason(format!("Unknown preset {}", preset))),
+    };
+    let builder = match options.cache_ttl_seconds {
+        Some(0) => builder.no_cache(),
+        Some(ttl) => builder.cache_ttl_seconds(ttl as u64),
+        None => builder,
+    };
+    let builder = with_endpoints(builder, &options)?;
+    let resolver = builder.build().await.map_err(resolution_error)?;
+    Ok(Resolver { inner: Arc::new(resolver) })
+}
+
+#[napi]
+impl Resolver {
+    /// Resolves the domain into the best target.
+    #[napi]
+    pub async fn resolve(&self, domain: String) -> Result<Target> {
+        let report = self.inner.resolve_report(&domain).await.map_err(resolution_error)?;
+        Ok(Target::new(&report.data, &report.tag, &report.source))
+    }
+
+    /// Resolves all matching targets of the domain, in resolver priority order.
+    #[napi]
+    pub async fn resolve_all(&self, domain: String) -> Result<Vec<Target>> {
+        let candidates = self.inner.resolve_all(&domain).await.map_err(resolution_error)?;
+        Ok(candidates.iter()
+            .map(|candidate| Target::new(&candidate.data, &candidate.tag, &candidate.source))
+            .collect())
+    }
+
+    /// Raw and decoded records of the domain.
+    #[napi]
+    pub async fn records(&self, domain: String) -> Result<Vec<Record>> {
+        let records = self.inner.records(&domain).await.map_err(resolution_error)?;
+        Ok(records.records.into_iter()
+            .map(|record| Record { key: record.key, raw: record.raw, value: record.value })
+            .collect())
+    }
+
+    /// Removes the domain from the resolution cache.
+    #[napi]
+    pub fn invalidate(&self, domain: String) {
+        self.inner.invalidate(&domain);
+    }
+}
+
+/// Applies endpoints of the enabled resolvers, fails on endpoints of disabled ones.
+#[allow(unused_mut)]
+fn with_endpoints(mut builder: DomainResolverBuilder, options: &ResolverOptions) -> Result<DomainResolverBuilder> {
+    #[cfg(feature = "evername")]
+    if let Some(endpoint) = &options.everscale_endpoint {
+        let endpoint = url::Url::parse(endpoint).map_err(|e| Error::from_reason(e.to_string()))?;
+        builder = builder.with_everscale_endpoints(vec![endpoint]);
+    }
+    #[cfg(feature = "ud")]
+    if let Some(base_url) = &options.ud_base_url {
+        builder = builder.with_unstoppable_domain_base_url(base_url);
+    }
+    #[cfg(feature = "ens")]
+    if let Some(endpoint) = &options.ethereum_endpoint {
+        let endpoint = url::Url::parse(endpoint).map_err(|e| Error::from_reason(e.to_string()))?;
+        builder = builder.with_ethereum_endpoint(endpoint);
+    }
+    let disabled = [
+        (cfg!(feature = "evername"), options.everscale_endpoint.is_some(), "evername"),
+        (cfg!(feature = "ud"), options.ud_base_url.is_some(), "ud"),
+        (cfg!(feature = "ens"), options.ethereum_endpoint.is_some(), "ens"),
+    ];
+    if let Some((_, _, feature)) = disabled.iter().find(|(enabled, set, _)| !enabled && *set) {
+        return Err(Error::from_reason(format!("Bindings are built without the {} feature", feature)));
+    }
+    Ok(builder)
+}
@@ -0,0 +1,189 @@
+use std::sync::Arc;
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyValueError};
+use pyo3::prelude::*;
+use web3_resolver::models::{AddressTag, ResolvedDomainData};
+use web3_resolver::{DomainResolverBuilder, Web3DomainResolver};
+
+create_exception!(pyweb3resolver, ResolutionError, PyException, "Domain resolution failed.");
+
+fn resolution_error(error: anyhow::Error) -> PyErr {
+    ResolutionError::new_err(format!("{:#}", error))
+}
+
+/// Resolution target of a domain.
+#[pyclass(frozen, get_all, module = "pyweb3resolver")]
+#[derive(Clone)]
+struct Target {
+    /// `domain-string`, `onchain-data`, `onchain-contract-data` or `dns-records`
+    kind: String,
+    /// Address, onchain content, or comma separated IP addresses of `dns-records`
+    value: String,
+    /// Content type of onchain contract content
+    content_type: Option<String>,
+    /// Address tag, e.g. `ipfs` or `web2-url`
+    tag: String,
+    /// Resolver the target came from
+    source: String,
+}
+
+impl Target {
+    fn new(data: &ResolvedDomainData, tag: &AddressTag, source: &str) -> Self {
+        Self {
+            kind: data.kind().to_string(),
+            value: data.value_string(),
+            content_type: data.content_type().map(|content_type| content_type.to_string()),
+            tag: tag.as_str().to_string(),
+            source: source.to_string(),
+        }
+    }
+}
+
+#[pymethods]
+impl Target {
+    fn __repr__(&self) -> String {
+        format!("Target(kind={:?}, tag={:?}, source={:?}, value={:?})", self.kind, self.tag, self.source, self.value)
+    }
+}
+
+/// Raw record of a domain.
+#[pyclass(frozen, get_all, module = "pyweb3resolver")]
+#[derive(Clone)]
+struct Record {
+    /// Record key, e.g. `1003`, `crypto.ETH.address` or `contenthash`
+    key: String,
+    /// Raw value: hex encoded BOC of Evername cells, JSON of Unstoppable Domains records, hex or text of ENS records
+    raw: String,
+    /// Decoded value, `None` if the record could not be decoded
+    value: Option<String>,
+}
+
+#[pymethods]
+impl Record {
+    fn __repr__(&self) -> String {
+        format!("Record(key={:?}, value={:?})", self.key, self.value)
+    }
+}
+
+/// `Web3DomainResolver` with its caches, shared by all calls. Methods return awaitables.
+///
+/// ```python
+/// resolver = await Resolver.create(cache_ttl_seconds=600)
+/// target = await resolver.resolve("maksimzubov.ever")
+/// ```
+#[pyclass(frozen, module = "pyweb3resolver")]
+struct Resolver {
+    inner: Arc<Web3DomainResolver>,
+}
+
+#[pymethods]
+impl Resolver {
+    /// Builds the resolver. `preset` is `gateway` or `wallet` (see `DomainResolverBuilder::gateway_defaults`
+    /// and `wallet_defaults`), other options override it. `cache_ttl_seconds=0` disables the results cache.
+    #[staticmethod]
+    #[pyo3(signature = (preset=None, cache_ttl_seconds=None, everscale_endpoint=None, ud_base_url=None, ethereum_endpoint=None))]
+    fn create<'py>(
+        py: Python<'py>,
+        preset: Option<&str>,
+        cache_ttl_seconds: Option<u64>,
+        everscale_endpoint: Option<String>,
+        ud_base_url: Option<String>,
+        ethereum_endpoint: Option<String>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let builder = match preset {
+            None => DomainResolverBuilder::default(),
+            Some("gateway") => DomainResolverBuilder::gateway_defaults(),
+            Some("wallet") => DomainResolverBuilder::wallet_defaults(),
+            Some(preset) => return Err(PyValueError::new_err(format!("Unknown preset {}", preset))),
+        };
+        let builder = match cache_ttl_seconds {
+            Some(0) => builder.no_cache(),
+            Some(ttl) => builder.cache_ttl_seconds(ttl),
+            None => builder,
+        };
+        let builder = with_endpoints(builder, everscale_endpoint, ud_base_url, ethereum_endpoint)?;
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            let resolver = builder.build().await.map_err(resolution_error)?;
+            Ok(Resolver { inner: Arc::new(resolver) })
+        })
+    }
+
+    /// Resolves the domain into the best `Target`.
+    fn resolve<'py>(&self, py: Python<'py>, domain: String) -> PyResult<Bound<'py, PyAny>> {
+        let resolver = self.inner.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            let report = resolver.resolve_report(&domain).await.map_err(resolution_error)?;
+            Ok(Target::new(&report.data, &report.tag, &report.source))
+        })
+    }
+
+    /// Resolves all matching targets of the domain, in resolver priority order.
+    fn resolve_all<'py>(&self, py: Python<'py>, domain: String) -> PyResult<Bound<'py, PyAny>> {
+        let resolver = self.inner.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            let candidates = resolver.resolve_all(&domain).await.map_err(resolution_error)?;
+            Ok(candidates.iter()
+                .map(|candidate| Target::new(&candidate.data, &candidate.tag, &candidate.source))
+                .collect::<Vec<_>>())
+        })
+    }
+
+    /// Raw and decoded records of the domain.
+    fn records<'py>(&self, py: Python<'py>, domain: String) -> PyResult<Bound<'py, PyAny>> {
+        let resolver = self.inner.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            let records = resolver.records(&domain).await.map_err(resolution_error)?;
+            Ok(records.records.into_iter()
+                .map(|record| Record { key: record.key, raw: record.raw, value: record.value })
+                .collect::<Vec<_>>())
+        })
+    }
+
+    /// Removes the domain from the resolution cache.
+    fn invalidate(&self, domain: &str) {
+        self.inner.invalidate(domain);
+    }
+}
+
+/// Applies endpoints of the enabled resolvers, fails on endpoints of disabled ones.
+#[allow(unused_variables, unused_mut)]
+fn with_endpoints(
+    mut builder: DomainResolverBuilder,
+    everscale_endpoint: Option<String>,
+    ud_base_url: Option<String>,
+    ethereum_endpoint: Option<String>,
+) -> PyResult<DomainResolverBuilder> {
+    #[cfg(feature = "evername")]
+    if let Some(endpoint) = &everscale_endpoint {
+        let endpoint = url::Url::parse(endpoint).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        builder = builder.with_everscale_endpoints(vec![endpoint]);
+    }
+    #[cfg(feature = "ud")]
+    if let Some(base_url) = &ud_base_url {
+        builder = builder.with_unstoppable_domain_base_url(base_url);
+    }
+    #[cfg(feature = "ens")]
+    if let Some(endpoint) = &ethereum_endpoint {
+        let endpoint = url::Url::parse(endpoint).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        builder = builder.with_ethereum_endpoint(endpoint);
+    }
+    let disabled = [
+        (cfg!(feature = "evername"), everscale_endpoint.is_some(), "evername"),
+        (cfg!(feature = "ud"), ud_base_url.is_some(), "ud"),
+        (cfg!(feature = "ens"), ethereum_endpoint.is_some(), "ens"),
+    ];
+    if let Some((_, _, feature)) = disabled.iter().find(|(enabled, set, _)| !enabled && *set) {
+        return Err(PyValueError::new_err(format!("Bindings are built without the {} feature", feature)));
+    }
+    Ok(builder)
+}
+
+/// Python bindings of web3-resolver.
+#[pymodule]
+fn pyweb3resolver(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Resolver>()?;
+    m.add_class::<Target>()?;
+    m.add_class::<Record>()?;
+    m.add("ResolutionError", m.py().get_type::<ResolutionError>())?;
+    Ok(())
+}
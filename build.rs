@@ -0,0 +1,81 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    abi::validate_bundled();
+    #[cfg(feature = "grpc-server")]
+    grpc::generate_service();
+}
+
+/// Validates the ABI JSON bundled by `src/resolver/abi.rs`, so a broken or drifted ABI fails the build
+/// instead of the first getter call. Versions are passed to the crate as `WEB3_RESOLVER_<CONTRACT>_ABI_VERSION`.
+mod abi {
+    // contract, ABI file and functions the resolver calls
+    const BUNDLED: &[(&str, &str, &[&str])] = &[
+        ("DOMAIN", "src/resolver/abi/domain.abi.json", &["resolve", "getRecords", "getDetails", "getJson", "setRecord", "deleteRecord"]),
+        ("ROOT", "src/resolver/abi/root.abi.json", &["resolve", "getDetails", "getJson", "expectedPrice", "expectedRegisterAmount", "buildRegisterPayload"]),
+        ("ONCHAIN_SITE", "src/resolver/abi/onchain_site.abi.json", &["getDetails"]),
+        ("WALLET", "src/resolver/abi/wallet.abi.json", &["sendTransaction"]),
+    ];
+
+    pub fn validate_bundled() {
+        for (contract, path, functions) in BUNDLED {
+            println!("cargo:rerun-if-changed={}", path);
+            let json = std::fs::read_to_string(path).unwrap_or_else(|e| panic!("Failed to read {}: {}", path, e));
+            let abi: serde_json::Value = serde_json::from_str(&json).unwrap_or_else(|e| panic!("{} is not a valid JSON: {}", path, e));
+            if abi.get("ABI version").and_then(|v| v.as_u64()) != Some(2) {
+                panic!("{} is not an ABI version 2 JSON", path);
+            }
+            let version = abi.get("version")
+                .and_then(|v| v.as_str())
+                .unwrap_or_else(|| panic!("{} declares no version", path));
+            let declared = abi.get("functions")
+                .and_then(|f| f.as_array())
+                .unwrap_or_else(|| panic!("{} has no functions", path));
+            for function in declared {
+                let name = function.get("name").and_then(|n| n.as_str()).unwrap_or_else(|| panic!("{} has a function without name", path));
+                for params in ["inputs", "outputs"] {
+                    let valid = function.get(params)
+                        .and_then(|p| p.as_array())
+                        .is_some_and(|p| p.iter().all(|param| param.get("name").is_some_and(|n| n.is_string()) && param.get("type").is_some_and(|t| t.is_string())));
+                    if !valid {
+                        panic!("{} has malformed {} of {}", path, params, name);
+                    }
+                }
+            }
+            for function in functions.iter() {
+                if !declared.iter().any(|f| f.get("name").and_then(|n| n.as_str()) == Some(function)) {
+                    panic!("{} has no {} function called by the resolver", path, function);
+                }
+            }
+            println!("cargo:rustc-env=WEB3_RESOLVER_{}_ABI_VERSION={}", contract, version);
+        }
+    }
+}
+
+/// Generates the `Web3Resolver` service of `proto/web3_resolver.proto` without `protoc`,
+/// messages are defined in `src/resolver/grpc.rs`.
+#[cfg(feature = "grpc-server")]
+mod grpc {
+    use tonic_build::manual::{Builder, Method, Service};
+
+    fn method(name: &str, route_name: &str, input: &str, output: &str) -> Method {
+        Method::builder()
+            .name(name)
+            .route_name(route_name)
+            .input_type(format!("crate::resolver::grpc::proto::{}", input))
+            .output_type(format!("crate::resolver::grpc::proto::{}", output))
+            .codec_path("tonic::codec::ProstCodec")
+            .build()
+    }
+
+    pub fn generate_service() {
+        let service = Service::builder()
+            .name("Web3Resolver")
+            .package("web3resolver.v1")
+            .method(method("resolve", "Resolve", "ResolveRequest", "ResolveResponse"))
+            .method(method("resolve_all", "ResolveAll", "ResolveRequest", "ResolveAllResponse"))
+            .method(method("records", "Records", "RecordsRequest", "RecordsResponse"))
+            .method(method("health", "Health", "HealthRequest", "HealthResponse"))
+            .build();
+        Builder::new().build_client(false).compile(&[service]);
+    }
+}
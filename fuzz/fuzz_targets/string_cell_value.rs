@@ -0,0 +1,7 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|boc: &[u8]| {
+    let _ = web3_resolver::fuzzing::string_cell_value(boc);
+});
@@ -0,0 +1,160 @@
+use std::any::Any;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Typed errors of the resolvers.
+/// Resolvers return `anyhow::Error`, so these can be obtained with `downcast_ref::<ResolverError>()`.
+#[derive(Debug, Error)]
+pub enum ResolverError {
+    /// On-chain contract does not match any of the known ABIs
+    #[error("Contract {contract} does not match known ABIs for '{function}': {reason}")]
+    AbiMismatch {
+        contract: String,
+        function: String,
+        reason: String,
+    },
+    /// Account has changed after the requested point in time and its historical state is unavailable
+    #[error("State of {address} at the requested point is unavailable, last transaction lt is {last_trans_lt}")]
+    HistoricalStateUnavailable {
+        address: String,
+        last_trans_lt: u64,
+    },
+    /// Domain resolution is refused by an override or policy
+    #[error("Domain {domain} is blocked: {reason}")]
+    Blocked {
+        domain: String,
+        reason: String,
+    },
+    /// Quorum endpoint returned result different from the main endpoint one
+    #[error("Quorum mismatch for {domain}: endpoint {endpoint} returned {actual}, expected {expected}")]
+    QuorumMismatch {
+        domain: String,
+        endpoint: String,
+        expected: String,
+        actual: String,
+    },
+    /// Requested byte range is outside of the content, maps to HTTP 416
+    #[error("Range not satisfiable, content length is {total_length}")]
+    RangeNotSatisfiable {
+        total_length: u64,
+    },
+    /// Domain TLD is claimed by several resolvers and the ambiguity policy rejects it
+    #[error("Domain {domain} is ambiguous, it can be resolved by {}", sources.join(", "))]
+    AmbiguousDomain {
+        domain: String,
+        sources: Vec<String>,
+    },
+    /// Domain name breaks the label rules of the naming service, it is rejected without querying the chain
+    #[error("Invalid domain name {domain}: {reason}")]
+    InvalidName {
+        domain: String,
+        reason: String,
+    },
+    /// On-chain data of the domain could not be decoded, e.g. a malformed record cell
+    #[error("Malformed {what}: {reason}")]
+    MalformedData {
+        what: String,
+        reason: String,
+    },
+    /// Redirects of the domain lead back to an already visited domain
+    #[error("Redirect loop of {domain}: {}", chain.join(" -> "))]
+    RedirectLoop {
+        domain: String,
+        chain: Vec<String>,
+    },
+    /// Redirects of the domain are longer than `DomainResolverBuilder::follow_redirects` allows
+    #[error("Redirects of {domain} exceed the max depth of {max_depth}")]
+    TooManyRedirects {
+        domain: String,
+        max_depth: usize,
+    },
+    /// Aliases of the domain lead back to an already visited domain
+    #[error("Alias loop of {domain}: {}", chain.join(" -> "))]
+    AliasLoop {
+        domain: String,
+        chain: Vec<String>,
+    },
+    /// Aliases of the domain are longer than `DomainResolverBuilder::follow_aliases` allows
+    #[error("Aliases of {domain} exceed the max depth of {max_depth}")]
+    TooManyAliases {
+        domain: String,
+        max_depth: usize,
+    },
+    /// Get-method ran out of gas or overflowed a cell, e.g. returning a huge records or content map
+    #[error("{contract}.{function} exceeded the TVM limits, exit code {exit_code}")]
+    GetMethodLimitExceeded {
+        contract: String,
+        function: String,
+        exit_code: i32,
+    },
+    /// Get-method execution exceeded its wall-time budget, e.g. on a huge or malicious contract state
+    #[error("{contract}.{function} exceeded the get-method budget of {budget:?}")]
+    GetMethodTimeout {
+        contract: String,
+        function: String,
+        budget: Duration,
+    },
+    /// Domain TLD is not handled by any resolver and strict mode rejects non-web3 passthrough
+    #[error("Domain {domain} has no TLD handled by the resolvers")]
+    UnsupportedTld {
+        domain: String,
+    },
+    /// Domain is not registered in the naming service
+    #[error("Domain {domain} is not found")]
+    NotFound {
+        domain: String,
+    },
+    /// Domain is registered, but has no content records to resolve into, e.g. a fresh registration
+    /// not configured yet. `owner` is the owner address if the registry reports it
+    #[error("Domain {domain} is registered but has no content records")]
+    NoContent {
+        domain: String,
+        owner: Option<String>,
+    },
+    /// Account does not exist on chain, e.g. a certificate of an unregistered domain
+    #[error("Account {address} does not exist")]
+    AccountNotFound {
+        address: String,
+    },
+    /// Upstream API of the resolver rejects requests over its rate limit, e.g. HTTP 429,
+    /// `retry_after` is the wait it asks for if any
+    #[error("Resolver {resolver} is rate limited{}", retry_after.map(|after| format!(", retry after {:?}", after)).unwrap_or_default())]
+    RateLimited {
+        resolver: String,
+        retry_after: Option<Duration>,
+    },
+    /// Upstream API of the resolver failed with a server error, e.g. HTTP 5xx
+    #[error("Resolver {resolver} upstream is unavailable, status {status}")]
+    UpstreamUnavailable {
+        resolver: String,
+        status: u16,
+    },
+    /// Resolver failed to initialize, e.g. on first use in lazy mode, the cause is kept as the error source
+    #[error("Failed to initialize {resolver} resolver")]
+    InitializationFailed {
+        resolver: String,
+        #[source]
+        source: anyhow::Error,
+    },
+    /// Resolver panicked while resolving the domain, the panic is caught at the resolver boundary
+    #[error("Resolver {resolver} panicked: {message}")]
+    ResolverPanicked {
+        resolver: String,
+        message: String,
+    },
+}
+
+impl ResolverError {
+    /// Error is transient and the resolution may succeed if retried: rate limiting or upstream server errors.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, ResolverError::RateLimited { .. } | ResolverError::UpstreamUnavailable { .. })
+    }
+}
+
+/// Message of a caught panic payload.
+pub(crate) fn panic_message(panic: &(dyn Any + Send)) -> String {
+    panic.downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
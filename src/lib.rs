@@ -1,8 +1,43 @@
 mod resolver;
 
 pub mod models;
-pub use resolver::Web3DomainResolver;
+pub mod error;
+pub use resolver::{Web3DomainResolver, MAX_SEARCH_RESULTS};
 pub use resolver::Resolver;
-pub use resolver::evername::EvernameResolver;
-pub use resolver::ud::UnstoppableDomainsResolver;
+pub use error::ResolverError;
+#[cfg(feature = "evername")]
+pub use resolver::evername::{AbiSource, EvernameResolver, EvernameResolverBuilder, DEFAULT_GET_METHOD_BUDGET, DEFAULT_MAX_CONCURRENT_GET_METHODS, DOMAIN_ABI_VERSION, EVERNAME_TLD, MAX_EVERNAME_LABEL_LENGTH, MAX_EVERNAME_LENGTH, ONCHAIN_SITE_ABI_VERSION, ROOT_ABI_VERSION, SITE_MANIFEST_RECORD_KEY, TXT_RECORD_KEYS, YEAR_SECONDS};
+#[cfg(feature = "signer")]
+pub use resolver::evername::signer::{OwnerWallet, RecordValue, WALLET_ABI_VERSION};
+#[cfg(feature = "ud")]
+pub use resolver::ud::{TldMetadata, UnstoppableDomainsResolver, UnstoppableDomainsResolverBuilder, UD_SANDBOX_BASE_URL};
+#[cfg(feature = "ens")]
+pub use resolver::ens::{EnsResolver, EnsResolverBuilder};
 pub use resolver::builder::DomainResolverBuilder;
+pub use resolver::overrides::{DomainOverrides, OverrideEntry};
+#[cfg(any(feature = "evername", feature = "ud", feature = "ens"))]
+pub use resolver::limit::InFlightLimit;
+pub use resolver::dns::{DnsBackend, DohDns, SystemDns, CLOUDFLARE_DOH_ENDPOINT, GOOGLE_DOH_ENDPOINT};
+pub use resolver::synthetic::{SyntheticDns, SyntheticRecord, SyntheticRecordData, SYNTHETIC_RECORD_TTL};
+pub use resolver::policy::{ListPolicy, LiteralPrivateNetworkPolicy, PolicyVerdict, ResolutionPolicy};
+pub use resolver::rewrite::{ResultRewriter, Web2UrlNormalizer};
+pub use resolver::pipeline::{PipelineStage, PipelineState, Stage};
+pub use resolver::targets::{TargetSelector, WeightedSelector};
+pub use resolver::context::ResolverContext;
+pub use resolver::expiry::CacheExpiry;
+pub use resolver::layer::{CacheLayer, CachedResolver, MetricsLayer, MetricsResolver, PolicyLayer, PolicyResolver, ResolverExt, ResolverLayer, RetryLayer, RetryResolver};
+pub use ed25519_dalek::{SigningKey, VerifyingKey};
+pub use url::Url;
+#[cfg(feature = "fuzzing")]
+#[doc(hidden)]
+pub use resolver::evername::fuzzing;
+#[cfg(feature = "evername")]
+pub use nekoton_utils::{Clock, ConstClock, SimpleClock};
+#[cfg(feature = "plugins")]
+pub use resolver::plugin::PluginResolver;
+#[cfg(feature = "grpc-server")]
+pub use resolver::grpc;
+#[cfg(feature = "jsonrpc-server")]
+pub use resolver::jsonrpc::JsonRpcServer;
+#[cfg(feature = "otel")]
+pub use resolver::otel;
@@ -1,24 +1,57 @@
 use std::fmt::{Display, Formatter};
-use anyhow::{anyhow, Result};
+use std::str::FromStr;
+use anyhow::{anyhow, Context, Result};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use crate::error::ResolverError;
+use crate::resolver::context::ResolverContext;
+use crate::resolver::ipfs::split_gateway_link;
+use crate::resolver::normalize;
 
-#[derive(Clone, PartialEq, Hash, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Hash, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum AddressTag {
+    /// Record `1001`: onion address of a Tor hidden service
     Tor,
+    /// Record `1002`: IPFS CID of the site
     Ipfs,
+    /// Record `1003`: web2 URL or IP address the domain points to
     Web2,
+    /// Record `1004`: site content stored in the record itself
     Onchain,
+    /// Record `1005`: address of an Eversite contract storing the site content
     OnchainContract,
+    /// Record `1006`: TLS certificate or public key of the site, see `TlsRecord`
+    TlsCertificate,
+    /// Record `1007`: redirect to another web3 or web2 domain with HTTP status, see `Redirect`
+    Redirect,
+    /// Domain is not a web3 one and is passed through as is
     NonWeb3,
+    /// Domain is resolved by Unstoppable Domains profile
     UnstoppableDomain,
 }
 
 impl AddressTag {
-    const TOR_ADDRESS_TAG: u128 = 1001;
-    const IPFS_ADDRESS_TAG: u128 = 1002;
-    const WEB2_ADDRESS_TAG: u128 = 1003;
-    const ONCHAIN_ADDRESS_TAG: u128 = 1004;
-    const ONCHAIN_CONTRACT_ADDRESS_TAG: u128 = 1005;
+    /// Evername record key of Tor onion address.
+    pub const TOR_ADDRESS_TAG: u128 = 1001;
+    /// Evername record key of IPFS CID.
+    pub const IPFS_ADDRESS_TAG: u128 = 1002;
+    /// Evername record key of web2 URL or IP address.
+    pub const WEB2_ADDRESS_TAG: u128 = 1003;
+    /// Evername record key of onchain site content.
+    pub const ONCHAIN_ADDRESS_TAG: u128 = 1004;
+    /// Evername record key of Eversite contract address.
+    pub const ONCHAIN_CONTRACT_ADDRESS_TAG: u128 = 1005;
+    /// Evername record key of TLS certificate or public key.
+    pub const TLS_CERTIFICATE_ADDRESS_TAG: u128 = 1006;
+    /// Evername record key of redirect to another domain.
+    pub const REDIRECT_ADDRESS_TAG: u128 = 1007;
+    /// Stable number of the non-web3 tag, not a record key.
+    pub const NON_WEB3_ADDRESS_TAG: u128 = 1;
+    /// Stable number of the Unstoppable Domains tag, not a record key.
+    pub const UNSTOPPABLE_DOMAIN_ADDRESS_TAG: u128 = 2;
 
+    /// Stable number of the tag, distinct for every tag, so it can be used on the wire and as a map key.
+    /// Equals the record key for tags stored in domain records.
     pub fn tag(&self) -> u128 {
         match self {
             AddressTag::Tor => Self::TOR_ADDRESS_TAG,
@@ -26,11 +59,76 @@ impl AddressTag {
             AddressTag::Web2 => Self::WEB2_ADDRESS_TAG,
             AddressTag::Onchain => Self::ONCHAIN_ADDRESS_TAG,
             AddressTag::OnchainContract => Self::ONCHAIN_CONTRACT_ADDRESS_TAG,
-            AddressTag::NonWeb3 => 0,
-            AddressTag::UnstoppableDomain => 0,
+            AddressTag::TlsCertificate => Self::TLS_CERTIFICATE_ADDRESS_TAG,
+            AddressTag::Redirect => Self::REDIRECT_ADDRESS_TAG,
+            AddressTag::NonWeb3 => Self::NON_WEB3_ADDRESS_TAG,
+            AddressTag::UnstoppableDomain => Self::UNSTOPPABLE_DOMAIN_ADDRESS_TAG,
         }
     }
 
+    /// All known tags, including ones not stored in domain records.
+    pub fn all() -> impl Iterator<Item = AddressTag> {
+        [
+            AddressTag::Tor,
+            AddressTag::Ipfs,
+            AddressTag::Web2,
+            AddressTag::Onchain,
+            AddressTag::OnchainContract,
+            AddressTag::TlsCertificate,
+            AddressTag::Redirect,
+            AddressTag::NonWeb3,
+            AddressTag::UnstoppableDomain,
+        ].into_iter()
+    }
+
+    /// Key of the domain record holding this tag, `None` for tags not stored in records.
+    pub fn record_key(&self) -> Option<u128> {
+        match self {
+            AddressTag::NonWeb3 | AddressTag::UnstoppableDomain => None,
+            _ => Some(self.tag()),
+        }
+    }
+
+    /// Tag stored in the domain record with the key, `None` for unknown keys and numbers of tags not stored in records.
+    pub fn from_record_key(key: u128) -> Option<AddressTag> {
+        AddressTag::try_from(key).ok().filter(|tag| tag.record_key() == Some(key))
+    }
+
+    /// Human readable description of the tag, e.g. for registrar UIs.
+    pub fn description(&self) -> &'static str {
+        match self {
+            AddressTag::Tor => "Onion address of a Tor hidden service",
+            AddressTag::Ipfs => "IPFS CID of the site",
+            AddressTag::Web2 => "Web2 URL or IP address",
+            AddressTag::Onchain => "Site content stored in the record",
+            AddressTag::OnchainContract => "Address of an Eversite contract storing the site content",
+            AddressTag::TlsCertificate => "TLS certificate or public key of the site",
+            AddressTag::Redirect => "Redirect to another domain",
+            AddressTag::NonWeb3 => "Non-web3 domain",
+            AddressTag::UnstoppableDomain => "Unstoppable Domains profile",
+        }
+    }
+
+    /// Name of the tag as accepted by `FromStr`, e.g. `tor` or `onchain-contract`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AddressTag::Tor => "tor",
+            AddressTag::Ipfs => "ipfs",
+            AddressTag::Web2 => "web2",
+            AddressTag::Onchain => "onchain",
+            AddressTag::OnchainContract => "onchain-contract",
+            AddressTag::TlsCertificate => "tls-certificate",
+            AddressTag::Redirect => "redirect",
+            AddressTag::NonWeb3 => "non-web3",
+            AddressTag::UnstoppableDomain => "unstoppable-domain",
+        }
+    }
+
+    /// Tag of content stored on-chain, which is served as is instead of being redirected to.
+    pub fn is_onchain(&self) -> bool {
+        matches!(self, AddressTag::Onchain | AddressTag::OnchainContract)
+    }
+
     /// Available address tags for resolving
     /// Order is priority!
     pub fn resolvable() -> Vec<AddressTag> {
@@ -39,23 +137,29 @@ impl AddressTag {
             AddressTag::Ipfs,
             AddressTag::Web2,
             AddressTag::Onchain,
-            AddressTag::OnchainContract
+            AddressTag::OnchainContract,
+            AddressTag::Redirect,
         ]
     }
 }
 
+/// Parses tag by its number, the inverse of `AddressTag::tag` for every tag.
+/// Use `AddressTag::from_record_key` for domain record keys.
+impl TryFrom<u128> for AddressTag {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u128) -> Result<Self> {
+        AddressTag::all()
+            .find(|tag| tag.tag() == value)
+            .ok_or_else(|| anyhow!("Unknown address tag {}", value))
+    }
+}
+
 impl TryFrom<u32> for AddressTag {
     type Error = anyhow::Error;
 
     fn try_from(value: u32) -> Result<Self> {
-        match value as u128 {
-            AddressTag::TOR_ADDRESS_TAG => Ok(AddressTag::Tor),
-            AddressTag::IPFS_ADDRESS_TAG => Ok(AddressTag::Ipfs),
-            AddressTag::WEB2_ADDRESS_TAG => Ok(AddressTag::Web2),
-            AddressTag::ONCHAIN_ADDRESS_TAG => Ok(AddressTag::Onchain),
-            AddressTag::ONCHAIN_CONTRACT_ADDRESS_TAG => Ok(AddressTag::OnchainContract),
-            _ => Err(anyhow!("Unknown address tag"))
-        }
+        AddressTag::try_from(value as u128)
     }
 }
 
@@ -67,17 +171,425 @@ impl Display for AddressTag {
             AddressTag::Web2 => write!(f, "ip({})", Self::WEB2_ADDRESS_TAG),
             AddressTag::Onchain => write!(f, "onchain({})", Self::ONCHAIN_ADDRESS_TAG),
             AddressTag::OnchainContract => write!(f, "onchain-contract({})", Self::ONCHAIN_CONTRACT_ADDRESS_TAG),
+            AddressTag::TlsCertificate => write!(f, "tls-certificate({})", Self::TLS_CERTIFICATE_ADDRESS_TAG),
+            AddressTag::Redirect => write!(f, "redirect({})", Self::REDIRECT_ADDRESS_TAG),
             AddressTag::NonWeb3 => write!(f, "non-ever(plain)"),
             AddressTag::UnstoppableDomain => write!(f, "unstoppable-domain"),
         }
     }
 }
 
-#[derive(Debug, Clone)]
+/// Parses tag by its name, e.g. `tor`, `ipfs`, `web2`, `onchain`, `onchain-contract`, `tls-certificate`, `redirect` or `non-web3`.
+impl FromStr for AddressTag {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "tor" => Ok(AddressTag::Tor),
+            "ipfs" => Ok(AddressTag::Ipfs),
+            "web2" | "ip" => Ok(AddressTag::Web2),
+            "onchain" => Ok(AddressTag::Onchain),
+            "onchain-contract" => Ok(AddressTag::OnchainContract),
+            "tls-certificate" => Ok(AddressTag::TlsCertificate),
+            "redirect" => Ok(AddressTag::Redirect),
+            "non-web3" | "non-ever" => Ok(AddressTag::NonWeb3),
+            "unstoppable-domain" => Ok(AddressTag::UnstoppableDomain),
+            _ => Err(anyhow!("Unknown address tag name: {}", value))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum ResolvedDomainData {
     DomainString(String),
     OnchainData(String),
     OnchainContractData((String, String)),
+    /// A and AAAA records of a non-web3 domain resolved by the DNS fallback
+    DnsRecords(DnsRecords),
+    /// Redirect to another domain, see `DomainResolverBuilder::follow_redirects`
+    Redirect(Redirect),
+}
+
+/// Status of redirects without an explicit one, permanent redirect.
+pub const DEFAULT_REDIRECT_STATUS: u16 = 301;
+
+/// Redirect record value: target domain or URL, optionally prefixed with the HTTP status,
+/// e.g. `alice.crypto`, `302 alice.crypto` or `308 https://example.com/alice`.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Redirect {
+    /// Target web3 or web2 domain, or URL
+    pub target: String,
+    /// HTTP status of the redirect: 301, 302, 303, 307 or 308
+    pub status: u16,
+}
+
+impl Redirect {
+    pub fn parse(value: &str) -> Result<Self> {
+        let value = value.trim();
+        let (status, target) = match value.split_once(char::is_whitespace) {
+            Some((status, target)) if status.chars().all(|c| c.is_ascii_digit()) => (status.parse::<u16>()?, target.trim()),
+            _ => (DEFAULT_REDIRECT_STATUS, value),
+        };
+        if !matches!(status, 301 | 302 | 303 | 307 | 308) {
+            return Err(anyhow!("Redirect status {} is not a redirect one", status));
+        }
+        if target.is_empty() || target.contains(char::is_whitespace) {
+            return Err(anyhow!("Invalid redirect target {:?}", target));
+        }
+        Ok(Self { target: target.to_string(), status })
+    }
+
+    /// Domain of the target, the host of URL targets.
+    pub fn target_domain(&self) -> Option<String> {
+        match url::Url::parse(&self.target) {
+            Ok(url) if url.has_host() => url.host_str().map(|host| host.to_lowercase()),
+            _ => Some(self.target.trim_end_matches('/').to_lowercase()).filter(|domain| !domain.contains('/')),
+        }
+    }
+}
+
+/// Target of a record publishing several Web2 or IPFS targets separated by whitespace, each optionally
+/// followed by `;priority=N` and `;weight=N`, e.g. `https://a.example;weight=3 https://b.example https://backup.example;priority=1`.
+/// Targets with the lowest priority are chosen first, targets of the same priority share requests by weight.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WeightedTarget {
+    pub url: String,
+    /// Lower priorities are chosen first, 0 by default
+    pub priority: u32,
+    /// Relative share of requests among targets of the same priority, 1 by default
+    pub weight: u32,
+}
+
+impl WeightedTarget {
+    pub fn parse(value: &str) -> Result<Self> {
+        let mut url = value.trim();
+        let (mut priority, mut weight) = (0, 1);
+        // parameters are taken from the end, so URLs may contain `;` themselves
+        while let Some((rest, param)) = url.rsplit_once(';') {
+            match param.split_once('=') {
+                Some(("priority", value)) => priority = value.parse().with_context(|| format!("Invalid priority of target {}", rest))?,
+                Some(("weight", value)) => weight = value.parse().with_context(|| format!("Invalid weight of target {}", rest))?,
+                _ => break,
+            }
+            url = rest;
+        }
+        if url.is_empty() {
+            return Err(anyhow!("Empty target in {:?}", value));
+        }
+        Ok(Self { url: url.to_string(), priority, weight })
+    }
+
+    /// Targets of the record value, a plain value is a single target.
+    pub fn parse_list(value: &str) -> Result<Vec<Self>> {
+        value.split_whitespace().map(Self::parse).collect()
+    }
+
+    /// Record value publishing the targets, the inverse of `parse_list`.
+    pub fn join(targets: &[Self]) -> String {
+        targets.iter().map(|target| target.to_string()).collect::<Vec<_>>().join(" ")
+    }
+}
+
+impl Display for WeightedTarget {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.url)?;
+        if self.priority != 0 {
+            write!(f, ";priority={}", self.priority)?;
+        }
+        if self.weight != 1 {
+            write!(f, ";weight={}", self.weight)?;
+        }
+        Ok(())
+    }
+}
+
+/// Structured Web2 or Tor record value: a JSON object like `{"host": "example.com", "port": 8080, "path": "/app", "proto": "https"}`,
+/// so site owners can publish more than a bare hostname. Records that are not such objects are plain strings.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize)]
+pub struct StructuredTarget {
+    pub host: String,
+    pub port: Option<u16>,
+    pub path: Option<String>,
+    /// URL scheme, `https` for Web2 and `http` for Tor targets by default
+    pub proto: Option<String>,
+}
+
+impl StructuredTarget {
+    /// Parses the record value, `None` if it is not a structured target.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if !value.starts_with('{') {
+            return None;
+        }
+        serde_json::from_str::<Self>(value).ok()
+            .filter(|target| !target.host.is_empty() && !target.host.contains(['/', ' ']))
+    }
+
+    /// URL of the target, the scheme is `default_proto` unless the target sets one.
+    pub fn url(&self, default_proto: &str) -> String {
+        let proto = self.proto.as_deref().unwrap_or(default_proto).trim_end_matches("://").to_lowercase();
+        // IPv6 addresses are bracketed in URLs
+        let host = match self.host.contains(':') && !self.host.starts_with('[') {
+            true => format!("[{}]", self.host),
+            false => self.host.clone(),
+        };
+        let port = self.port.map(|port| format!(":{}", port)).unwrap_or_default();
+        let path = match self.path.as_deref() {
+            Some(path) if !path.is_empty() && !path.starts_with('/') => format!("/{}", path),
+            Some(path) => path.to_string(),
+            None => String::new(),
+        };
+        format!("{}://{}{}{}", proto, host, port, path)
+    }
+
+    /// Record value of the tag with a structured target expanded into its URL, other values are returned as is.
+    pub fn expand(value: String, tag: &AddressTag) -> String {
+        let default_proto = match tag {
+            AddressTag::Web2 => "https",
+            AddressTag::Tor => "http",
+            _ => return value,
+        };
+        match Self::parse(&value) {
+            Some(target) => target.url(default_proto),
+            None => value,
+        }
+    }
+}
+
+/// Naming system a TLD is resolved with.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Registry {
+    Evername,
+    UnstoppableDomains,
+    Ens,
+    /// Custom resolver by its name
+    Custom(String),
+}
+
+impl Display for Registry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Registry::Evername => write!(f, "evername"),
+            Registry::UnstoppableDomains => write!(f, "unstoppable-domains"),
+            Registry::Ens => write!(f, "ens"),
+            Registry::Custom(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// TLD of a domain with the registry resolving it, so integrators branch on the same rules as the resolver
+/// instead of `ends_with(".ever")` checks. The TLD is given with leading dot, e.g. `.ever` or `.eth.link`.
+///
+/// # Example
+/// ```
+/// use web3_resolver::models::Tld;
+///
+/// let tld = Tld::parse("Site.Ever.").unwrap();
+/// assert_eq!(tld.name(), ".ever");
+/// # #[cfg(feature = "evername")]
+/// assert!(tld.is_web3());
+/// assert!(!Tld::parse("example.com").unwrap().is_web3());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash, serde::Serialize)]
+pub struct Tld {
+    name: String,
+    registry: Option<Registry>,
+}
+
+impl Tld {
+    pub(crate) fn new(name: String, registry: Option<Registry>) -> Self {
+        Self { name, registry }
+    }
+
+    /// Parses TLD of the domain by the fixed TLDs of the enabled built-in resolvers: `.ever` (Evername)
+    /// and `.eth` (ENS). Unstoppable Domains TLDs are fetched at runtime and custom resolvers register their own,
+    /// `Web3DomainResolver::tld` knows them all. Other domains get their last label with no registry.
+    pub fn parse(domain: &str) -> Result<Self> {
+        let name = normalize::domain_name(domain);
+        let label = name.rsplit('.').next().unwrap_or_default();
+        if label.is_empty() {
+            return Err(ResolverError::InvalidName {
+                domain: domain.to_string(),
+                reason: "empty label".to_string(),
+            }.into());
+        }
+        let registry = match label {
+            #[cfg(feature = "evername")]
+            "ever" => Some(Registry::Evername),
+            #[cfg(feature = "ens")]
+            "eth" => Some(Registry::Ens),
+            _ => None,
+        };
+        Ok(Self::new(format!(".{}", label), registry))
+    }
+
+    /// TLD with leading dot.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Registry resolving the TLD, `None` for non-web3 TLDs.
+    pub fn registry(&self) -> Option<&Registry> {
+        self.registry.as_ref()
+    }
+
+    /// Whether domains of the TLD are resolved by a web3 registry rather than passed through as non-web3.
+    pub fn is_web3(&self) -> bool {
+        self.registry.is_some()
+    }
+}
+
+impl Display for Tld {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// Unified description of the resolution target all resolvers produce, so gateways serve from one type
+/// instead of matching `ResolvedDomainData` variants and address tags, see `ResolvedDomainData::descriptor`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContentDescriptor {
+    pub location: TargetLocation,
+    /// MIME type of the content if known, e.g. of onchain contract content
+    pub content_type: Option<String>,
+    /// Subresource Integrity value of inline content, e.g. `sha256-...`, IPFS CIDs are self-certifying
+    pub integrity: Option<String>,
+    /// Size of the content in bytes if known
+    pub size_hint: Option<u64>,
+}
+
+/// Where the content of a domain is served from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TargetLocation {
+    /// Web2 URL, IP address or domain name
+    Url(String),
+    /// IPFS content identifier with the path after it and the gateway link it is resolved into
+    Ipfs {
+        cid: String,
+        path: String,
+        gateway_link: String,
+    },
+    /// Onion address of a Tor hidden service
+    Onion(String),
+    /// Content stored on chain
+    Inline(Vec<u8>),
+    /// Redirect to another domain or URL
+    Redirect(Redirect),
+    /// A and AAAA records of a non-web3 domain
+    Dns(DnsRecords),
+}
+
+/// Addresses of a non-web3 domain resolved by `DomainResolverBuilder::with_dns_fallback`.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct DnsRecords {
+    pub name: String,
+    /// IPv4 and IPv6 addresses
+    pub addresses: Vec<std::net::IpAddr>,
+    /// The lowest TTL of the records in seconds, `None` if the backend does not report it
+    pub ttl: Option<u32>,
+}
+
+impl ResolvedDomainData {
+    /// Address (URL, IPFS link, onion address, DNS name), onchain data or onchain contract content.
+    pub fn as_str(&self) -> &str {
+        match self {
+            ResolvedDomainData::DomainString(s) => s,
+            ResolvedDomainData::DnsRecords(records) => &records.name,
+            ResolvedDomainData::Redirect(redirect) => &redirect.target,
+            ResolvedDomainData::OnchainData(s) => s,
+            ResolvedDomainData::OnchainContractData((content, _)) => content,
+        }
+    }
+
+    /// Value of the result for clients working with strings: `as_str` of the data,
+    /// comma separated IP addresses of DNS records.
+    pub fn value_string(&self) -> String {
+        match self {
+            ResolvedDomainData::DnsRecords(records) => records.addresses_string(),
+            data => data.as_str().to_string(),
+        }
+    }
+
+    /// Name of the variant: `domain-string`, `onchain-data`, `onchain-contract-data`, `dns-records` or `redirect`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ResolvedDomainData::DomainString(_) => "domain-string",
+            ResolvedDomainData::OnchainData(_) => "onchain-data",
+            ResolvedDomainData::OnchainContractData(_) => "onchain-contract-data",
+            ResolvedDomainData::DnsRecords(_) => "dns-records",
+            ResolvedDomainData::Redirect(_) => "redirect",
+        }
+    }
+
+    /// Content type of onchain contract content.
+    pub fn content_type(&self) -> Option<&str> {
+        match self {
+            ResolvedDomainData::OnchainContractData((_, content_type)) => Some(content_type),
+            _ => None,
+        }
+    }
+
+    /// Data is the content itself rather than an address to redirect to.
+    pub fn is_content(&self) -> bool {
+        !matches!(self, ResolvedDomainData::DomainString(_) | ResolvedDomainData::DnsRecords(_) | ResolvedDomainData::Redirect(_))
+    }
+
+    /// Targets published by the record, see `WeightedTarget`, empty for other data and malformed target lists.
+    pub fn targets(&self) -> Vec<WeightedTarget> {
+        match self {
+            ResolvedDomainData::DomainString(s) => WeightedTarget::parse_list(s).unwrap_or_default(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Unified descriptor of the target resolved with the tag.
+    pub fn descriptor(&self, tag: AddressTag) -> ContentDescriptor {
+        let (location, content_type) = match self {
+            ResolvedDomainData::OnchainData(content) => (TargetLocation::Inline(content.as_bytes().to_vec()), None),
+            ResolvedDomainData::OnchainContractData((content, content_type)) =>
+                (TargetLocation::Inline(content.as_bytes().to_vec()), Some(content_type.clone())),
+            ResolvedDomainData::DnsRecords(records) => (TargetLocation::Dns(records.clone()), None),
+            ResolvedDomainData::Redirect(redirect) => (TargetLocation::Redirect(redirect.clone()), None),
+            ResolvedDomainData::DomainString(address) => {
+                let location = match (tag, split_gateway_link(address)) {
+                    (AddressTag::Tor, _) => TargetLocation::Onion(address.clone()),
+                    (AddressTag::Ipfs | AddressTag::UnstoppableDomain, Some((cid, path))) => TargetLocation::Ipfs {
+                        cid: cid.to_string(),
+                        path: path.to_string(),
+                        gateway_link: address.clone(),
+                    },
+                    _ => TargetLocation::Url(address.clone()),
+                };
+                (location, None)
+            },
+        };
+        let (integrity, size_hint) = match &location {
+            TargetLocation::Inline(content) => {
+                use sha2::{Digest, Sha256};
+                (Some(format!("sha256-{}", base64(&Sha256::digest(content)))), Some(content.len() as u64))
+            },
+            _ => (None, None),
+        };
+        ContentDescriptor { location, content_type, integrity, size_hint }
+    }
+
+    /// Address to redirect to, `None` for onchain content.
+    pub fn address(&self) -> Option<&str> {
+        match self {
+            ResolvedDomainData::DomainString(s) => Some(s),
+            ResolvedDomainData::DnsRecords(records) => Some(&records.name),
+            ResolvedDomainData::Redirect(redirect) => Some(&redirect.target),
+            _ => None,
+        }
+    }
+}
+
+impl DnsRecords {
+    /// Comma separated addresses.
+    pub fn addresses_string(&self) -> String {
+        self.addresses.iter().map(|address| address.to_string()).collect::<Vec<_>>().join(",")
+    }
 }
 
 impl Display for ResolvedDomainData {
@@ -87,6 +599,1392 @@ impl Display for ResolvedDomainData {
             ResolvedDomainData::OnchainData(s) => write!(f, "OnchainData({}...)", &s.get(0..10).unwrap_or_default()),
             ResolvedDomainData::OnchainContractData((content, content_type)) =>
                 write!(f, "OnchainContractData({}..., {})", &content.get(0..10).unwrap_or_default(), content_type),
+            ResolvedDomainData::DnsRecords(records) => write!(f, "DnsRecords({}: {})", records.name, records.addresses_string()),
+            ResolvedDomainData::Redirect(redirect) => write!(f, "Redirect({} {})", redirect.status, redirect.target),
+        }
+    }
+}
+
+/// What a resolver supports, see `Resolver::capabilities`.
+#[derive(Clone, Debug, PartialEq, Eq, Default, serde::Serialize)]
+pub struct ResolverCapabilities {
+    /// TLDs the resolver handles (with leading dot), empty if they are set on registration only
+    pub tlds: Vec<String>,
+    /// Tags of the records the resolver resolves domains into
+    pub record_kinds: Vec<AddressTag>,
+    /// Coin addresses of domains are resolved with `Resolver::resolve_address`
+    pub addresses: bool,
+    /// Addresses are looked up back into domains
+    pub reverse: bool,
+}
+
+/// Domain an address resolves back into together with the resolver it came from, see `Web3DomainResolver::reverse`.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct ReverseCandidate {
+    pub source: String,
+    pub domain: String,
+}
+
+/// Single resolution result together with the resolver it came from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolutionCandidate {
+    /// Name of the resolver produced this candidate
+    pub source: String,
+    pub data: ResolvedDomainData,
+    pub tag: AddressTag,
+}
+
+/// Registered domain matching a search prefix, see `Web3DomainResolver::search`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DomainSuggestion {
+    pub domain: String,
+    /// Name of the resolver the domain is registered in
+    pub source: String,
+}
+
+impl Display for ResolutionCandidate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} with tag {} from {}", self.data, self.tag, self.source)
+    }
+}
+
+/// How `Web3DomainResolver::resolve` picks the result.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResolutionMode {
+    /// First matching record by resolver priority is returned
+    #[default]
+    First,
+    /// All matching records are collected and the best ranked one is returned
+    Aggregate,
+}
+
+/// How a domain is resolved when its TLD is claimed by several resolvers, e.g. on TLD collisions.
+/// Resolvers are ordered by the configured precedence, then by resolver priority.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AmbiguityPolicy {
+    /// Only the resolver with the highest precedence is queried
+    #[default]
+    First,
+    /// All resolvers are queried in parallel, the successful result with the highest precedence wins
+    Parallel,
+    /// Resolution fails with `ResolverError::AmbiguousDomain`
+    Reject,
+}
+
+/// Which answer wins for TLDs existing both in a web3 registry and ICANN DNS, see `DualResolution`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum DualPrecedence {
+    /// Web3 registry answer wins, DNS is the fallback
+    #[default]
+    Web3,
+    /// DNS answer wins, web3 registry is the fallback
+    Dns,
+}
+
+/// TLDs existing both in a web3 registry and ICANN DNS, e.g. `.art`: domains are resolved by the web3
+/// resolver and as non-web3 ones in parallel, the answer of the preferred side is returned and the other
+/// one is the fallback. The DNS side is checked with `DomainResolverBuilder::with_dns_fallback`,
+/// without it the domain is passed through as is.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct DualResolution {
+    precedence: DualPrecedence,
+    // TLDs with leading dot, `None` for the default precedence
+    tlds: std::collections::BTreeMap<String, Option<DualPrecedence>>,
+}
+
+impl DualResolution {
+    pub fn new(precedence: DualPrecedence) -> Self {
+        Self {
+            precedence,
+            tlds: Default::default(),
+        }
+    }
+
+    /// Adds TLD resolved with the default precedence, e.g. `.art`.
+    pub fn tld(mut self, tld: &str) -> Self {
+        self.tlds.insert(Self::normalize_tld(tld), None);
+        self
+    }
+
+    /// Adds TLD resolved with its own precedence.
+    pub fn tld_with_precedence(mut self, tld: &str, precedence: DualPrecedence) -> Self {
+        self.tlds.insert(Self::normalize_tld(tld), Some(precedence));
+        self
+    }
+
+    /// Precedence of the TLD, `None` if it is not a dual one.
+    pub fn precedence_of(&self, tld: &str) -> Option<DualPrecedence> {
+        self.tlds.get(&Self::normalize_tld(tld))
+            .map(|precedence| precedence.unwrap_or(self.precedence))
+    }
+
+    fn normalize_tld(tld: &str) -> String {
+        format!(".{}", tld.trim_start_matches('.').to_lowercase())
+    }
+}
+
+/// Per-call options of `Web3DomainResolver::resolve_with`, default options resolve as `resolve` does.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct ResolveOptions {
+    /// Tags preferred over the resolver priority, in order of preference
+    pub preferred_tags: Vec<AddressTag>,
+    /// Source names of the resolvers to consult, e.g. `ens`, all resolvers if empty
+    pub resolvers: Vec<String>,
+    /// Tenant the domain is resolved for, see `ResolverContext`
+    pub context: Option<ResolverContext>,
+}
+
+impl ResolveOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn prefer_tags(self, preferred_tags: &[AddressTag]) -> Self {
+        Self { preferred_tags: preferred_tags.to_vec(), ..self }
+    }
+
+    pub fn only_resolvers(self, resolvers: &[&str]) -> Self {
+        Self { resolvers: resolvers.iter().map(|resolver| resolver.to_string()).collect(), ..self }
+    }
+
+    pub fn in_context(self, context: ResolverContext) -> Self {
+        Self { context: Some(context), ..self }
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.preferred_tags.is_empty() && self.resolvers.is_empty() && self.context.is_none()
+    }
+
+    /// Part of the cache key identifying the options, empty for default options.
+    pub(crate) fn cache_key_suffix(&self) -> String {
+        if self.is_default() {
+            return String::new();
+        }
+        let tags: Vec<&str> = self.preferred_tags.iter().map(|tag| tag.as_str()).collect();
+        let tenant = self.context.as_ref().map(|context| format!(";tenant={}", context.tenant())).unwrap_or_default();
+        format!("#tags={};resolvers={}{}", tags.join(","), self.resolvers.join(","), tenant)
+    }
+}
+
+/// Timeouts of JRPC and HTTP requests, unset timeouts are unbounded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct Timeouts {
+    /// Time to establish a connection
+    pub connect: Option<std::time::Duration>,
+    /// Time between reads of the response, catches slow endpoints a connect timeout can't
+    pub read: Option<std::time::Duration>,
+    /// Time of the whole request, from connecting to reading the response body
+    pub total: Option<std::time::Duration>,
+}
+
+impl Timeouts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn connect(self, connect: std::time::Duration) -> Self {
+        Self { connect: Some(connect), ..self }
+    }
+
+    pub fn read(self, read: std::time::Duration) -> Self {
+        Self { read: Some(read), ..self }
+    }
+
+    pub fn total(self, total: std::time::Duration) -> Self {
+        Self { total: Some(total), ..self }
+    }
+
+    /// No timeout is set.
+    pub fn is_unbounded(&self) -> bool {
+        self.connect.is_none() && self.read.is_none() && self.total.is_none()
+    }
+}
+
+/// Connection reuse settings of JRPC and HTTP clients, unset options keep the `reqwest` defaults:
+/// idle connections are kept for 90 seconds without a per-host limit and TCP keep-alive is off.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct ConnectionPool {
+    /// Interval of TCP keep-alive probes, keeps idle connections through NATs and load balancers
+    pub tcp_keepalive: Option<std::time::Duration>,
+    /// Time an idle connection is kept in the pool
+    pub idle_timeout: Option<std::time::Duration>,
+    /// Max idle connections kept per host
+    pub max_idle_per_host: Option<usize>,
+}
+
+impl ConnectionPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Settings for gateway workloads: warm connections are kept long enough to skip most TLS handshakes.
+    pub fn gateway() -> Self {
+        Self::new()
+            .tcp_keepalive(std::time::Duration::from_secs(30))
+            .idle_timeout(std::time::Duration::from_secs(5 * 60))
+            .max_idle_per_host(64)
+    }
+
+    pub fn tcp_keepalive(self, tcp_keepalive: std::time::Duration) -> Self {
+        Self { tcp_keepalive: Some(tcp_keepalive), ..self }
+    }
+
+    pub fn idle_timeout(self, idle_timeout: std::time::Duration) -> Self {
+        Self { idle_timeout: Some(idle_timeout), ..self }
+    }
+
+    pub fn max_idle_per_host(self, max_idle_per_host: usize) -> Self {
+        Self { max_idle_per_host: Some(max_idle_per_host), ..self }
+    }
+
+    /// No option is set.
+    pub fn is_default(&self) -> bool {
+        self.tcp_keepalive.is_none() && self.idle_timeout.is_none() && self.max_idle_per_host.is_none()
+    }
+}
+
+/// How requests are spread over several Everscale JRPC endpoints or IPFS gateways.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum EndpointSelection {
+    /// Endpoints get shares of requests proportional to their weights
+    #[default]
+    WeightedRoundRobin,
+    /// The better of two randomly picked endpoints by latency and error rate moving averages
+    /// divided by weight is used (power of two choices)
+    LatencyAware,
+}
+
+/// Health of an Everscale JRPC endpoint or IPFS gateway.
+#[derive(Clone, Debug)]
+pub struct EndpointStats {
+    pub endpoint: String,
+    pub requests: u64,
+    pub errors: u64,
+    /// Exponentially weighted moving average of the request latency
+    pub latency_ewma: std::time::Duration,
+    /// Exponentially weighted moving average of the error rate, from 0 to 1
+    pub error_rate_ewma: f64,
+}
+
+/// JRPC endpoint with its share of requests, see `DomainResolverBuilder::with_weighted_everscale_endpoints`.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct WeightedEndpoint {
+    pub url: String,
+    pub weight: u32,
+}
+
+/// Effective runtime configuration of `Web3DomainResolver`, e.g. for an admin or diagnostics endpoint.
+/// Endpoints are the configured ones, also of resolvers not initialized yet in lazy mode.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ResolverConfig {
+    /// Source names of the enabled resolvers, e.g. `evername`, `unstoppable-domains` or custom resolver names
+    pub resolvers: Vec<String>,
+    /// Resolvers of every registered TLD (with leading dot) in precedence order
+    pub tlds: std::collections::BTreeMap<String, Vec<String>>,
+    /// Resolvers preferred for TLDs claimed by several ones
+    pub precedence: Vec<String>,
+    pub mode: ResolutionMode,
+    pub ambiguity: AmbiguityPolicy,
+    /// Time-to-live of cached results, `None` if the cache is disabled
+    pub cache_ttl_seconds: Option<u64>,
+    pub everscale_endpoints: Vec<WeightedEndpoint>,
+    pub quorum_endpoints: Vec<String>,
+    pub unstoppable_domains_base_url: Option<String>,
+    pub unstoppable_domains_sandbox: bool,
+    pub ens_endpoint: Option<String>,
+    /// IPFS gateways, the first one is the primary
+    pub ipfs_gateways: Vec<String>,
+    /// Name of the DNS fallback backend
+    pub dns_fallback: Option<String>,
+    pub max_redirects: usize,
+    pub max_aliases: usize,
+    pub strict: bool,
+}
+
+/// Cached resolution result exported with `WarmState`.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CachedResult {
+    /// Cache key of the domain, its lowercase ASCII form
+    pub domain: String,
+    pub data: ResolvedDomainData,
+    pub tag: AddressTag,
+    /// Time left until the result expires
+    pub ttl_seconds: u64,
+}
+
+/// Configuration, cached results and TLD lists exported by `Web3DomainResolver::export_warm_state`,
+/// so a standby instance built with `Web3DomainResolver::from_warm_state` is hot before taking traffic.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct WarmState {
+    pub config: ResolverConfig,
+    pub cache: Vec<CachedResult>,
+    /// Unstoppable Domains supported TLDs, the standby does not fetch them on build
+    #[cfg(feature = "ud")]
+    #[serde(default)]
+    pub unstoppable_domains_tlds: Vec<crate::resolver::ud::TldMetadata>,
+}
+
+/// ENS record the site content is taken from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EnsContentSource {
+    /// EIP-1577 `contenthash` record: IPFS, IPNS or onion address
+    Contenthash,
+    /// `url` text record, a web2 URL the domain redirects to
+    Url,
+    /// `ipfs` text record holding an IPFS CID
+    Ipfs,
+    /// `ipns` text record holding an IPNS name
+    Ipns,
+}
+
+impl EnsContentSource {
+    /// Default order of ENS content records for resolving.
+    /// Order is priority!
+    pub fn resolvable() -> Vec<EnsContentSource> {
+        vec![
+            EnsContentSource::Contenthash,
+            EnsContentSource::Url,
+            EnsContentSource::Ipfs,
+            EnsContentSource::Ipns,
+        ]
+    }
+
+    /// Key of the text record, `None` for the `contenthash` record.
+    pub fn text_key(&self) -> Option<&'static str> {
+        match self {
+            EnsContentSource::Contenthash => None,
+            EnsContentSource::Url => Some("url"),
+            EnsContentSource::Ipfs => Some("ipfs"),
+            EnsContentSource::Ipns => Some("ipns"),
+        }
+    }
+
+    /// Name of the source as accepted by `FromStr`, e.g. `contenthash` or `url`.
+    pub fn as_str(&self) -> &'static str {
+        self.text_key().unwrap_or("contenthash")
+    }
+}
+
+impl Display for EnsContentSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for EnsContentSource {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "contenthash" => Ok(EnsContentSource::Contenthash),
+            "url" => Ok(EnsContentSource::Url),
+            "ipfs" => Ok(EnsContentSource::Ipfs),
+            "ipns" => Ok(EnsContentSource::Ipns),
+            _ => Err(anyhow!("Unknown ENS content source: {}", value))
+        }
+    }
+}
+
+/// SLIP-44 coin type of a multi-chain address, e.g. `CoinType::ETH` or `"btc".parse::<CoinType>()`.
+/// EVM chains use ENSIP-11 coin types, see `CoinType::evm_chain`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CoinType(pub u32);
+
+impl CoinType {
+    pub const BTC: CoinType = CoinType(0);
+    pub const LTC: CoinType = CoinType(2);
+    pub const DOGE: CoinType = CoinType(3);
+    pub const ETH: CoinType = CoinType(60);
+    pub const ETC: CoinType = CoinType(61);
+    pub const XRP: CoinType = CoinType(144);
+    pub const BCH: CoinType = CoinType(145);
+    pub const TRX: CoinType = CoinType(195);
+    pub const EVER: CoinType = CoinType(396);
+    pub const SOL: CoinType = CoinType(501);
+    pub const TON: CoinType = CoinType(607);
+    pub const BNB: CoinType = CoinType(714);
+    pub const ADA: CoinType = CoinType(1815);
+
+    const TICKERS: [(CoinType, &'static str); 13] = [
+        (CoinType::BTC, "BTC"),
+        (CoinType::LTC, "LTC"),
+        (CoinType::DOGE, "DOGE"),
+        (CoinType::ETH, "ETH"),
+        (CoinType::ETC, "ETC"),
+        (CoinType::XRP, "XRP"),
+        (CoinType::BCH, "BCH"),
+        (CoinType::TRX, "TRX"),
+        (CoinType::EVER, "EVER"),
+        (CoinType::SOL, "SOL"),
+        (CoinType::TON, "TON"),
+        (CoinType::BNB, "BNB"),
+        (CoinType::ADA, "ADA"),
+    ];
+
+    /// ENSIP-11 coin type of an EVM chain by its chain id, e.g. `137` for Polygon.
+    pub fn evm_chain(chain_id: u32) -> CoinType {
+        CoinType(0x8000_0000 | chain_id)
+    }
+
+    /// Ticker of the coin, `None` for coins unknown to this crate.
+    pub fn ticker(&self) -> Option<&'static str> {
+        Self::TICKERS.iter().find(|(coin, _)| coin == self).map(|(_, ticker)| *ticker)
+    }
+
+    /// Coin with addresses in the Ethereum format: Ethereum, Ethereum Classic or an ENSIP-11 EVM chain.
+    pub fn is_evm(&self) -> bool {
+        matches!(*self, CoinType::ETH | CoinType::ETC) || self.0 & 0x8000_0000 != 0
+    }
+}
+
+impl From<u32> for CoinType {
+    fn from(value: u32) -> Self {
+        CoinType(value)
+    }
+}
+
+impl Display for CoinType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.ticker() {
+            Some(ticker) => write!(f, "{}({})", ticker, self.0),
+            None => write!(f, "coin({})", self.0),
+        }
+    }
+}
+
+/// Parses coin by its ticker, e.g. `ETH` or `btc`, or by its SLIP-44 number.
+impl FromStr for CoinType {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        if let Ok(number) = value.parse::<u32>() {
+            return Ok(CoinType(number));
+        }
+        Self::TICKERS.iter()
+            .find(|(_, ticker)| ticker.eq_ignore_ascii_case(value))
+            .map(|(coin, _)| *coin)
+            .ok_or_else(|| anyhow!("Unknown coin ticker: {}", value))
+    }
+}
+
+/// Point in time for historical resolution.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HistoricalPoint {
+    /// Unix timestamp in seconds
+    Timestamp(u64),
+    /// Logical time of the blockchain
+    Lt(u64),
+}
+
+/// Resolution change observed by `Web3DomainResolver::watch`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolutionChange {
+    pub domain: String,
+    /// Previous resolved data and tag, `None` only if the previous state was unknown
+    pub previous: Option<(ResolvedDomainData, AddressTag)>,
+    pub current: (ResolvedDomainData, AddressTag),
+}
+
+/// Detailed resolution result returned by `Web3DomainResolver::resolve_report`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolutionReport {
+    pub domain: String,
+    pub data: ResolvedDomainData,
+    pub tag: AddressTag,
+    /// Name of the resolver produced the result
+    pub source: String,
+    /// Result was taken from the resolution cache
+    pub from_cache: bool,
+    /// Result is the last known one served because the resolution exceeded its budget
+    pub stale: bool,
+    /// Decision trail, filled only in explain mode
+    pub trace: Vec<String>,
+    /// Raw record values seen during the resolution as (key, value), filled only by `explain`
+    pub records: Vec<(String, String)>,
+    /// Signature of the result, set if the resolver has a signing key
+    pub signature: Option<ReportSignature>,
+    /// Warnings about the domain for the user, not covered by the signature
+    pub warnings: Vec<ResolutionWarning>,
+    /// Chain states the result was read from, filled only if `DomainResolverBuilder::record_state_refs` is on
+    /// and the result is not taken from the cache
+    pub state_refs: Vec<StateRef>,
+    /// Raw records the result was decoded from, filled only if `DomainResolverBuilder::record_raw_records` is on
+    /// and the result is not taken from the cache
+    pub raw_records: Vec<RawRecord>,
+    /// Domains followed by redirects and aliases to reach the result, starting with the requested domain,
+    /// empty if none were followed or the result is taken from the cache
+    pub chain: Vec<String>,
+}
+
+/// Raw record value as stored on chain, so verification tools can decode it independently of the resolver.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RawRecord {
+    /// Record key, e.g. Evername record number `1003`
+    pub key: String,
+    /// Base64 encoded BOC of the record cell
+    pub boc: String,
+}
+
+/// Reference of an Everscale account state read during a resolution, pins the exact on-chain state
+/// that produced the result, e.g. for reproducible builds of sites.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct StateRef {
+    /// Account address, e.g. of the DomainRoot, certificate or Eversite contract
+    pub address: String,
+    /// Last transaction lt of the account state
+    pub last_trans_lt: u64,
+    /// Hex encoded representation hash of the account state
+    pub hash: String,
+}
+
+/// Warning about a resolved domain, e.g. for anti-phishing UX.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ResolutionWarning {
+    /// Domain looks like a popular domain, see `DomainResolverBuilder::with_confusable_detection`
+    Confusable {
+        similar_to: String,
+    },
+}
+
+impl Display for ResolutionWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResolutionWarning::Confusable { similar_to } => write!(f, "Domain is confusable with {}", similar_to),
+        }
+    }
+}
+
+/// Ed25519 signature of a resolution result made by the resolver operator.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReportSignature {
+    /// Hex encoded public key of the signer
+    pub public_key: String,
+    /// Unix timestamp in seconds of the signing
+    pub signed_at: u64,
+    /// Hex encoded signature of `ResolutionReport::signing_payload`
+    pub signature: String,
+}
+
+impl ResolutionReport {
+    /// Report of a freshly resolved result, without trace, signature or warnings.
+    pub fn new(domain: impl Into<String>, data: ResolvedDomainData, tag: AddressTag, source: impl Into<String>) -> Self {
+        Self {
+            domain: domain.into(),
+            data,
+            tag,
+            source: source.into(),
+            from_cache: false,
+            stale: false,
+            trace: Vec::new(),
+            records: Vec::new(),
+            signature: None,
+            warnings: Vec::new(),
+            state_refs: Vec::new(),
+            raw_records: Vec::new(),
+            chain: Vec::new(),
+        }
+    }
+
+    pub fn with_from_cache(self, from_cache: bool) -> Self {
+        Self { from_cache, ..self }
+    }
+
+    pub fn with_stale(self, stale: bool) -> Self {
+        Self { stale, ..self }
+    }
+
+    /// Unified descriptor of the resolved target.
+    pub fn descriptor(&self) -> ContentDescriptor {
+        self.data.descriptor(self.tag)
+    }
+
+    /// Canonical bytes covered by the signature: domain, tag, source, signing time, resolved data,
+    /// the state references and the followed chain if any.
+    pub fn signing_payload(&self, signed_at: u64) -> Vec<u8> {
+        let data = match &self.data {
+            ResolvedDomainData::DomainString(s) => format!("domain:{}", s),
+            ResolvedDomainData::OnchainData(s) => format!("onchain:{}", s),
+            ResolvedDomainData::OnchainContractData((content, content_type)) => format!("contract:{}:{}", content_type, content),
+            ResolvedDomainData::DnsRecords(records) => format!("dns:{}:{}", records.name, records.addresses_string()),
+            ResolvedDomainData::Redirect(redirect) => format!("redirect:{}:{}", redirect.status, redirect.target),
+        };
+        let mut payload = format!("web3-resolver-report-v1\n{}\n{}\n{}\n{}\n{}", self.domain, self.tag, self.source, signed_at, data);
+        for state_ref in &self.state_refs {
+            payload.push_str(&format!("\nstate:{}:{}:{}", state_ref.address, state_ref.last_trans_lt, state_ref.hash));
+        }
+        if !self.chain.is_empty() {
+            payload.push_str(&format!("\nchain:{}", self.chain.join(" -> ")));
+        }
+        payload.into_bytes()
+    }
+
+    /// Verifies the result signature against the trusted public key of the resolver operator.
+    pub fn verify_signature(&self, public_key: &VerifyingKey) -> Result<()> {
+        let signature = self.signature.as_ref().ok_or_else(|| anyhow!("Report of {} is not signed", self.domain))?;
+        let bytes = hex::decode(&signature.signature).context("Malformed report signature")?;
+        let signature_value = Signature::from_slice(&bytes).context("Malformed report signature")?;
+        public_key.verify(&self.signing_payload(signature.signed_at), &signature_value)
+            .map_err(|_| anyhow!("Report signature of {} is invalid", self.domain))
+    }
+}
+
+/// Rolling resolution statistics returned by `Web3DomainResolver::stats`.
+#[derive(Clone, Debug, Default)]
+pub struct ResolverStats {
+    /// Total number of resolutions
+    pub requests: u64,
+    /// Number of failed resolutions
+    pub errors: u64,
+    /// Most often successfully resolved domains with their counters, the most popular first
+    pub top_domains: Vec<(String, u64)>,
+    /// Counters per resolver
+    pub sources: Vec<SourceStats>,
+    /// Median latency over the recent resolutions
+    pub latency_p50: std::time::Duration,
+    /// 99th percentile latency over the recent resolutions
+    pub latency_p99: std::time::Duration,
+    /// Number of times the namehash cache of the ENS resolver was cleared on reaching its capacity,
+    /// growing steadily means the working set of names exceeds the cache
+    pub namehash_cache_clears: u64,
+}
+
+/// Resolution counters of a single resolver.
+#[derive(Clone, Debug)]
+pub struct SourceStats {
+    pub source: String,
+    pub requests: u64,
+    pub errors: u64,
+}
+
+impl SourceStats {
+    /// Share of failed resolutions, from 0 to 1.
+    pub fn error_rate(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.errors as f64 / self.requests as f64
+        }
+    }
+}
+
+/// Asset manifest of a multi-asset onchain site, returned by `Web3DomainResolver::site_manifest`.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct SiteManifest {
+    /// Assets by their paths, e.g. `/style.css`
+    pub assets: std::collections::BTreeMap<String, SiteAsset>,
+}
+
+/// Location of a site asset.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SiteAsset {
+    /// Address of the Eversite contract holding the asset
+    Contract(String),
+    /// IPFS CID of the asset
+    Ipfs(String),
+}
+
+impl SiteManifest {
+    /// Parses manifest JSON: `{"assets": {"<path>": "<contract address | ipfs://CID>"}}`.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let value: serde_json::Value = serde_json::from_str(json)?;
+        let assets_value = value.get("assets")
+            .and_then(|assets| assets.as_object())
+            .ok_or_else(|| anyhow!("Manifest has no assets object"))?;
+        let mut assets = std::collections::BTreeMap::new();
+        for (path, target) in assets_value {
+            let target = target.as_str().ok_or_else(|| anyhow!("Asset {} target is not a string", path))?;
+            let asset = match target.strip_prefix("ipfs://") {
+                Some(cid) => SiteAsset::Ipfs(cid.to_string()),
+                None => SiteAsset::Contract(target.to_string()),
+            };
+            let path = if path.starts_with('/') { path.clone() } else { format!("/{}", path) };
+            assets.insert(path, asset);
         }
+        Ok(Self { assets })
+    }
+
+    /// Returns asset by its path, `/` is mapped to `/index.html`.
+    pub fn asset(&self, path: &str) -> Option<&SiteAsset> {
+        let path = if path.is_empty() || path == "/" { "/index.html" } else { path };
+        self.assets.get(path)
     }
-}
\ No newline at end of file
+}
+
+/// CARv1 archive of an onchain site, returned by `Web3DomainResolver::export_site_car`,
+/// so operators can mirror the site to IPFS with e.g. `ipfs dag import`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SiteArchive {
+    /// CIDv1 of the site root directory
+    pub root_cid: String,
+    /// CAR archive bytes
+    pub car: Vec<u8>,
+    /// Paths of the packaged files, e.g. `/index.html`
+    pub files: Vec<String>,
+    /// Manifest assets already stored on IPFS by their paths, they are not packaged
+    pub ipfs_assets: std::collections::BTreeMap<String, String>,
+}
+
+/// Full resolution state of a domain at a point in time, returned by `Web3DomainResolver::snapshot`
+/// for audits and disputes about what a name pointed to. Serializable with serde, e.g. `to_json`.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct DomainSnapshot {
+    pub domain: String,
+    /// Name of the resolver that produced the snapshot
+    pub source: String,
+    /// Unix timestamp in seconds of the snapshot
+    pub taken_at: u64,
+    /// Account the records were read from: Evername certificate contract or ENS resolver contract
+    pub account: Option<String>,
+    /// Last transaction lt of the Evername certificate contract, pins the state the records were read at
+    pub last_transaction_lt: Option<u64>,
+    /// Raw profile JSON of Unstoppable Domains
+    pub profile: Option<String>,
+    pub records: Vec<SnapshotRecord>,
+    /// Resolution result as (data, tag) at the snapshot time, onchain content is given by its `sha256:<hex>` hash
+    pub resolved: Option<(String, String)>,
+    /// Resolution error at the snapshot time
+    pub resolution_error: Option<String>,
+}
+
+/// Records of a domain returned by `Web3DomainResolver::records`, read without resolving the domain
+/// or loading the content of Eversite contract records.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct DomainRecords {
+    pub domain: String,
+    /// Name of the resolver the records were read with
+    pub source: String,
+    pub records: Vec<SnapshotRecord>,
+}
+
+/// Record of a `DomainSnapshot` or `DomainRecords`.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SnapshotRecord {
+    /// Record key, e.g. `1003`, `crypto.ETH.address` or `contenthash`
+    pub key: String,
+    /// Raw value: hex encoded BOC of Evername cells, JSON of Unstoppable Domains records, hex or text of ENS records
+    pub raw: String,
+    /// Decoded value, `None` if the record could not be decoded
+    pub value: Option<String>,
+    /// Hex encoded SHA-256 of the decoded value, of the loaded content for Eversite contract records of snapshots
+    pub sha256: Option<String>,
+    /// Content type of the loaded Eversite contract content, `None` in `DomainRecords`
+    pub content_type: Option<String>,
+}
+
+impl DomainSnapshot {
+    #[cfg(any(feature = "evername", feature = "ud", feature = "ens"))]
+    pub(crate) fn new(domain: &str, source: &str) -> Self {
+        let taken_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        Self {
+            domain: domain.to_string(),
+            source: source.to_string(),
+            taken_at,
+            account: None,
+            last_transaction_lt: None,
+            profile: None,
+            records: Vec::new(),
+            resolved: None,
+            resolution_error: None,
+        }
+    }
+
+    #[cfg(any(feature = "evername", feature = "ud", feature = "ens"))]
+    pub(crate) fn set_resolution(&mut self, result: Result<(ResolvedDomainData, AddressTag)>) {
+        match result {
+            Ok((data, tag)) => {
+                let data = match &data {
+                    ResolvedDomainData::DomainString(address) => address.clone(),
+                    ResolvedDomainData::DnsRecords(records) => records.addresses_string(),
+                    onchain => {
+                        use sha2::{Digest, Sha256};
+                        format!("sha256:{}", hex::encode(Sha256::digest(onchain.as_str().as_bytes())))
+                    },
+                };
+                self.resolved = Some((data, tag.as_str().to_string()));
+            },
+            Err(e) => self.resolution_error = Some(format!("{:#}", e)),
+        }
+    }
+
+    /// Serializes the snapshot to pretty printed JSON.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Parses snapshot from JSON made by `to_json`.
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+impl SnapshotRecord {
+    #[cfg(any(feature = "evername", feature = "ud", feature = "ens"))]
+    pub(crate) fn new(key: impl ToString, raw: String, value: Option<String>) -> Self {
+        use sha2::{Digest, Sha256};
+        Self {
+            key: key.to_string(),
+            raw,
+            sha256: value.as_ref().map(|value| hex::encode(Sha256::digest(value.as_bytes()))),
+            value,
+            content_type: None,
+        }
+    }
+}
+
+/// Byte range of onchain content, for serving HTTP range requests.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContentRange {
+    pub content_type: String,
+    /// Length of the whole content in bytes
+    pub total_length: u64,
+    /// Range of the content bytes in `data`, end is exclusive
+    pub range: std::ops::Range<u64>,
+    pub data: Vec<u8>,
+    /// HTTP caching metadata of the content
+    pub cache: CacheHeaders,
+}
+
+impl ContentRange {
+    /// Value of the HTTP `Content-Range` header, e.g. `bytes 0-499/1234`.
+    pub fn content_range_header(&self) -> String {
+        if self.range.is_empty() {
+            format!("bytes */{}", self.total_length)
+        } else {
+            format!("bytes {}-{}/{}", self.range.start, self.range.end - 1, self.total_length)
+        }
+    }
+}
+
+/// HTTP caching metadata of fetched content, so the serving layer can send `Cache-Control`, `ETag`
+/// and `Last-Modified` and answer conditional requests.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CacheHeaders {
+    /// Value of the `Cache-Control` header, e.g. `public, max-age=60`
+    pub cache_control: Option<String>,
+    /// Quoted entity tag of the whole content, e.g. `"2c26b46b..."`
+    pub etag: Option<String>,
+    /// Unix timestamp in seconds of the last content change
+    pub last_modified: Option<u64>,
+}
+
+impl CacheHeaders {
+    /// Value of the `Last-Modified` header in the IMF-fixdate format, e.g. `Sun, 06 Nov 1994 08:49:37 GMT`.
+    pub fn last_modified_header(&self) -> Option<String> {
+        self.last_modified.map(http_date)
+    }
+
+    /// Headers to send as (name, value), only the known ones.
+    pub fn headers(&self) -> Vec<(&'static str, String)> {
+        let mut headers = Vec::new();
+        if let Some(cache_control) = &self.cache_control {
+            headers.push(("Cache-Control", cache_control.clone()));
+        }
+        if let Some(etag) = &self.etag {
+            headers.push(("ETag", etag.clone()));
+        }
+        if let Some(last_modified) = self.last_modified_header() {
+            headers.push(("Last-Modified", last_modified));
+        }
+        headers
+    }
+
+    /// The `If-None-Match` header value matches the entity tag, so `304 Not Modified` can be returned.
+    /// Tags are compared weakly, `W/` prefixes are ignored.
+    pub fn matches_etag(&self, if_none_match: &str) -> bool {
+        let Some(etag) = &self.etag else {
+            return false;
+        };
+        let etag = etag.trim_start_matches("W/");
+        if_none_match.split(',')
+            .map(|tag| tag.trim())
+            .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+    }
+}
+
+/// Standard base64 with padding, as used by Subresource Integrity.
+pub(crate) fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let buffer = chunk.iter().enumerate().fold(0u32, |buffer, (i, byte)| buffer | ((*byte as u32) << (16 - 8 * i)));
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => encoded.push(ALPHABET[((buffer >> (18 - 6 * i)) & 0x3f) as usize] as char),
+                false => encoded.push('='),
+            }
+        }
+    }
+    encoded
+}
+
+/// HTTP date of the unix timestamp in seconds.
+fn http_date(timestamp: u64) -> String {
+    const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+    const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+    let days = timestamp / 86400;
+    let seconds = timestamp % 86400;
+    // civil date of the days since the epoch, March-based years
+    let shifted = days + 719468;
+    let era = shifted / 146097;
+    let day_of_era = shifted % 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    format!("{}, {:02} {} {} {:02}:{:02}:{:02} GMT", WEEKDAYS[(days % 7) as usize], day, MONTHS[(month - 1) as usize],
+        year, seconds / 3600, seconds % 3600 / 60, seconds % 60)
+}
+
+/// Kind of the key material of a `TlsRecord`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TlsRecordKind {
+    /// X.509 certificate
+    Certificate,
+    /// SubjectPublicKeyInfo of a public key
+    PublicKey,
+}
+
+/// TLS certificate or public key published in the record `1006` of a domain, so gateways can serve
+/// web3 sites over end-to-end authenticated TLS or pin the key of the origin.
+///
+/// The record holds PEM (`CERTIFICATE` or `PUBLIC KEY` block), hex encoded DER or raw DER.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TlsRecord {
+    pub kind: TlsRecordKind,
+    /// DER encoded certificate or SubjectPublicKeyInfo
+    pub der: Vec<u8>,
+}
+
+impl TlsRecord {
+    /// Parses record value: PEM, hex encoded DER or raw DER.
+    pub fn from_bytes(value: &[u8]) -> Result<Self> {
+        let text = std::str::from_utf8(value).map(|text| text.trim());
+        match text {
+            Ok(text) if text.starts_with("-----BEGIN") => Self::from_pem(text),
+            Ok(text) if !text.is_empty() && text.chars().all(|c| c.is_ascii_hexdigit()) => {
+                Self::from_der(&hex::decode(text).context("Invalid hex encoded DER")?)
+            },
+            _ => Self::from_der(value),
+        }
+    }
+
+    /// Parses PEM `CERTIFICATE` or `PUBLIC KEY` block.
+    pub fn from_pem(pem: &str) -> Result<Self> {
+        let pem = pem::parse(pem).context("Invalid PEM")?;
+        let kind = match pem.tag() {
+            "CERTIFICATE" => TlsRecordKind::Certificate,
+            "PUBLIC KEY" => TlsRecordKind::PublicKey,
+            tag => return Err(anyhow!("Unsupported PEM block {}", tag)),
+        };
+        let record = Self::from_der(pem.contents())?;
+        if record.kind != kind {
+            return Err(anyhow!("PEM block {} does not match its content", pem.tag()));
+        }
+        Ok(record)
+    }
+
+    /// Parses DER encoded certificate or SubjectPublicKeyInfo, the kind is detected by the structure:
+    /// the first field of a certificate is its TBS certificate, the first field of a SubjectPublicKeyInfo
+    /// is its algorithm identifier starting with an OID.
+    pub fn from_der(der: &[u8]) -> Result<Self> {
+        let header_len = der_header_len(der).ok_or(anyhow!("Invalid DER"))?;
+        let (tag, outer) = der_element(der).ok_or(anyhow!("Invalid DER"))?;
+        if tag != 0x30 || header_len + outer.len() != der.len() {
+            return Err(anyhow!("DER is not a single sequence"));
+        }
+        let (tag, first) = der_element(outer).ok_or(anyhow!("Invalid DER sequence"))?;
+        if tag != 0x30 {
+            return Err(anyhow!("DER is neither a certificate nor a public key"));
+        }
+        let kind = match first.first() {
+            Some(0x06) => TlsRecordKind::PublicKey,
+            Some(0xa0) | Some(0x02) => TlsRecordKind::Certificate,
+            _ => return Err(anyhow!("DER is neither a certificate nor a public key")),
+        };
+        Ok(Self { kind, der: der.to_vec() })
+    }
+
+    /// PEM encoding of the record.
+    pub fn to_pem(&self) -> String {
+        let tag = match self.kind {
+            TlsRecordKind::Certificate => "CERTIFICATE",
+            TlsRecordKind::PublicKey => "PUBLIC KEY",
+        };
+        pem::encode(&pem::Pem::new(tag, self.der.clone()))
+    }
+
+    /// Hex encoded SHA-256 of the DER, i.e. the certificate fingerprint or the public key pin.
+    pub fn sha256_fingerprint(&self) -> String {
+        use sha2::{Digest, Sha256};
+        hex::encode(Sha256::digest(&self.der))
+    }
+}
+
+/// Length of the DER element header (tag and length).
+fn der_header_len(data: &[u8]) -> Option<usize> {
+    let length_byte = *data.get(1)?;
+    match length_byte {
+        0..=0x7f => Some(2),
+        0x81..=0x84 => Some(2 + (length_byte & 0x7f) as usize),
+        _ => None,
+    }
+}
+
+/// Returns tag and content of the first DER element.
+fn der_element(data: &[u8]) -> Option<(u8, &[u8])> {
+    let tag = *data.first()?;
+    let header_len = der_header_len(data)?;
+    let length = match data[1] {
+        length @ 0..=0x7f => length as usize,
+        _ => data.get(2..header_len)?.iter().fold(0usize, |length, byte| (length << 8) | *byte as usize),
+    };
+    data.get(header_len..header_len.checked_add(length)?).map(|content| (tag, content))
+}
+
+/// Registration terms of an Evername domain, see `EvernameResolver::registration_quote`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegistrationQuote {
+    pub domain: String,
+    /// Domain is not registered yet
+    pub available: bool,
+    /// Yearly price in nano WEVER
+    pub price: u128,
+    /// Name goes through a zero auction before it can be registered
+    pub needs_zero_auction: bool,
+    /// Registration duration in seconds
+    pub duration: u32,
+    /// WEVER amount registering the domain for the duration, in nano WEVER
+    pub amount: u128,
+}
+
+/// Registration of an Evername domain: `amount` WEVER transferred to the DomainRoot
+/// as a TIP-3 transfer with the payload, see `EvernameResolver::registration_request`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegistrationRequest {
+    pub domain: String,
+    /// Address of the DomainRoot receiving the transfer
+    pub root_address: String,
+    /// WEVER amount to transfer, in nano WEVER
+    pub amount: u128,
+    /// BOC of the transfer payload
+    pub payload: Vec<u8>,
+}
+
+/// Owner and registration period of an Evername domain, see `EvernameResolver::domain_details`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DomainDetails {
+    pub domain: String,
+    /// Owner wallet address
+    pub owner: String,
+    /// Unix timestamp in seconds of the registration
+    pub registered_at: u64,
+    /// Unix timestamp in seconds the registration expires at
+    pub expires_at: u64,
+}
+
+/// Event of the domain expiry watch, see `Web3DomainResolver::watch_expiry`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExpiryEvent {
+    /// Domain expires within the warning period, emitted once per expiry date
+    Expiring { domain: String, expires_at: u64 },
+    /// Domain has expired, emitted once per expiry date
+    Expired { domain: String, expires_at: u64 },
+    /// Expiry date moved forward, e.g. the domain was renewed
+    Renewed { domain: String, previous_expires_at: u64, expires_at: u64 },
+}
+
+impl ExpiryEvent {
+    pub fn domain(&self) -> &str {
+        match self {
+            ExpiryEvent::Expiring { domain, .. } | ExpiryEvent::Expired { domain, .. } | ExpiryEvent::Renewed { domain, .. } => domain,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn address_tag_round_trips() {
+        for tag in AddressTag::all() {
+            assert_eq!(AddressTag::try_from(tag.tag()).unwrap(), tag);
+            if let Some(key) = tag.record_key() {
+                assert_eq!(AddressTag::from_record_key(key), Some(tag));
+            }
+        }
+        assert_eq!(AddressTag::from_record_key(AddressTag::NonWeb3.tag()), None);
+        assert_eq!(AddressTag::from_record_key(AddressTag::UnstoppableDomain.tag()), None);
+    }
+
+    #[test]
+    fn tld_parse() {
+        let tld = Tld::parse("Site.Example.").unwrap();
+        assert_eq!(tld.name(), ".example");
+        assert!(!tld.is_web3());
+        assert!(Tld::parse("site.").is_ok());
+        assert!(Tld::parse("").is_err());
+        #[cfg(feature = "evername")]
+        assert_eq!(Tld::parse("maksimzubov.ever").unwrap().registry(), Some(&Registry::Evername));
+        #[cfg(feature = "ens")]
+        assert_eq!(Tld::parse("vitalik.eth").unwrap().registry(), Some(&Registry::Ens));
+    }
+
+    #[test]
+    fn der_elements() {
+        assert_eq!(der_element(&[0x04, 0x02, 0xab, 0xcd, 0xff]), Some((0x04, &[0xab, 0xcd][..])));
+        let mut long = vec![0x04, 0x81, 0x80];
+        long.extend([0x11; 0x80]);
+        assert_eq!(der_element(&long).map(|(tag, content)| (tag, content.len())), Some((0x04, 0x80)));
+        assert_eq!(der_element(&[0x04, 0x05, 0x01]), None);
+        assert_eq!(der_element(&[0x04, 0x80]), None);
+        assert_eq!(der_element(&[0x04]), None);
+    }
+
+    // Ed25519 SubjectPublicKeyInfo
+    fn public_key_der() -> Vec<u8> {
+        let mut der = hex::decode("302a300506032b6570032100").unwrap();
+        der.extend([0x42; 32]);
+        der
+    }
+
+    #[test]
+    fn tls_record_kinds() {
+        let public_key = TlsRecord::from_der(&public_key_der()).unwrap();
+        assert_eq!(public_key.kind, TlsRecordKind::PublicKey);
+        // sequence of a TBS certificate starting with the explicit version
+        let certificate = TlsRecord::from_der(&[0x30, 0x05, 0x30, 0x03, 0xa0, 0x01, 0x00]).unwrap();
+        assert_eq!(certificate.kind, TlsRecordKind::Certificate);
+        assert!(TlsRecord::from_der(&[0x30, 0x03, 0x04, 0x01, 0x00]).is_err());
+        // trailing bytes after the sequence
+        let mut trailing = public_key_der();
+        trailing.push(0);
+        assert!(TlsRecord::from_der(&trailing).is_err());
+    }
+
+    #[test]
+    fn tls_record_encodings() {
+        let der = public_key_der();
+        let record = TlsRecord::from_der(&der).unwrap();
+        assert_eq!(TlsRecord::from_bytes(&der).unwrap(), record);
+        assert_eq!(TlsRecord::from_bytes(hex::encode(&der).as_bytes()).unwrap(), record);
+        assert_eq!(TlsRecord::from_bytes(record.to_pem().as_bytes()).unwrap(), record);
+        let mismatched = pem::encode(&pem::Pem::new("CERTIFICATE", der));
+        assert!(TlsRecord::from_pem(&mismatched).is_err());
+    }
+
+    #[test]
+    fn value_strings() {
+        let records = DnsRecords { name: "example.com".to_string(), addresses: vec!["10.0.0.1".parse().unwrap(), "::1".parse().unwrap()], ttl: None };
+        assert_eq!(ResolvedDomainData::DnsRecords(records).value_string(), "10.0.0.1,::1");
+        assert_eq!(ResolvedDomainData::DomainString("https://chums.chat".to_string()).value_string(), "https://chums.chat");
+        assert_eq!(ResolvedDomainData::OnchainContractData(("<p>hi</p>".to_string(), "text/html".to_string())).value_string(), "<p>hi</p>");
+        let redirect = Redirect { target: "alice.crypto".to_string(), status: 302 };
+        assert_eq!(ResolvedDomainData::Redirect(redirect).value_string(), "alice.crypto");
+    }
+
+    fn signed_report(signing_key: &ed25519_dalek::SigningKey) -> ResolutionReport {
+        use ed25519_dalek::Signer;
+        let mut report = ResolutionReport::new("chums.ever", ResolvedDomainData::DomainString("https://chums.chat".to_string()), AddressTag::Web2, "evername");
+        report.state_refs = vec![StateRef { address: "0:abc".to_string(), last_trans_lt: 42, hash: "00ff".to_string() }];
+        report.chain = vec!["chums.ever".to_string(), "www.chums.ever".to_string()];
+        let signed_at = 1_700_000_000;
+        let signature = signing_key.sign(&report.signing_payload(signed_at));
+        report.signature = Some(ReportSignature {
+            public_key: hex::encode(signing_key.verifying_key().as_bytes()),
+            signed_at,
+            signature: hex::encode(signature.to_bytes()),
+        });
+        report
+    }
+
+    #[test]
+    fn report_signature_round_trip() {
+        let signing_key = ed25519_dalek::SigningKey::from_bytes(&[7; 32]);
+        let public_key = signing_key.verifying_key();
+        let report = signed_report(&signing_key);
+        report.verify_signature(&public_key).unwrap();
+        // warnings and trace are not signed
+        let mut annotated = report.clone();
+        annotated.warnings.push(ResolutionWarning::Confusable { similar_to: "chums.eth".to_string() });
+        annotated.trace.push("Resolution cache: miss".to_string());
+        annotated.verify_signature(&public_key).unwrap();
+        let other_key = ed25519_dalek::SigningKey::from_bytes(&[8; 32]).verifying_key();
+        assert!(report.verify_signature(&other_key).is_err());
+        let unsigned = ResolutionReport { signature: None, ..report };
+        assert!(unsigned.verify_signature(&public_key).is_err());
+    }
+
+    #[test]
+    fn tampered_reports_fail_verification() {
+        let signing_key = ed25519_dalek::SigningKey::from_bytes(&[7; 32]);
+        let public_key = signing_key.verifying_key();
+        let report = signed_report(&signing_key);
+        type Tamper = fn(&mut ResolutionReport);
+        let tampered: [(&str, Tamper); 11] = [
+            ("domain", |report| report.domain = "chums.eth".to_string()),
+            ("data", |report| report.data = ResolvedDomainData::DomainString("https://evil.example".to_string())),
+            ("data kind", |report| report.data = ResolvedDomainData::OnchainData("https://chums.chat".to_string())),
+            ("tag", |report| report.tag = AddressTag::Ipfs),
+            ("source", |report| report.source = "ens".to_string()),
+            ("state ref", |report| report.state_refs[0].last_trans_lt = 43),
+            ("state refs", |report| report.state_refs.clear()),
+            ("chain", |report| { report.chain.pop(); }),
+            ("signed at", |report| report.signature.as_mut().unwrap().signed_at += 1),
+            ("signature", |report| report.signature.as_mut().unwrap().signature.replace_range(0..2, "00")),
+            ("malformed signature", |report| report.signature.as_mut().unwrap().signature = "zz".to_string()),
+        ];
+        for (field, tamper) in tampered {
+            let mut report = report.clone();
+            tamper(&mut report);
+            assert!(report.verify_signature(&public_key).is_err(), "{}", field);
+        }
+    }
+
+    #[test]
+    fn redirect_parse() {
+        let valid = [
+            ("alice.crypto", "alice.crypto", DEFAULT_REDIRECT_STATUS),
+            ("302 alice.crypto", "alice.crypto", 302),
+            ("  308   https://example.com/alice ", "https://example.com/alice", 308),
+            ("303\thttps://example.com", "https://example.com", 303),
+            ("307 alice.ever", "alice.ever", 307),
+        ];
+        for (value, target, status) in valid {
+            assert_eq!(Redirect::parse(value).unwrap(), Redirect { target: target.to_string(), status }, "{}", value);
+        }
+        for value in ["", "  ", "200 alice.crypto", "404 alice.crypto", "99999 alice.crypto", "302 alice crypto"] {
+            assert!(Redirect::parse(value).is_err(), "{}", value);
+        }
+        let target_domain = |value: &str| Redirect::parse(value).unwrap().target_domain();
+        assert_eq!(target_domain("Alice.Crypto"), Some("alice.crypto".to_string()));
+        assert_eq!(target_domain("alice.crypto/"), Some("alice.crypto".to_string()));
+        assert_eq!(target_domain("301 https://Example.com/alice"), Some("example.com".to_string()));
+        assert_eq!(target_domain("alice.crypto/path"), None);
+    }
+
+    #[test]
+    fn weighted_target_parse() {
+        let target = |url: &str, priority, weight| WeightedTarget { url: url.to_string(), priority, weight };
+        let valid = [
+            ("https://a.example", target("https://a.example", 0, 1)),
+            (" https://a.example;weight=3 ", target("https://a.example", 0, 3)),
+            ("https://a.example;priority=1", target("https://a.example", 1, 1)),
+            ("https://a.example;priority=2;weight=5", target("https://a.example", 2, 5)),
+            ("https://a.example;weight=5;priority=2", target("https://a.example", 2, 5)),
+            ("https://a.example/?a=1;b=2;weight=2", target("https://a.example/?a=1;b=2", 0, 2)),
+        ];
+        for (value, expected) in valid {
+            assert_eq!(WeightedTarget::parse(value).unwrap(), expected, "{}", value);
+        }
+        for value in ["", ";weight=3", "https://a.example;weight=x", "https://a.example;priority=-1"] {
+            assert!(WeightedTarget::parse(value).is_err(), "{}", value);
+        }
+        let value = "https://a.example;weight=3 https://b.example https://backup.example;priority=1";
+        let targets = WeightedTarget::parse_list(value).unwrap();
+        assert_eq!(targets, vec![
+            target("https://a.example", 0, 3),
+            target("https://b.example", 0, 1),
+            target("https://backup.example", 1, 1),
+        ]);
+        assert_eq!(WeightedTarget::join(&targets), value);
+        assert!(WeightedTarget::parse_list("https://a.example ;weight=3").is_err());
+    }
+
+    #[test]
+    fn structured_target_expand() {
+        let cases = [
+            (r#"{"host": "example.com", "port": 8080, "path": "/app", "proto": "https"}"#, AddressTag::Web2, "https://example.com:8080/app"),
+            (r#"{"host": "example.com", "path": "app"}"#, AddressTag::Web2, "https://example.com/app"),
+            (r#" {"host": "example.com", "proto": "HTTP://"} "#, AddressTag::Web2, "http://example.com"),
+            (r#"{"host": "2001:db8::1", "port": 443}"#, AddressTag::Web2, "https://[2001:db8::1]:443"),
+            (r#"{"host": "[2001:db8::1]"}"#, AddressTag::Web2, "https://[2001:db8::1]"),
+            (r#"{"host": "abcdefghijklmnop.onion"}"#, AddressTag::Tor, "http://abcdefghijklmnop.onion"),
+            (r#"{"host": "abcdefghijklmnop.onion", "proto": "https"}"#, AddressTag::Tor, "https://abcdefghijklmnop.onion"),
+            // not structured targets, returned as is
+            ("example.com", AddressTag::Web2, "example.com"),
+            ("https://example.com/app", AddressTag::Web2, "https://example.com/app"),
+            (r#"{"host": ""}"#, AddressTag::Web2, r#"{"host": ""}"#),
+            (r#"{"host": "example.com/app"}"#, AddressTag::Web2, r#"{"host": "example.com/app"}"#),
+            (r#"{"port": 8080}"#, AddressTag::Web2, r#"{"port": 8080}"#),
+            (r#"{"host": "example.com""#, AddressTag::Web2, r#"{"host": "example.com""#),
+            (r#"{"host": "example.com"}"#, AddressTag::Ipfs, r#"{"host": "example.com"}"#),
+        ];
+        for (value, tag, expected) in cases {
+            assert_eq!(StructuredTarget::expand(value.to_string(), &tag), expected, "{} {:?}", value, tag);
+        }
+    }
+
+    #[test]
+    fn coin_types() {
+        for (value, coin) in [("ETH", CoinType::ETH), ("btc", CoinType::BTC), ("Ever", CoinType::EVER), ("60", CoinType::ETH), ("9999", CoinType(9999))] {
+            assert_eq!(value.parse::<CoinType>().unwrap(), coin, "{}", value);
+        }
+        for value in ["", "XYZ", "-1", "eth "] {
+            assert!(value.parse::<CoinType>().is_err(), "{}", value);
+        }
+        assert_eq!(CoinType::TON.to_string(), "TON(607)");
+        assert_eq!(CoinType(9999).to_string(), "coin(9999)");
+        assert_eq!(CoinType::from(1815), CoinType::ADA);
+        assert_eq!(CoinType::evm_chain(137), CoinType(0x8000_0089));
+        assert_eq!(CoinType::evm_chain(137).ticker(), None);
+        for coin in [CoinType::ETH, CoinType::ETC, CoinType::evm_chain(137), CoinType::evm_chain(0)] {
+            assert!(coin.is_evm(), "{}", coin);
+        }
+        for coin in [CoinType::BTC, CoinType::EVER, CoinType::BNB, CoinType(9999)] {
+            assert!(!coin.is_evm(), "{}", coin);
+        }
+    }
+
+    #[test]
+    fn http_dates() {
+        let dates = [
+            (0, "Thu, 01 Jan 1970 00:00:00 GMT"),
+            (784111777, "Sun, 06 Nov 1994 08:49:37 GMT"),
+            (951782400, "Tue, 29 Feb 2000 00:00:00 GMT"),
+            (1709164800, "Thu, 29 Feb 2024 00:00:00 GMT"),
+            (4102444799, "Thu, 31 Dec 2099 23:59:59 GMT"),
+        ];
+        for (timestamp, date) in dates {
+            assert_eq!(http_date(timestamp), date, "{}", timestamp);
+        }
+    }
+
+    #[test]
+    fn base64_vectors() {
+        let vectors = [("", ""), ("f", "Zg=="), ("fo", "Zm8="), ("foo", "Zm9v"), ("foob", "Zm9vYg=="), ("fooba", "Zm9vYmE="), ("foobar", "Zm9vYmFy")];
+        for (bytes, encoded) in vectors {
+            assert_eq!(base64(bytes.as_bytes()), encoded, "{}", bytes);
+        }
+        assert_eq!(base64(&[0xfb, 0xff, 0xbf]), "+/+/");
+    }
+
+    #[test]
+    fn cache_headers() {
+        let headers = CacheHeaders {
+            cache_control: Some("public, max-age=60".to_string()),
+            etag: Some("\"abc\"".to_string()),
+            last_modified: Some(784111777),
+        };
+        assert_eq!(headers.headers(), vec![
+            ("Cache-Control", "public, max-age=60".to_string()),
+            ("ETag", "\"abc\"".to_string()),
+            ("Last-Modified", "Sun, 06 Nov 1994 08:49:37 GMT".to_string()),
+        ]);
+        assert!(CacheHeaders::default().headers().is_empty());
+        for if_none_match in ["\"abc\"", "W/\"abc\"", "\"x\", \"abc\"", "*"] {
+            assert!(headers.matches_etag(if_none_match), "{}", if_none_match);
+        }
+        for if_none_match in ["", "\"abcd\"", "abc"] {
+            assert!(!headers.matches_etag(if_none_match), "{}", if_none_match);
+        }
+        assert!(!CacheHeaders::default().matches_etag("*"));
+    }
+}
@@ -1,17 +1,106 @@
-use crate::models::{AddressTag, ResolvedDomainData};
+use crate::models::{AddressTag, AmbiguityPolicy, CoinType, ContentDescriptor, DnsRecords, DomainRecords, DomainSnapshot, DomainSuggestion, DualPrecedence, DualResolution, ReportSignature, ResolutionCandidate, ResolutionChange, ResolutionMode, ResolutionReport, ResolveOptions, ResolvedDomainData, ResolverCapabilities, ReverseCandidate, Registry, ResolverConfig, Tld, ResolverStats, WarmState, CachedResult, WeightedTarget, EndpointSelection, EndpointStats};
+#[cfg(feature = "evername")]
+use crate::models::{ExpiryEvent, ContentRange, SiteArchive, SiteAsset, SiteManifest, TlsRecord};
+use crate::error::{panic_message, ResolverError};
+use crate::resolver::overrides::{DomainOverrides, OverrideEntry};
+use crate::resolver::policy::ResolutionPolicy;
+use crate::resolver::rewrite::ResultRewriter;
+use crate::resolver::pipeline::{Pipeline, PipelineState, Stage};
+use crate::resolver::context::ResolverContext;
+use crate::resolver::dns::DnsBackend;
+#[cfg(any(feature = "evername", feature = "ud"))]
+use crate::resolver::lazy::Lazy;
+use crate::resolver::tld_index::{TldIndex, TldTarget};
+use crate::resolver::stats::StatsCollector;
+use crate::resolver::stale::StaleFallback;
+use crate::resolver::pinning::PinnedTargets;
+use crate::resolver::expiry::{CacheExpiry, ExpiringCache, Lookup};
+use crate::resolver::confusables::ConfusableDetector;
+use crate::resolver::targets::{TargetSelector, WeightedSelector};
+use crate::resolver::health::GatewayHealth;
+use crate::resolver::trace::Trace;
+#[cfg(feature = "evername")]
 use crate::resolver::evername::EvernameResolver;
-use anyhow::{anyhow, Result};
+#[cfg(feature = "evername")]
+use crate::models::WeightedEndpoint;
+use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
+use ed25519_dalek::{Signer, SigningKey};
+use futures::{future, stream, FutureExt, Stream};
 use log::debug;
-use mini_moka::sync::Cache;
+use std::any::Any;
+use std::collections::HashSet;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
 use std::time::Duration;
+#[cfg(feature = "ud")]
 use ud::UnstoppableDomainsResolver;
+#[cfg(feature = "ens")]
+use ens::EnsResolver;
+#[cfg(feature = "ud")]
+use crate::resolver::avatar::AvatarUri;
 
+#[cfg(feature = "ud")]
 pub mod ud;
+#[cfg(feature = "evername")]
 pub mod evername;
+#[cfg(feature = "ens")]
+pub mod ens;
 pub mod builder;
+#[cfg(feature = "plugins")]
+pub mod plugin;
+#[cfg(feature = "grpc-server")]
+pub mod grpc;
+#[cfg(feature = "jsonrpc-server")]
+pub mod jsonrpc;
+#[cfg(feature = "otel")]
+pub mod otel;
+pub mod dns;
+pub mod synthetic;
+#[cfg(any(feature = "evername", feature = "ud", feature = "ens"))]
+pub mod limit;
+#[cfg(feature = "evername")]
 mod abi;
-mod ipfs;
+#[cfg(feature = "evername")]
+mod endpoints;
+#[cfg(any(feature = "evername", feature = "ud", feature = "ens"))]
+mod avatar;
+#[cfg(any(feature = "evername", feature = "ud", feature = "ens"))]
+mod http;
+pub(crate) mod ipfs;
+#[cfg(feature = "evername")]
+mod car;
+#[cfg(feature = "evername")]
+mod range;
+#[cfg(feature = "evername")]
+mod sniff;
+#[cfg(feature = "evername")]
+mod state_cache;
+#[cfg(feature = "evername")]
+mod invalidation;
+#[cfg(feature = "evername")]
+mod renewal;
+mod health;
+mod stale;
+mod pinning;
+mod confusables;
+mod trace;
+mod tld_index;
+#[cfg(any(feature = "evername", feature = "ud"))]
+mod lazy;
+pub(crate) mod normalize;
+pub mod targets;
+mod stats;
+pub mod overrides;
+pub mod policy;
+pub mod rewrite;
+pub mod pipeline;
+pub mod context;
+pub mod layer;
+pub mod expiry;
 
 
 /// Universal trait for resolving web3 domains.
@@ -20,6 +109,83 @@ pub trait Resolver {
     
     /// Resolves a domain name to its corresponding address, maybe content and tag.
     async fn resolve(&self, domain: &str) -> Result<(ResolvedDomainData, AddressTag)>;
+
+    /// Name of the resolver, reported as the source of resolution candidates.
+    fn name(&self) -> &str {
+        "custom"
+    }
+
+    /// Resolves all matching records of the domain, in resolver priority order.
+    /// Default implementation returns the single result of `resolve`.
+    async fn resolve_all(&self, domain: &str) -> Result<Vec<ResolutionCandidate>> {
+        let (data, tag) = self.resolve(domain).await?;
+        Ok(vec![ResolutionCandidate { source: self.name().to_string(), data, tag }])
+    }
+
+    /// Resolves the domain into the unified descriptor of its target.
+    /// Default implementation describes the result of `resolve`.
+    async fn resolve_descriptor(&self, domain: &str) -> Result<ContentDescriptor> {
+        let (data, tag) = self.resolve(domain).await?;
+        Ok(data.descriptor(tag))
+    }
+
+    /// Supported TLDs, record kinds and lookups, so `Web3DomainResolver` routes feature-specific calls
+    /// (e.g. `resolve_address`) only to capable resolvers. Default implementation supports forward resolution only.
+    fn capabilities(&self) -> ResolverCapabilities {
+        ResolverCapabilities {
+            record_kinds: AddressTag::resolvable(),
+            ..ResolverCapabilities::default()
+        }
+    }
+
+    /// Resolves address of the domain for the coin, `None` if it is not set.
+    /// Called only for resolvers reporting `ResolverCapabilities::addresses`, default implementation returns `None`.
+    async fn resolve_address(&self, _domain: &str, _coin: CoinType) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// Looks the address up back into its primary domain, `chain_hint` is the chain of the address if known.
+    /// Called only for resolvers reporting `ResolverCapabilities::reverse`, default implementation returns `None`.
+    async fn reverse(&self, _address: &str, _chain_hint: Option<CoinType>) -> Result<Option<String>> {
+        Ok(None)
+    }
+}
+
+/// Default bound of distinct `ResolveOptions` whose results are cached.
+pub(crate) const MAX_CACHED_OPTION_VARIANTS: usize = 64;
+/// Max number of domains returned by `Web3DomainResolver::search`.
+pub const MAX_SEARCH_RESULTS: usize = 20;
+
+/// Ranking hook for aggregated results, candidates with higher rank are preferred.
+pub type RankingFn = Arc<dyn Fn(&ResolutionCandidate) -> i64 + Send + Sync>;
+
+// search of a single resolver with the resolver name
+type SourceSearch<'a> = future::BoxFuture<'a, (&'static str, Result<Vec<String>>)>;
+
+/// Additional resolver registered for a set of TLDs, e.g. from a runtime plugin.
+#[derive(Clone)]
+pub(crate) struct CustomResolver {
+    tlds: Vec<String>,
+    resolver: Arc<dyn Resolver + Send + Sync>,
+}
+
+impl CustomResolver {
+    pub(crate) fn new(tlds: Vec<String>, resolver: Arc<dyn Resolver + Send + Sync>) -> Self {
+        Self { tlds, resolver }
+    }
+
+}
+
+/// Resolver selected for a domain by its TLD.
+enum Route<'a> {
+    #[cfg(feature = "evername")]
+    Evername,
+    #[cfg(feature = "ud")]
+    UnstoppableDomains,
+    #[cfg(feature = "ens")]
+    Ens,
+    Custom(&'a CustomResolver),
+    NonWeb3,
 }
 
 /// Main resolver that combines all supported methods for resolving web3 domains.
@@ -27,16 +193,19 @@ pub trait Resolver {
 /// It also supports caching of resolved domains to improve performance.
 /// 
 /// # Example
-/// ```
+/// ```no_run
+/// # #[cfg(all(feature = "evername", feature = "ud"))]
+/// # async fn example() -> anyhow::Result<()> {
 /// use web3_resolver::{Resolver, Web3DomainResolver};
+/// use web3_resolver::Url;
 ///
 /// // Create domains resolver
-/// let domain_resolver = Web3DomainResolver::default();
+/// let domain_resolver = Web3DomainResolver::default().await?;
 /// // Equivalent to:
 /// let domain_resolver = Web3DomainResolver::builder()
 ///     .use_cache(true)
 ///     .cache_ttl_seconds(5 * 30)
-///     .with_eversacale_endpoint("https://jrpc.everwallet.net/rpc")
+///     .with_everscale_endpoints(vec![Url::parse("https://jrpc.everwallet.net/rpc")?])
 ///     .with_unstoppable_domain_base_url("https://api.unstoppabledomains.com")
 ///     .build().await?;
 ///
@@ -44,11 +213,69 @@ pub trait Resolver {
 /// let (resolved_data, address_tag) = domain_resolver.resolve("donate.chums.chat").await?;
 ///
 /// println!("Resolved domain data: {}, with tag {}", resolved_data, address_tag);
+/// # Ok(())
+/// # }
 /// ```
 pub struct Web3DomainResolver {
-    ud_resolver: UnstoppableDomainsResolver,
-    evername_resolver: EvernameResolver,
-    dns_cache: Option<Cache<String, (ResolvedDomainData, AddressTag)>>,
+    #[cfg(feature = "ud")]
+    ud_resolver: Lazy<UnstoppableDomainsResolver>,
+    #[cfg(feature = "evername")]
+    evername_resolver: Lazy<EvernameResolver>,
+    #[cfg(feature = "ens")]
+    ens_resolver: EnsResolver,
+    custom_resolvers: Vec<CustomResolver>,
+    dns_cache: Option<ExpiringCache<(ResolvedDomainData, AddressTag)>>,
+    // cache key suffixes of options passed to `resolve_with`, bounded by max_option_variants
+    option_variants: Mutex<HashSet<String>>,
+    max_option_variants: usize,
+    tld_index: RwLock<TldIndex>,
+    mode: ResolutionMode,
+    ranking: Option<RankingFn>,
+    ambiguity: AmbiguityPolicy,
+    // source names of resolvers preferred for TLDs claimed by several resolvers
+    precedence: Vec<String>,
+    explain: bool,
+    // reports include references of the chain states read
+    record_state_refs: bool,
+    // reports include BOCs of the records the result is decoded from
+    record_raw_records: bool,
+    // max redirects followed, redirects are returned as is if 0
+    max_redirects: usize,
+    // max aliases to other web3 domains followed, aliases are returned as is if 0
+    max_aliases: usize,
+    overrides: DomainOverrides,
+    policies: Vec<Arc<dyn ResolutionPolicy>>,
+    rewriters: Vec<Arc<dyn ResultRewriter>>,
+    pipeline: Pipeline,
+    signing_key: Option<SigningKey>,
+    // resolves non-web3 domains into A and AAAA records instead of passing them through
+    dns_fallback: Option<Arc<dyn DnsBackend>>,
+    // fails non-web3 domains with `UnsupportedTld` instead of passing them through
+    strict: bool,
+    dual_resolution: DualResolution,
+    // Evername resolvers of quorum endpoints by endpoint URL
+    #[cfg(feature = "evername")]
+    quorum: Vec<(String, EvernameResolver)>,
+    // additional Evername-style deployments, also registered as custom resolvers of their TLDs
+    #[cfg(feature = "evername")]
+    evername_networks: Vec<Arc<EvernameResolver>>,
+    // configured endpoints reported by `config`, lazy resolvers may not be created yet
+    #[cfg(feature = "evername")]
+    everscale_endpoints: Vec<WeightedEndpoint>,
+    #[cfg(feature = "ud")]
+    ud_base_url: String,
+    #[cfg(feature = "ud")]
+    ud_sandbox: bool,
+    stats: StatsCollector,
+    stale_fallback: Option<StaleFallback>,
+    pinned_targets: Option<PinnedTargets>,
+    confusables: ConfusableDetector,
+    target_selector: Arc<dyn TargetSelector>,
+    // IPFS gateways IPFS targets are offered on, see `DomainResolverBuilder::with_ipfs_gateways`
+    ipfs_gateways: Vec<String>,
+    gateway_health: GatewayHealth,
+    cancellation: CancellationToken,
+    tasks: TaskTracker,
 }
 
 /// Creation of the Web3DomainResolver is done through the builder pattern.
@@ -61,25 +288,1801 @@ impl Web3DomainResolver {
     
     /// Creates a new instance of the Web3DomainResolver with default settings.
     pub async fn default() -> Result<Self> {
+        #[cfg(feature = "ud")]
         let ud_resolver = UnstoppableDomainsResolver::default().await?;
+        #[cfg(feature = "evername")]
         let evername_resolver = EvernameResolver::default()?;
-        let dns_cache = Some(Cache::builder().time_to_live(Duration::from_secs(5 * 60)).build());
-        Ok(Self {
-            ud_resolver,
-            evername_resolver,
-            dns_cache
-        })
+        #[cfg(feature = "ens")]
+        let ens_resolver = EnsResolver::default()?;
+        let dns_cache = Some(ExpiringCache::new(Duration::from_secs(5 * 60), CacheExpiry::default()));
+        Ok(Self::new(
+            #[cfg(feature = "ud")]
+            Lazy::ready(ud_resolver),
+            #[cfg(feature = "evername")]
+            Lazy::ready(evername_resolver),
+            #[cfg(feature = "ens")]
+            ens_resolver,
+            Vec::new(),
+            dns_cache,
+        ))
     }
     
     /// Creates a new instance of the Web3DomainResolver with the given resolvers and cache.
-    pub(crate) fn new(ud_resolver: UnstoppableDomainsResolver, 
-                      evername_resolver: EvernameResolver,
-                      dns_cache: Option<Cache<String, (ResolvedDomainData, AddressTag)>>) -> Self {
-        Self {
+    pub(crate) fn new(#[cfg(feature = "ud")] ud_resolver: Lazy<UnstoppableDomainsResolver>,
+                      #[cfg(feature = "evername")] evername_resolver: Lazy<EvernameResolver>,
+                      #[cfg(feature = "ens")] ens_resolver: EnsResolver,
+                      custom_resolvers: Vec<CustomResolver>,
+                      dns_cache: Option<ExpiringCache<(ResolvedDomainData, AddressTag)>>) -> Self {
+        let resolver = Self {
+            #[cfg(feature = "ud")]
             ud_resolver,
+            #[cfg(feature = "evername")]
             evername_resolver,
-            dns_cache
+            #[cfg(feature = "ens")]
+            ens_resolver,
+            custom_resolvers,
+            dns_cache,
+            option_variants: Mutex::new(HashSet::new()),
+            max_option_variants: MAX_CACHED_OPTION_VARIANTS,
+            tld_index: RwLock::new(TldIndex::default()),
+            mode: ResolutionMode::default(),
+            ranking: None,
+            ambiguity: AmbiguityPolicy::default(),
+            precedence: Vec::new(),
+            explain: false,
+            record_state_refs: false,
+            record_raw_records: false,
+            max_redirects: 0,
+            max_aliases: 0,
+            overrides: DomainOverrides::default(),
+            policies: Vec::new(),
+            rewriters: Vec::new(),
+            pipeline: Pipeline::default(),
+            signing_key: None,
+            dns_fallback: None,
+            strict: false,
+            dual_resolution: DualResolution::default(),
+            #[cfg(feature = "evername")]
+            quorum: Vec::new(),
+            #[cfg(feature = "evername")]
+            evername_networks: Vec::new(),
+            #[cfg(feature = "evername")]
+            everscale_endpoints: vec![WeightedEndpoint { url: evername::EVERSCALE_RPC_ENDPOINT.to_string(), weight: 1 }],
+            #[cfg(feature = "ud")]
+            ud_base_url: ud::UD_BASE_URL.to_string(),
+            #[cfg(feature = "ud")]
+            ud_sandbox: false,
+            stats: StatsCollector::default(),
+            stale_fallback: None,
+            pinned_targets: None,
+            confusables: ConfusableDetector::default(),
+            target_selector: Arc::new(WeightedSelector::default()),
+            ipfs_gateways: Vec::new(),
+            gateway_health: GatewayHealth::new(0, EndpointSelection::default()),
+            cancellation: CancellationToken::new(),
+            tasks: TaskTracker::new(),
+        };
+        resolver.reindex_tlds();
+        resolver
+    }
+
+    /// Resolves all matching records of the domain with their source resolver and tag.
+    /// Candidates are ordered by the ranking hook if set, otherwise by resolver priority.
+    /// Aggregated results are never cached.
+    pub async fn resolve_all(&self, domain: &str) -> Result<Vec<ResolutionCandidate>> {
+        self.resolve_all_traced(&normalize::domain_name(domain), &[], &mut Trace::disabled()).await
+    }
+
+    /// Resolves all candidates with the resolvers of the given source names, all resolvers if empty.
+    async fn resolve_all_traced(&self, domain: &str, sources: &[String], trace: &mut Trace) -> Result<Vec<ResolutionCandidate>> {
+        let candidates = self.resolve_all_unrewritten(domain, sources, trace).await?;
+        Ok(candidates.into_iter()
+            .map(|candidate| {
+                let (data, tag) = self.apply_rewriters(domain, candidate.data, candidate.tag, trace);
+                ResolutionCandidate { source: candidate.source, data, tag }
+            })
+            .collect())
+    }
+
+    async fn resolve_all_unrewritten(&self, domain: &str, sources: &[String], trace: &mut Trace) -> Result<Vec<ResolutionCandidate>> {
+        if let Some((data, tag)) = self.check_override(domain, trace)? {
+            return Ok(vec![ResolutionCandidate { source: "override".to_string(), data, tag }]);
+        }
+        if let Some((data, tag)) = self.apply_policies_before(domain, trace)? {
+            return Ok(vec![ResolutionCandidate { source: "policy".to_string(), data, tag }]);
+        }
+        self.prepare_route(domain).await?;
+        let routes = self.routes_for(domain, sources, trace);
+        if routes.is_empty() {
+            return Err(anyhow!("Domain {} is not handled by resolvers {}", domain, sources.join(", ")));
+        }
+        self.check_ambiguity(domain, &routes)?;
+        let route_trace = trace.fork();
+        let results = future::join_all(routes.iter().map(|route| self.resolve_route_all(route, domain, &route_trace))).await;
+        // failure of one of several resolvers claiming the TLD is tolerated
+        let mut candidates = Vec::new();
+        let mut first_error = None;
+        for result in results {
+            match result {
+                Ok(route_candidates) => candidates.extend(route_candidates),
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                },
+            }
+        }
+        if let (true, Some(e)) = (candidates.is_empty(), first_error) {
+            return Err(e);
+        }
+        // candidates blocked by a policy are dropped, the domain is blocked only if none is left
+        let mut allowed = Vec::with_capacity(candidates.len());
+        let mut blocked = None;
+        for candidate in candidates {
+            match self.apply_policies_after(domain, candidate.data, candidate.tag, trace) {
+                Ok((data, tag)) => allowed.push(ResolutionCandidate { source: candidate.source, data, tag }),
+                Err(e) => blocked = Some(e),
+            }
+        }
+        let mut candidates = match (allowed.is_empty(), blocked) {
+            (true, Some(e)) => return Err(e),
+            _ => allowed,
+        };
+        if let Some(ranking) = &self.ranking {
+            // stable sort keeps resolver priority for equally ranked candidates
+            candidates.sort_by_key(|candidate| std::cmp::Reverse(ranking(candidate)));
+        }
+        debug!("Host {} resolved into {} candidates", domain, candidates.len());
+        Ok(candidates)
+    }
+
+    /// Registered domains matching the prefix, for typo suggestions and "did you mean" of unresolvable names.
+    /// Evername (see `DomainResolverBuilder::with_evername_search_indexer`) and Unstoppable Domains are searched
+    /// in parallel, failed sources are skipped unless all of them fail. At most `MAX_SEARCH_RESULTS` domains are returned.
+    #[cfg_attr(not(any(feature = "evername", feature = "ud")), allow(unused_variables, unused_mut))]
+    pub async fn search(&self, prefix: &str) -> Result<Vec<DomainSuggestion>> {
+        let mut searches: Vec<SourceSearch<'_>> = Vec::new();
+        #[cfg(feature = "evername")]
+        searches.push(async {
+            let result = async { self.evername().await?.search(prefix, MAX_SEARCH_RESULTS).await }.await;
+            ("evername", result)
+        }.boxed());
+        #[cfg(feature = "ud")]
+        searches.push(async {
+            let result = async { self.ud().await?.search(prefix, MAX_SEARCH_RESULTS).await }.await;
+            ("unstoppable-domains", result)
+        }.boxed());
+        if searches.is_empty() {
+            return Err(anyhow!("None of the enabled resolvers supports search"));
+        }
+        let mut suggestions: Vec<DomainSuggestion> = Vec::new();
+        let mut first_error = None;
+        let mut succeeded = false;
+        for (source, result) in future::join_all(searches).await {
+            match result {
+                Ok(domains) => {
+                    succeeded = true;
+                    for domain in domains {
+                        if !suggestions.iter().any(|suggestion| suggestion.domain == domain) {
+                            suggestions.push(DomainSuggestion { domain, source: source.to_string() });
+                        }
+                    }
+                },
+                Err(e) => {
+                    debug!("Search of {} in {} failed: {}", prefix, source, e);
+                    first_error.get_or_insert(e);
+                },
+            }
+        }
+        match first_error {
+            Some(e) if !succeeded => Err(e),
+            _ => {
+                suggestions.truncate(MAX_SEARCH_RESULTS);
+                Ok(suggestions)
+            },
+        }
+    }
+
+    /// Removes the domain from the resolution cache, Evername domains also from the account states cache.
+    pub fn invalidate(&self, domain: &str) {
+        if let Some(cache) = &self.dns_cache {
+            let key = normalize::cache_key(domain);
+            if let Ok(option_variants) = self.option_variants.lock() {
+                for suffix in option_variants.iter() {
+                    cache.invalidate(&format!("{}{}", key, suffix));
+                }
+            }
+            cache.invalidate(&key);
+        }
+        if let Some(fallback) = &self.stale_fallback {
+            fallback.invalidate(&normalize::cache_key(domain));
+        }
+        if let Some(pinned_targets) = &self.pinned_targets {
+            pinned_targets.invalidate(&normalize::cache_key(domain));
+        }
+        #[cfg(feature = "ud")]
+        if let Some(ud_resolver) = self.ud_resolver.get() {
+            ud_resolver.invalidate_profile(&normalize::domain_name(domain));
+        }
+        #[cfg(feature = "evername")]
+        if let Some(evername) = self.evername_of(&normalize::domain_name(domain)) {
+            evername.invalidate(domain);
+        }
+    }
+
+    /// Fetches the Unstoppable Domains TLDs again and rebuilds the TLD routing table,
+    /// so a resolver shared behind `Arc` picks up newly supported TLDs without a rebuild.
+    /// Resolutions in progress keep using the previous table.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # async fn example() -> anyhow::Result<()> {
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    /// use web3_resolver::Web3DomainResolver;
+    ///
+    /// let domain_resolver = Arc::new(Web3DomainResolver::default().await?);
+    /// let refresher = domain_resolver.clone();
+    /// tokio::spawn(async move {
+    ///     loop {
+    ///         tokio::time::sleep(Duration::from_secs(24 * 60 * 60)).await;
+    ///         let _ = refresher.refresh_tlds().await;
+    ///     }
+    /// });
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn refresh_tlds(&self) -> Result<()> {
+        #[cfg(feature = "ud")]
+        self.ud().await?.update_tlds().await?;
+        self.reindex_tlds();
+        Ok(())
+    }
+
+    /// Watches the domain for resolution changes by polling it every `interval`.
+    /// The stream yields an event each time the resolved data or tag differs from the previous
+    /// successful resolution, and the cached entry of the domain is invalidated.
+    /// Failed resolutions (e.g. network errors) are skipped. The stream ends on `shutdown`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # async fn example() -> anyhow::Result<()> {
+    /// use std::time::Duration;
+    /// use futures::StreamExt;
+    /// use web3_resolver::Web3DomainResolver;
+    ///
+    /// let domain_resolver = Web3DomainResolver::default().await?;
+    /// let mut changes = Box::pin(domain_resolver.watch("maksimzubov.ever", Duration::from_secs(60)));
+    /// while let Some(change) = changes.next().await {
+    ///     println!("{} now resolves into {}", change.domain, change.current.0);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn watch<'a>(&'a self, domain: &str, interval: Duration) -> impl Stream<Item = ResolutionChange> + 'a {
+        let domain = normalize::domain_name(domain);
+        stream::unfold((None, true), move |(previous, first): (Option<(ResolvedDomainData, AddressTag)>, bool)| {
+            let domain = domain.clone();
+            async move {
+                let mut previous = previous;
+                let mut first = first;
+                loop {
+                    if !first {
+                        tokio::select! {
+                            _ = self.cancellation.cancelled() => return None,
+                            _ = tokio::time::sleep(interval) => {},
+                        }
+                    }
+                    first = false;
+                    let current = match self.resolve_uncached(&domain).await {
+                        Ok(current) => current,
+                        Err(e) => {
+                            debug!("Watch of {} failed to resolve: {:#}", domain, e);
+                            continue;
+                        }
+                    };
+                    match &previous {
+                        None => previous = Some(current),
+                        Some(prev) if *prev == current => {},
+                        Some(_) => {
+                            debug!("Host {} resolution changed into: {} with tag {}", domain, current.0, current.1);
+                            self.invalidate(&domain);
+                            let change = ResolutionChange {
+                                domain: domain.clone(),
+                                previous: previous.take(),
+                                current: current.clone(),
+                            };
+                            return Some((change, (Some(current), false)));
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    /// Stream of expiry events of the `.ever` domains for registrar and ops tooling: expiry dates are read
+    /// from the certificates every `interval`, domains expiring within `warn_before` are reported once per
+    /// expiry date, as well as expired and renewed ones. Failed reads are skipped. The stream ends on `shutdown`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # async fn example() -> anyhow::Result<()> {
+    /// use std::time::Duration;
+    /// use futures::StreamExt;
+    /// use web3_resolver::Web3DomainResolver;
+    ///
+    /// let domain_resolver = Web3DomainResolver::default().await?;
+    /// let day = Duration::from_secs(24 * 60 * 60);
+    /// let mut events = Box::pin(domain_resolver.watch_expiry(&["maksimzubov.ever"], 30 * day, day));
+    /// while let Some(event) = events.next().await {
+    ///     println!("{:?}", event);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "evername")]
+    pub fn watch_expiry<'a>(&'a self, domains: &[&str], warn_before: Duration, interval: Duration) -> impl Stream<Item = ExpiryEvent> + 'a {
+        let tracker = renewal::ExpiryTracker::new(domains, warn_before);
+        stream::unfold((tracker, std::collections::VecDeque::new(), true), move |(mut tracker, mut pending, mut first)| async move {
+            loop {
+                if let Some(event) = pending.pop_front() {
+                    return Some((event, (tracker, pending, first)));
+                }
+                if !first {
+                    tokio::select! {
+                        _ = self.cancellation.cancelled() => return None,
+                        _ = tokio::time::sleep(interval) => {},
+                    }
+                }
+                first = false;
+                match self.evername().await {
+                    Ok(evername) => pending.extend(tracker.scan(evername).await),
+                    Err(e) => debug!("Expiry watch skipped the scan: {:#}", e),
+                }
+            }
+        })
+    }
+
+    /// Spawns background task watching expiry of the `.ever` domains as `watch_expiry` does
+    /// and passing the events to the callback. The task stops when the resolver is dropped or shut down.
+    #[cfg(feature = "evername")]
+    pub fn spawn_expiry_watch<F>(self: &Arc<Self>, domains: &[&str], warn_before: Duration, interval: Duration, on_event: F) -> JoinHandle<()>
+    where
+        F: Fn(ExpiryEvent) + Send + Sync + 'static,
+    {
+        let tracker = renewal::ExpiryTracker::new(domains, warn_before);
+        self.tasks.spawn(renewal::run(Arc::downgrade(self), tracker, interval, on_event, self.cancellation.child_token()))
+    }
+
+    /// Spawns background task that scans certificate contracts of cached Evername domains (of every deployment)
+    /// every `interval` and invalidates entries as soon as the contract gets new transactions
+    /// (record updates, transfers), so cached results do not live up to the full TTL after changes.
+    /// The task stops when the resolver is dropped or shut down. Requires cache to be enabled.
+    #[cfg(feature = "evername")]
+    pub fn spawn_cache_invalidation(self: &Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        self.tasks.spawn(invalidation::run(Arc::downgrade(self), interval, self.cancellation.child_token()))
+    }
+
+    /// Spawns background task refreshing domains whose stale results were served because the resolution
+    /// exceeded the budget (see `DomainResolverBuilder::resolution_budget`). Without it stale results
+    /// are refreshed by the next resolution fitting into the budget.
+    /// Returns `None` if the budget is not set or the task is already spawned.
+    pub fn spawn_stale_refresh(self: &Arc<Self>) -> Option<JoinHandle<()>> {
+        let receiver = self.stale_fallback.as_ref()?.take_receiver()?;
+        Some(self.tasks.spawn(stale::run(Arc::downgrade(self), receiver, self.cancellation.child_token())))
+    }
+
+    /// Returns the asset manifest of a multi-asset onchain `.ever` site, or `None` if the domain has no manifest,
+    /// so gateways can serve css, js and images of the site. Assets are loaded with `load_site_asset`.
+    #[cfg(feature = "evername")]
+    pub async fn site_manifest(&self, domain: &str) -> Result<Option<SiteManifest>> {
+        self.evername().await?.site_manifest(&normalize::domain_name(domain)).await
+    }
+
+    /// Returns TLS certificate or public key published in the record `1006` of a `.ever` domain, `None` if it is not set,
+    /// so gateways can authenticate the origin of the site end to end or pin its key.
+    #[cfg(feature = "evername")]
+    pub async fn tls_record(&self, domain: &str) -> Result<Option<TlsRecord>> {
+        self.evername().await?.tls_record(&normalize::domain_name(domain)).await
+    }
+
+    /// Loads content of a site manifest asset.
+    #[cfg(feature = "evername")]
+    pub async fn load_site_asset(&self, asset: &SiteAsset) -> Result<ResolvedDomainData> {
+        self.evername().await?.load_site_asset(asset).await
+    }
+
+    /// Packages the onchain `.ever` site (content and manifest assets) into a CARv1 archive with a UnixFS directory,
+    /// so operators can mirror on-chain sites to IPFS.
+    #[cfg(feature = "evername")]
+    pub async fn export_site_car(&self, domain: &str) -> Result<SiteArchive> {
+        self.evername().await?.export_site_car(&normalize::domain_name(domain)).await
+    }
+
+    /// Loads a byte range of the onchain contract content of a `.ever` domain for HTTP range requests,
+    /// so media stored on-chain can be scrubbed. `range_header` is the HTTP `Range` header value.
+    #[cfg(feature = "evername")]
+    pub async fn onchain_content_range(&self, domain: &str, range_header: Option<&str>) -> Result<ContentRange> {
+        self.evername().await?.content_range(&normalize::domain_name(domain), range_header).await
+    }
+
+    /// Loads a byte range of a site manifest asset stored in an Eversite contract.
+    #[cfg(feature = "evername")]
+    pub async fn load_site_asset_range(&self, asset: &SiteAsset, range_header: Option<&str>) -> Result<ContentRange> {
+        self.evername().await?.load_site_asset_range(asset, range_header).await
+    }
+
+    /// Returns address of the domain for the SLIP-44 coin, e.g. `CoinType::ETH` or `"btc".parse()?`:
+    /// ENS multicoin `addr` record (EVM coins only) or Unstoppable Domains `crypto.<TICKER>.address` record.
+    /// `None` if the address is not set or the naming system of the domain has no multi-chain addresses.
+    #[cfg_attr(not(any(feature = "ud", feature = "ens")), allow(unused_variables))]
+    pub async fn resolve_address(&self, domain: &str, coin: CoinType) -> Result<Option<String>> {
+        let domain = normalize::domain_name(domain);
+        if !policy::allows_lookup(&self.policies, &domain)? {
+            return Ok(None);
+        }
+        self.prepare_route(&domain).await?;
+        for route in self.routes(&domain, &mut Trace::disabled()) {
+            let Some(resolver) = self.route_resolver(&route).await? else {
+                continue;
+            };
+            if resolver.capabilities().addresses {
+                return resolver.resolve_address(&domain, coin).await;
+            }
+            debug!("Resolver {} of {} does not resolve addresses", route.source(), domain);
+        }
+        Ok(None)
+    }
+
+    /// Looks the address up back into domains with every resolver supporting reverse lookups in parallel,
+    /// e.g. ENS primary names of Ethereum addresses. `chain_hint` is the chain of the address if known,
+    /// resolvers of other chains skip it. Domains are returned with the resolver they came from, in resolver order.
+    /// Fails only if no domain is found and a resolver failed.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # async fn example() -> anyhow::Result<()> {
+    /// use web3_resolver::Web3DomainResolver;
+    /// use web3_resolver::models::CoinType;
+    ///
+    /// let domain_resolver = Web3DomainResolver::default().await?;
+    /// for candidate in domain_resolver.reverse("0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045", Some(CoinType::ETH)).await? {
+    ///     println!("{} (from {})", candidate.domain, candidate.source);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn reverse(&self, address: &str, chain_hint: Option<CoinType>) -> Result<Vec<ReverseCandidate>> {
+        let mut routes = Vec::new();
+        #[cfg(feature = "evername")]
+        routes.push(Route::Evername);
+        #[cfg(feature = "ud")]
+        routes.push(Route::UnstoppableDomains);
+        #[cfg(feature = "ens")]
+        routes.push(Route::Ens);
+        routes.extend(self.custom_resolvers.iter().map(Route::Custom));
+        let lookups = routes.iter().map(|route| async move {
+            let resolver = match self.route_resolver(route).await {
+                Ok(Some(resolver)) if resolver.capabilities().reverse => resolver,
+                Ok(_) => return (route.source(), Ok(None)),
+                Err(e) => return (route.source(), Err(e)),
+            };
+            (route.source(), resolver.reverse(address, chain_hint).await)
+        });
+        let mut candidates = Vec::new();
+        let mut first_error = None;
+        for (source, result) in future::join_all(lookups).await {
+            match result {
+                Ok(Some(domain)) if !matches!(policy::allows_lookup(&self.policies, &domain), Ok(true)) => {
+                    debug!("Reverse lookup of {} with {} found domain {} blocked or rewritten by a policy", address, source, domain);
+                },
+                Ok(Some(domain)) => candidates.push(ReverseCandidate { source, domain }),
+                Ok(None) => {},
+                Err(e) => {
+                    debug!("Reverse lookup of {} with {} failed: {:#}", address, source, e);
+                    first_error.get_or_insert(e);
+                },
+            }
+        }
+        match (candidates.is_empty(), first_error) {
+            (true, Some(e)) => Err(e),
+            _ => Ok(candidates),
+        }
+    }
+
+    /// Takes snapshot of the full resolution state of the domain: raw records (Evername cells as BOCs,
+    /// Unstoppable Domains profile JSON, ENS record values), decoded values, content hashes, the state reference
+    /// and the resolution result at the moment, for audits and disputes about what the name pointed to.
+    /// Resolution caches are bypassed, the Evername account state the records were read at is identified by its
+    /// last transaction lt. Domains of custom resolvers and non-web3 ones are not supported.
+    pub async fn snapshot(&self, domain: &str) -> Result<DomainSnapshot> {
+        let domain = normalize::domain_name(domain);
+        self.prepare_route(&domain).await?;
+        let route = self.routes(&domain, &mut Trace::disabled()).into_iter().next();
+        match route {
+            #[cfg(feature = "evername")]
+            Some(Route::Evername) => self.evername().await?.snapshot(&domain).await,
+            #[cfg(feature = "ud")]
+            Some(Route::UnstoppableDomains) => self.ud().await?.snapshot(&domain).await,
+            #[cfg(feature = "ens")]
+            Some(Route::Ens) => self.ens_resolver.snapshot(&domain).await,
+            _ => Err(anyhow!("Snapshots of {} are not supported", domain)),
+        }
+    }
+
+    /// Returns raw and decoded records of the domain without resolving it: Evername records (Eversite
+    /// contract records by the contract address), Unstoppable Domains profile records or ENS content records.
+    /// Cheaper than `snapshot`, cached Unstoppable Domains profiles are used.
+    /// Domains of custom resolvers and non-web3 ones are not supported.
+    pub async fn records(&self, domain: &str) -> Result<DomainRecords> {
+        let domain = normalize::domain_name(domain);
+        self.prepare_route(&domain).await?;
+        let unsupported = || anyhow!("Records of {} are not supported", domain);
+        let route = self.routes(&domain, &mut Trace::disabled()).into_iter().next().ok_or_else(unsupported)?;
+        let records = match route {
+            #[cfg(feature = "evername")]
+            Route::Evername => self.evername().await?.records(&domain).await,
+            #[cfg(feature = "ud")]
+            Route::UnstoppableDomains => self.ud().await?.records(&domain).await,
+            #[cfg(feature = "ens")]
+            Route::Ens => self.ens_resolver.records(&domain).await,
+            _ => Err(unsupported()),
+        }?;
+        Ok(DomainRecords { source: route.source(), domain, records })
+    }
+
+    /// Returns keys of the records the domain has populated, without fetching or decoding the values:
+    /// Evername record numbers (e.g. `1003`) or Unstoppable Domains record names (e.g. `crypto.ETH.address`).
+    /// Empty if the domain is not handled by Evername or Unstoppable Domains.
+    pub async fn record_keys(&self, domain: &str) -> Result<Vec<String>> {
+        let domain = normalize::domain_name(domain);
+        self.prepare_route(&domain).await?;
+        let Some(route) = self.routes(&domain, &mut Trace::disabled()).into_iter().next() else {
+            return Ok(Vec::new());
+        };
+        match route {
+            #[cfg(feature = "evername")]
+            Route::Evername => Ok(self.evername().await?.record_keys(&domain).await?
+                .into_iter()
+                .map(|key| key.to_string())
+                .collect()),
+            #[cfg(feature = "ud")]
+            Route::UnstoppableDomains => self.ud().await?.record_keys(&domain).await,
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Returns TXT-like records of the domain, e.g. domain verification strings: Evername records `3000`-`3099`
+    /// (see `TXT_RECORD_KEYS`) in key order or Unstoppable Domains `dns.TXT` record.
+    /// Empty if the domain is not handled by Evername or Unstoppable Domains.
+    pub async fn txt_records(&self, domain: &str) -> Result<Vec<String>> {
+        let domain = normalize::domain_name(domain);
+        if !policy::allows_lookup(&self.policies, &domain)? {
+            return Ok(Vec::new());
+        }
+        self.prepare_route(&domain).await?;
+        let Some(route) = self.routes(&domain, &mut Trace::disabled()).into_iter().next() else {
+            return Ok(Vec::new());
+        };
+        match route {
+            #[cfg(feature = "evername")]
+            Route::Evername => self.evername().await?.txt_records(&domain).await,
+            #[cfg(feature = "ud")]
+            Route::UnstoppableDomains => self.ud().await?.txt_records(&domain).await,
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Checks that the challenge string is published in a TXT record of the domain (see `txt_records`),
+    /// like ACME DNS-01 challenges, so certificate issuance and onboarding services can verify domain control.
+    /// Records are read from the chain on every call, Unstoppable Domains profiles may be cached briefly
+    /// (see `DomainResolverBuilder::ud_profile_cache_seconds`).
+    pub async fn verify_ownership(&self, domain: &str, challenge: &str) -> Result<bool> {
+        let challenge = challenge.trim();
+        if challenge.is_empty() {
+            return Err(anyhow!("Empty ownership challenge"));
+        }
+        let records = self.txt_records(domain).await?;
+        Ok(records.iter().any(|record| record.trim() == challenge))
+    }
+
+    /// Returns fetchable URL of the domain avatar by the convention of its naming system: ENS `avatar` text record
+    /// (with ERC-721 and ERC-1155 NFTs), Unstoppable Domains `social.picture.value` record or Evername domain NFT
+    /// metadata image. NFT pictures of Unstoppable Domains are looked up with the `ens` feature only.
+    /// `None` if the avatar is not set or the domain is not handled by a built-in resolver.
+    pub async fn resolve_avatar(&self, domain: &str) -> Result<Option<String>> {
+        let domain = normalize::domain_name(domain);
+        if !policy::allows_lookup(&self.policies, &domain)? {
+            return Ok(None);
+        }
+        self.prepare_route(&domain).await?;
+        let Some(route) = self.routes(&domain, &mut Trace::disabled()).into_iter().next() else {
+            return Ok(None);
+        };
+        match route {
+            #[cfg(feature = "evername")]
+            Route::Evername => self.evername().await?.avatar(&domain).await,
+            #[cfg(feature = "ud")]
+            Route::UnstoppableDomains => match self.ud().await?.avatar_uri(&domain).await? {
+                Some(AvatarUri::Link(link)) => Ok(Some(link)),
+                #[cfg(feature = "ens")]
+                Some(AvatarUri::Nft(nft)) => self.ens_resolver.nft_image(&nft).await.map(Some),
+                #[cfg(not(feature = "ens"))]
+                Some(AvatarUri::Nft(nft)) => Err(anyhow!("Profile picture of {} is NFT {}", domain, nft)),
+                None => Ok(None),
+            },
+            #[cfg(feature = "ens")]
+            Route::Ens => self.ens_resolver.avatar(&domain).await,
+            Route::Custom(_) | Route::NonWeb3 => Ok(None),
+        }
+    }
+
+    /// Stops background tasks and watch streams of the resolver and waits for the tasks to finish.
+    /// Dropping the resolver stops them as well, but does not wait.
+    pub async fn shutdown(&self) {
+        self.cancellation.cancel();
+        self.tasks.close();
+        self.tasks.wait().await;
+        debug!("Resolver background tasks are stopped");
+    }
+
+    /// Resolver is shut down, its background tasks and watch streams are stopped.
+    pub fn is_shut_down(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+
+    /// Token cancelled on shutdown, for servers of the resolver to stop with it.
+    #[cfg(feature = "jsonrpc-server")]
+    pub(crate) fn cancellation_token(&self) -> CancellationToken {
+        self.cancellation.clone()
+    }
+
+    /// Resolves the domain and reports how the result was obtained.
+    /// With explain mode on (see `DomainResolverBuilder::explain`) the report includes the decision
+    /// trail: consulted caches, matched TLD rule, present records and why the tag was chosen.
+    /// Records publishing several targets resolve into one of them, see `targets`.
+    pub async fn resolve_report(&self, domain: &str) -> Result<ResolutionReport> {
+        let report = self.resolve_report_unfinished(domain).await?;
+        Ok(self.finish(report).await)
+    }
+
+    /// Candidate targets of the domain in selection order of the target selector, by default by priority,
+    /// then weighted random within the same priority, see `WeightedTarget` and `TargetSelector`.
+    /// A plain Web2 or IPFS record is a single target.
+    pub async fn targets(&self, domain: &str) -> Result<Vec<WeightedTarget>> {
+        let report = self.resolve_report_unfinished(domain).await?;
+        let targets = self.candidate_targets(&report.data, report.tag);
+        if targets.is_empty() {
+            return Err(anyhow!("Domain {} does not resolve into Web2 or IPFS targets", report.domain));
+        }
+        Ok(self.target_selector.select(&report.domain, targets).await)
+    }
+
+    /// Fetches from the targets of the domain in selection order until the fetch succeeds,
+    /// so a failing target fails over to the next one. The error of the last target is returned if all fail.
+    /// Fetches from IPFS gateways are recorded in gateway health, see `gateway_stats`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # async fn example() -> anyhow::Result<()> {
+    /// use web3_resolver::Web3DomainResolver;
+    ///
+    /// let domain_resolver = Web3DomainResolver::default().await?;
+    /// let page = domain_resolver.fetch_with_failover("site.ever", |url| async move {
+    ///     Ok(reqwest::get(url).await?.error_for_status()?.text().await?)
+    /// }).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn fetch_with_failover<T, F, Fut>(&self, domain: &str, fetch: F) -> Result<T>
+    where
+        F: Fn(String) -> Fut,
+        Fut: std::future::Future<Output = Result<T>>,
+    {
+        let mut last_error = None;
+        for target in self.targets(domain).await? {
+            let gateway = self.ipfs_gateways.iter()
+                .position(|gateway| ipfs::move_to_gateway(gateway, &target.url).as_deref() == Some(target.url.as_str()));
+            let started = std::time::Instant::now();
+            let result = fetch(target.url.clone()).await;
+            if let Some(gateway) = gateway {
+                self.gateway_health.record(gateway, started.elapsed(), result.is_ok());
+            }
+            match result {
+                Ok(fetched) => return Ok(fetched),
+                Err(e) => {
+                    debug!("Target {} of {} failed, failing over: {}", target.url, domain, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| anyhow!("No targets of domain {}", domain)))
+    }
+
+    /// Counted resolution, the report is not finished yet.
+    async fn resolve_report_unfinished(&self, domain: &str) -> Result<ResolutionReport> {
+        let started = std::time::Instant::now();
+        #[cfg(feature = "otel")]
+        let started_at = std::time::SystemTime::now();
+        let result = self.resolve_report_uncounted(domain).await;
+        let domain = normalize::domain_name(domain);
+        let source = match &result {
+            Ok(report) => report.source.clone(),
+            Err(_) => self.primary_source(&domain, &mut Trace::disabled()),
+        };
+        self.stats.record(&domain, &source, result.is_ok(), started.elapsed());
+        #[cfg(feature = "otel")]
+        otel::export_resolution(&domain, &source, started_at, &result);
+        result
+    }
+
+    /// Resolves the domain with per-call options, e.g. preferring IPFS over the resolver priority:
+    /// `ResolveOptions::new().prefer_tags(&[AddressTag::Ipfs])`. The best candidate of `resolve_all`
+    /// by the preferred tags is returned. Results are cached per domain and options.
+    pub async fn resolve_with(&self, domain: &str, options: &ResolveOptions) -> Result<(ResolvedDomainData, AddressTag)> {
+        let report = self.resolve_report_with(domain, options).await?;
+        Ok((report.data, report.tag))
+    }
+
+    /// Same as `resolve_report` with per-call options, default options resolve as `resolve_report` does.
+    /// Only results of the first `DomainResolverBuilder::max_cached_option_variants` distinct options are cached.
+    pub async fn resolve_report_with(&self, domain: &str, options: &ResolveOptions) -> Result<ResolutionReport> {
+        if options.is_default() {
+            return self.resolve_report(domain).await;
+        }
+        let started = std::time::Instant::now();
+        #[cfg(feature = "otel")]
+        let started_at = std::time::SystemTime::now();
+        let domain = normalize::domain_name(domain);
+        let result = match self.resolve_report_with_options(&domain, options).await {
+            Ok(report) => Ok(self.finish(report).await),
+            Err(e) => Err(e),
+        };
+        let source = match &result {
+            Ok(report) => report.source.clone(),
+            Err(_) => self.primary_source(&domain, &mut Trace::disabled()),
+        };
+        self.stats.record(&domain, &source, result.is_ok(), started.elapsed());
+        #[cfg(feature = "otel")]
+        otel::export_resolution(&domain, &source, started_at, &result);
+        result
+    }
+
+    async fn resolve_report_with_options(&self, domain: &str, options: &ResolveOptions) -> Result<ResolutionReport> {
+        let mut trace = Trace::new(self.explain).with_state_refs(self.record_state_refs).with_raw_records(self.record_raw_records);
+        if let Some(context) = &options.context {
+            if let Some((data, tag)) = context.apply_before(domain, &mut trace)? {
+                return Ok(ResolutionReport::new(domain, data, tag, "policy").with_trace(trace));
+            }
+        }
+        let suffix = options.cache_key_suffix();
+        let key = format!("{}{}", normalize::cache_key(domain), suffix);
+        let cache = self.dns_cache.as_ref().filter(|_| self.admit_option_variant(&suffix));
+        // cached result refreshed early, served if the refresh fails
+        let mut early_refresh = None;
+        match cache.map(|cache| cache.lookup(&key)) {
+            Some(Lookup::Hit((data, tag))) => {
+                trace.push(|| "Resolution cache: hit".to_string());
+                let source = self.routes_for(domain, &options.resolvers, &mut trace).first().map(Route::source).unwrap_or_default();
+                return Ok(self.cached_report(domain.to_string(), data, tag, source, trace));
+            },
+            Some(Lookup::Refresh(cached)) => {
+                trace.push(|| "Resolution cache: hit, refreshing early".to_string());
+                early_refresh = Some(cached);
+            },
+            _ => {},
+        }
+        let candidates = match (self.resolve_all_traced(domain, &options.resolvers, &mut trace).await, early_refresh) {
+            (Ok(candidates), _) => candidates,
+            (Err(e), Some((data, tag))) => {
+                trace.push(|| format!("Early refresh failed: {:#}, cached result served", e));
+                let source = self.routes_for(domain, &options.resolvers, &mut trace).first().map(Route::source).unwrap_or_default();
+                return Ok(self.cached_report(domain.to_string(), data, tag, source, trace));
+            },
+            (Err(e), None) => return Err(e),
+        };
+        let candidates = match &options.context {
+            Some(context) => self.apply_context(domain, context, candidates, &mut trace)?,
+            None => candidates,
+        };
+        // the first candidate with the most preferred tag, resolver priority otherwise
+        let best = candidates
+            .into_iter()
+            .min_by_key(|candidate| options.preferred_tags.iter().position(|tag| *tag == candidate.tag).unwrap_or(usize::MAX))
+            .ok_or_else(|| anyhow!("No address for requested domain {}", domain))?;
+        trace.push(|| format!("Options: {} chosen by preferred tags", best));
+        if let Some(cache) = cache.filter(|_| !best.tag.is_onchain()) {
+            cache.insert(key, (best.data.clone(), best.tag));
+            trace.push(|| "Resolution cache: result stored".to_string());
+        }
+        Ok(ResolutionReport::new(domain, best.data, best.tag, best.source).with_trace(trace))
+    }
+
+    /// Runs policies of the tenant on the candidates, candidates blocked by them are dropped.
+    fn apply_context(&self, domain: &str, context: &ResolverContext, candidates: Vec<ResolutionCandidate>, trace: &mut Trace) -> Result<Vec<ResolutionCandidate>> {
+        let mut allowed = Vec::with_capacity(candidates.len());
+        let mut blocked = None;
+        for candidate in candidates {
+            match context.apply_after(domain, candidate.data, candidate.tag, trace) {
+                Ok((data, tag)) => allowed.push(ResolutionCandidate { source: candidate.source, data, tag }),
+                Err(e) => blocked = Some(e),
+            }
+        }
+        match (allowed.is_empty(), blocked) {
+            (true, Some(e)) => Err(e),
+            _ => Ok(allowed),
+        }
+    }
+
+    /// Registers the options variant for caching, `false` if the bound of distinct variants is reached.
+    fn admit_option_variant(&self, suffix: &str) -> bool {
+        let Ok(mut option_variants) = self.option_variants.lock() else {
+            return false;
+        };
+        if option_variants.contains(suffix) {
+            return true;
+        }
+        if option_variants.len() >= self.max_option_variants {
+            debug!("Results of options {} are not cached, {} option variants are cached already", suffix, option_variants.len());
+            return false;
+        }
+        option_variants.insert(suffix.to_string());
+        true
+    }
+
+    /// Effective runtime configuration: enabled resolvers, TLD routing table, endpoints, cache TTL and gateways.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # async fn example() -> anyhow::Result<()> {
+    /// use web3_resolver::Web3DomainResolver;
+    ///
+    /// let domain_resolver = Web3DomainResolver::default().await?;
+    /// // served by an admin endpoint or written next to the warm state
+    /// let config = serde_json::to_string_pretty(&domain_resolver.config())?;
+    /// println!("{}", config);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn config(&self) -> ResolverConfig {
+        let mut resolvers = Vec::new();
+        #[cfg(feature = "evername")]
+        resolvers.push(Route::Evername.source());
+        #[cfg(feature = "ud")]
+        resolvers.push(Route::UnstoppableDomains.source());
+        #[cfg(feature = "ens")]
+        resolvers.push(Route::Ens.source());
+        resolvers.extend(self.custom_resolvers.iter().map(|custom| Route::Custom(custom).source()));
+        let tlds = self.tld_index.read()
+            .map(|tld_index| tld_index.entries()
+                .map(|(tld, targets)| (format!(".{}", tld), targets.iter().map(|target| self.target_route(*target).source()).collect()))
+                .collect())
+            .unwrap_or_default();
+        ResolverConfig {
+            resolvers,
+            tlds,
+            precedence: self.precedence.clone(),
+            mode: self.mode,
+            ambiguity: self.ambiguity,
+            cache_ttl_seconds: self.dns_cache.as_ref().map(|cache| cache.ttl().as_secs()),
+            #[cfg(feature = "evername")]
+            everscale_endpoints: self.everscale_endpoints.clone(),
+            #[cfg(not(feature = "evername"))]
+            everscale_endpoints: Vec::new(),
+            #[cfg(feature = "evername")]
+            quorum_endpoints: self.quorum.iter().map(|(endpoint, _)| endpoint.clone()).collect(),
+            #[cfg(not(feature = "evername"))]
+            quorum_endpoints: Vec::new(),
+            #[cfg(feature = "ud")]
+            unstoppable_domains_base_url: Some(self.ud_base_url.clone()),
+            #[cfg(not(feature = "ud"))]
+            unstoppable_domains_base_url: None,
+            #[cfg(feature = "ud")]
+            unstoppable_domains_sandbox: self.ud_sandbox,
+            #[cfg(not(feature = "ud"))]
+            unstoppable_domains_sandbox: false,
+            #[cfg(feature = "ens")]
+            ens_endpoint: Some(self.ens_resolver.endpoint().to_string()),
+            #[cfg(not(feature = "ens"))]
+            ens_endpoint: None,
+            ipfs_gateways: match self.ipfs_gateways.is_empty() {
+                true => self.primary_ipfs_gateway().into_iter().collect(),
+                false => self.ipfs_gateways.clone(),
+            },
+            dns_fallback: self.dns_fallback.as_ref().map(|dns| dns.name().to_string()),
+            max_redirects: self.max_redirects,
+            max_aliases: self.max_aliases,
+            strict: self.strict,
+        }
+    }
+
+    /// Exports configuration, cached results and Unstoppable Domains TLDs, so another instance
+    /// (e.g. the green one of a blue/green deploy) can be built hot with `from_warm_state`.
+    /// Results of `resolve_with` options are not exported.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # async fn example() -> anyhow::Result<()> {
+    /// use web3_resolver::Web3DomainResolver;
+    ///
+    /// let domain_resolver = Web3DomainResolver::default().await?;
+    /// let state = serde_json::to_vec(&domain_resolver.export_warm_state())?;
+    /// std::fs::write("warm-state.json", state)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn export_warm_state(&self) -> WarmState {
+        let cache = self.dns_cache.as_ref()
+            .map(|cache| cache.alive_entries().into_iter()
+                .filter(|(key, _, _)| !key.contains('#'))
+                .map(|(domain, (data, tag), ttl)| CachedResult { domain, data, tag, ttl_seconds: ttl.as_secs() })
+                .collect())
+            .unwrap_or_default();
+        WarmState {
+            config: self.config(),
+            cache,
+            #[cfg(feature = "ud")]
+            unstoppable_domains_tlds: self.ud_resolver.get()
+                .map(|ud_resolver| ud_resolver.tld_metadata().to_vec())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Builds the resolver from a state exported by `export_warm_state`: the configuration is applied
+    /// (see `DomainResolverBuilder::from_config`), Unstoppable Domains TLDs are not fetched and
+    /// cached results are served until their remaining TTL runs out.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # async fn example() -> anyhow::Result<()> {
+    /// use web3_resolver::Web3DomainResolver;
+    /// use web3_resolver::models::WarmState;
+    ///
+    /// let state: WarmState = serde_json::from_slice(&std::fs::read("warm-state.json")?)?;
+    /// let domain_resolver = Web3DomainResolver::from_warm_state(state).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn from_warm_state(state: WarmState) -> Result<Self> {
+        builder::DomainResolverBuilder::from_config(&state.config)?
+            .with_warm_state(state)
+            .build().await
+    }
+
+    /// Inserts exported results into the resolution cache, expired ones are skipped.
+    fn import_cache(&self, cached: &[CachedResult]) {
+        let Some(cache) = &self.dns_cache else {
+            return;
+        };
+        for result in cached.iter().filter(|result| result.ttl_seconds > 0) {
+            cache.insert_for(result.domain.clone(), (result.data.clone(), result.tag), Duration::from_secs(result.ttl_seconds));
+        }
+    }
+
+    /// IPFS gateway of the initialized resolvers, set by `DomainResolverBuilder::with_ipfs_gateway`.
+    fn primary_ipfs_gateway(&self) -> Option<String> {
+        #[cfg(feature = "evername")]
+        if let Some(evername_resolver) = self.evername_resolver.get() {
+            return Some(evername_resolver.ipfs_gateway().to_string());
+        }
+        #[cfg(feature = "ud")]
+        if let Some(ud_resolver) = self.ud_resolver.get() {
+            return Some(ud_resolver.ipfs_gateway().to_string());
+        }
+        None
+    }
+
+    /// TLD of the domain by the dispatch rules of the resolver: the longest registered TLD, including
+    /// Unstoppable Domains TLDs and TLDs of custom resolvers, with the registry of the highest precedence.
+    /// Unlike `Tld::parse`, in lazy mode Unstoppable Domains resolver is initialized if the TLD is unknown yet.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # async fn example() -> anyhow::Result<()> {
+    /// use web3_resolver::Web3DomainResolver;
+    ///
+    /// let resolver = Web3DomainResolver::default().await?;
+    /// let tld = resolver.tld("brad.crypto").await?;
+    /// if tld.is_web3() {
+    ///     println!("{} is resolved with {:?}", tld, tld.registry());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn tld(&self, domain: &str) -> Result<Tld> {
+        let domain = normalize::domain_name(domain);
+        self.prepare_route(&domain).await?;
+        match self.lookup_tld(&domain) {
+            Some((tld, targets)) => Ok(Tld::new(tld, targets.first().map(|target| self.target_registry(*target)))),
+            None => Tld::parse(&domain).map(|tld| Tld::new(tld.name().to_string(), None)),
+        }
+    }
+
+    /// Rolling statistics of resolutions: top resolved domains, per-resolver error rates and latency.
+    pub fn stats(&self) -> ResolverStats {
+        #[allow(unused_mut)]
+        let mut stats = self.stats.snapshot();
+        #[cfg(feature = "ens")]
+        {
+            stats.namehash_cache_clears = self.ens_resolver.namehash_cache_clears();
+        }
+        stats
+    }
+
+    /// Health of the IPFS gateways set by `DomainResolverBuilder::with_ipfs_gateways`, from fetches by `fetch_with_failover`.
+    pub fn gateway_stats(&self) -> Vec<EndpointStats> {
+        self.gateway_health.stats(&self.ipfs_gateways)
+    }
+
+    /// Health of the Everscale JRPC endpoints, empty until the Evername resolver is initialized.
+    #[cfg(feature = "evername")]
+    pub fn endpoint_stats(&self) -> Vec<EndpointStats> {
+        self.evername_resolver.get().map(|evername_resolver| evername_resolver.endpoint_stats()).unwrap_or_default()
+    }
+
+    async fn resolve_report_uncounted(&self, domain: &str) -> Result<ResolutionReport> {
+        let mut trace = Trace::new(self.explain).with_state_refs(self.record_state_refs).with_raw_records(self.record_raw_records);
+        let key = normalize::cache_key(domain);
+        let domain = normalize::domain_name(domain);
+        // cached result refreshed early, served if the refresh fails
+        let mut early_refresh = None;
+        match self.dns_cache.as_ref().map(|cache| cache.lookup(&key)) {
+            Some(Lookup::Hit((data, tag))) => {
+                trace.push(|| "Resolution cache: hit".to_string());
+                let source = self.primary_source(&domain, &mut trace);
+                return Ok(self.cached_report(domain, data, tag, source, trace));
+            },
+            Some(Lookup::Refresh(cached)) => {
+                trace.push(|| "Resolution cache: hit, refreshing early".to_string());
+                early_refresh = Some(cached);
+            },
+            Some(Lookup::Miss) => trace.push(|| "Resolution cache: miss".to_string()),
+            None => trace.push(|| "Resolution cache: disabled".to_string()),
+        }
+        if let Some((data, tag, source)) = self.pinned_targets.as_ref().and_then(|pinned_targets| pinned_targets.get(&key)) {
+            trace.push(|| "Pinned immutable target: hit".to_string());
+            return Ok(ResolutionReport::new(domain, data, tag, source).with_from_cache(true).with_trace(trace));
+        }
+        // stale result served when the budget is exceeded, with the fallback it came from
+        let mut stale_result = None;
+        let mut waited = false;
+        let resolved = {
+            let resolution = self.resolve_traced(&domain, &mut trace);
+            tokio::pin!(resolution);
+            match &self.stale_fallback {
+                Some(fallback) => {
+                    let finished = tokio::select! {
+                        result = &mut resolution => Some(result),
+                        _ = tokio::time::sleep(fallback.budget()) => None,
+                    };
+                    match finished {
+                        Some(result) => result.map(Some),
+                        None if early_refresh.is_some() => Ok(None),
+                        None => match fallback.get(&key) {
+                            Some(stale) => {
+                                stale_result = Some((fallback, stale));
+                                Ok(None)
+                            },
+                            // nothing to fall back to, the resolution under way is awaited rather than restarted
+                            None => {
+                                waited = true;
+                                resolution.as_mut().await.map(Some)
+                            },
+                        },
+                    }
+                },
+                None => resolution.as_mut().await.map(Some),
+            }
+        };
+        if waited {
+            trace.push(|| "Resolution budget exceeded, no stale result, resolution was awaited".to_string());
+        }
+        let (data, tag, source) = match (resolved, early_refresh, stale_result) {
+            (Ok(Some(resolved)), _, _) => resolved,
+            (result, Some((data, tag)), _) => {
+                match result {
+                    Err(e) => trace.push(|| format!("Early refresh failed: {:#}, cached result served", e)),
+                    _ => {
+                        trace.push(|| "Early refresh exceeded the resolution budget, cached result served, refresh queued".to_string());
+                        if let Some(fallback) = &self.stale_fallback {
+                            fallback.request_refresh(&domain);
+                        }
+                    },
+                }
+                let source = self.primary_source(&domain, &mut trace);
+                return Ok(self.cached_report(domain, data, tag, source, trace));
+            },
+            (_, None, Some((fallback, (data, tag, source)))) => {
+                trace.push(|| format!("Resolution budget of {:?} exceeded, stale result served, refresh queued", fallback.budget()));
+                fallback.request_refresh(&domain);
+                return Ok(ResolutionReport::new(domain, data, tag, source).with_from_cache(true).with_stale(true).with_trace(trace));
+            },
+            // without a cached or stale result the resolution is always awaited
+            (result, None, None) => result?.ok_or_else(|| anyhow!("Resolution of {} returned no result", domain))?,
+        };
+        self.cache_result(key, &domain, &data, tag, &source, &mut trace).await?;
+        Ok(ResolutionReport::new(domain, data, tag, source).with_trace(trace))
+    }
+
+    /// Report of the result from the resolution cache.
+    fn cached_report(&self, domain: String, data: ResolvedDomainData, tag: AddressTag, source: String, trace: Trace) -> ResolutionReport {
+        ResolutionReport::new(domain, data, tag, source).with_from_cache(true).with_trace(trace)
+    }
+
+    /// Stores the result in the resolution cache and the stale results, onchain content is not cached.
+    fn store(&self, key: String, data: &ResolvedDomainData, tag: AddressTag, source: &str, trace: &mut Trace) {
+        if tag.is_onchain() {
+            if self.dns_cache.is_some() {
+                trace.push(|| "Resolution cache: onchain content is not cached".to_string());
+            }
+            return;
+        }
+        if let Some(fallback) = &self.stale_fallback {
+            fallback.insert(key.clone(), data.clone(), tag, source.to_string());
+        }
+        if let Some(pinned_targets) = &self.pinned_targets {
+            if pinned_targets.update(key.clone(), data, tag, source) {
+                trace.push(|| format!("Pinned immutable target: record is re-pointed to {}", data));
+            }
+        }
+        if let Some(cache) = &self.dns_cache {
+            cache.insert(key, (data.clone(), tag));
+            trace.push(|| "Resolution cache: result stored".to_string());
+        }
+    }
+
+    /// Runs the cache stage of the pipeline: stores the result unless the stage is replaced,
+    /// then runs the custom stages inserted after it.
+    async fn cache_result(&self, key: String, domain: &str, data: &ResolvedDomainData, tag: AddressTag, source: &str, trace: &mut Trace) -> Result<()> {
+        let mut state = PipelineState {
+            domain: domain.to_string(),
+            sources: vec![source.to_string()],
+            result: Some((data.clone(), tag, source.to_string())),
+            chain: Vec::new(),
+        };
+        match self.pipeline.replacement(Stage::Cache) {
+            Some(custom) => {
+                trace.push(|| format!("Pipeline: {} stage is replaced by {}", Stage::Cache, custom.name()));
+                custom.run(&mut state).await?;
+            },
+            None => self.store(key, data, tag, source, trace),
+        }
+        self.pipeline.run_inserted(Stage::Cache, &mut state, trace).await
+    }
+
+    /// Resolves the domain without the budget and stores the result.
+    pub(crate) async fn refresh(&self, domain: &str) -> Result<()> {
+        let (data, tag, source) = self.resolve_traced(domain, &mut Trace::disabled()).await?;
+        self.cache_result(normalize::cache_key(domain), domain, &data, tag, &source, &mut Trace::disabled()).await
+    }
+
+    /// Dry run of the resolution for diagnostics: resolves the domain bypassing and never writing
+    /// caches, and returns the full decision trace together with raw record values.
+    pub async fn explain(&self, domain: &str) -> Result<ResolutionReport> {
+        let mut trace = Trace::dry_run().with_state_refs(self.record_state_refs).with_raw_records(self.record_raw_records);
+        let key = normalize::cache_key(domain);
+        let domain = normalize::domain_name(domain);
+        match &self.dns_cache {
+            Some(cache) if cache.contains_key(&key) => trace.push(|| "Resolution cache: entry present, bypassed".to_string()),
+            Some(_) => trace.push(|| "Resolution cache: no entry".to_string()),
+            None => trace.push(|| "Resolution cache: disabled".to_string()),
+        }
+        let (data, tag, source) = self.resolve_traced(&domain, &mut trace).await?;
+        Ok(self.finish(ResolutionReport::new(domain, data, tag, source).with_trace(trace)).await)
+    }
+
+    /// Finishes the report: chooses one of the targets published by the record, adds warnings and signs it.
+    async fn finish(&self, report: ResolutionReport) -> ResolutionReport {
+        let mut report = self.select_target(report).await;
+        if !self.confusables.is_empty() {
+            report.warnings.extend(self.confusables.check(&report.domain));
+        }
+        self.sign(report)
+    }
+
+    /// Chooses the first target in selection order if there are several candidate targets, see `TargetSelector`.
+    async fn select_target(&self, mut report: ResolutionReport) -> ResolutionReport {
+        let targets = self.candidate_targets(&report.data, report.tag);
+        if targets.is_empty() || (targets.len() == 1 && targets[0].url == report.data.as_str()) {
+            return report;
+        }
+        let candidates = WeightedTarget::join(&targets);
+        let targets = self.target_selector.select(&report.domain, targets).await;
+        let Some(selected) = targets.into_iter().next() else {
+            return report;
+        };
+        // steps are collected in explain mode only
+        if !report.trace.is_empty() {
+            report.trace.push(format!("Targets: {} chosen by {} selector of {}", selected.url, self.target_selector.name(), candidates));
+        }
+        report.data = ResolvedDomainData::DomainString(selected.url);
+        report
+    }
+
+    /// Targets published by the record, IPFS ones on every gateway set by `DomainResolverBuilder::with_ipfs_gateways`.
+    fn candidate_targets(&self, data: &ResolvedDomainData, tag: AddressTag) -> Vec<WeightedTarget> {
+        match tag {
+            AddressTag::Ipfs | AddressTag::UnstoppableDomain => {
+                let order = self.gateway_health.order(self.ipfs_gateways.len());
+                targets::on_gateways(data.targets(), &self.ipfs_gateways, order.as_deref())
+            },
+            _ => data.targets(),
+        }
+    }
+
+    /// Signs the report if the signing key is set.
+    fn sign(&self, report: ResolutionReport) -> ResolutionReport {
+        let Some(signing_key) = &self.signing_key else {
+            return report;
+        };
+        let signed_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        let signature = signing_key.sign(&report.signing_payload(signed_at));
+        ResolutionReport {
+            signature: Some(ReportSignature {
+                public_key: hex::encode(signing_key.verifying_key().as_bytes()),
+                signed_at,
+                signature: hex::encode(signature.to_bytes()),
+            }),
+            ..report
+        }
+    }
+
+    /// Returns pinned result or blocking error if the domain is overridden.
+    fn check_override(&self, domain: &str, trace: &mut Trace) -> Result<Option<(ResolvedDomainData, AddressTag)>> {
+        match self.overrides.get(domain) {
+            Some(OverrideEntry::Pin(data, tag)) => {
+                trace.push(|| format!("Override: domain is pinned to {} with tag {}", data, tag));
+                Ok(Some((data.clone(), *tag)))
+            },
+            Some(OverrideEntry::Block(reason)) => {
+                trace.push(|| format!("Override: domain is blocked: {}", reason));
+                Err(ResolverError::Blocked { domain: domain.to_string(), reason: reason.clone() }.into())
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Runs policies before the resolution, returns rewritten result or blocking error if any.
+    fn apply_policies_before(&self, domain: &str, trace: &mut Trace) -> Result<Option<(ResolvedDomainData, AddressTag)>> {
+        policy::apply_before(&self.policies, domain, trace)
+    }
+
+    /// Runs policies on the resolved result, returns the result rewritten by them or blocking error.
+    fn apply_policies_after(&self, domain: &str, data: ResolvedDomainData, tag: AddressTag, trace: &mut Trace) -> Result<(ResolvedDomainData, AddressTag)> {
+        policy::apply_after(&self.policies, domain, data, tag, trace)
+    }
+
+    /// Runs result rewriters on the final result.
+    fn apply_rewriters(&self, domain: &str, data: ResolvedDomainData, tag: AddressTag, trace: &mut Trace) -> (ResolvedDomainData, AddressTag) {
+        rewrite::apply(&self.rewriters, domain, data, tag, trace)
+    }
+
+    /// Resolves Evername domain, in quorum mode the result must be confirmed by all quorum endpoints.
+    #[cfg(feature = "evername")]
+    async fn resolve_evername(&self, domain: &str, trace: &mut Trace) -> Result<(ResolvedDomainData, AddressTag)> {
+        let evername_resolver = self.evername().await?;
+        if self.quorum.is_empty() {
+            return evername_resolver.resolve_traced(domain, trace).await;
+        }
+        let verifier_trace = trace.fork();
+        let verifications = self.quorum.iter().map(|(endpoint, resolver)| {
+            let mut verifier_trace = verifier_trace.fork();
+            async move {
+                let result = resolver.resolve_traced(domain, &mut verifier_trace).await
+                    .with_context(|| format!("Quorum endpoint {} failed to resolve {}", endpoint, domain))?;
+                Ok::<_, anyhow::Error>((endpoint, result))
+            }
+        });
+        let (expected, verified) = future::join(
+            evername_resolver.resolve_traced(domain, trace),
+            future::try_join_all(verifications),
+        ).await;
+        let (expected, verified) = (expected?, verified?);
+        for (endpoint, actual) in verified {
+            if actual != expected {
+                trace.push(|| format!("Quorum: endpoint {} disagrees: {} with tag {}", endpoint, actual.0, actual.1));
+                return Err(ResolverError::QuorumMismatch {
+                    domain: domain.to_string(),
+                    endpoint: endpoint.clone(),
+                    expected: format!("{} with tag {}", expected.0, expected.1),
+                    actual: format!("{} with tag {}", actual.0, actual.1),
+                }.into());
+            }
+        }
+        trace.push(|| format!("Quorum: result confirmed by {} endpoints", self.quorum.len()));
+        Ok(expected)
+    }
+
+    /// Returns Evername resolver, initializing it on first use in lazy mode.
+    #[cfg(feature = "evername")]
+    async fn evername(&self) -> Result<&EvernameResolver> {
+        self.evername_resolver.force().await
+            .map_err(|source| ResolverError::InitializationFailed { resolver: "evername".to_string(), source }.into())
+    }
+
+    /// Initialized Evername resolver of the domain TLD: the mainnet one or an additional deployment.
+    #[cfg(feature = "evername")]
+    fn evername_of(&self, domain: &str) -> Option<&EvernameResolver> {
+        self.evername_resolver.get().into_iter()
+            .chain(self.evername_networks.iter().map(Arc::as_ref))
+            .find(|evername| domain.ends_with(evername.tld()))
+    }
+
+    /// Resolver of the route, initializing it on first use in lazy mode, `None` for non-web3 domains.
+    async fn route_resolver<'a>(&'a self, route: &Route<'a>) -> Result<Option<&'a (dyn Resolver + Send + Sync)>> {
+        Ok(match route {
+            #[cfg(feature = "evername")]
+            Route::Evername => Some(self.evername().await?),
+            #[cfg(feature = "ud")]
+            Route::UnstoppableDomains => Some(self.ud().await?),
+            #[cfg(feature = "ens")]
+            Route::Ens => Some(&self.ens_resolver),
+            Route::Custom(custom) => Some(custom.resolver.as_ref()),
+            Route::NonWeb3 => None,
+        })
+    }
+
+    /// Returns Unstoppable Domains resolver, initializing it on first use in lazy mode.
+    #[cfg(feature = "ud")]
+    async fn ud(&self) -> Result<&UnstoppableDomainsResolver> {
+        if let Some(ud_resolver) = self.ud_resolver.get() {
+            return Ok(ud_resolver);
+        }
+        let ud_resolver = self.ud_resolver.force().await
+            .map_err(|source| ResolverError::InitializationFailed { resolver: "unstoppable-domains".to_string(), source })?;
+        // TLDs are known only after initialization
+        self.reindex_tlds();
+        Ok(ud_resolver)
+    }
+
+    /// In lazy mode initializes Unstoppable Domains resolver if the domain TLD is unknown yet,
+    /// as it may be one of the Unstoppable Domains TLDs.
+    async fn prepare_route(&self, _domain: &str) -> Result<()> {
+        #[cfg(feature = "ud")]
+        if self.ud_resolver.get().is_none() && self.lookup_tld(_domain).is_none() {
+            self.ud().await?;
         }
+        Ok(())
+    }
+
+    /// Rebuilds TLD index of all resolvers, TLDs are registered in resolver priority order.
+    fn reindex_tlds(&self) {
+        let mut tld_index = TldIndex::default();
+        #[cfg(feature = "evername")]
+        tld_index.insert(".ever", TldTarget::Evername);
+        #[cfg(feature = "ud")]
+        if let Some(ud_resolver) = self.ud_resolver.get() {
+            for tld in ud_resolver.get_tlds() {
+                tld_index.insert(&tld, TldTarget::UnstoppableDomains);
+            }
+        }
+        #[cfg(feature = "ens")]
+        for tld in self.ens_resolver.get_tlds() {
+            tld_index.insert(&tld, TldTarget::Ens);
+        }
+        for (index, custom) in self.custom_resolvers.iter().enumerate() {
+            for tld in &custom.tlds {
+                tld_index.insert(tld, TldTarget::Custom(index));
+            }
+        }
+        if !self.precedence.is_empty() {
+            tld_index.sort_targets_by_key(|target| {
+                let source = self.target_route(*target).source();
+                self.precedence.iter().position(|preferred| *preferred == source).unwrap_or(usize::MAX)
+            });
+        }
+        if let Ok(mut current) = self.tld_index.write() {
+            *current = tld_index;
+        }
+    }
+
+    fn lookup_tld(&self, domain: &str) -> Option<(String, Vec<TldTarget>)> {
+        self.tld_index.read().ok().and_then(|tld_index| tld_index.lookup(domain))
+    }
+
+    /// Selects resolvers for the domain by its TLD in precedence order.
+    /// Only the first one is selected unless the ambiguity policy needs all of them.
+    fn routes(&self, domain: &str, trace: &mut Trace) -> Vec<Route<'_>> {
+        self.routes_for(domain, &[], trace)
+    }
+
+    /// Same as `routes`, limited to the resolvers with the given source names unless they are empty.
+    fn routes_for(&self, domain: &str, sources: &[String], trace: &mut Trace) -> Vec<Route<'_>> {
+        let Some((tld, targets)) = self.lookup_tld(domain) else {
+            trace.push(|| "TLD rule: no web3 TLD matched, domain is passed through as non-web3".to_string());
+            return vec![Route::NonWeb3];
+        };
+        let mut routes: Vec<Route> = targets.into_iter()
+            .map(|target| self.target_route(target))
+            .filter(|route| sources.is_empty() || sources.contains(&route.source()))
+            .collect();
+        if routes.is_empty() {
+            trace.push(|| format!("TLD rule: {} is not handled by the requested resolvers {}", tld, sources.join(", ")));
+            return routes;
+        }
+        if routes.len() > 1 {
+            trace.push(|| format!("TLD rule: {} is claimed by {}, ambiguity policy is {:?}", tld,
+                routes.iter().map(|route| route.source()).collect::<Vec<_>>().join(", "), self.ambiguity));
+        }
+        if self.ambiguity == AmbiguityPolicy::First {
+            routes.truncate(1);
+        }
+        let non_web3_requested = sources.is_empty() || sources.contains(&Route::NonWeb3.source());
+        if let Some(precedence) = self.dual_resolution.precedence_of(&tld).filter(|_| non_web3_requested) {
+            trace.push(|| format!("TLD rule: {} is a dual TLD, DNS is checked as well with {:?} precedence", tld, precedence));
+            match precedence {
+                DualPrecedence::Web3 => routes.push(Route::NonWeb3),
+                DualPrecedence::Dns => routes.insert(0, Route::NonWeb3),
+            }
+        }
+        for route in &routes {
+            trace.push(|| format!("TLD rule: {} is handled by {}", tld, route.description()));
+        }
+        routes
+    }
+
+    /// Source name of the resolver with the highest precedence for the domain.
+    fn primary_source(&self, domain: &str, trace: &mut Trace) -> String {
+        self.routes(domain, trace).first().map(Route::source).unwrap_or_default()
+    }
+
+    fn target_route(&self, target: TldTarget) -> Route<'_> {
+        match target {
+            #[cfg(feature = "evername")]
+            TldTarget::Evername => Route::Evername,
+            #[cfg(feature = "ud")]
+            TldTarget::UnstoppableDomains => Route::UnstoppableDomains,
+            #[cfg(feature = "ens")]
+            TldTarget::Ens => Route::Ens,
+            TldTarget::Custom(index) => Route::Custom(&self.custom_resolvers[index]),
+        }
+    }
+
+    fn target_registry(&self, target: TldTarget) -> Registry {
+        match target {
+            #[cfg(feature = "evername")]
+            TldTarget::Evername => Registry::Evername,
+            #[cfg(feature = "ud")]
+            TldTarget::UnstoppableDomains => Registry::UnstoppableDomains,
+            #[cfg(feature = "ens")]
+            TldTarget::Ens => Registry::Ens,
+            TldTarget::Custom(index) => Registry::Custom(self.custom_resolvers[index].resolver.name().to_string()),
+        }
+    }
+
+    /// Fails if the domain is claimed by several resolvers and the ambiguity policy rejects such domains.
+    /// DNS of dual TLDs is not a competing resolver.
+    fn check_ambiguity(&self, domain: &str, routes: &[Route<'_>]) -> Result<()> {
+        let web3_routes: Vec<&Route> = routes.iter().filter(|route| !matches!(route, Route::NonWeb3)).collect();
+        if web3_routes.len() > 1 && self.ambiguity == AmbiguityPolicy::Reject {
+            return Err(ResolverError::AmbiguousDomain {
+                domain: domain.to_string(),
+                sources: web3_routes.iter().map(|route| route.source()).collect(),
+            }.into());
+        }
+        Ok(())
+    }
+
+    /// Resolves the domain with all selected resolvers in parallel,
+    /// the successful result of the resolver with the highest precedence wins.
+    async fn resolve_routes(&self, domain: &str, routes: Vec<Route<'_>>, trace: &mut Trace) -> Result<(ResolvedDomainData, AddressTag, String)> {
+        self.check_ambiguity(domain, &routes)?;
+        if let [route] = routes.as_slice() {
+            let (resolved_data, address_tag) = self.resolve_route(route, domain, trace).await?;
+            return Ok((resolved_data, address_tag, route.source()));
+        }
+        let route_trace = trace.fork();
+        let results = future::join_all(routes.iter().map(|route| {
+            let mut route_trace = route_trace.fork();
+            async move { (route.source(), self.resolve_route(route, domain, &mut route_trace).await) }
+        })).await;
+        let mut first_error = None;
+        for (source, result) in results {
+            match result {
+                Ok((resolved_data, address_tag)) => {
+                    trace.push(|| format!("Ambiguity: result of {} is chosen by precedence", source));
+                    return Ok((resolved_data, address_tag, source));
+                },
+                Err(e) => {
+                    trace.push(|| format!("Ambiguity: {} failed: {:#}", source, e));
+                    first_error.get_or_insert(e);
+                },
+            }
+        }
+        Err(first_error.unwrap_or_else(|| anyhow!("No address for requested domain {}", domain)))
+    }
+
+    /// Resolves the domain with the route, a panic of the resolver, e.g. on hostile on-chain data,
+    /// fails the route with `ResolverError::ResolverPanicked` instead of unwinding into the caller.
+    async fn resolve_route(&self, route: &Route<'_>, domain: &str, trace: &mut Trace) -> Result<(ResolvedDomainData, AddressTag)> {
+        AssertUnwindSafe(self.resolve_route_unguarded(route, domain, trace))
+            .catch_unwind()
+            .await
+            .unwrap_or_else(|panic| Err(route_panicked(route, panic)))
+    }
+
+    #[cfg_attr(not(any(feature = "evername", feature = "ud", feature = "ens")), allow(unused_variables))]
+    async fn resolve_route_unguarded(&self, route: &Route<'_>, domain: &str, trace: &mut Trace) -> Result<(ResolvedDomainData, AddressTag)> {
+        let (resolved_data, address_tag) = match route {
+            #[cfg(feature = "evername")]
+            Route::Evername => {
+                let (resolved_data, address_tag) = self.resolve_evername(domain, trace).await?;
+                debug!("Ever host {} resolved into: {} with tag {}", domain, resolved_data, address_tag);
+                (resolved_data, address_tag)
+            },
+            #[cfg(feature = "ud")]
+            Route::UnstoppableDomains => {
+                let (resolved_data, address_tag) = self.ud().await?.resolve_traced(domain, trace).await
+                    .context("Failed to resolve Unstoppable Domain")?;
+                debug!("Unstoppable domain host {} resolved into: {} with tag {}", domain, resolved_data, address_tag);
+                (resolved_data, address_tag)
+            },
+            #[cfg(feature = "ens")]
+            Route::Ens => {
+                let (resolved_data, address_tag) = self.ens_resolver.resolve_traced(domain, trace).await
+                    .context("Failed to resolve ENS domain")?;
+                debug!("ENS host {} resolved into: {} with tag {}", domain, resolved_data, address_tag);
+                (resolved_data, address_tag)
+            },
+            Route::Custom(custom) => {
+                let (resolved_data, address_tag) = custom.resolver.resolve(domain).await?;
+                debug!("Custom resolver host {} resolved into: {} with tag {}", domain, resolved_data, address_tag);
+                (resolved_data, address_tag)
+            },
+            Route::NonWeb3 => (self.resolve_non_web3(domain, trace).await?, AddressTag::NonWeb3),
+        };
+        Ok((resolved_data, address_tag))
+    }
+
+    async fn resolve_route_all(&self, route: &Route<'_>, domain: &str, trace: &Trace) -> Result<Vec<ResolutionCandidate>> {
+        AssertUnwindSafe(self.resolve_route_all_unguarded(route, domain, trace))
+            .catch_unwind()
+            .await
+            .unwrap_or_else(|panic| Err(route_panicked(route, panic)))
+    }
+
+    /// Resolves all candidates of the domain with the route, caches are not written in dry runs of the trace.
+    async fn resolve_route_all_unguarded(&self, route: &Route<'_>, domain: &str, _trace: &Trace) -> Result<Vec<ResolutionCandidate>> {
+        let candidates = match route {
+            #[cfg(feature = "evername")]
+            Route::Evername => self.evername().await?.resolve_all_traced(domain, _trace).await?,
+            #[cfg(feature = "ud")]
+            Route::UnstoppableDomains => self.ud().await?.resolve_all_traced(domain, _trace).await
+                .context("Failed to resolve Unstoppable Domain")?,
+            #[cfg(feature = "ens")]
+            Route::Ens => self.ens_resolver.resolve_all(domain).await
+                .context("Failed to resolve ENS domain")?,
+            Route::Custom(custom) => custom.resolver.resolve_all(domain).await?,
+            Route::NonWeb3 => vec![ResolutionCandidate {
+                source: "non-web3".to_string(),
+                data: self.resolve_non_web3(domain, &mut Trace::disabled()).await?,
+                tag: AddressTag::NonWeb3,
+            }],
+        };
+        Ok(candidates)
+    }
+
+    /// Passes non-web3 domain through as is or resolves its A and AAAA records with the DNS fallback.
+    /// In strict mode the passthrough fails with `ResolverError::UnsupportedTld`.
+    async fn resolve_non_web3(&self, domain: &str, trace: &mut Trace) -> Result<ResolvedDomainData> {
+        let Some(dns) = &self.dns_fallback else {
+            if self.strict {
+                trace.push(|| "Strict mode: non-web3 passthrough is rejected".to_string());
+                return Err(ResolverError::UnsupportedTld { domain: domain.to_string() }.into());
+            }
+            return Ok(ResolvedDomainData::DomainString(domain.to_owned()));
+        };
+        if let Ok(address) = domain.parse::<std::net::IpAddr>() {
+            return Ok(ResolvedDomainData::DnsRecords(DnsRecords { name: domain.to_owned(), addresses: vec![address], ttl: None }));
+        }
+        let records = dns.lookup(domain).await
+            .with_context(|| format!("Failed to resolve {} with {}", domain, dns.name()))?;
+        trace.push(|| format!("DNS fallback {}: {} resolved into {}", dns.name(), domain, records.addresses_string()));
+        Ok(ResolvedDomainData::DnsRecords(records))
+    }
+
+    /// Resolves the domain bypassing the cache.
+    async fn resolve_uncached(&self, domain: &str) -> Result<(ResolvedDomainData, AddressTag)> {
+        let (data, tag, _) = self.resolve_traced(domain, &mut Trace::disabled()).await?;
+        Ok((data, tag))
+    }
+
+    /// Resolves the domain bypassing the cache, returns the result with its source resolver name.
+    /// The domain is passed through the normalize, dispatch, resolve and post-process stages of the pipeline.
+    async fn resolve_traced(&self, domain: &str, trace: &mut Trace) -> Result<(ResolvedDomainData, AddressTag, String)> {
+        let mut state = PipelineState::new(domain);
+        for stage in [Stage::Normalize, Stage::Dispatch, Stage::Resolve, Stage::PostProcess] {
+            match self.pipeline.replacement(stage) {
+                Some(custom) => {
+                    trace.push(|| format!("Pipeline: {} stage is replaced by {}", stage, custom.name()));
+                    custom.run(&mut state).await?;
+                },
+                None => self.run_stage(stage, &mut state, trace).await?,
+            }
+            self.pipeline.run_inserted(stage, &mut state, trace).await?;
+        }
+        trace.set_chain(state.chain);
+        state.result.ok_or_else(|| anyhow!("No address for requested domain {}", domain))
+    }
+
+    /// Runs the built-in stage of the pipeline.
+    async fn run_stage(&self, stage: Stage, state: &mut PipelineState, trace: &mut Trace) -> Result<()> {
+        match stage {
+            Stage::Normalize => state.domain = normalize::domain_name(&state.domain),
+            Stage::Dispatch => self.dispatch(state, trace).await?,
+            Stage::Resolve => {
+                self.resolve_state(state, trace).await?;
+                self.follow_chain(state, trace).await?;
+            },
+            Stage::PostProcess => {
+                if let Some((data, tag, source)) = state.result.take() {
+                    let (data, tag) = self.apply_rewriters(&state.domain, data, tag, trace);
+                    state.result = Some((data, tag, source));
+                }
+            },
+            // the result is stored by `cache_result`, see `resolve_report_uncounted`
+            Stage::Cache => {},
+        }
+        Ok(())
+    }
+
+    /// Applies overrides and policies, selects resolvers of the domain unless the result is set by them.
+    async fn dispatch(&self, state: &mut PipelineState, trace: &mut Trace) -> Result<()> {
+        if state.result.is_some() {
+            return Ok(());
+        }
+        if self.mode != ResolutionMode::Aggregate {
+            if let Some((data, tag)) = self.check_override(&state.domain, trace)? {
+                state.result = Some((data, tag, "override".to_string()));
+                return Ok(());
+            }
+            if let Some((data, tag)) = self.apply_policies_before(&state.domain, trace)? {
+                state.result = Some((data, tag, "policy".to_string()));
+                return Ok(());
+            }
+        }
+        self.prepare_route(&state.domain).await?;
+        // aggregate mode traces the TLD rules while resolving all candidates
+        let routes = match self.mode {
+            ResolutionMode::Aggregate => self.routes(&state.domain, &mut Trace::disabled()),
+            _ => self.routes(&state.domain, trace),
+        };
+        state.sources = routes.iter().map(Route::source).collect();
+        Ok(())
+    }
+
+    /// Resolves the domain with the selected resolvers unless the result is set already.
+    async fn resolve_state(&self, state: &mut PipelineState, trace: &mut Trace) -> Result<()> {
+        if state.result.is_some() {
+            return Ok(());
+        }
+        if state.sources.is_empty() {
+            return Err(anyhow!("No resolvers are selected for {}", state.domain));
+        }
+        let domain = state.domain.as_str();
+        if self.mode == ResolutionMode::Aggregate {
+            let candidates = self.resolve_all_unrewritten(domain, &state.sources, trace).await?;
+            trace.push(|| format!("Aggregate mode: {} candidates: {}", candidates.len(),
+                candidates.iter().map(|c| c.to_string()).collect::<Vec<_>>().join("; ")));
+            let best = candidates
+                .into_iter()
+                .next()
+                .ok_or_else(|| anyhow!("No address for requested domain {}", domain))?;
+            trace.push(|| format!("Aggregate mode: best ranked candidate chosen: {}", best));
+            state.result = Some((best.data, best.tag, best.source));
+            return Ok(());
+        }
+        let mut routes = self.routes_for(domain, &state.sources, &mut Trace::disabled());
+        routes.sort_by_key(|route| state.sources.iter().position(|source| *source == route.source()));
+        let (resolved_data, address_tag, source) = self.resolve_routes(domain, routes, trace).await?;
+        trace.push(|| format!("Resolved by {} into {} with tag {}", source, resolved_data, address_tag));
+        let (resolved_data, address_tag) = self.apply_policies_after(domain, resolved_data, address_tag, trace)?;
+        state.result = Some((resolved_data, address_tag, source));
+        Ok(())
+    }
+
+    /// Follows redirects and aliases to web3 domains up to the max depths set by `DomainResolverBuilder::follow_redirects`
+    /// and `DomainResolverBuilder::follow_aliases`, the result of the last domain replaces the one of the requested domain.
+    /// Redirects to web2 domains are returned as is.
+    async fn follow_chain(&self, state: &mut PipelineState, trace: &mut Trace) -> Result<()> {
+        if self.max_redirects == 0 && self.max_aliases == 0 {
+            return Ok(());
+        }
+        let mut chain = vec![state.domain.clone()];
+        let (mut redirects, mut aliases) = (0, 0);
+        while let Some((data, tag, _)) = &state.result {
+            let from = chain.last().map(String::as_str).unwrap_or_default();
+            let (target, is_redirect) = match data {
+                ResolvedDomainData::Redirect(redirect) if *tag == AddressTag::Redirect && self.max_redirects > 0 => {
+                    let Some(target) = redirect.target_domain().filter(|target| self.lookup_tld(target).is_some()) else {
+                        trace.push(|| format!("Redirect: {} is not a web3 domain, redirect is returned", redirect.target));
+                        break;
+                    };
+                    trace.push(|| format!("Redirect: {} redirects to {} with status {}, following", from, target, redirect.status));
+                    (target, true)
+                },
+                ResolvedDomainData::DomainString(value) if self.max_aliases > 0 => {
+                    let Some(target) = normalize::alias_domain(value).filter(|target| self.lookup_tld(target).is_some()) else {
+                        break;
+                    };
+                    trace.push(|| format!("Alias: {} is an alias of {}, following", from, target));
+                    (target, false)
+                },
+                _ => break,
+            };
+            if chain.contains(&target) {
+                chain.push(target);
+                let domain = state.domain.clone();
+                return Err(match is_redirect {
+                    true => ResolverError::RedirectLoop { domain, chain },
+                    false => ResolverError::AliasLoop { domain, chain },
+                }.into());
+            }
+            if is_redirect {
+                redirects += 1;
+                if redirects > self.max_redirects {
+                    return Err(ResolverError::TooManyRedirects { domain: state.domain.clone(), max_depth: self.max_redirects }.into());
+                }
+            } else {
+                aliases += 1;
+                if aliases > self.max_aliases {
+                    return Err(ResolverError::TooManyAliases { domain: state.domain.clone(), max_depth: self.max_aliases }.into());
+                }
+            }
+            let mut hop = PipelineState::new(&target);
+            self.dispatch(&mut hop, trace).await?;
+            self.resolve_state(&mut hop, trace).await?;
+            chain.push(target);
+            state.result = hop.result;
+        }
+        if chain.len() > 1 {
+            state.chain = chain;
+        }
+        Ok(())
+    }
+}
+
+impl Route<'_> {
+    fn source(&self) -> String {
+        match self {
+            #[cfg(feature = "evername")]
+            Route::Evername => "evername".to_string(),
+            #[cfg(feature = "ud")]
+            Route::UnstoppableDomains => "unstoppable-domains".to_string(),
+            #[cfg(feature = "ens")]
+            Route::Ens => "ens".to_string(),
+            Route::Custom(custom) => custom.resolver.name().to_string(),
+            Route::NonWeb3 => "non-web3".to_string(),
+        }
+    }
+
+    fn description(&self) -> String {
+        match self {
+            #[cfg(feature = "evername")]
+            Route::Evername => "Evername".to_string(),
+            #[cfg(feature = "ud")]
+            Route::UnstoppableDomains => "Unstoppable Domains".to_string(),
+            #[cfg(feature = "ens")]
+            Route::Ens => "ENS".to_string(),
+            Route::Custom(custom) => custom.resolver.name().to_string(),
+            Route::NonWeb3 => "non-web3".to_string(),
+        }
+    }
+}
+
+fn route_panicked(route: &Route<'_>, panic: Box<dyn Any + Send>) -> anyhow::Error {
+    let message = panic_message(panic.as_ref());
+    debug!("Resolver {} panicked: {}", route.source(), message);
+    ResolverError::ResolverPanicked {
+        resolver: route.source(),
+        message,
+    }.into()
+}
+
+impl Drop for Web3DomainResolver {
+    fn drop(&mut self) {
+        self.cancellation.cancel();
     }
 }
 
@@ -88,30 +2091,42 @@ impl Web3DomainResolver {
 impl Resolver for Web3DomainResolver {
     
     async fn resolve(&self, domain: &str) -> Result<(ResolvedDomainData, AddressTag)> {
-        let domain = domain.to_owned();
-        if let Some(cache) = &self.dns_cache {
-            if let Some(found) = cache.get(&domain) {
-                return Ok(found);
-            }
-        }
-        let (resolved_data, address_tag) = if domain.ends_with(".ever") {
-            let (resolved_data, address_tag) = self.evername_resolver.resolve(&domain).await?;
-            debug!("Ever host {} resolved into: {} with tag {}", domain, resolved_data, address_tag);
-            (resolved_data, address_tag)
-        } else if self.ud_resolver.get_tlds().iter().any(|tld| domain.ends_with(tld)) {
-            let (resolved_data, address_tag) = self.ud_resolver.resolve(&domain).await
-                .map_err(|e| anyhow!("Failed to resolve Unstoppable Domain: {}", e))?;
-            debug!("Unstoppable domain host {} resolved into: {} with tag {}", domain, resolved_data, address_tag);
-            (resolved_data, address_tag)
-        } else {
-            (ResolvedDomainData::DomainString(domain.to_owned()), AddressTag::NonWeb3)
+        let report = self.resolve_report(domain).await?;
+        Ok((report.data, report.tag))
+    }
+
+    /// Union of the capabilities of the initialized resolvers, TLDs are the ones of the routing table.
+    fn capabilities(&self) -> ResolverCapabilities {
+        let mut resolvers: Vec<&(dyn Resolver + Send + Sync)> = Vec::new();
+        #[cfg(feature = "evername")]
+        resolvers.extend(self.evername_resolver.get().map(|resolver| resolver as &(dyn Resolver + Send + Sync)));
+        #[cfg(feature = "ud")]
+        resolvers.extend(self.ud_resolver.get().map(|resolver| resolver as &(dyn Resolver + Send + Sync)));
+        #[cfg(feature = "ens")]
+        resolvers.push(&self.ens_resolver);
+        resolvers.extend(self.custom_resolvers.iter().map(|custom| custom.resolver.as_ref()));
+        let mut capabilities = ResolverCapabilities {
+            tlds: self.config().tlds.into_keys().collect(),
+            ..ResolverCapabilities::default()
         };
-        if let Some(cache) = &self.dns_cache {
-            // do not cache onchain content
-            if address_tag != AddressTag::Onchain && address_tag != AddressTag::OnchainContract {
-                cache.insert(domain.clone(), (resolved_data.clone(), address_tag.clone()));
+        for resolver_capabilities in resolvers.iter().map(|resolver| resolver.capabilities()) {
+            for tag in resolver_capabilities.record_kinds {
+                if !capabilities.record_kinds.contains(&tag) {
+                    capabilities.record_kinds.push(tag);
+                }
             }
-        };
-        Ok((resolved_data, address_tag))
+            capabilities.addresses |= resolver_capabilities.addresses;
+            capabilities.reverse |= resolver_capabilities.reverse;
+        }
+        capabilities
     }
-}
\ No newline at end of file
+
+    async fn resolve_address(&self, domain: &str, coin: CoinType) -> Result<Option<String>> {
+        Web3DomainResolver::resolve_address(self, domain, coin).await
+    }
+
+    async fn reverse(&self, address: &str, chain_hint: Option<CoinType>) -> Result<Option<String>> {
+        let candidates = Web3DomainResolver::reverse(self, address, chain_hint).await?;
+        Ok(candidates.into_iter().next().map(|candidate| candidate.domain))
+    }
+}
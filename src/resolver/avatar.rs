@@ -0,0 +1,74 @@
+use std::fmt::{Display, Formatter};
+use anyhow::{anyhow, Result};
+use crate::resolver::ipfs::make_gateway_link;
+
+/// NFT token standard of an avatar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum NftStandard {
+    Erc721,
+    Erc1155,
+}
+
+/// NFT referenced by an avatar record, e.g. `eip155:1/erc721:0xb47e.../2430`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct NftAvatar {
+    pub chain_id: u64,
+    pub standard: NftStandard,
+    pub contract: String,
+    pub token_id: String,
+}
+
+impl Display for NftAvatar {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let standard = match self.standard {
+            NftStandard::Erc721 => "erc721",
+            NftStandard::Erc1155 => "erc1155",
+        };
+        write!(f, "eip155:{}/{}:{}/{}", self.chain_id, standard, self.contract, self.token_id)
+    }
+}
+
+/// Avatar record value: a fetchable link or an NFT whose image has to be looked up on chain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum AvatarUri {
+    Link(String),
+    Nft(NftAvatar),
+}
+
+/// Parses avatar URI of ENSIP-12 and Unstoppable Domains conventions: HTTP(S) and data URIs are used as is,
+/// IPFS ones are linked through the gateway, NFTs are written as `eip155:<chain>/<standard>:<contract>/<token id>`
+/// (Unstoppable Domains omit the `eip155:` prefix).
+pub(crate) fn parse_avatar_uri(value: &str, ipfs_gateway: &str) -> Result<AvatarUri> {
+    let value = value.trim();
+    let lowercase = value.to_lowercase();
+    if lowercase.starts_with("https://") || lowercase.starts_with("http://") || lowercase.starts_with("data:") {
+        return Ok(AvatarUri::Link(value.to_string()));
+    }
+    if lowercase.starts_with("ipfs://") || lowercase.starts_with("/ipfs/") {
+        return Ok(AvatarUri::Link(make_gateway_link(ipfs_gateway, value)));
+    }
+    parse_nft(&lowercase)
+        .map(AvatarUri::Nft)
+        .ok_or(anyhow!("Unsupported avatar URI {}", value))
+}
+
+fn parse_nft(value: &str) -> Option<NftAvatar> {
+    let value = value.strip_prefix("eip155:").unwrap_or(value);
+    let (chain_id, asset) = value.split_once('/')?;
+    let (standard, asset) = asset.split_once(':')?;
+    let (contract, token_id) = asset.split_once('/')?;
+    let standard = match standard {
+        "erc721" => NftStandard::Erc721,
+        "erc1155" => NftStandard::Erc1155,
+        _ => return None,
+    };
+    if !contract.starts_with("0x") || token_id.is_empty() || !token_id.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(NftAvatar {
+        chain_id: chain_id.parse().ok()?,
+        standard,
+        contract: contract.to_string(),
+        token_id: token_id.to_string(),
+    })
+}
@@ -1,28 +1,271 @@
+use std::sync::Arc;
+#[cfg(feature = "plugins")]
+use std::path::{Path, PathBuf};
 use anyhow::{anyhow, Result};
-use mini_moka::sync::Cache;
-use crate::resolver::evername::{EvernameResolver, EVERSCALE_RPC_ENDPOINT};
-use crate::resolver::ud::{UnstoppableDomainsResolver, UD_BASE_URL};
+use ed25519_dalek::SigningKey;
+#[cfg(any(feature = "evername", feature = "ens"))]
+use url::Url;
+#[cfg(feature = "evername")]
+use nekoton_utils::Clock;
+#[cfg(feature = "evername")]
+use crate::resolver::evername::{EvernameResolver, EvernameResolverBuilder};
+#[cfg(feature = "ud")]
+use crate::resolver::ud::UnstoppableDomainsResolverBuilder;
+#[cfg(feature = "ens")]
+use crate::resolver::ens::EnsResolverBuilder;
+use crate::models::{AmbiguityPolicy, CachedResult, DualResolution, ResolutionCandidate, ResolutionMode, ResolverConfig, WarmState};
+#[cfg(feature = "evername")]
+use crate::models::WeightedEndpoint;
+#[cfg(any(feature = "evername", feature = "ud", feature = "ens"))]
+use crate::models::{ConnectionPool, Timeouts};
+#[cfg(feature = "ens")]
+use crate::models::EnsContentSource;
+use crate::models::EndpointSelection;
+use crate::resolver::{CustomResolver, RankingFn, Resolver, MAX_CACHED_OPTION_VARIANTS};
+use crate::resolver::overrides::DomainOverrides;
+use crate::resolver::policy::{LiteralPrivateNetworkPolicy, ResolutionPolicy};
+use crate::resolver::rewrite::{ResultRewriter, Web2UrlNormalizer};
+use crate::resolver::pipeline::{Pipeline, PipelineStage, Stage};
+use crate::resolver::targets::TargetSelector;
+use crate::resolver::health::GatewayHealth;
+use crate::resolver::dns::DnsBackend;
+#[cfg(any(feature = "evername", feature = "ud", feature = "ens"))]
+use crate::resolver::limit::InFlightLimit;
+use crate::resolver::stale::StaleFallback;
+use crate::resolver::pinning::PinnedTargets;
+use crate::resolver::expiry::{CacheExpiry, ExpiringCache};
+use crate::resolver::confusables::ConfusableDetector;
+#[cfg(any(feature = "evername", feature = "ud"))]
+use crate::resolver::lazy::Lazy;
+#[cfg(feature = "plugins")]
+use crate::resolver::plugin::PluginResolver;
 use crate::Web3DomainResolver;
 
+#[derive(Clone)]
 pub struct DomainResolverBuilder {
-    eversacale_endpoint: String,
-    unstoppable_domain_base_url: String,
+    #[cfg(feature = "evername")]
+    evername: EvernameResolverBuilder,
+    #[cfg(feature = "evername")]
+    invalid_endpoint: Option<String>,
+    #[cfg(feature = "ud")]
+    ud: UnstoppableDomainsResolverBuilder,
+    #[cfg(feature = "ens")]
+    ens: EnsResolverBuilder,
     use_cache: bool,
     cache_ttl_seconds: Option<u64>,
+    cache_expiry: CacheExpiry,
+    max_option_variants: usize,
+    // resolution budget and max age of stale results
+    budget: Option<(std::time::Duration, std::time::Duration)>,
+    // max pin time and re-pointing check interval of immutable targets
+    pinning: Option<(std::time::Duration, std::time::Duration)>,
+    // popular domains confusable domains are checked against
+    popular_domains: Vec<String>,
+    custom_resolvers: Vec<CustomResolver>,
+    mode: ResolutionMode,
+    ranking: Option<RankingFn>,
+    ambiguity: AmbiguityPolicy,
+    precedence: Vec<String>,
+    explain: bool,
+    record_state_refs: bool,
+    record_raw_records: bool,
+    max_redirects: usize,
+    max_aliases: usize,
+    overrides: DomainOverrides,
+    overrides_files: Vec<std::path::PathBuf>,
+    policies: Vec<Arc<dyn ResolutionPolicy>>,
+    rewriters: Vec<Arc<dyn ResultRewriter>>,
+    web2_urls: Option<Web2UrlNormalizer>,
+    target_selector: Option<Arc<dyn TargetSelector>>,
+    ipfs_gateways: Vec<String>,
+    endpoint_selection: EndpointSelection,
+    pipeline: Pipeline,
+    signing_key: Option<SigningKey>,
+    dns_fallback: Option<Arc<dyn DnsBackend>>,
+    strict: bool,
+    dual_resolution: DualResolution,
+    warm_cache: Vec<CachedResult>,
+    #[cfg(any(feature = "evername", feature = "ud"))]
+    lazy: bool,
+    #[cfg(feature = "evername")]
+    quorum_endpoints: Vec<String>,
+    #[cfg(feature = "evername")]
+    evername_networks: Vec<EvernameResolverBuilder>,
+    #[cfg(feature = "plugins")]
+    plugin_paths: Vec<PathBuf>,
+    #[cfg(feature = "plugins")]
+    plugin_dirs: Vec<PathBuf>,
 }
 
 impl Default for DomainResolverBuilder {
     fn default() -> Self {
         DomainResolverBuilder {
-            eversacale_endpoint: EVERSCALE_RPC_ENDPOINT.to_string(),
-            unstoppable_domain_base_url: UD_BASE_URL.to_string(),
+            #[cfg(feature = "evername")]
+            evername: EvernameResolverBuilder::default(),
+            #[cfg(feature = "evername")]
+            invalid_endpoint: None,
+            #[cfg(feature = "ud")]
+            ud: UnstoppableDomainsResolverBuilder::default(),
+            #[cfg(feature = "ens")]
+            ens: EnsResolverBuilder::default(),
             use_cache: true,
             cache_ttl_seconds: Some(5 * 60),
+            cache_expiry: CacheExpiry::default(),
+            max_option_variants: MAX_CACHED_OPTION_VARIANTS,
+            budget: None,
+            pinning: None,
+            popular_domains: Vec::new(),
+            custom_resolvers: Vec::new(),
+            mode: ResolutionMode::default(),
+            ranking: None,
+            ambiguity: AmbiguityPolicy::default(),
+            precedence: Vec::new(),
+            explain: false,
+            record_state_refs: false,
+            record_raw_records: false,
+            max_redirects: 0,
+            max_aliases: 0,
+            overrides: DomainOverrides::default(),
+            overrides_files: Vec::new(),
+            policies: Vec::new(),
+            rewriters: Vec::new(),
+            web2_urls: None,
+            target_selector: None,
+            ipfs_gateways: Vec::new(),
+            endpoint_selection: EndpointSelection::default(),
+            pipeline: Pipeline::default(),
+            signing_key: None,
+            dns_fallback: None,
+            strict: false,
+            dual_resolution: DualResolution::default(),
+            warm_cache: Vec::new(),
+            #[cfg(any(feature = "evername", feature = "ud"))]
+            lazy: false,
+            #[cfg(feature = "evername")]
+            quorum_endpoints: Vec::new(),
+            #[cfg(feature = "evername")]
+            evername_networks: Vec::new(),
+            #[cfg(feature = "plugins")]
+            plugin_paths: Vec::new(),
+            #[cfg(feature = "plugins")]
+            plugin_dirs: Vec::new(),
         }
     }
 }
 
 impl DomainResolverBuilder {
+    /// Preset for HTTP gateways serving web3 sites: results are cached for 30 minutes and served stale
+    /// (up to a day old) when resolution exceeds a 2 seconds budget, Everscale account states and
+    /// Unstoppable Domains profiles are cached, onchain content is resolved, `LiteralPrivateNetworkPolicy`
+    /// blocks targets with internal IP literals or names (host names are not resolved) and web2 records are normalized into HTTPS URLs. Requests are bounded by timeouts and
+    /// connections are kept warm with `ConnectionPool::gateway()`. Any option can be overridden afterwards.
+    pub fn gateway_defaults() -> Self {
+        let builder = Self::default()
+            .cache_ttl_seconds(30 * 60)
+            .resolution_budget(std::time::Duration::from_secs(2), 24 * 60 * 60)
+            .with_policy(LiteralPrivateNetworkPolicy::new())
+            .normalize_web2_urls(Web2UrlNormalizer::new());
+        #[cfg(feature = "evername")]
+        let builder = builder
+            .cache_account_states(30, 10 * 60)
+            .content_records(true);
+        #[cfg(feature = "ud")]
+        let builder = builder.ud_profile_cache_seconds(5 * 60);
+        #[cfg(any(feature = "evername", feature = "ud", feature = "ens"))]
+        let builder = builder.with_timeouts(Timeouts::new()
+            .connect(std::time::Duration::from_secs(3))
+            .read(std::time::Duration::from_secs(10))
+            .total(std::time::Duration::from_secs(20)))
+            .with_connection_pool(ConnectionPool::gateway());
+        builder
+    }
+
+    /// Preset for wallets looking up addresses: onchain content records are not resolved,
+    /// results and Unstoppable Domains profiles are cached for a few seconds only, resolvers
+    /// are initialized lazily and requests are bounded by short timeouts.
+    /// Any option can be overridden afterwards.
+    pub fn wallet_defaults() -> Self {
+        let builder = Self::default().cache_ttl_seconds(30);
+        #[cfg(feature = "evername")]
+        let builder = builder.content_records(false);
+        #[cfg(feature = "ud")]
+        let builder = builder.ud_profile_cache_seconds(10);
+        #[cfg(any(feature = "evername", feature = "ud"))]
+        let builder = builder.lazy(true);
+        #[cfg(any(feature = "evername", feature = "ud", feature = "ens"))]
+        let builder = builder.with_timeouts(Timeouts::new()
+            .connect(std::time::Duration::from_secs(3))
+            .total(std::time::Duration::from_secs(10)));
+        builder
+    }
+
+    /// Builder applying a configuration exported by `Web3DomainResolver::config`: endpoints, gateways,
+    /// cache TTL, precedence, modes and limits. Custom resolvers, plugins, policies and the DNS fallback
+    /// are not part of the configuration and have to be set again.
+    pub fn from_config(config: &ResolverConfig) -> Result<Self> {
+        let builder = match config.cache_ttl_seconds {
+            Some(ttl) => Self::default().cache_ttl_seconds(ttl),
+            None => Self::default().no_cache(),
+        };
+        let precedence: Vec<&str> = config.precedence.iter().map(String::as_str).collect();
+        let builder = builder
+            .resolution_mode(config.mode)
+            .ambiguity_policy(config.ambiguity)
+            .with_resolver_precedence(&precedence)
+            .follow_redirects(config.max_redirects)
+            .follow_aliases(config.max_aliases)
+            .strict(config.strict);
+        #[cfg(feature = "evername")]
+        let builder = {
+            let endpoints = config.everscale_endpoints.iter()
+                .map(|endpoint| Url::parse(&endpoint.url)
+                    .map(|url| (url, endpoint.weight))
+                    .map_err(|e| anyhow!("Invalid Everscale JRPC endpoint {}: {}", endpoint.url, e)))
+                .collect::<Result<Vec<_>>>()?;
+            let quorum_endpoints: Vec<&str> = config.quorum_endpoints.iter().map(String::as_str).collect();
+            let builder = builder.with_quorum_endpoints(&quorum_endpoints);
+            match endpoints.is_empty() {
+                true => builder,
+                false => builder.with_weighted_everscale_endpoints(endpoints),
+            }
+        };
+        #[cfg(feature = "ud")]
+        let builder = {
+            let builder = builder.use_unstoppable_domains_sandbox(config.unstoppable_domains_sandbox);
+            match &config.unstoppable_domains_base_url {
+                Some(base_url) => builder.with_unstoppable_domain_base_url(base_url),
+                None => builder,
+            }
+        };
+        #[cfg(feature = "ens")]
+        let builder = match &config.ens_endpoint {
+            Some(endpoint) => builder.with_ethereum_endpoint(Url::parse(endpoint).map_err(|e| anyhow!("Invalid Ethereum endpoint {}: {}", endpoint, e))?),
+            None => builder,
+        };
+        #[cfg(any(feature = "evername", feature = "ud", feature = "ens"))]
+        let builder = {
+            let gateways: Vec<&str> = config.ipfs_gateways.iter().map(String::as_str).collect();
+            builder.with_ipfs_gateways(&gateways)
+        };
+        Ok(builder)
+    }
+
+    /// Fills the resolution cache with the results of a `WarmState` on build and uses its
+    /// Unstoppable Domains TLDs instead of fetching them, see `Web3DomainResolver::from_warm_state`.
+    pub fn with_warm_state(self, state: WarmState) -> Self {
+        #[cfg(feature = "ud")]
+        let ud = match state.unstoppable_domains_tlds.is_empty() {
+            true => self.ud,
+            false => self.ud.with_tld_metadata(state.unstoppable_domains_tlds),
+        };
+        Self {
+            #[cfg(feature = "ud")]
+            ud,
+            warm_cache: state.cache,
+            ..self
+        }
+    }
+
     pub fn no_cache(self) -> Self {
         Self {
             use_cache: false,
@@ -31,20 +274,92 @@ impl DomainResolverBuilder {
         }
     }
     
+    #[cfg(feature = "evername")]
+    #[deprecated(note = "use `with_everscale_endpoints`")]
     pub fn with_eversacale_endpoint(self, endpoint: &str) -> Self {
+        match Url::parse(endpoint) {
+            Ok(url) => self.with_everscale_endpoints(vec![url]),
+            Err(e) => Self {
+                invalid_endpoint: Some(format!("Invalid Everscale JRPC endpoint {}: {}", endpoint, e)),
+                ..self
+            },
+        }
+    }
+
+    /// Sets Everscale JRPC endpoints, requests are spread over them evenly
+    /// and retried on the other endpoints on failures.
+    #[cfg(feature = "evername")]
+    pub fn with_everscale_endpoints(self, endpoints: Vec<Url>) -> Self {
+        self.with_weighted_everscale_endpoints(endpoints.into_iter().map(|url| (url, 1)).collect())
+    }
+
+    /// Sets Everscale JRPC endpoints with weights, each endpoint gets a share of requests
+    /// proportional to its weight. Endpoints are validated on build.
+    #[cfg(feature = "evername")]
+    pub fn with_weighted_everscale_endpoints(self, endpoints: Vec<(Url, u32)>) -> Self {
         Self {
-            eversacale_endpoint: endpoint.to_string(),
+            evername: self.evername.with_weighted_everscale_endpoints(endpoints),
+            invalid_endpoint: None,
             ..self
         }
     }
     
+    #[cfg(feature = "ud")]
     pub fn with_unstoppable_domain_base_url(self, base_url: &str) -> Self {
         Self {
-            unstoppable_domain_base_url: base_url.to_string(),
+            ud: self.ud.with_base_url(base_url),
+            ..self
+        }
+    }
+
+    /// Points the Unstoppable Domains resolver at the sandbox API with testnet domains, only TLDs
+    /// the sandbox lists are routed to it, see `UnstoppableDomainsResolverBuilder::sandbox`.
+    #[cfg(feature = "ud")]
+    pub fn use_unstoppable_domains_sandbox(self, sandbox: bool) -> Self {
+        Self {
+            ud: self.ud.sandbox(sandbox),
+            ..self
+        }
+    }
+
+    /// Sets how many distinct `ResolveOptions` of `Web3DomainResolver::resolve_with` get their results cached,
+    /// results of further options are resolved uncached. Default is 64.
+    pub fn max_cached_option_variants(self, max_option_variants: usize) -> Self {
+        Self {
+            max_option_variants,
+            ..self
+        }
+    }
+
+    /// Sets time-to-live of cached Unstoppable Domains profiles, so resolving and reading records
+    /// of the same domain fetch its profile once. Default is 30 seconds, `0` disables the cache.
+    #[cfg(feature = "ud")]
+    pub fn ud_profile_cache_seconds(self, ud_profile_cache_seconds: u64) -> Self {
+        Self {
+            ud: self.ud.profile_cache_seconds(ud_profile_cache_seconds),
             ..self
         }
     }
     
+    /// Sets Ethereum JSON-RPC endpoint used to resolve ENS domains.
+    #[cfg(feature = "ens")]
+    pub fn with_ethereum_endpoint(self, endpoint: Url) -> Self {
+        Self {
+            ens: self.ens.with_ethereum_endpoint(endpoint),
+            ..self
+        }
+    }
+
+    /// Sets order of ENS content records for resolving, e.g. `&[EnsContentSource::Url, EnsContentSource::Contenthash]`.
+    /// Unlisted records are not consulted. Default is `EnsContentSource::resolvable()`.
+    #[cfg(feature = "ens")]
+    pub fn ens_content_priority(self, content_priority: &[EnsContentSource]) -> Self {
+        Self {
+            ens: self.ens.content_priority(content_priority),
+            ..self
+        }
+    }
+
     pub fn use_cache(self, use_cache: bool) -> Self {
         Self {
             use_cache,
@@ -59,18 +374,701 @@ impl DomainResolverBuilder {
         }
     }
 
+    /// Sets TTL jitter and early refresh of the resolution cache entries, see `CacheExpiry`.
+    /// Entries live exactly the TTL by default.
+    pub fn cache_expiry(self, cache_expiry: CacheExpiry) -> Self {
+        Self {
+            cache_expiry,
+            ..self
+        }
+    }
+
+    /// Enables Everscale account states caching: states are used as is for `fresh_seconds`,
+    /// then revalidated by the latest account transaction, and dropped after `max_age_seconds`.
+    #[cfg(feature = "evername")]
+    pub fn cache_account_states(self, fresh_seconds: u64, max_age_seconds: u64) -> Self {
+        Self {
+            evername: self.evername.cache_account_states(fresh_seconds, max_age_seconds),
+            ..self
+        }
+    }
+
+    /// Sets the clock used for Everscale get-methods execution.
+    #[cfg(feature = "evername")]
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            evername: self.evername.with_clock(clock),
+            ..self
+        }
+    }
+
+    /// Sets the wall-time budget of an Everscale get-method execution, 5 seconds by default.
+    #[cfg(feature = "evername")]
+    pub fn with_get_method_budget(self, budget: std::time::Duration) -> Self {
+        Self {
+            evername: self.evername.with_get_method_budget(budget),
+            ..self
+        }
+    }
+
+    /// Sets the number of Everscale get-methods executed at once, 16 by default.
+    #[cfg(feature = "evername")]
+    pub fn with_max_concurrent_get_methods(self, max_concurrent_get_methods: usize) -> Self {
+        Self {
+            evername: self.evername.with_max_concurrent_get_methods(max_concurrent_get_methods),
+            ..self
+        }
+    }
+
+    /// Retries Everscale lookups of accounts not deployed yet with backoff for up to `window`,
+    /// so freshly registered `.ever` domains resolve before reaching all RPC replicas.
+    #[cfg(feature = "evername")]
+    pub fn with_deployment_wait(self, window: std::time::Duration) -> Self {
+        Self {
+            evername: self.evername.with_deployment_wait(window),
+            ..self
+        }
+    }
+
+    /// Sets DomainRoot contract ABI to be tried before the bundled one.
+    #[cfg(feature = "evername")]
+    pub fn with_root_abi_json(self, abi_json: &str) -> Self {
+        Self {
+            evername: self.evername.with_root_abi_json(abi_json),
+            ..self
+        }
+    }
+
+    /// Sets Domain contract ABI to be tried before the bundled one.
+    #[cfg(feature = "evername")]
+    pub fn with_domain_abi_json(self, abi_json: &str) -> Self {
+        Self {
+            evername: self.evername.with_domain_abi_json(abi_json),
+            ..self
+        }
+    }
+
+    /// Sets Eversite contract ABI to be tried before the bundled one.
+    #[cfg(feature = "evername")]
+    pub fn with_onchain_site_abi_json(self, abi_json: &str) -> Self {
+        Self {
+            evername: self.evername.with_onchain_site_abi_json(abi_json),
+            ..self
+        }
+    }
+
+    /// Registers an additional resolver for the given TLDs (e.g. `&[".sol"]`).
+    /// Custom resolvers are consulted after the built-in ones.
+    pub fn with_resolver<R: Resolver + Send + Sync + 'static>(self, tlds: &[&str], resolver: R) -> Self {
+        let tlds = tlds.iter().map(|tld| tld.to_string()).collect();
+        let mut custom_resolvers = self.custom_resolvers;
+        custom_resolvers.push(CustomResolver::new(tlds, Arc::new(resolver)));
+        Self {
+            custom_resolvers,
+            ..self
+        }
+    }
+
+    /// Sets how `resolve` picks the result when several records match.
+    pub fn resolution_mode(self, mode: ResolutionMode) -> Self {
+        Self {
+            mode,
+            ..self
+        }
+    }
+
+    /// Sets ranking hook for aggregated results, candidates with higher rank are preferred.
+    pub fn with_ranking<F>(self, ranking: F) -> Self
+    where
+        F: Fn(&ResolutionCandidate) -> i64 + Send + Sync + 'static,
+    {
+        Self {
+            ranking: Some(Arc::new(ranking)),
+            ..self
+        }
+    }
+
+    /// Sets how domains with TLDs claimed by several resolvers are resolved.
+    pub fn ambiguity_policy(self, ambiguity: AmbiguityPolicy) -> Self {
+        Self {
+            ambiguity,
+            ..self
+        }
+    }
+
+    /// Sets precedence of resolvers by their source names, e.g. `["unstoppable-domains", "evername"]`,
+    /// for TLDs claimed by several resolvers. Unlisted resolvers follow in their priority order.
+    pub fn with_resolver_precedence(self, sources: &[&str]) -> Self {
+        Self {
+            precedence: sources.iter().map(|source| source.to_string()).collect(),
+            ..self
+        }
+    }
+
+    /// Enables explain mode: `resolve_report` includes the decision trail of each resolution.
+    pub fn explain(self, explain: bool) -> Self {
+        Self {
+            explain,
+            ..self
+        }
+    }
+
+    /// Follows redirect records (`AddressTag::Redirect`) to web3 domains, so one web3 name can alias another,
+    /// up to `max_depth` redirects. Redirect loops fail with `ResolverError::RedirectLoop`, longer chains with
+    /// `ResolverError::TooManyRedirects`. Redirects to web2 domains and redirects with `max_depth` 0 (default)
+    /// are returned as `ResolvedDomainData::Redirect`. `resolve_all` does not follow redirects.
+    pub fn follow_redirects(self, max_depth: usize) -> Self {
+        Self {
+            max_redirects: max_depth,
+            ..self
+        }
+    }
+
+    /// Follows records whose value is another web3 domain, e.g. a `.ever` domain pointing to a `.crypto` one,
+    /// resolving them transitively up to `max_depth` aliases. The followed domains are reported in
+    /// `ResolutionReport::chain`. Alias loops fail with `ResolverError::AliasLoop`, longer chains with
+    /// `ResolverError::TooManyAliases`. With `max_depth` 0 (default) such values are returned as is.
+    pub fn follow_aliases(self, max_depth: usize) -> Self {
+        Self {
+            max_aliases: max_depth,
+            ..self
+        }
+    }
+
+    /// Enables recording of the Everscale account states (address, last transaction lt and state hash) read
+    /// by Evername resolutions into `ResolutionReport::state_refs`, so static site pipelines can prove which
+    /// on-chain state produced a published artifact. Results taken from the cache have no state references.
+    pub fn record_state_refs(self, record_state_refs: bool) -> Self {
+        Self {
+            record_state_refs,
+            ..self
+        }
+    }
+
+    /// Includes the raw record cells the result is decoded from into `ResolutionReport::raw_records`
+    /// as base64 BOCs, so verification tools can re-decode and audit the result independently.
+    /// Results taken from the cache have no raw records.
+    pub fn record_raw_records(self, record_raw_records: bool) -> Self {
+        Self {
+            record_raw_records,
+            ..self
+        }
+    }
+
+    /// Sets resolution budget: if the upstream resolution takes longer, the last known result
+    /// (up to `max_stale_seconds` old, even if expired in the resolution cache) is returned
+    /// and the domain is queued for refresh, see `Web3DomainResolver::spawn_stale_refresh`.
+    /// Domains without a known result wait for the resolution as usual.
+    pub fn resolution_budget(self, budget: std::time::Duration, max_stale_seconds: u64) -> Self {
+        Self {
+            budget: Some((budget, std::time::Duration::from_secs(max_stale_seconds))),
+            ..self
+        }
+    }
+
+    /// Pins results resolved into immutable targets (IPFS CIDs, onion addresses) for up to `max_pin_seconds`,
+    /// beyond the resolution cache TTL. Pinned domains are re-resolved only every `recheck_seconds`
+    /// to detect the record re-pointing to another target. Onchain content is never pinned.
+    pub fn pin_immutable_targets(self, max_pin_seconds: u64, recheck_seconds: u64) -> Self {
+        Self {
+            pinning: Some((std::time::Duration::from_secs(max_pin_seconds), std::time::Duration::from_secs(recheck_seconds))),
+            ..self
+        }
+    }
+
+    /// Enables detection of domains visually confusable with the popular ones (homoglyphs, e.g. a Cyrillic `а`
+    /// in place of the Latin `a`): reports of such domains get `ResolutionWarning::Confusable`.
+    pub fn with_confusable_detection(self, popular_domains: Vec<String>) -> Self {
+        Self {
+            popular_domains,
+            ..self
+        }
+    }
+
+    /// Sets local overrides table consulted before any resolver.
+    pub fn with_overrides(self, overrides: DomainOverrides) -> Self {
+        Self {
+            overrides,
+            ..self
+        }
+    }
+
+    /// Loads overrides from TOML file on build, entries are added to `with_overrides` ones.
+    pub fn with_overrides_file<P: AsRef<std::path::Path>>(self, path: P) -> Self {
+        let mut overrides_files = self.overrides_files;
+        overrides_files.push(path.as_ref().to_path_buf());
+        Self {
+            overrides_files,
+            ..self
+        }
+    }
+
+    /// Adds resolution policy, policies are invoked in registration order.
+    /// Domain policies also apply to address, TXT record and avatar lookups and filter reverse lookups.
+    pub fn with_policy<P: ResolutionPolicy + 'static>(self, policy: P) -> Self {
+        let mut policies = self.policies;
+        policies.push(Arc::new(policy));
+        Self {
+            policies,
+            ..self
+        }
+    }
+
+    /// Adds result rewriter transforming resolved targets before caching, rewriters are invoked in registration order.
+    pub fn with_result_rewriter<W: ResultRewriter + 'static>(self, rewriter: W) -> Self {
+        let mut rewriters = self.rewriters;
+        rewriters.push(Arc::new(rewriter));
+        Self {
+            rewriters,
+            ..self
+        }
+    }
+
+    /// Normalizes web2 records into well-formed URLs, e.g. `example.com:8080/app` into
+    /// `https://example.com:8080/app`, before other result rewriters run. See `Web2UrlNormalizer`.
+    pub fn normalize_web2_urls(self, normalizer: Web2UrlNormalizer) -> Self {
+        Self {
+            web2_urls: Some(normalizer),
+            ..self
+        }
+    }
+
+    /// Sets target selector choosing among several candidate targets of a domain, e.g. geo-aware
+    /// or RTT-probing selection. Default is `WeightedSelector`, by priority and weight of the targets.
+    pub fn with_target_selector<S: TargetSelector + 'static>(self, selector: S) -> Self {
+        Self {
+            target_selector: Some(Arc::new(selector)),
+            ..self
+        }
+    }
+
+    /// Inserts custom stage into the resolution pipeline after the built-in stage, e.g. a blocklist check
+    /// after `Stage::Normalize`. Stages inserted after the same stage run in registration order.
+    pub fn with_pipeline_stage<S: PipelineStage + 'static>(self, after: Stage, stage: S) -> Self {
+        let mut pipeline = self.pipeline;
+        pipeline.insert_after(after, Arc::new(stage));
+        Self {
+            pipeline,
+            ..self
+        }
+    }
+
+    /// Replaces the built-in stage of the resolution pipeline with a custom one,
+    /// e.g. `Stage::Cache` with a store shared by several resolver instances.
+    pub fn replace_pipeline_stage<S: PipelineStage + 'static>(self, stage: Stage, replacement: S) -> Self {
+        let mut pipeline = self.pipeline;
+        pipeline.replace(stage, Arc::new(replacement));
+        Self {
+            pipeline,
+            ..self
+        }
+    }
+
+    /// Sets ed25519 key used to sign resolution reports, so clients of a hosted resolver
+    /// can verify results with `ResolutionReport::verify_signature`.
+    pub fn with_signing_key(self, signing_key: SigningKey) -> Self {
+        Self {
+            signing_key: Some(signing_key),
+            ..self
+        }
+    }
+
+    /// Resolves non-web3 domains with the DNS backend (`SystemDns`, `DohDns` or a custom `DnsBackend`) into
+    /// `ResolvedDomainData::DnsRecords` instead of passing them through as `DomainString`.
+    pub fn with_dns_fallback<D: DnsBackend + 'static>(self, dns: D) -> Self {
+        Self {
+            dns_fallback: Some(Arc::new(dns)),
+            ..self
+        }
+    }
+
+    /// Enables strict mode: domains of TLDs no resolver handles fail with `ResolverError::UnsupportedTld`
+    /// instead of being passed through as non-web3 ones. Domains resolved with the DNS fallback are not affected.
+    pub fn strict(self, strict: bool) -> Self {
+        Self {
+            strict,
+            ..self
+        }
+    }
+
+    /// Sets TLDs existing both in a web3 registry and ICANN DNS, e.g. `.art`, which are resolved both ways
+    /// with the configured precedence, e.g. `DualResolution::new(DualPrecedence::Web3).tld(".art")`.
+    pub fn dual_resolution(self, dual_resolution: DualResolution) -> Self {
+        Self {
+            dual_resolution,
+            ..self
+        }
+    }
+
+    /// Enables quorum mode: Evername domains are additionally resolved against each of the given
+    /// independent JRPC endpoints, and `ResolverError::QuorumMismatch` is returned unless all results
+    /// agree with the main endpoint one. Applies to `resolve`, `resolve_report` and `explain`.
+    #[cfg(feature = "evername")]
+    pub fn with_quorum_endpoints(self, endpoints: &[&str]) -> Self {
+        Self {
+            quorum_endpoints: endpoints.iter().map(|endpoint| endpoint.to_string()).collect(),
+            ..self
+        }
+    }
+
+    /// Enables or disables resolving of Evername onchain content records (`1004` and `1005`),
+    /// so resolution never loads site content from Eversite contracts. Enabled by default.
+    #[cfg(feature = "evername")]
+    pub fn content_records(self, content_records: bool) -> Self {
+        Self {
+            evername: self.evername.content_records(content_records),
+            ..self
+        }
+    }
+
+    /// Sets content type of onchain contract content used when the Eversite contract omits it
+    /// and the content is not recognized as HTML, SVG, JSON or CSS.
+    #[cfg(feature = "evername")]
+    pub fn with_default_content_type(self, content_type: &str) -> Self {
+        Self {
+            evername: self.evername.with_default_content_type(content_type),
+            ..self
+        }
+    }
+
+    /// Sets IPFS gateway used for IPFS records of Evername, Unstoppable Domains and ENS, `{cid}` is replaced
+    /// with the content hash, otherwise the hash is appended to the gateway path.
+    #[cfg(any(feature = "evername", feature = "ud", feature = "ens"))]
+    pub fn with_ipfs_gateway(self, gateway: &str) -> Self {
+        Self {
+            #[cfg(feature = "evername")]
+            evername: self.evername.with_ipfs_gateway(gateway),
+            #[cfg(feature = "ud")]
+            ud: self.ud.with_ipfs_gateway(gateway),
+            #[cfg(feature = "ens")]
+            ens: self.ens.with_ipfs_gateway(gateway),
+            ..self
+        }
+    }
+
+    /// Sets several IPFS gateways: IPFS records resolve on the first one, all of them are offered to the
+    /// target selector as candidate targets (see `with_target_selector`) and tried in order by `fetch_with_failover`.
+    #[cfg(any(feature = "evername", feature = "ud", feature = "ens"))]
+    pub fn with_ipfs_gateways(self, gateways: &[&str]) -> Self {
+        let Some(primary) = gateways.first() else {
+            return self;
+        };
+        Self {
+            ipfs_gateways: gateways.iter().map(|gateway| gateway.to_string()).collect(),
+            ..self.with_ipfs_gateway(primary)
+        }
+    }
+
+    /// Sets indexer of the Evername collection used by `Web3DomainResolver::search`,
+    /// see `EvernameResolver::with_search_indexer`.
+    #[cfg(feature = "evername")]
+    pub fn with_evername_search_indexer(self, indexer: Url) -> Self {
+        Self {
+            evername: self.evername.with_search_indexer(indexer),
+            ..self
+        }
+    }
+
+    /// Registers an additional Evername-style deployment (testnet `.ever-test`, `.venom`) with its own
+    /// endpoints, DomainRoot address and TLD, set by `EvernameResolverBuilder::with_root_address` and
+    /// `with_tld`. Domains are dispatched to the deployment by their TLD, like custom resolvers.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # async fn example() -> anyhow::Result<()> {
+    /// use web3_resolver::{DomainResolverBuilder, EvernameResolver, Url};
+    ///
+    /// let domain_resolver = DomainResolverBuilder::default()
+    ///     .with_evername_network(EvernameResolver::builder()
+    ///         .with_everscale_endpoints(vec![Url::parse("https://jrpc-testnet.everwallet.net/rpc")?])
+    ///         .with_root_address("0:...")
+    ///         .with_tld(".ever-test"))
+    ///     .build().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "evername")]
+    pub fn with_evername_network(self, network: EvernameResolverBuilder) -> Self {
+        let mut evername_networks = self.evername_networks;
+        evername_networks.push(network);
+        Self {
+            evername_networks,
+            ..self
+        }
+    }
+
+    /// Sets how requests are spread over Everscale JRPC endpoints and IPFS gateways: by weights (default)
+    /// or to the endpoints with the best latency and error rate.
+    pub fn endpoint_selection(self, selection: EndpointSelection) -> Self {
+        Self {
+            #[cfg(feature = "evername")]
+            evername: self.evername.endpoint_selection(selection),
+            endpoint_selection: selection,
+            ..self
+        }
+    }
+
+    /// Sets connect, read and total timeouts of Everscale JRPC, Unstoppable Domains HTTP and Ethereum JSON-RPC requests.
+    /// Slow reads of a JRPC endpoint are only caught by the read or total timeout.
+    #[cfg(any(feature = "evername", feature = "ud", feature = "ens"))]
+    pub fn with_timeouts(self, timeouts: Timeouts) -> Self {
+        Self {
+            #[cfg(feature = "evername")]
+            evername: self.evername.with_timeouts(timeouts),
+            #[cfg(feature = "ud")]
+            ud: self.ud.with_timeouts(timeouts),
+            #[cfg(feature = "ens")]
+            ens: self.ens.with_timeouts(timeouts),
+            ..self
+        }
+    }
+
+    /// Bounds in-flight upstream calls of all resolvers: Everscale JRPC, Unstoppable Domains HTTP
+    /// and Ethereum JSON-RPC requests. Calls over the limit wait for a permit.
+    #[cfg(any(feature = "evername", feature = "ud", feature = "ens"))]
+    pub fn max_in_flight(self, max_in_flight: usize) -> Self {
+        self.with_in_flight_limit(InFlightLimit::new(max_in_flight))
+    }
+
+    /// Same as `max_in_flight`, the limit may be shared with other resolvers of the process.
+    #[cfg(any(feature = "evername", feature = "ud", feature = "ens"))]
+    pub fn with_in_flight_limit(self, in_flight: InFlightLimit) -> Self {
+        Self {
+            #[cfg(feature = "evername")]
+            evername: self.evername.with_in_flight_limit(in_flight.clone()),
+            #[cfg(feature = "ud")]
+            ud: self.ud.with_in_flight_limit(in_flight.clone()),
+            #[cfg(feature = "ens")]
+            ens: self.ens.with_in_flight_limit(in_flight),
+            ..self
+        }
+    }
+
+    /// Sets keep-alive and idle connection settings of Everscale JRPC, Unstoppable Domains HTTP
+    /// and Ethereum JSON-RPC clients, see `ConnectionPool`.
+    #[cfg(any(feature = "evername", feature = "ud", feature = "ens"))]
+    pub fn with_connection_pool(self, connection_pool: ConnectionPool) -> Self {
+        Self {
+            #[cfg(feature = "evername")]
+            evername: self.evername.with_connection_pool(connection_pool),
+            #[cfg(feature = "ud")]
+            ud: self.ud.with_connection_pool(connection_pool),
+            #[cfg(feature = "ens")]
+            ens: self.ens.with_connection_pool(connection_pool),
+            ..self
+        }
+    }
+
+    /// Enables lazy construction: Evername and Unstoppable Domains resolvers are initialized on first use
+    /// instead of on build, and their initialization errors are returned from the resolution requests.
+    /// Unstoppable Domains TLDs are fetched on the first request of a domain with an unknown TLD.
+    #[cfg(any(feature = "evername", feature = "ud"))]
+    pub fn lazy(self, lazy: bool) -> Self {
+        Self {
+            lazy,
+            ..self
+        }
+    }
+
+    /// Loads resolver plugin from the dynamic library at the given path on build.
+    ///
+    /// # Safety
+    /// The library is loaded with `PluginResolver::load` and must satisfy its contract.
+    #[cfg(feature = "plugins")]
+    pub unsafe fn with_plugin<P: AsRef<Path>>(self, path: P) -> Self {
+        let mut plugin_paths = self.plugin_paths;
+        plugin_paths.push(path.as_ref().to_path_buf());
+        Self {
+            plugin_paths,
+            ..self
+        }
+    }
+
+    /// Loads all resolver plugins found in the given directory on build.
+    ///
+    /// # Safety
+    /// Every library in the directory is loaded with `PluginResolver::load` and must satisfy its contract.
+    #[cfg(feature = "plugins")]
+    pub unsafe fn with_plugin_dir<P: AsRef<Path>>(self, dir: P) -> Self {
+        let mut plugin_dirs = self.plugin_dirs;
+        plugin_dirs.push(dir.as_ref().to_path_buf());
+        Self {
+            plugin_dirs,
+            ..self
+        }
+    }
+
+    /// Creates Evername resolver for the endpoints with configured caching, clock and ABIs.
+    #[cfg(feature = "evername")]
+    fn build_evername(&self, endpoints: Vec<(Url, u32)>) -> Result<EvernameResolver> {
+        self.evername.clone().with_weighted_everscale_endpoints(endpoints).build()
+    }
+
     pub async fn build(&self) -> Result<Web3DomainResolver> {
-        let ud_resolver = UnstoppableDomainsResolver::new(&self.unstoppable_domain_base_url).await?;
-        let evername_resolver = EvernameResolver::new(&self.eversacale_endpoint)?;
+        #[cfg(feature = "ud")]
+        let ud_resolver = if self.lazy {
+            let ud_builder = self.ud.clone();
+            Lazy::new(move || {
+                let ud_builder = ud_builder.clone();
+                Box::pin(async move { ud_builder.build().await })
+            })
+        } else {
+            Lazy::ready(self.ud.build().await?)
+        };
+        #[cfg(feature = "evername")]
+        if let Some(error) = &self.invalid_endpoint {
+            return Err(anyhow!("{}", error));
+        }
+        #[cfg(feature = "evername")]
+        let evername_resolver = if self.lazy {
+            self.evername.validate()?;
+            let evername_builder = self.evername.clone();
+            Lazy::new(move || {
+                let evername_resolver = evername_builder.build();
+                Box::pin(async move { evername_resolver })
+            })
+        } else {
+            Lazy::ready(self.evername.build()?)
+        };
+        #[cfg(feature = "ens")]
+        let ens_resolver = self.ens.build()?;
+        #[cfg(feature = "evername")]
+        let quorum = self.quorum_endpoints.iter()
+            .map(|endpoint| {
+                let url = Url::parse(endpoint).map_err(|e| anyhow!("Invalid quorum endpoint {}: {}", endpoint, e))?;
+                Ok((endpoint.clone(), self.build_evername(vec![(url, 1)])?))
+            })
+            .collect::<Result<Vec<_>>>()?;
         let dns_cache = match (self.use_cache, self.cache_ttl_seconds) {
-            (true, Some(ttl)) if ttl > 0 => Some(Cache::builder()
-                .time_to_live(std::time::Duration::from_secs(ttl))
-                .build()),
+            (true, Some(ttl)) if ttl > 0 => Some(ExpiringCache::new(std::time::Duration::from_secs(ttl), self.cache_expiry)),
             (true, ttl_val) => {
                 return Err(anyhow!("Cache is on, but TTL is not set or invalid: {:?}", ttl_val));
             }
             (false, _) => None,
         };
-        Ok(Web3DomainResolver::new(ud_resolver, evername_resolver, dns_cache))
+        #[allow(unused_mut)]
+        let mut custom_resolvers = self.custom_resolvers.clone();
+        #[cfg(feature = "evername")]
+        let evername_networks = self.evername_networks.iter()
+            .map(|network| Ok(Arc::new(network.build()?)))
+            .collect::<Result<Vec<Arc<EvernameResolver>>>>()?;
+        #[cfg(feature = "evername")]
+        for network_resolver in &evername_networks {
+            let tlds = vec![network_resolver.tld().to_string()];
+            custom_resolvers.push(CustomResolver::new(tlds, network_resolver.clone()));
+        }
+        #[cfg(feature = "plugins")]
+        {
+            let mut plugins = Vec::new();
+            // SAFETY: plugin paths are only set by the unsafe `with_plugin` and `with_plugin_dir`
+            for path in &self.plugin_paths {
+                plugins.push(unsafe { PluginResolver::load(path)? });
+            }
+            for dir in &self.plugin_dirs {
+                plugins.extend(unsafe { PluginResolver::discover(dir)? });
+            }
+            for plugin in plugins {
+                custom_resolvers.push(CustomResolver::new(plugin.get_tlds(), Arc::new(plugin)));
+            }
+        }
+        let mut resolver = Web3DomainResolver::new(
+            #[cfg(feature = "ud")]
+            ud_resolver,
+            #[cfg(feature = "evername")]
+            evername_resolver,
+            #[cfg(feature = "ens")]
+            ens_resolver,
+            custom_resolvers,
+            dns_cache,
+        );
+        resolver.mode = self.mode;
+        resolver.max_option_variants = self.max_option_variants;
+        resolver.ranking = self.ranking.clone();
+        resolver.ambiguity = self.ambiguity;
+        resolver.stale_fallback = self.budget.map(|(budget, max_stale)| StaleFallback::new(budget, max_stale));
+        resolver.pinned_targets = self.pinning.map(|(max_pin, recheck)| PinnedTargets::new(max_pin, recheck));
+        resolver.confusables = ConfusableDetector::new(&self.popular_domains);
+        if !self.precedence.is_empty() {
+            resolver.precedence = self.precedence.clone();
+            resolver.reindex_tlds();
+        }
+        resolver.explain = self.explain;
+        resolver.record_state_refs = self.record_state_refs;
+        resolver.record_raw_records = self.record_raw_records;
+        resolver.max_redirects = self.max_redirects;
+        resolver.max_aliases = self.max_aliases;
+        resolver.overrides = self.overrides.clone();
+        for path in &self.overrides_files {
+            resolver.overrides.extend(DomainOverrides::from_file(path)?);
+        }
+        resolver.policies = self.policies.clone();
+        resolver.rewriters = self.web2_urls.iter()
+            .map(|normalizer| Arc::new(normalizer.clone()) as Arc<dyn ResultRewriter>)
+            .chain(self.rewriters.iter().cloned())
+            .collect();
+        if let Some(target_selector) = &self.target_selector {
+            resolver.target_selector = target_selector.clone();
+        }
+        resolver.ipfs_gateways = self.ipfs_gateways.clone();
+        resolver.gateway_health = GatewayHealth::new(self.ipfs_gateways.len(), self.endpoint_selection);
+        resolver.pipeline = self.pipeline.clone();
+        resolver.signing_key = self.signing_key.clone();
+        resolver.dns_fallback = self.dns_fallback.clone();
+        resolver.strict = self.strict;
+        resolver.dual_resolution = self.dual_resolution.clone();
+        resolver.import_cache(&self.warm_cache);
+        #[cfg(feature = "evername")]
+        {
+            resolver.quorum = quorum;
+            resolver.evername_networks = evername_networks;
+            resolver.everscale_endpoints = self.evername.endpoints().iter()
+                .map(|(url, weight)| WeightedEndpoint { url: url.to_string(), weight: *weight })
+                .collect();
+        }
+        #[cfg(feature = "ud")]
+        {
+            resolver.ud_base_url = self.ud.base_url().to_string();
+            resolver.ud_sandbox = self.ud.is_sandbox();
+        }
+        Ok(resolver)
     }
-}
\ No newline at end of file
+}
+#[cfg(all(test, any(feature = "evername", feature = "ud")))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn config_round_trip() {
+        let builder = DomainResolverBuilder::default()
+            .lazy(true)
+            .resolution_mode(ResolutionMode::Aggregate)
+            .ambiguity_policy(AmbiguityPolicy::Reject);
+        #[cfg(feature = "evername")]
+        let builder = builder.with_weighted_everscale_endpoints(vec![
+            (Url::parse("https://a.example/rpc").unwrap(), 3),
+            (Url::parse("https://b.example/rpc").unwrap(), 1),
+        ]);
+        #[cfg(feature = "ud")]
+        let builder = builder.use_unstoppable_domains_sandbox(true);
+        // lazy resolvers are not created yet, endpoints come from the builder settings
+        let config = builder.build().await.unwrap().config();
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(json["mode"], "aggregate");
+        assert_eq!(json["ambiguity"], "reject");
+        #[cfg(feature = "evername")]
+        assert_eq!(config.everscale_endpoints, vec![
+            WeightedEndpoint { url: "https://a.example/rpc".to_string(), weight: 3 },
+            WeightedEndpoint { url: "https://b.example/rpc".to_string(), weight: 1 },
+        ]);
+        #[cfg(feature = "ud")]
+        {
+            assert_eq!(config.unstoppable_domains_base_url.as_deref(), Some(crate::resolver::ud::UD_SANDBOX_BASE_URL));
+            assert!(config.unstoppable_domains_sandbox);
+        }
+        let restored = DomainResolverBuilder::from_config(&config).unwrap()
+            .lazy(true)
+            .build().await.unwrap()
+            .config();
+        assert_eq!(restored, config);
+    }
+}
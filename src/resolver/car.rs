@@ -0,0 +1,276 @@
+use std::collections::{BTreeMap, HashSet};
+use anyhow::{anyhow, Result};
+use sha2::{Digest, Sha256};
+use crate::resolver::ipfs::base32_lower;
+
+const RAW_CODEC: u64 = 0x55;
+const DAG_PB_CODEC: u64 = 0x70;
+const SHA2_256: u64 = 0x12;
+// chunking and raw leaves of `ipfs add --cid-version=1`, so mirrored files get the same CIDs
+const CHUNK_SIZE: usize = 256 * 1024;
+const UNIXFS_DIRECTORY: u64 = 1;
+const UNIXFS_FILE: u64 = 2;
+
+enum Entry {
+    File(Vec<u8>),
+    Directory(BTreeMap<String, Entry>),
+}
+
+/// UnixFS directory tree packaged into a CARv1 archive: files are chunked into raw leaves,
+/// directories are dag-pb nodes, all blocks are addressed by CIDv1 with sha2-256.
+#[derive(Default)]
+pub(crate) struct UnixFsDirectory {
+    entries: BTreeMap<String, Entry>,
+}
+
+impl UnixFsDirectory {
+    /// Adds file at the given path, e.g. `/css/style.css`, creating parent directories.
+    pub fn add_file(&mut self, path: &str, content: Vec<u8>) -> Result<()> {
+        let mut segments: Vec<&str> = path.split('/').filter(|segment| !segment.is_empty()).collect();
+        let name = segments.pop().ok_or(anyhow!("Empty file path"))?;
+        if segments.iter().chain([&name]).any(|segment| *segment == "." || *segment == "..") {
+            return Err(anyhow!("Relative file path {}", path));
+        }
+        let mut entries = &mut self.entries;
+        for segment in segments {
+            let entry = entries.entry(segment.to_string()).or_insert_with(|| Entry::Directory(BTreeMap::new()));
+            entries = match entry {
+                Entry::Directory(children) => children,
+                Entry::File(_) => return Err(anyhow!("{} is both a file and a directory", segment)),
+            };
+        }
+        if entries.insert(name.to_string(), Entry::File(content)).is_some() {
+            return Err(anyhow!("Duplicate file {}", path));
+        }
+        Ok(())
+    }
+
+    /// Encodes the directory into CARv1 archive, returns the root CID and the archive bytes.
+    pub fn into_car(self) -> (String, Vec<u8>) {
+        let mut blocks = Vec::new();
+        let (root, _) = write_directory(self.entries, &mut blocks);
+
+        let mut car = Vec::new();
+        let header = car_header(&root);
+        write_varint(&mut car, header.len() as u64);
+        car.extend(header);
+        // root first, then children, identical blocks are stored once
+        let mut written = HashSet::new();
+        for (cid, data) in blocks.into_iter().rev() {
+            if written.insert(cid.clone()) {
+                write_varint(&mut car, (cid.len() + data.len()) as u64);
+                car.extend(cid);
+                car.extend(data);
+            }
+        }
+        (cid_string(&root), car)
+    }
+}
+
+/// Writes blocks of the entry, returns its CID and cumulative size of its blocks.
+fn write_entry(entry: Entry, blocks: &mut Vec<(Vec<u8>, Vec<u8>)>) -> (Vec<u8>, u64) {
+    match entry {
+        Entry::File(content) => write_file(content, blocks),
+        Entry::Directory(entries) => write_directory(entries, blocks),
+    }
+}
+
+fn write_file(content: Vec<u8>, blocks: &mut Vec<(Vec<u8>, Vec<u8>)>) -> (Vec<u8>, u64) {
+    if content.len() <= CHUNK_SIZE {
+        let cid = cid(RAW_CODEC, &content);
+        let size = content.len() as u64;
+        blocks.push((cid.clone(), content));
+        return (cid, size);
+    }
+    let mut links = Vec::new();
+    let mut block_sizes = Vec::new();
+    for chunk in content.chunks(CHUNK_SIZE) {
+        let cid = cid(RAW_CODEC, chunk);
+        links.push((cid.clone(), String::new(), chunk.len() as u64));
+        block_sizes.push(chunk.len() as u64);
+        blocks.push((cid, chunk.to_vec()));
+    }
+    let node = pb_node(&links, &unixfs_data(UNIXFS_FILE, Some(content.len() as u64), &block_sizes));
+    finish_node(node, &links, blocks)
+}
+
+fn write_directory(entries: BTreeMap<String, Entry>, blocks: &mut Vec<(Vec<u8>, Vec<u8>)>) -> (Vec<u8>, u64) {
+    // dag-pb links are sorted by name bytes, as the map is
+    let links: Vec<(Vec<u8>, String, u64)> = entries.into_iter()
+        .map(|(name, entry)| {
+            let (cid, size) = write_entry(entry, blocks);
+            (cid, name, size)
+        })
+        .collect();
+    let node = pb_node(&links, &unixfs_data(UNIXFS_DIRECTORY, None, &[]));
+    finish_node(node, &links, blocks)
+}
+
+fn finish_node(node: Vec<u8>, links: &[(Vec<u8>, String, u64)], blocks: &mut Vec<(Vec<u8>, Vec<u8>)>) -> (Vec<u8>, u64) {
+    let cid = cid(DAG_PB_CODEC, &node);
+    let size = node.len() as u64 + links.iter().map(|(_, _, size)| size).sum::<u64>();
+    blocks.push((cid.clone(), node));
+    (cid, size)
+}
+
+fn cid(codec: u64, data: &[u8]) -> Vec<u8> {
+    let mut cid = Vec::with_capacity(36);
+    write_varint(&mut cid, 1);
+    write_varint(&mut cid, codec);
+    write_varint(&mut cid, SHA2_256);
+    write_varint(&mut cid, 32);
+    cid.extend_from_slice(&Sha256::digest(data));
+    cid
+}
+
+fn cid_string(cid: &[u8]) -> String {
+    format!("b{}", base32_lower(cid))
+}
+
+/// UnixFS `Data` message.
+fn unixfs_data(data_type: u64, file_size: Option<u64>, block_sizes: &[u64]) -> Vec<u8> {
+    let mut data = Vec::new();
+    write_varint_field(&mut data, 1, data_type);
+    if let Some(file_size) = file_size {
+        write_varint_field(&mut data, 3, file_size);
+    }
+    for block_size in block_sizes {
+        write_varint_field(&mut data, 4, *block_size);
+    }
+    data
+}
+
+/// dag-pb `PBNode` message, links are encoded before data as the spec requires.
+fn pb_node(links: &[(Vec<u8>, String, u64)], data: &[u8]) -> Vec<u8> {
+    let mut node = Vec::new();
+    for (cid, name, size) in links {
+        let mut link = Vec::new();
+        write_bytes_field(&mut link, 1, cid);
+        write_bytes_field(&mut link, 2, name.as_bytes());
+        write_varint_field(&mut link, 3, *size);
+        write_bytes_field(&mut node, 2, &link);
+    }
+    write_bytes_field(&mut node, 1, data);
+    node
+}
+
+/// dag-cbor `{"roots": [root], "version": 1}`.
+fn car_header(root: &[u8]) -> Vec<u8> {
+    let mut header = vec![0xa2, 0x65];
+    header.extend_from_slice(b"roots");
+    // array of one CID: tag 42 over bytes with the identity multibase prefix
+    header.extend_from_slice(&[0x81, 0xd8, 0x2a, 0x58, (root.len() + 1) as u8, 0x00]);
+    header.extend_from_slice(root);
+    header.push(0x67);
+    header.extend_from_slice(b"version");
+    header.push(0x01);
+    header
+}
+
+fn write_varint_field(buffer: &mut Vec<u8>, field: u64, value: u64) {
+    write_varint(buffer, field << 3);
+    write_varint(buffer, value);
+}
+
+fn write_bytes_field(buffer: &mut Vec<u8>, field: u64, value: &[u8]) {
+    write_varint(buffer, (field << 3) | 2);
+    write_varint(buffer, value.len() as u64);
+    buffer.extend_from_slice(value);
+}
+
+/// Writes unsigned LEB128 varint.
+fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_varint(data: &[u8], offset: &mut usize) -> u64 {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let byte = data[*offset];
+            *offset += 1;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte < 0x80 {
+                return value;
+            }
+            shift += 7;
+        }
+    }
+
+    /// Sections of the archive after the header: CID followed by the block data.
+    fn sections(car: &[u8]) -> Vec<Vec<u8>> {
+        let mut offset = 0;
+        let header_len = read_varint(car, &mut offset) as usize;
+        offset += header_len;
+        let mut sections = Vec::new();
+        while offset < car.len() {
+            let len = read_varint(car, &mut offset) as usize;
+            sections.push(car[offset..offset + len].to_vec());
+            offset += len;
+        }
+        sections
+    }
+
+    #[test]
+    fn varints() {
+        let mut buffer = Vec::new();
+        write_varint(&mut buffer, 1);
+        write_varint(&mut buffer, 300);
+        assert_eq!(buffer, [0x01, 0xac, 0x02]);
+    }
+
+    #[test]
+    fn cids_match_ipfs() {
+        assert_eq!(cid_string(&cid(RAW_CODEC, b"hello world")), "bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e");
+        let (root, _) = UnixFsDirectory::default().into_car();
+        assert_eq!(root, "bafybeiczsscdsbs7ffqz55asqdf3smv6klcw3gofszvwlyarci47bgf354");
+    }
+
+    #[test]
+    fn rejects_bad_paths() {
+        let mut directory = UnixFsDirectory::default();
+        directory.add_file("/css/style.css", b"body {}".to_vec()).unwrap();
+        assert!(directory.add_file("/css/style.css", Vec::new()).is_err());
+        assert!(directory.add_file("/css/style.css/inner", Vec::new()).is_err());
+        assert!(directory.add_file("/../secret", Vec::new()).is_err());
+        assert!(directory.add_file("/", Vec::new()).is_err());
+    }
+
+    #[test]
+    fn archive_starts_with_root_and_stores_blocks_once() {
+        let mut directory = UnixFsDirectory::default();
+        directory.add_file("a.txt", b"same".to_vec()).unwrap();
+        directory.add_file("b.txt", b"same".to_vec()).unwrap();
+        let (root, car) = directory.into_car();
+        let sections = sections(&car);
+        // the directory node and one raw block
+        assert_eq!(sections.len(), 2);
+        let root_cid = &sections[0][..36];
+        assert_eq!(cid_string(root_cid), root);
+        assert_eq!(root_cid, &cid(DAG_PB_CODEC, &sections[0][36..])[..]);
+    }
+
+    #[test]
+    fn chunks_large_files() {
+        let mut directory = UnixFsDirectory::default();
+        directory.add_file("video.bin", vec![7; CHUNK_SIZE + 1]).unwrap();
+        let (_, car) = directory.into_car();
+        let sections = sections(&car);
+        // directory, file node and two distinct chunks
+        assert_eq!(sections.len(), 4);
+        let mut chunk_sizes: Vec<usize> = sections.iter()
+            .filter(|section| section[1] == RAW_CODEC as u8)
+            .map(|section| section.len() - 36)
+            .collect();
+        chunk_sizes.sort();
+        assert_eq!(chunk_sizes, [1, CHUNK_SIZE]);
+    }
+}
@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+use unicode_security::skeleton;
+use crate::models::ResolutionWarning;
+use crate::resolver::normalize;
+
+/// Detects domains visually confusable with popular ones, e.g. `аpple.ever` with a Cyrillic `а`,
+/// by comparing UTS #39 skeletons of the names.
+#[derive(Clone, Default)]
+pub(crate) struct ConfusableDetector {
+    // popular domains by their skeletons
+    popular: HashMap<String, Vec<String>>,
+}
+
+impl ConfusableDetector {
+    pub fn new(popular_domains: &[String]) -> Self {
+        let mut popular: HashMap<String, Vec<String>> = HashMap::new();
+        for domain in popular_domains {
+            let domain = normalize::domain_name(domain);
+            popular.entry(skeleton_of(&domain)).or_default().push(domain);
+        }
+        Self { popular }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.popular.is_empty()
+    }
+
+    /// Warnings of popular domains confusable with the domain, the popular domains themselves get none.
+    pub fn check(&self, domain: &str) -> Vec<ResolutionWarning> {
+        let domain = normalize::domain_name(domain);
+        self.popular.get(&skeleton_of(&domain))
+            .map(|similar| similar.iter()
+                .filter(|similar| **similar != domain)
+                .map(|similar| ResolutionWarning::Confusable { similar_to: similar.clone() })
+                .collect())
+            .unwrap_or_default()
+    }
+}
+
+fn skeleton_of(domain: &str) -> String {
+    skeleton(domain).collect()
+}
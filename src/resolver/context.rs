@@ -0,0 +1,103 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use anyhow::Result;
+use crate::models::{AddressTag, ResolvedDomainData};
+use crate::resolver::ipfs::{map_targets, move_to_gateway};
+use crate::resolver::policy::{self, ResolutionPolicy};
+use crate::resolver::trace::Trace;
+
+/// Context of a tenant of a multi-tenant gateway, passed to `Web3DomainResolver::resolve_with`
+/// by `ResolveOptions::in_context`, so a single resolver instance can serve all tenants.
+///
+/// Results are cached separately per tenant id, contexts with the same tenant id are expected
+/// to have the same settings.
+///
+/// # Example
+/// ```no_run
+/// # async fn example() -> anyhow::Result<()> {
+/// use web3_resolver::{ListPolicy, ResolverContext, Web3DomainResolver};
+/// use web3_resolver::models::ResolveOptions;
+///
+/// let domain_resolver = Web3DomainResolver::default().await?;
+/// let options = ResolveOptions::new().in_context(ResolverContext::new("tenant-1")
+///     .with_ipfs_gateway("https://{cid}.ipfs.tenant-1.example/")
+///     .with_policy(ListPolicy::new().block_domain("*.scam.ever")));
+/// let (resolved_data, address_tag) = domain_resolver.resolve_with("maksimzubov.ever", &options).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ResolverContext {
+    tenant: String,
+    ipfs_gateway: Option<String>,
+    policies: Vec<Arc<dyn ResolutionPolicy>>,
+}
+
+impl ResolverContext {
+    pub fn new(tenant: &str) -> Self {
+        Self {
+            tenant: tenant.to_string(),
+            ipfs_gateway: None,
+            policies: Vec::new(),
+        }
+    }
+
+    pub fn tenant(&self) -> &str {
+        &self.tenant
+    }
+
+    /// Sets IPFS gateway IPFS targets of the tenant are rewritten to, subdomain gateways have `{cid}`
+    /// placeholder, e.g. `https://{cid}.ipfs.dweb.link/`, path gateways get the hash appended.
+    pub fn with_ipfs_gateway(self, gateway: &str) -> Self {
+        Self {
+            ipfs_gateway: Some(gateway.to_string()),
+            ..self
+        }
+    }
+
+    /// Adds policy of the tenant, invoked after the policies of the resolver.
+    pub fn with_policy<P: ResolutionPolicy + 'static>(mut self, policy: P) -> Self {
+        self.policies.push(Arc::new(policy));
+        self
+    }
+
+    /// Runs policies of the tenant before the resolution, returns rewritten result or blocking error if any.
+    pub(crate) fn apply_before(&self, domain: &str, trace: &mut Trace) -> Result<Option<(ResolvedDomainData, AddressTag)>> {
+        policy::apply_before(&self.policies, domain, trace)
+    }
+
+    /// Runs policies of the tenant on the resolved result and rewrites IPFS targets to the tenant gateway.
+    pub(crate) fn apply_after(&self, domain: &str, data: ResolvedDomainData, tag: AddressTag, trace: &mut Trace) -> Result<(ResolvedDomainData, AddressTag)> {
+        let (data, tag) = policy::apply_after(&self.policies, domain, data, tag, trace)?;
+        let (Some(gateway), ResolvedDomainData::DomainString(link), AddressTag::Ipfs) = (&self.ipfs_gateway, &data, tag) else {
+            return Ok((data, tag));
+        };
+        let rewritten = map_targets(link, |link| move_to_gateway(gateway, link).unwrap_or_else(|| link.to_string()));
+        if rewritten == *link {
+            return Ok((data, tag));
+        }
+        trace.push(|| format!("Tenant {}: IPFS target is rewritten into {}", self.tenant, rewritten));
+        Ok((ResolvedDomainData::DomainString(rewritten), tag))
+    }
+}
+
+impl Debug for ResolverContext {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResolverContext")
+            .field("tenant", &self.tenant)
+            .field("ipfs_gateway", &self.ipfs_gateway)
+            .field("policies", &self.policies.iter().map(|policy| policy.name()).collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl PartialEq for ResolverContext {
+    fn eq(&self, other: &Self) -> bool {
+        self.tenant == other.tenant
+            && self.ipfs_gateway == other.ipfs_gateway
+            && self.policies.len() == other.policies.len()
+            && self.policies.iter().zip(&other.policies).all(|(a, b)| Arc::ptr_eq(a, b))
+    }
+}
+
+impl Eq for ResolverContext {}
@@ -0,0 +1,136 @@
+use std::net::IpAddr;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use futures::future;
+use reqwest::header::ACCEPT;
+use reqwest::Client;
+use url::Url;
+use crate::models::DnsRecords;
+
+/// Cloudflare DNS-over-HTTPS JSON endpoint.
+pub const CLOUDFLARE_DOH_ENDPOINT: &str = "https://cloudflare-dns.com/dns-query";
+/// Google DNS-over-HTTPS JSON endpoint.
+pub const GOOGLE_DOH_ENDPOINT: &str = "https://dns.google/resolve";
+
+const A_RECORD: u64 = 1;
+const AAAA_RECORD: u64 = 28;
+
+/// DNS backend resolving non-web3 domains into A and AAAA records,
+/// see `DomainResolverBuilder::with_dns_fallback`.
+#[async_trait]
+pub trait DnsBackend: Send + Sync {
+    /// Name of the backend, reported as the result source.
+    fn name(&self) -> &str {
+        "dns"
+    }
+
+    /// Resolves A and AAAA records of the domain, fails if it has none.
+    async fn lookup(&self, domain: &str) -> Result<DnsRecords>;
+}
+
+/// Backend using the system resolver (`getaddrinfo`), record TTLs are unknown.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemDns;
+
+#[async_trait]
+impl DnsBackend for SystemDns {
+    fn name(&self) -> &str {
+        "system-dns"
+    }
+
+    async fn lookup(&self, domain: &str) -> Result<DnsRecords> {
+        let mut addresses: Vec<IpAddr> = Vec::new();
+        // an address is listed once per socket type
+        for address in tokio::net::lookup_host((domain, 0)).await? {
+            if !addresses.contains(&address.ip()) {
+                addresses.push(address.ip());
+            }
+        }
+        if addresses.is_empty() {
+            return Err(anyhow!("No A or AAAA records of {}", domain));
+        }
+        Ok(DnsRecords { name: domain.to_string(), addresses, ttl: None })
+    }
+}
+
+/// Backend querying a DNS-over-HTTPS server with the JSON API (`application/dns-json`),
+/// e.g. Cloudflare or Google public resolvers.
+#[derive(Clone)]
+pub struct DohDns {
+    endpoint: Url,
+    http_client: Client,
+}
+
+impl DohDns {
+    pub fn new(endpoint: Url) -> Self {
+        Self { endpoint, http_client: Client::new() }
+    }
+
+    pub fn cloudflare() -> Self {
+        Self::new(Url::parse(CLOUDFLARE_DOH_ENDPOINT).expect("Cloudflare endpoint is a valid URL"))
+    }
+
+    pub fn google() -> Self {
+        Self::new(Url::parse(GOOGLE_DOH_ENDPOINT).expect("Google endpoint is a valid URL"))
+    }
+
+    /// Sets HTTP client used for queries, e.g. with timeouts or a proxy.
+    pub fn with_http_client(self, http_client: Client) -> Self {
+        Self {
+            http_client,
+            ..self
+        }
+    }
+
+    /// Queries records of the type, returns their addresses and TTLs.
+    async fn query(&self, domain: &str, record_type: u64) -> Result<Vec<(IpAddr, u32)>> {
+        let mut url = self.endpoint.clone();
+        url.query_pairs_mut()
+            .append_pair("name", domain)
+            .append_pair("type", &record_type.to_string());
+        let response = self.http_client.get(url)
+            .header(ACCEPT, "application/dns-json")
+            .send().await?
+            .error_for_status()?;
+        let body = response.bytes().await?;
+        let response: serde_json::Value = serde_json::from_slice(&body)?;
+        match response.get("Status").and_then(|status| status.as_u64()) {
+            Some(0) => {},
+            Some(3) => return Err(anyhow!("Domain {} does not exist", domain)),
+            status => return Err(anyhow!("DNS query of {} failed with status {:?}", domain, status)),
+        }
+        let answers = response.get("Answer").and_then(|answer| answer.as_array()).cloned().unwrap_or_default();
+        Ok(answers.iter()
+            // CNAME answers of the chain are skipped
+            .filter(|answer| answer.get("type").and_then(|t| t.as_u64()) == Some(record_type))
+            .filter_map(|answer| {
+                let address = answer.get("data")?.as_str()?.parse().ok()?;
+                let ttl = answer.get("TTL").and_then(|ttl| ttl.as_u64()).unwrap_or_default();
+                Some((address, u32::try_from(ttl).unwrap_or(u32::MAX)))
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl DnsBackend for DohDns {
+    fn name(&self) -> &str {
+        "doh"
+    }
+
+    async fn lookup(&self, domain: &str) -> Result<DnsRecords> {
+        let (a, aaaa) = future::join(self.query(domain, A_RECORD), self.query(domain, AAAA_RECORD)).await;
+        let records: Vec<(IpAddr, u32)> = match (a, aaaa) {
+            (Err(e), Err(_)) => return Err(e),
+            (a, aaaa) => a.unwrap_or_default().into_iter().chain(aaaa.unwrap_or_default()).collect(),
+        };
+        if records.is_empty() {
+            return Err(anyhow!("No A or AAAA records of {}", domain));
+        }
+        Ok(DnsRecords {
+            name: domain.to_string(),
+            addresses: records.iter().map(|(address, _)| *address).collect(),
+            ttl: records.iter().map(|(_, ttl)| *ttl).min(),
+        })
+    }
+}
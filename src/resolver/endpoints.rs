@@ -0,0 +1,134 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use log::debug;
+use nekoton::external::{JrpcConnection, JrpcRequest};
+use reqwest::Client;
+use reqwest::header::CONTENT_TYPE;
+use url::Url;
+use crate::models::{ConnectionPool, EndpointSelection, EndpointStats, Timeouts};
+use crate::resolver::health::{failover_order, select_p2c, HealthTracker, DEFAULT_FAILURE_LATENCY};
+use crate::resolver::http::http_client;
+
+/// Checks that the endpoints list is not empty and all endpoints are HTTP(S) URLs with non-zero weights.
+pub(crate) fn validate_endpoints(endpoints: &[(Url, u32)]) -> Result<()> {
+    if endpoints.is_empty() {
+        return Err(anyhow!("No Everscale JRPC endpoints are set"));
+    }
+    for (url, weight) in endpoints {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(anyhow!("Everscale JRPC endpoint {} must be an HTTP(S) URL", url));
+        }
+        if *weight == 0 {
+            return Err(anyhow!("Everscale JRPC endpoint {} has zero weight", url));
+        }
+    }
+    Ok(())
+}
+
+struct PoolInner {
+    endpoints: Vec<(Url, u32)>,
+    total_weight: u64,
+    counter: AtomicU64,
+    http_client: Client,
+    selection: EndpointSelection,
+    health: HealthTracker,
+}
+
+/// JRPC connection spreading requests over several endpoints proportionally to their weights,
+/// or to the endpoints with the best latency and error rate.
+/// If the selected endpoint fails, the others are tried in order.
+/// Clones share the endpoints health, so it can be read while the pool is owned by the transport.
+#[derive(Clone)]
+pub(crate) struct EndpointPool {
+    inner: Arc<PoolInner>,
+}
+
+impl EndpointPool {
+    pub fn new(endpoints: Vec<(Url, u32)>, timeouts: &Timeouts, pool: &ConnectionPool, selection: EndpointSelection) -> Result<Self> {
+        validate_endpoints(&endpoints)?;
+        let total_weight = endpoints.iter().map(|(_, weight)| *weight as u64).sum();
+        // failures are charged the whole request time, so endpoints refusing connections at once lose
+        let health = HealthTracker::new(endpoints.len(), timeouts.total.unwrap_or(DEFAULT_FAILURE_LATENCY));
+        Ok(Self {
+            inner: Arc::new(PoolInner {
+                endpoints,
+                total_weight,
+                counter: AtomicU64::new(0),
+                http_client: http_client(timeouts, pool)?,
+                selection,
+                health,
+            }),
+        })
+    }
+
+    /// Request counters, latency and error rate moving averages per endpoint.
+    pub fn stats(&self) -> Vec<EndpointStats> {
+        self.inner.health.stats(self.inner.endpoints.iter().map(|(url, _)| url.to_string()))
+    }
+
+    /// Selects the endpoint index and the failover order of the others.
+    fn order(&self) -> Vec<usize> {
+        let count = self.inner.endpoints.len();
+        let tick = self.inner.counter.fetch_add(1, Ordering::Relaxed);
+        match self.inner.selection {
+            EndpointSelection::WeightedRoundRobin => {
+                let selected = self.select_weighted(tick);
+                std::iter::once(selected).chain((0..count).filter(|index| *index != selected)).collect()
+            },
+            EndpointSelection::LatencyAware => {
+                let costs = self.inner.health.costs(self.inner.endpoints.iter().map(|(_, weight)| *weight));
+                failover_order(select_p2c(tick, &costs), &costs)
+            },
+        }
+    }
+
+    /// Weighted round-robin selection of the endpoint index.
+    fn select_weighted(&self, tick: u64) -> usize {
+        let mut point = tick % self.inner.total_weight;
+        for (index, (_, weight)) in self.inner.endpoints.iter().enumerate() {
+            if point < *weight as u64 {
+                return index;
+            }
+            point -= *weight as u64;
+        }
+        0
+    }
+
+    fn record(&self, index: usize, latency: std::time::Duration, success: bool) {
+        self.inner.health.record(index, latency, success);
+    }
+
+    async fn post_to(&self, url: &Url, data: &str) -> Result<String> {
+        let response = self.inner.http_client.post(url.clone())
+            .header(CONTENT_TYPE, "application/json")
+            .body(data.to_string())
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(response.text().await?)
+    }
+}
+
+#[async_trait]
+impl JrpcConnection for EndpointPool {
+    async fn post(&self, req: JrpcRequest) -> Result<String> {
+        let mut last_error = None;
+        for index in self.order() {
+            let url = &self.inner.endpoints[index].0;
+            let started = Instant::now();
+            let result = self.post_to(url, &req.data).await;
+            self.record(index, started.elapsed(), result.is_ok());
+            match result {
+                Ok(response) => return Ok(response),
+                Err(e) => {
+                    debug!("JRPC endpoint {} failed: {}", url, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| anyhow!("No Everscale JRPC endpoints are set")))
+    }
+}
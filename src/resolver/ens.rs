@@ -0,0 +1,451 @@
+use crate::resolver::avatar::{parse_avatar_uri, AvatarUri, NftAvatar, NftStandard};
+use crate::models::{AddressTag, CoinType, ConnectionPool, DomainSnapshot, EnsContentSource, ResolutionCandidate, ResolvedDomainData, ResolverCapabilities, SnapshotRecord, StructuredTarget, Timeouts};
+use crate::resolver::ens::contenthash::ContentHash;
+use crate::resolver::ens::namehash::{NameHashAlgorithm, NameHashCache};
+use crate::resolver::ens::rpc::{checksum_address, decode_address, decode_bytes, decode_string, parse_uint256, EthRpcClient, Token};
+use crate::resolver::http::http_client;
+use crate::resolver::limit::{permit, InFlightLimit};
+use crate::resolver::ipfs::{make_gateway_link, make_ipns_gateway_link, DEFAULT_IPFS_GATEWAY, DEFAULT_IPNS_GATEWAY};
+use crate::resolver::normalize;
+use crate::resolver::trace::Trace;
+use crate::resolver::Resolver;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use log::debug;
+use mini_moka::sync::Cache;
+use reqwest::{Client, IntoUrl};
+use std::time::Duration;
+use url::Url;
+
+mod builder;
+mod contenthash;
+mod namehash;
+mod rpc;
+
+pub use builder::EnsResolverBuilder;
+
+/// Default Ethereum JSON-RPC endpoint.
+pub const ETHEREUM_RPC_ENDPOINT: &str = "https://ethereum-rpc.publicnode.com";
+/// Address of the ENS registry contract, the same on the mainnet and testnets.
+pub const ENS_REGISTRY_ADDRESS: &str = "0x00000000000C2E074eC69A0dFb2997BA6C7d2e1e";
+
+/// Resolver of ENS (.eth) domains over Ethereum JSON-RPC.
+///
+/// Content is taken from the first present record of the content priority list,
+/// `EnsContentSource::resolvable()` by default: `contenthash`, then `url` text record
+/// (web2 redirect target), then `ipfs` and `ipns` text records.
+pub struct EnsResolver {
+    rpc: EthRpcClient,
+    // fetches NFT metadata of avatars
+    http_client: Client,
+    registry_address: String,
+    tlds: Vec<String>,
+    content_priority: Vec<EnsContentSource>,
+    ipfs_gateway: String,
+    ipns_gateway: String,
+    // results of standalone `resolve` calls, `Web3DomainResolver` has its own cache
+    result_cache: Option<Cache<String, (ResolvedDomainData, AddressTag)>>,
+    in_flight: Option<InFlightLimit>,
+    namehashes: NameHashCache,
+}
+
+impl EnsResolver {
+    pub fn new<U: IntoUrl>(endpoint: U) -> Result<Self> {
+        Self::new_with_timeouts(endpoint, &Timeouts::default())
+    }
+
+    /// Same as `new`, JSON-RPC requests are bounded by the given timeouts.
+    pub fn new_with_timeouts<U: IntoUrl>(endpoint: U, timeouts: &Timeouts) -> Result<Self> {
+        Self::new_with_connection_pool(endpoint, timeouts, &ConnectionPool::default())
+    }
+
+    /// Same as `new_with_timeouts`, connections are reused with the given settings.
+    pub fn new_with_connection_pool<U: IntoUrl>(endpoint: U, timeouts: &Timeouts, pool: &ConnectionPool) -> Result<Self> {
+        let endpoint = endpoint.into_url()?;
+        let http_client = http_client(timeouts, pool)?;
+        Ok(Self {
+            rpc: EthRpcClient::new(endpoint, http_client.clone()),
+            http_client,
+            registry_address: ENS_REGISTRY_ADDRESS.to_string(),
+            tlds: vec![".eth".to_string()],
+            content_priority: EnsContentSource::resolvable(),
+            ipfs_gateway: DEFAULT_IPFS_GATEWAY.to_string(),
+            ipns_gateway: DEFAULT_IPNS_GATEWAY.to_string(),
+            result_cache: None,
+            in_flight: None,
+            namehashes: NameHashCache::default(),
+        })
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn default() -> Result<Self> {
+        Self::new(ETHEREUM_RPC_ENDPOINT)
+    }
+
+    /// Creates builder of a standalone resolver for dapps resolving ENS domains only.
+    pub fn builder() -> EnsResolverBuilder {
+        EnsResolverBuilder::default()
+    }
+
+    /// Sets address of the ENS registry contract, e.g. for private networks.
+    pub fn with_registry_address(self, registry_address: &str) -> Self {
+        Self {
+            registry_address: registry_address.to_string(),
+            ..self
+        }
+    }
+
+    /// Sets order of content records for resolving. Order is priority, unlisted records are not consulted.
+    pub fn with_content_priority(self, content_priority: Vec<EnsContentSource>) -> Self {
+        Self {
+            content_priority,
+            ..self
+        }
+    }
+
+    /// Bounds in-flight JSON-RPC and NFT metadata requests by the limit, which may be shared with other resolvers.
+    pub fn with_in_flight_limit(self, in_flight: InFlightLimit) -> Self {
+        Self {
+            rpc: self.rpc.with_in_flight_limit(in_flight.clone()),
+            in_flight: Some(in_flight),
+            ..self
+        }
+    }
+
+    /// Sets IPFS gateway for IPFS content, `{cid}` is replaced with the content hash,
+    /// otherwise the hash is appended to the gateway path. Default is `https://{cid}.ipfs.w3s.link/`.
+    pub fn with_ipfs_gateway(self, gateway: &str) -> Self {
+        Self {
+            ipfs_gateway: gateway.to_string(),
+            ..self
+        }
+    }
+
+    /// Sets IPNS gateway for IPNS content, `{cid}` is replaced with the IPNS name,
+    /// otherwise the name is appended to the gateway path. Default is `https://{cid}.ipns.dweb.link/`.
+    pub fn with_ipns_gateway(self, gateway: &str) -> Self {
+        Self {
+            ipns_gateway: gateway.to_string(),
+            ..self
+        }
+    }
+
+    /// Enables caching of `resolve` results for the given time-to-live.
+    pub fn with_result_cache(self, ttl: Duration) -> Self {
+        Self {
+            result_cache: Some(Cache::builder().time_to_live(ttl).build()),
+            ..self
+        }
+    }
+
+    /// Removes the domain from the results cache.
+    pub fn invalidate(&self, domain: &str) {
+        if let Some(cache) = &self.result_cache {
+            cache.invalidate(&normalize::cache_key(domain));
+        }
+    }
+
+    pub fn get_tlds(&self) -> Vec<String> {
+        self.tlds.clone()
+    }
+
+    pub fn endpoint(&self) -> &Url {
+        self.rpc.endpoint()
+    }
+
+    /// Number of times the namehash cache was cleared on reaching its capacity.
+    pub fn namehash_cache_clears(&self) -> u64 {
+        self.namehashes.clears()
+    }
+
+    /// Cached EIP-137 namehash of the normalized domain name.
+    fn node(&self, name: &str) -> [u8; 32] {
+        self.namehashes.get(NameHashAlgorithm::Eip137, name)
+    }
+
+    /// Returns address of the resolver contract set for the domain in the ENS registry.
+    pub async fn resolver_address(&self, domain: &str) -> Result<String> {
+        let name = normalize::domain_name(domain);
+        self.resolver_of(&name, &self.node(&name)).await
+    }
+
+    /// Returns text record of the domain, e.g. `url`, `avatar` or `com.twitter`.
+    pub async fn text(&self, domain: &str, key: &str) -> Result<Option<String>> {
+        let name = normalize::domain_name(domain);
+        let node = self.node(&name);
+        let resolver = self.resolver_of(&name, &node).await?;
+        self.text_record(&resolver, &node, key).await
+    }
+
+    /// Takes snapshot of the domain: resolver contract, `contenthash` and legacy `addr` records as raw hex
+    /// with decoded values, present `url`, `ipfs`, `ipns` and `avatar` text records (text records can not
+    /// be enumerated), and the resolution result.
+    pub async fn snapshot(&self, domain: &str) -> Result<DomainSnapshot> {
+        let name = normalize::domain_name(domain);
+        let node = self.node(&name);
+        let mut snapshot = DomainSnapshot::new(&name, self.name());
+        let resolver = self.resolver_of(&name, &node).await?;
+        snapshot.account = Some(resolver.clone());
+        snapshot.records = self.records_of(&resolver, &node).await?;
+        snapshot.set_resolution(self.resolve_traced(&name, &mut Trace::disabled()).await);
+        Ok(snapshot)
+    }
+
+    /// Returns `contenthash`, `addr` and content text records of the domain that are set.
+    pub async fn records(&self, domain: &str) -> Result<Vec<SnapshotRecord>> {
+        let name = normalize::domain_name(domain);
+        let node = self.node(&name);
+        let resolver = self.resolver_of(&name, &node).await?;
+        self.records_of(&resolver, &node).await
+    }
+
+    async fn records_of(&self, resolver: &str, node: &[u8; 32]) -> Result<Vec<SnapshotRecord>> {
+        let mut records = Vec::new();
+        let contenthash = decode_bytes(&self.rpc.call(resolver, "contenthash(bytes32)", &[Token::Bytes32(*node)]).await?)?;
+        if !contenthash.is_empty() {
+            let value = contenthash::decode(&contenthash).ok().map(|content| content.uri());
+            records.push(SnapshotRecord::new("contenthash", format!("0x{}", hex::encode(&contenthash)), value));
+        }
+        let addr = self.rpc.call(resolver, "addr(bytes32)", &[Token::Bytes32(*node)]).await?;
+        if let Some(address) = decode_address(&addr)? {
+            records.push(SnapshotRecord::new("addr", format!("0x{}", hex::encode(&addr)), Some(address)));
+        }
+        for key in ["url", "ipfs", "ipns", "avatar"] {
+            if let Some(value) = self.text_record(resolver, node, key).await? {
+                records.push(SnapshotRecord::new(key, value.clone(), Some(value)));
+            }
+        }
+        Ok(records)
+    }
+
+    /// Returns address of the domain for the coin (ENSIP-9 multicoin `addr` record), `None` if it is not set.
+    /// Only addresses of EVM coins (`CoinType::is_evm`) are decoded, they are returned with EIP-55 checksum.
+    pub async fn address(&self, domain: &str, coin: CoinType) -> Result<Option<String>> {
+        if !coin.is_evm() {
+            return Err(anyhow!("Decoding of ENS {} addresses is not supported", coin));
+        }
+        let name = normalize::domain_name(domain);
+        let node = self.node(&name);
+        let resolver = self.resolver_of(&name, &node).await?;
+        let address = match self.rpc.call(&resolver, "addr(bytes32,uint256)", &[Token::Bytes32(node), Token::Uint(coin.0 as u64)]).await {
+            Ok(result) => decode_bytes(&result)?,
+            // legacy resolvers have Ethereum addresses only
+            Err(e) if coin == CoinType::ETH => {
+                debug!("Multicoin addr of {} failed, falling back to legacy addr: {}", name, e);
+                let result = self.rpc.call(&resolver, "addr(bytes32)", &[Token::Bytes32(node)]).await?;
+                return decode_address(&result);
+            },
+            Err(e) => return Err(e),
+        };
+        match address.len() {
+            0 => Ok(None),
+            20 => Ok(Some(checksum_address(&address))),
+            length => Err(anyhow!("Invalid {} address length {} of {}", coin, length, name)),
+        }
+    }
+
+    /// Returns primary name of the Ethereum address (ENSIP-3 `<address>.addr.reverse` record), `None` if it is not set.
+    /// The reverse record is set by the address owner, so names not resolving back to the address are ignored.
+    pub async fn primary_name(&self, address: &str) -> Result<Option<String>> {
+        let hex_address = address.trim().trim_start_matches("0x").to_lowercase();
+        if hex_address.len() != 40 || !hex_address.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(anyhow!("{} is not an Ethereum address", address));
+        }
+        let node = self.node(&format!("{}.addr.reverse", hex_address));
+        let result = self.rpc.call(&self.registry_address, "resolver(bytes32)", &[Token::Bytes32(node)]).await?;
+        let Some(resolver) = decode_address(&result)? else {
+            return Ok(None);
+        };
+        let name = decode_string(&self.rpc.call(&resolver, "name(bytes32)", &[Token::Bytes32(node)]).await?)?;
+        let name = normalize::domain_name(name.trim());
+        if name.is_empty() {
+            return Ok(None);
+        }
+        let forward = self.address(&name, CoinType::ETH).await?;
+        match forward {
+            Some(forward) if forward.to_lowercase() == format!("0x{}", hex_address) => Ok(Some(name)),
+            _ => {
+                debug!("Primary name {} of {} does not resolve back to the address", name, address);
+                Ok(None)
+            },
+        }
+    }
+
+    /// Returns fetchable URL of the domain avatar (ENSIP-12 `avatar` text record), `None` if it is not set.
+    /// HTTP(S), data and IPFS URIs are supported, as well as ERC-721 and ERC-1155 NFTs on Ethereum mainnet,
+    /// whose image is taken from the token metadata. NFT ownership is not verified.
+    pub async fn avatar(&self, domain: &str) -> Result<Option<String>> {
+        let Some(value) = self.text(domain, "avatar").await? else {
+            return Ok(None);
+        };
+        match parse_avatar_uri(&value, &self.ipfs_gateway)? {
+            AvatarUri::Link(link) => Ok(Some(link)),
+            AvatarUri::Nft(nft) => self.nft_image(&nft).await.map(Some),
+        }
+    }
+
+    /// Looks up image of the NFT in its metadata.
+    pub(crate) async fn nft_image(&self, nft: &NftAvatar) -> Result<String> {
+        if nft.chain_id != 1 {
+            return Err(anyhow!("NFT {} is not on Ethereum mainnet", nft));
+        }
+        let token_id = parse_uint256(&nft.token_id)?;
+        let signature = match nft.standard {
+            NftStandard::Erc721 => "tokenURI(uint256)",
+            NftStandard::Erc1155 => "uri(uint256)",
+        };
+        let metadata_uri = decode_string(&self.rpc.call(&nft.contract, signature, &[Token::Bytes32(token_id)]).await?)?
+            // ERC-1155 clients substitute the token id in hex
+            .replace("{id}", &hex::encode(token_id));
+        let AvatarUri::Link(metadata_url) = parse_avatar_uri(&metadata_uri, &self.ipfs_gateway)? else {
+            return Err(anyhow!("Unsupported metadata URI {} of NFT {}", metadata_uri, nft));
+        };
+        debug!("Loading metadata of NFT {} from {}", nft, metadata_url);
+        let body = {
+            let _permit = permit(&self.in_flight).await;
+            self.http_client.get(metadata_url).send().await?.error_for_status()?.bytes().await?
+        };
+        let metadata: serde_json::Value = serde_json::from_slice(&body)?;
+        let image = metadata.get("image")
+            .or(metadata.get("image_url"))
+            .and_then(|image| image.as_str())
+            .ok_or(anyhow!("Metadata of NFT {} has no image", nft))?;
+        match parse_avatar_uri(image, &self.ipfs_gateway)? {
+            AvatarUri::Link(link) => Ok(link),
+            AvatarUri::Nft(_) => Err(anyhow!("Unsupported image {} of NFT {}", image, nft)),
+        }
+    }
+
+    pub(crate) async fn resolve_traced(&self, domain: &str, trace: &mut Trace) -> Result<(ResolvedDomainData, AddressTag)> {
+        let name = normalize::domain_name(domain);
+        let node = self.node(&name);
+        let resolver = self.resolver_of(&name, &node).await?;
+        trace.push(|| format!("ENS: resolver contract {}", resolver));
+        for source in &self.content_priority {
+            debug!("Resolving ENS domain {} with {} record", name, source);
+            match self.content_record(&resolver, &node, *source, trace).await {
+                Ok(Some(result)) => {
+                    trace.push(|| format!("ENS: {} record chosen as the highest priority present record", source));
+                    return Ok(result);
+                },
+                Ok(None) => trace.push(|| format!("ENS: no {} record", source)),
+                Err(e) => {
+                    debug!("Skipping {} record of {}: {}", source, name, e);
+                    trace.push(|| format!("ENS: {} record skipped: {}", source, e));
+                },
+            }
+        }
+        Err(anyhow!("No address for requested domain {}", domain))
+    }
+
+    async fn resolver_of(&self, name: &str, node: &[u8; 32]) -> Result<String> {
+        let result = self.rpc.call(&self.registry_address, "resolver(bytes32)", &[Token::Bytes32(*node)]).await?;
+        decode_address(&result)?.ok_or(anyhow!("Domain {} has no ENS resolver", name))
+    }
+
+    async fn text_record(&self, resolver: &str, node: &[u8; 32], key: &str) -> Result<Option<String>> {
+        let result = self.rpc.call(resolver, "text(bytes32,string)", &[Token::Bytes32(*node), Token::String(key)]).await?;
+        let value = decode_string(&result)?;
+        Ok(Some(value.trim().to_string()).filter(|value| !value.is_empty()))
+    }
+
+    /// Decodes the content record into a link, `None` if the record is not set.
+    async fn content_record(&self, resolver: &str, node: &[u8; 32], source: EnsContentSource, trace: &mut Trace) -> Result<Option<(ResolvedDomainData, AddressTag)>> {
+        let Some(key) = source.text_key() else {
+            let result = self.rpc.call(resolver, "contenthash(bytes32)", &[Token::Bytes32(*node)]).await?;
+            let bytes = decode_bytes(&result)?;
+            if bytes.is_empty() {
+                return Ok(None);
+            }
+            trace.record(source.to_string(), format!("0x{}", hex::encode(&bytes)));
+            let content = match contenthash::decode(&bytes)? {
+                ContentHash::Ipfs(cid) => (ResolvedDomainData::DomainString(make_gateway_link(&self.ipfs_gateway, &cid)), AddressTag::Ipfs),
+                ContentHash::Ipns(name) => (ResolvedDomainData::DomainString(make_ipns_gateway_link(&self.ipns_gateway, &name)), AddressTag::Ipfs),
+                ContentHash::Onion(address) => (ResolvedDomainData::DomainString(address), AddressTag::Tor),
+                ContentHash::Swarm(hash) => return Err(anyhow!("Swarm content {} is not supported", hash)),
+            };
+            return Ok(Some(content));
+        };
+        let Some(value) = self.text_record(resolver, node, key).await? else {
+            return Ok(None);
+        };
+        trace.record(source.to_string(), value.clone());
+        let content = match source {
+            EnsContentSource::Url => {
+                // the domain redirects to the URL, only web links are followed
+                let value = StructuredTarget::expand(value, &AddressTag::Web2);
+                let url = Url::parse(&value)?;
+                if !matches!(url.scheme(), "http" | "https") {
+                    return Err(anyhow!("Unsupported url record scheme {}", url.scheme()));
+                }
+                (ResolvedDomainData::DomainString(value), AddressTag::Web2)
+            },
+            EnsContentSource::Ipns => (ResolvedDomainData::DomainString(make_ipns_gateway_link(&self.ipns_gateway, &value)), AddressTag::Ipfs),
+            _ => (ResolvedDomainData::DomainString(make_gateway_link(&self.ipfs_gateway, &value)), AddressTag::Ipfs),
+        };
+        Ok(Some(content))
+    }
+}
+
+#[async_trait]
+impl Resolver for EnsResolver {
+    async fn resolve(&self, domain: &str) -> Result<(ResolvedDomainData, AddressTag)> {
+        let Some(cache) = &self.result_cache else {
+            return self.resolve_traced(domain, &mut Trace::disabled()).await;
+        };
+        let key = normalize::cache_key(domain);
+        if let Some(result) = cache.get(&key) {
+            return Ok(result);
+        }
+        let result = self.resolve_traced(domain, &mut Trace::disabled()).await?;
+        cache.insert(key, result.clone());
+        Ok(result)
+    }
+
+    fn name(&self) -> &str {
+        "ens"
+    }
+
+    fn capabilities(&self) -> ResolverCapabilities {
+        ResolverCapabilities {
+            tlds: self.get_tlds(),
+            record_kinds: vec![AddressTag::Web2, AddressTag::Ipfs, AddressTag::Tor],
+            addresses: true,
+            reverse: true,
+        }
+    }
+
+    async fn resolve_address(&self, domain: &str, coin: CoinType) -> Result<Option<String>> {
+        self.address(domain, coin).await
+    }
+
+    /// Primary names of Ethereum addresses, addresses of other chains are skipped.
+    async fn reverse(&self, address: &str, chain_hint: Option<CoinType>) -> Result<Option<String>> {
+        let is_eth_address = address.trim().strip_prefix("0x")
+            .is_some_and(|hex| hex.len() == 40 && hex.chars().all(|c| c.is_ascii_hexdigit()));
+        match chain_hint {
+            Some(coin) if !coin.is_evm() => Ok(None),
+            _ if !is_eth_address => Ok(None),
+            _ => self.primary_name(address).await,
+        }
+    }
+
+    async fn resolve_all(&self, domain: &str) -> Result<Vec<ResolutionCandidate>> {
+        let name = normalize::domain_name(domain);
+        let node = self.node(&name);
+        let resolver = self.resolver_of(&name, &node).await?;
+        let mut candidates = Vec::new();
+        for source in &self.content_priority {
+            match self.content_record(&resolver, &node, *source, &mut Trace::disabled()).await {
+                Ok(Some((data, tag))) => candidates.push(ResolutionCandidate {
+                    source: self.name().to_string(),
+                    data,
+                    tag,
+                }),
+                Ok(None) => {},
+                Err(e) => debug!("Skipping {} record of {}: {}", source, name, e),
+            }
+        }
+        Ok(candidates)
+    }
+}
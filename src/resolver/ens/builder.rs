@@ -0,0 +1,148 @@
+use std::time::Duration;
+use anyhow::Result;
+use url::Url;
+use crate::models::{ConnectionPool, EnsContentSource, Timeouts};
+use crate::resolver::ens::{EnsResolver, ETHEREUM_RPC_ENDPOINT};
+use crate::resolver::limit::InFlightLimit;
+
+/// Builder of a standalone `EnsResolver`, also used by `DomainResolverBuilder`.
+///
+/// # Example
+/// ```
+/// # fn example() -> anyhow::Result<()> {
+/// use web3_resolver::{EnsResolver, Url};
+/// use web3_resolver::models::EnsContentSource;
+///
+/// let ens_resolver = EnsResolver::builder()
+///     .with_ethereum_endpoint(Url::parse("https://ethereum-rpc.publicnode.com")?)
+///     .content_priority(&[EnsContentSource::Url, EnsContentSource::Contenthash])
+///     .cache_ttl_seconds(60)
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct EnsResolverBuilder {
+    endpoint: Url,
+    registry_address: Option<String>,
+    content_priority: Option<Vec<EnsContentSource>>,
+    ipfs_gateway: Option<String>,
+    ipns_gateway: Option<String>,
+    cache_ttl_seconds: Option<u64>,
+    timeouts: Timeouts,
+    connection_pool: ConnectionPool,
+    in_flight: Option<InFlightLimit>,
+}
+
+impl Default for EnsResolverBuilder {
+    fn default() -> Self {
+        Self {
+            endpoint: Url::parse(ETHEREUM_RPC_ENDPOINT).expect("Default endpoint is a valid URL"),
+            registry_address: None,
+            content_priority: None,
+            ipfs_gateway: None,
+            ipns_gateway: None,
+            cache_ttl_seconds: None,
+            timeouts: Timeouts::default(),
+            connection_pool: ConnectionPool::default(),
+            in_flight: None,
+        }
+    }
+}
+
+impl EnsResolverBuilder {
+    /// Sets Ethereum JSON-RPC endpoint.
+    pub fn with_ethereum_endpoint(self, endpoint: Url) -> Self {
+        Self {
+            endpoint,
+            ..self
+        }
+    }
+
+    /// Sets address of the ENS registry contract, see `EnsResolver::with_registry_address`.
+    pub fn with_registry_address(self, registry_address: &str) -> Self {
+        Self {
+            registry_address: Some(registry_address.to_string()),
+            ..self
+        }
+    }
+
+    /// Sets order of content records for resolving, see `EnsResolver::with_content_priority`.
+    pub fn content_priority(self, content_priority: &[EnsContentSource]) -> Self {
+        Self {
+            content_priority: Some(content_priority.to_vec()),
+            ..self
+        }
+    }
+
+    /// Sets IPFS gateway for IPFS content, see `EnsResolver::with_ipfs_gateway`.
+    pub fn with_ipfs_gateway(self, gateway: &str) -> Self {
+        Self {
+            ipfs_gateway: Some(gateway.to_string()),
+            ..self
+        }
+    }
+
+    /// Sets IPNS gateway for IPNS content, see `EnsResolver::with_ipns_gateway`.
+    pub fn with_ipns_gateway(self, gateway: &str) -> Self {
+        Self {
+            ipns_gateway: Some(gateway.to_string()),
+            ..self
+        }
+    }
+
+    /// Enables caching of `resolve` results for the given time-to-live. Disabled by default.
+    pub fn cache_ttl_seconds(self, ttl: u64) -> Self {
+        Self {
+            cache_ttl_seconds: Some(ttl),
+            ..self
+        }
+    }
+
+    /// Sets connect, read and total timeouts of JSON-RPC requests. Unbounded by default.
+    pub fn with_timeouts(self, timeouts: Timeouts) -> Self {
+        Self {
+            timeouts,
+            ..self
+        }
+    }
+
+    /// Sets keep-alive and idle connection settings of the JSON-RPC client, see `ConnectionPool`.
+    pub fn with_connection_pool(self, connection_pool: ConnectionPool) -> Self {
+        Self {
+            connection_pool,
+            ..self
+        }
+    }
+
+    /// Bounds in-flight JSON-RPC requests by the limit, which may be shared with other resolvers.
+    pub fn with_in_flight_limit(self, in_flight: InFlightLimit) -> Self {
+        Self {
+            in_flight: Some(in_flight),
+            ..self
+        }
+    }
+
+    pub fn build(&self) -> Result<EnsResolver> {
+        let mut ens_resolver = EnsResolver::new_with_connection_pool(self.endpoint.clone(), &self.timeouts, &self.connection_pool)?;
+        if let Some(registry_address) = &self.registry_address {
+            ens_resolver = ens_resolver.with_registry_address(registry_address);
+        }
+        if let Some(content_priority) = &self.content_priority {
+            ens_resolver = ens_resolver.with_content_priority(content_priority.clone());
+        }
+        if let Some(gateway) = &self.ipfs_gateway {
+            ens_resolver = ens_resolver.with_ipfs_gateway(gateway);
+        }
+        if let Some(gateway) = &self.ipns_gateway {
+            ens_resolver = ens_resolver.with_ipns_gateway(gateway);
+        }
+        if let Some(in_flight) = &self.in_flight {
+            ens_resolver = ens_resolver.with_in_flight_limit(in_flight.clone());
+        }
+        if let Some(ttl) = self.cache_ttl_seconds.filter(|ttl| *ttl > 0) {
+            ens_resolver = ens_resolver.with_result_cache(Duration::from_secs(ttl));
+        }
+        Ok(ens_resolver)
+    }
+}
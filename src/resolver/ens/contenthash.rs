@@ -0,0 +1,138 @@
+use anyhow::{anyhow, Result};
+use crate::resolver::ipfs::base32_lower;
+
+const IPFS_NAMESPACE: u64 = 0xe3;
+const SWARM_NAMESPACE: u64 = 0xe4;
+const IPNS_NAMESPACE: u64 = 0xe5;
+const ONION_NAMESPACE: u64 = 0x01bc;
+const ONION3_NAMESPACE: u64 = 0x01bd;
+
+const DAG_PB_CODEC: u64 = 0x70;
+const IDENTITY_HASH: u64 = 0x00;
+
+/// Decoded EIP-1577 `contenthash` record.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ContentHash {
+    /// CIDv1 in base32
+    Ipfs(String),
+    /// CIDv1 in base32 or DNSLink name
+    Ipns(String),
+    /// Onion address with `.onion` suffix
+    Onion(String),
+    /// Swarm hash in hex
+    Swarm(String),
+}
+
+impl ContentHash {
+    /// URI of the content, e.g. `ipfs://bafy...` or `bzz://...`.
+    pub fn uri(&self) -> String {
+        match self {
+            ContentHash::Ipfs(cid) => format!("ipfs://{}", cid),
+            ContentHash::Ipns(name) => format!("ipns://{}", name),
+            ContentHash::Onion(address) => format!("onion://{}", address),
+            ContentHash::Swarm(hash) => format!("bzz://{}", hash),
+        }
+    }
+}
+
+/// Decodes the record by its multicodec namespace. CIDs are converted to CIDv1 in base32,
+/// which is accepted by both path and subdomain gateways.
+pub(crate) fn decode(bytes: &[u8]) -> Result<ContentHash> {
+    let (namespace, value) = read_varint(bytes)?;
+    match namespace {
+        IPFS_NAMESPACE => Ok(ContentHash::Ipfs(cid_string(value)?)),
+        IPNS_NAMESPACE => match dnslink_name(value) {
+            Some(name) => Ok(ContentHash::Ipns(name)),
+            None => Ok(ContentHash::Ipns(cid_string(value)?)),
+        },
+        ONION_NAMESPACE | ONION3_NAMESPACE => Ok(ContentHash::Onion(format!("{}.onion", std::str::from_utf8(value)?))),
+        SWARM_NAMESPACE => Ok(ContentHash::Swarm(hex::encode(value))),
+        namespace => Err(anyhow!("Unsupported contenthash namespace 0x{:x}", namespace)),
+    }
+}
+
+fn cid_string(cid: &[u8]) -> Result<String> {
+    let cid = match cid {
+        // CIDv0 is a bare sha2-256 multihash of a dag-pb node
+        [0x12, 0x20, ..] => [&[0x01, DAG_PB_CODEC as u8], cid].concat(),
+        [0x01, ..] => cid.to_vec(),
+        _ => return Err(anyhow!("Unsupported CID version")),
+    };
+    Ok(format!("b{}", base32_lower(&cid)))
+}
+
+/// IPNS records of DNSLink names hold the name as an identity multihash.
+fn dnslink_name(cid: &[u8]) -> Option<String> {
+    let (version, rest) = read_varint(cid).ok()?;
+    let (codec, rest) = read_varint(rest).ok()?;
+    let (hash, rest) = read_varint(rest).ok()?;
+    let (length, digest) = read_varint(rest).ok()?;
+    if version != 1 || codec != DAG_PB_CODEC || hash != IDENTITY_HASH || digest.len() as u64 != length {
+        return None;
+    }
+    std::str::from_utf8(digest).ok().map(|name| name.to_string())
+}
+
+/// Reads unsigned LEB128 varint, returns it with the rest of the bytes.
+fn read_varint(bytes: &[u8]) -> Result<(u64, &[u8])> {
+    let mut value = 0u64;
+    for (index, byte) in bytes.iter().enumerate().take(9) {
+        value |= ((byte & 0x7f) as u64) << (7 * index);
+        if byte & 0x80 == 0 {
+            return Ok((value, &bytes[index + 1..]));
+        }
+    }
+    Err(anyhow!("Invalid varint in contenthash"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_hex(value: &str) -> Result<ContentHash> {
+        decode(&hex::decode(value).unwrap())
+    }
+
+    #[test]
+    fn ipfs() {
+        // EIP-1577 example, CIDv1 dag-pb
+        let cid = "bafybeibj6lixxzqtsb45ysdjnupvqkufgdvzqbnvmhw2kf7cfkesy7r7d4";
+        let content = decode_hex("e3010170122029f2d17be6139079dc48696d1f582a8530eb9805b561eda517e22a892c7e3f1f").unwrap();
+        assert_eq!(content, ContentHash::Ipfs(cid.to_string()));
+        assert_eq!(content.uri(), format!("ipfs://{}", cid));
+        // the same content as CIDv0 QmRAQB6YaCyidP37UdDnjFY5vQuiBrcqdyoW1CuDgwxkD4
+        let content = decode_hex("e301122029f2d17be6139079dc48696d1f582a8530eb9805b561eda517e22a892c7e3f1f").unwrap();
+        assert_eq!(content, ContentHash::Ipfs(cid.to_string()));
+        assert!(decode_hex("e30102").is_err());
+    }
+
+    #[test]
+    fn ipns() {
+        // libp2p-key CID of an ed25519 public key
+        let key = "e5010172002408011220000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+        assert_eq!(decode_hex(key).unwrap(), ContentHash::Ipns("bafzaajaiaejcaaabaibqibiga4eascqlbqgq4dyqcejbgfavcylrqgi2dmob2hq7".to_string()));
+        let dnslink = format!("e5010170000b{}", hex::encode("example.com"));
+        let content = decode_hex(&dnslink).unwrap();
+        assert_eq!(content, ContentHash::Ipns("example.com".to_string()));
+        assert_eq!(content.uri(), "ipns://example.com");
+    }
+
+    #[test]
+    fn onion_and_swarm() {
+        let content = decode_hex(&format!("bc03{}", hex::encode("zqktlwi4fecvo6ri"))).unwrap();
+        assert_eq!(content, ContentHash::Onion("zqktlwi4fecvo6ri.onion".to_string()));
+        assert_eq!(content.uri(), "onion://zqktlwi4fecvo6ri.onion");
+        let onion3 = "p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd";
+        assert_eq!(decode_hex(&format!("bd03{}", hex::encode(onion3))).unwrap(), ContentHash::Onion(format!("{}.onion", onion3)));
+        let content = decode_hex("e40101fa011b20d1de9994b4d039f6548d191eb26786769f580809256b4685ef316805265ea162").unwrap();
+        assert_eq!(content.uri(), "bzz://01fa011b20d1de9994b4d039f6548d191eb26786769f580809256b4685ef316805265ea162");
+    }
+
+    #[test]
+    fn invalid_records() {
+        assert!(decode(&[]).is_err());
+        assert!(decode(&[0xff; 10]).is_err());
+        assert!(decode_hex("00").is_err());
+        assert!(decode_hex("bc03ff").is_err());
+    }
+}
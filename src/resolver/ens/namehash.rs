@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use crate::resolver::ens::rpc::keccak256;
+
+/// Number of cached names, the cache is cleared as a whole when it is full.
+const NAMEHASH_CACHE_CAPACITY: usize = 10_000;
+
+/// Algorithm deriving a node hash from a domain name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum NameHashAlgorithm {
+    /// EIP-137 namehash: keccak256 chain over the labels from the TLD down
+    Eip137,
+}
+
+/// Cache of computed node hashes keyed by (algorithm, name), so hot names do not recompute
+/// hash chains on every lookup. Parent nodes are cached along the way, so sibling names
+/// only hash their first label.
+pub(crate) struct NameHashCache {
+    hashes: Mutex<HashMap<(NameHashAlgorithm, String), [u8; 32]>>,
+    clears: AtomicU64,
+}
+
+impl Default for NameHashCache {
+    fn default() -> Self {
+        Self {
+            hashes: Mutex::new(HashMap::new()),
+            clears: AtomicU64::new(0),
+        }
+    }
+}
+
+impl NameHashCache {
+    /// Returns the node hash of the normalized name, computing it on a miss.
+    pub fn get(&self, algorithm: NameHashAlgorithm, name: &str) -> [u8; 32] {
+        let name = name.trim_matches('.');
+        if name.is_empty() {
+            return [0u8; 32];
+        }
+        let key = (algorithm, name.to_string());
+        if let Some(hash) = self.hashes.lock().ok().and_then(|hashes| hashes.get(&key).copied()) {
+            return hash;
+        }
+        let (label, parent) = name.split_once('.').unwrap_or((name, ""));
+        let parent_hash = self.get(algorithm, parent);
+        let hash = match algorithm {
+            NameHashAlgorithm::Eip137 => keccak256(&[parent_hash, keccak256(label.as_bytes())].concat()),
+        };
+        if let Ok(mut hashes) = self.hashes.lock() {
+            if hashes.len() >= NAMEHASH_CACHE_CAPACITY {
+                hashes.clear();
+                self.clears.fetch_add(1, Ordering::Relaxed);
+            }
+            hashes.insert(key, hash);
+        }
+        hash
+    }
+
+    /// Number of times the cache was cleared on reaching its capacity.
+    pub fn clears(&self) -> u64 {
+        self.clears.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eip137_vectors() {
+        let cache = NameHashCache::default();
+        let namehash = |name| hex::encode(cache.get(NameHashAlgorithm::Eip137, name));
+        assert_eq!(namehash(""), "0".repeat(64));
+        assert_eq!(namehash("eth"), "93cdeb708b7545dc668eb9280176169d1c33cfd8ed6f04690a0bcc88a93fc4ae");
+        assert_eq!(namehash("foo.eth"), "de9b09fd7c5f901e23a3f19fecc54828e9c848539801e86591bd9801b019f84f");
+        assert_eq!(namehash("foo.eth."), namehash("foo.eth"));
+        // cached parents give the same hashes as a fresh cache
+        assert_eq!(namehash("foo.eth"), hex::encode(NameHashCache::default().get(NameHashAlgorithm::Eip137, "foo.eth")));
+    }
+
+    #[test]
+    fn clears_when_full() {
+        let cache = NameHashCache::default();
+        let eth = cache.get(NameHashAlgorithm::Eip137, "eth");
+        // every name also caches the shared `eth` parent
+        for index in 0..NAMEHASH_CACHE_CAPACITY {
+            cache.get(NameHashAlgorithm::Eip137, &format!("name{}.eth", index));
+        }
+        assert_eq!(cache.clears(), 1);
+        assert_eq!(cache.get(NameHashAlgorithm::Eip137, "eth"), eth);
+    }
+}
@@ -0,0 +1,170 @@
+use anyhow::{anyhow, Result};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Client;
+use serde_json::json;
+use sha3::{Digest, Keccak256};
+use url::Url;
+use crate::error::ResolverError;
+use crate::resolver::limit::{permit, InFlightLimit};
+
+/// Argument of an encoded contract call.
+pub(crate) enum Token<'a> {
+    Bytes32([u8; 32]),
+    Uint(u64),
+    String(&'a str),
+}
+
+/// Minimal Ethereum JSON-RPC client for read-only contract calls.
+pub(crate) struct EthRpcClient {
+    endpoint: Url,
+    http_client: Client,
+    in_flight: Option<InFlightLimit>,
+}
+
+impl EthRpcClient {
+    pub fn new(endpoint: Url, http_client: Client) -> Self {
+        Self { endpoint, http_client, in_flight: None }
+    }
+
+    pub fn with_in_flight_limit(self, in_flight: InFlightLimit) -> Self {
+        Self {
+            in_flight: Some(in_flight),
+            ..self
+        }
+    }
+
+    pub fn endpoint(&self) -> &Url {
+        &self.endpoint
+    }
+
+    /// Calls the function of the contract at the latest block, returns ABI encoded result.
+    pub async fn call(&self, to: &str, signature: &str, tokens: &[Token<'_>]) -> Result<Vec<u8>> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_call",
+            "params": [{ "to": to, "data": format!("0x{}", hex::encode(encode_call(signature, tokens))) }, "latest"],
+        });
+        let body = {
+            let _permit = permit(&self.in_flight).await;
+            self.http_client.post(self.endpoint.clone())
+                .header(CONTENT_TYPE, "application/json")
+                .body(request.to_string())
+                .send().await?
+                .bytes().await?
+        };
+        let response: serde_json::Value = serde_json::from_slice(&body)?;
+        if let Some(error) = response.get("error") {
+            return Err(anyhow!("Call of {} on {} failed: {}", signature, to, error));
+        }
+        let result = response.get("result")
+            .and_then(|r| r.as_str())
+            .ok_or(anyhow!("Call of {} on {} returned no result", signature, to))?;
+        Ok(hex::decode(result.trim_start_matches("0x"))?)
+    }
+}
+
+pub(crate) fn keccak256(data: &[u8]) -> [u8; 32] {
+    Keccak256::digest(data).into()
+}
+
+/// Encodes call of the function, e.g. `text(bytes32,string)`, with its arguments.
+fn encode_call(signature: &str, tokens: &[Token<'_>]) -> Vec<u8> {
+    let mut head = keccak256(signature.as_bytes())[..4].to_vec();
+    let mut tail = Vec::new();
+    let head_length = 32 * tokens.len();
+    for token in tokens {
+        match token {
+            Token::Bytes32(word) => head.extend_from_slice(word),
+            Token::Uint(value) => head.extend_from_slice(&uint_word(*value)),
+            Token::String(value) => {
+                // dynamic arguments are referenced by their offset in the tail
+                head.extend_from_slice(&uint_word((head_length + tail.len()) as u64));
+                tail.extend_from_slice(&uint_word(value.len() as u64));
+                tail.extend_from_slice(value.as_bytes());
+                tail.resize(tail.len().div_ceil(32) * 32, 0);
+            },
+        }
+    }
+    head.extend(tail);
+    head
+}
+
+fn uint_word(value: u64) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+/// Typed error of an undecodable call result.
+fn malformed(reason: impl std::fmt::Display) -> anyhow::Error {
+    ResolverError::MalformedData {
+        what: "ABI encoded result".to_string(),
+        reason: reason.to_string(),
+    }.into()
+}
+
+fn read_uint(data: &[u8], at: usize) -> Result<usize> {
+    let word = data.get(at..at.saturating_add(32)).ok_or_else(|| malformed("value is too short"))?;
+    if word[..24].iter().any(|byte| *byte != 0) {
+        return Err(malformed("integer is too large"));
+    }
+    let mut value = [0u8; 8];
+    value.copy_from_slice(&word[24..]);
+    usize::try_from(u64::from_be_bytes(value)).map_err(|e| malformed(format!("integer is too large: {}", e)))
+}
+
+/// Parses decimal `uint256`, e.g. an NFT token id, into its ABI encoded word.
+pub(crate) fn parse_uint256(value: &str) -> Result<[u8; 32]> {
+    let mut word = [0u8; 32];
+    for digit in value.chars() {
+        let mut carry = digit.to_digit(10).ok_or(anyhow!("Invalid uint256 {}", value))?;
+        for byte in word.iter_mut().rev() {
+            let product = *byte as u32 * 10 + carry;
+            *byte = product as u8;
+            carry = product >> 8;
+        }
+        if carry != 0 {
+            return Err(anyhow!("uint256 {} is too large", value));
+        }
+    }
+    Ok(word)
+}
+
+/// Decodes `address` result with EIP-55 checksum, `None` for the zero address.
+pub(crate) fn decode_address(data: &[u8]) -> Result<Option<String>> {
+    let word = data.get(..32).ok_or_else(|| malformed("address is too short"))?;
+    if word.iter().all(|byte| *byte == 0) {
+        return Ok(None);
+    }
+    Ok(Some(checksum_address(&word[12..])))
+}
+
+/// Formats 20 bytes address with EIP-55 mixed-case checksum.
+pub(crate) fn checksum_address(address: &[u8]) -> String {
+    let address = hex::encode(address);
+    let hash = hex::encode(keccak256(address.as_bytes()));
+    let checksummed: String = address.chars()
+        .zip(hash.chars())
+        .map(|(c, h)| if h >= '8' { c.to_ascii_uppercase() } else { c })
+        .collect();
+    format!("0x{}", checksummed)
+}
+
+/// Decodes `bytes` result, empty result data is decoded as empty bytes.
+pub(crate) fn decode_bytes(data: &[u8]) -> Result<Vec<u8>> {
+    if data.is_empty() {
+        return Ok(Vec::new());
+    }
+    let offset = read_uint(data, 0)?;
+    let length = read_uint(data, offset)?;
+    let start = offset.saturating_add(32);
+    data.get(start..start.saturating_add(length))
+        .map(|bytes| bytes.to_vec())
+        .ok_or_else(|| malformed("bytes are too short"))
+}
+
+/// Decodes `string` result.
+pub(crate) fn decode_string(data: &[u8]) -> Result<String> {
+    String::from_utf8(decode_bytes(data)?).map_err(malformed)
+}
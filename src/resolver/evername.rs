@@ -1,254 +1,1654 @@
-use std::collections::HashMap;
-use std::io::Cursor;
+use std::collections::{BTreeMap, HashMap};
+use std::panic::AssertUnwindSafe;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 use std::str::FromStr;
 use anyhow::{Context, Result, anyhow};
 use async_trait::async_trait;
+use futures::stream::{self, StreamExt, TryStreamExt};
 use log::debug;
+use mini_moka::sync::Cache;
+use sha2::{Digest, Sha256};
 use nekoton::abi::FunctionExt;
 use nekoton::transport::jrpc::JrpcTransport;
 use nekoton::transport::Transport;
-use nekoton::transport::models::RawContractState;
+use nekoton::transport::models::{PollContractState, RawContractState};
 use nekoton_abi::unpack_from_cell;
 use nekoton_abi::num_traits::ToPrimitive;
 use nekoton_transport::jrpc::JrpcClient;
-use reqwest::IntoUrl;
-use ton_abi::{Token, Contract, TokenValue, ParamType, Param, Uint, contract};
-use ton_block::{MsgAddressInt, MsgAddrStd, AccountStuff};
-use ton_types::{AccountId, Cell, SliceData};
-use crate::resolver::ipfs::make_ipfs_link;
-use crate::resolver::{abi, Resolver};
-use crate::models::{ResolvedDomainData, AddressTag};
+use nekoton_utils::{Clock, ConstClock, SimpleClock};
+use reqwest::{Client, IntoUrl};
+use ton_abi::{Token, TokenValue, ParamType, Param, Uint, contract};
+use ton_block::{AccountState, MsgAddressInt, MsgAddrStd, AccountStuff, Serializable};
+use ton_types::{serialize_toc, AccountId, Cell, SliceData};
+use url::Url;
+use crate::resolver::avatar::{parse_avatar_uri, AvatarUri};
+use crate::resolver::car::UnixFsDirectory;
+use crate::resolver::ipfs::{make_gateway_link, make_gateway_links, DEFAULT_IPFS_GATEWAY};
+use crate::error::{panic_message, ResolverError};
+use crate::resolver::abi::VersionedAbi;
+use crate::resolver::endpoints::{validate_endpoints, EndpointPool};
+use crate::resolver::http::http_client;
+use crate::resolver::limit::{permit, InFlightLimit};
+use crate::resolver::sniff::{sniff_content_type, DEFAULT_CONTENT_TYPE};
+use crate::resolver::state_cache::StateCache;
+use crate::resolver::trace::Trace;
+use crate::resolver::{abi, normalize, Resolver};
+use crate::models::{CacheHeaders, ConnectionPool, ContentRange, DomainDetails, EndpointSelection, EndpointStats, Timeouts, ResolvedDomainData, AddressTag, HistoricalPoint, ResolutionCandidate, DomainSnapshot, Redirect, SiteArchive, SiteAsset, SiteManifest, SnapshotRecord, StateRef, StructuredTarget, TlsRecord, ResolverCapabilities};
+use crate::models::base64;
+use crate::resolver::range::{parse_byte_range, slice_chunks};
+
+mod builder;
+#[cfg(feature = "fuzzing")]
+pub mod fuzzing;
+#[cfg(feature = "signer")]
+pub mod signer;
+mod registration;
+
+pub use builder::EvernameResolverBuilder;
+pub use registration::YEAR_SECONDS;
+pub use crate::resolver::abi::{DOMAIN_ABI_VERSION, ONCHAIN_SITE_ABI_VERSION, ROOT_ABI_VERSION};
+
+/// TLD of the mainnet Evername deployment.
+pub const EVERNAME_TLD: &str = ".ever";
 
 const ROOT_ADDRESS: &str = "a7d0694c025b61e1a4a846f1cf88980a5df8adf737d17ac58e35bf172c9fca29";
 pub const EVERSCALE_RPC_ENDPOINT: &str = "https://jrpc.everwallet.net/rpc";
 
+/// Default wall-time budget of a get-method execution.
+pub const DEFAULT_GET_METHOD_BUDGET: Duration = Duration::from_secs(5);
+/// Default number of get-methods executed at once.
+pub const DEFAULT_MAX_CONCURRENT_GET_METHODS: usize = 16;
+
+/// First delay between lookups of an account not deployed yet, doubled up to `MAX_DEPLOYMENT_BACKOFF`.
+const DEPLOYMENT_BACKOFF: Duration = Duration::from_millis(250);
+const MAX_DEPLOYMENT_BACKOFF: Duration = Duration::from_secs(2);
+
+/// Number of records `resolve_all` decodes at once, onchain contract records load whole contracts.
+const RESOLVE_ALL_CONCURRENCY: usize = 4;
+/// Records queried at once when a records map is assembled from per-key `query` calls.
+const RECORD_QUERY_CONCURRENCY: usize = 8;
+/// TVM exit codes of get-methods running out of gas or overflowing a cell.
+const TVM_LIMIT_EXIT_CODES: [i32; 3] = [8, 13, -14];
+
+/// Domain record key of the Eversite asset manifest.
+///
+/// The record holds a JSON string mapping asset paths to Eversite contract addresses or IPFS CIDs:
+/// ```json
+/// {"assets": {"/index.html": "0:4f6a...", "/style.css": "0:91bc...", "/logo.png": "ipfs://bafy..."}}
+/// ```
+pub const SITE_MANIFEST_RECORD_KEY: u32 = 2001;
+
+/// Max length of a single label of Evername domain in its ASCII (punycode) form.
+pub const MAX_EVERNAME_LABEL_LENGTH: usize = 63;
+/// Max length of Evername domain in its ASCII (punycode) form.
+pub const MAX_EVERNAME_LENGTH: usize = 253;
+
+/// Domain record keys of TXT-like records: free-form strings such as ACME challenges or
+/// `google-site-verification=...` tokens, returned by `EvernameResolver::txt_records` in key order.
+pub const TXT_RECORD_KEYS: std::ops::RangeInclusive<u32> = 3000..=3099;
+
+/// Source of a contract ABI JSON.
+#[derive(Clone, Debug)]
+pub enum AbiSource {
+    /// Keep the bundled ABI only
+    Bundled,
+    /// ABI JSON string
+    Json(String),
+    /// Path to the ABI JSON file
+    File(PathBuf),
+    /// URL to download the ABI JSON from
+    Url(Url),
+}
+
+impl AbiSource {
+    async fn load(&self, http_client: &Client) -> Result<Option<String>> {
+        match self {
+            AbiSource::Bundled => Ok(None),
+            AbiSource::Json(json) => Ok(Some(json.clone())),
+            AbiSource::File(path) => {
+                let json = tokio::fs::read_to_string(path).await
+                    .with_context(|| format!("Failed to read ABI file {}", path.display()))?;
+                Ok(Some(json))
+            },
+            AbiSource::Url(url) => {
+                let response = http_client.get(url.clone()).send().await?.error_for_status()?;
+                Ok(Some(response.text().await?))
+            },
+        }
+    }
+}
+
+/// Detects the source kind: JSON if it starts with `{`, URL if it has http(s) scheme, file path otherwise.
+impl From<&str> for AbiSource {
+    fn from(value: &str) -> Self {
+        let trimmed = value.trim_start();
+        if trimmed.starts_with('{') {
+            return AbiSource::Json(value.to_string());
+        }
+        match Url::parse(value) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => AbiSource::Url(url),
+            _ => AbiSource::File(PathBuf::from(value)),
+        }
+    }
+}
+
+/// Options of a single resolution pass.
+#[derive(Clone, Copy, Default)]
+struct Query<'a> {
+    /// Point in time for historical resolution
+    at: Option<HistoricalPoint>,
+    /// Do not write account states cache
+    dry_run: bool,
+    /// Collects references of the account states read
+    state_refs: Option<&'a StateRefs>,
+}
+
+/// Get-method call: the input tokens, the output a matching ABI must return and the point in time to run it at.
+#[derive(Clone, Copy)]
+struct GetterCall<'a> {
+    inputs: &'a [Token],
+    output: Option<&'a str>,
+    at: Option<HistoricalPoint>,
+}
+
+impl<'a> GetterCall<'a> {
+    fn new(inputs: &'a [Token]) -> Self {
+        Self { inputs, output: None, at: None }
+    }
+
+    fn returning(self, output: &'a str) -> Self {
+        Self { output: Some(output), ..self }
+    }
+
+    fn at(self, at: Option<HistoricalPoint>) -> Self {
+        Self { at, ..self }
+    }
+}
+
+/// References of the account states read by a single resolution, each account is recorded once.
+#[derive(Default)]
+struct StateRefs(Mutex<Vec<StateRef>>);
+
+impl StateRefs {
+    fn record(&self, address: &MsgAddressInt, state: &AccountStuff) -> Result<()> {
+        let address = address.to_string();
+        let hash = state.serialize()?.repr_hash().to_hex_string();
+        let mut state_refs = self.0.lock().map_err(|_| anyhow!("State references are poisoned"))?;
+        if !state_refs.iter().any(|state_ref| state_ref.address == address) {
+            state_refs.push(StateRef { address, last_trans_lt: state.storage.last_trans_lt, hash });
+        }
+        Ok(())
+    }
+
+    fn into_inner(self) -> Vec<StateRef> {
+        self.0.into_inner().unwrap_or_default()
+    }
+}
+
 pub struct EvernameResolver {
     jrpc_transport: JrpcTransport,
+    endpoints: Vec<Url>,
     root_address: MsgAddressInt,
-    root_contract: Contract,
-    domain_contract: Contract,
-    onchain_site_contract: Contract,
+    // ABIs are ordered from the newest to the oldest one
+    root_abis: Vec<VersionedAbi>,
+    domain_abis: Vec<VersionedAbi>,
+    onchain_site_abis: Vec<VersionedAbi>,
+    state_cache: Option<StateCache>,
+    clock: Arc<dyn Clock>,
+    get_method_budget: Duration,
+    // permits of get-method executions, held by the blocking thread until TVM returns
+    get_method_slots: Arc<tokio::sync::Semaphore>,
+    // window of retries of accounts not deployed yet
+    deployment_wait: Option<Duration>,
+    default_content_type: String,
+    ipfs_gateway: String,
+    content_records: bool,
+    // results of standalone `resolve` calls, `Web3DomainResolver` has its own cache
+    result_cache: Option<Cache<String, (ResolvedDomainData, AddressTag)>>,
+    endpoint_pool: Option<EndpointPool>,
+    in_flight: Option<InFlightLimit>,
+    search_indexer: Option<Url>,
+    // client of search indexer and ABI requests, with the JRPC timeouts and connection pool settings
+    http_client: Client,
+    tld: String,
+    // source name, `evername` for the mainnet `.ever` deployment
+    name: String,
 }
 
 impl EvernameResolver {
     pub fn new<U: IntoUrl>(jrpc_endpoint: U) -> Result<Self> {
         let jrpc_endpoint = jrpc_endpoint.into_url()?;
-        let jrpc_client = JrpcClient::new(jrpc_endpoint)?;
-        let jrpc_transport = JrpcTransport::new(jrpc_client.clone());
+        let jrpc_client = JrpcClient::new(jrpc_endpoint.clone())?;
+        Ok(Self {
+            endpoints: vec![jrpc_endpoint],
+            ..Self::with_transport(JrpcTransport::new(jrpc_client))?
+        })
+    }
+
+    /// Creates resolver spreading requests over several JRPC endpoints proportionally to their weights,
+    /// failed requests are retried on the other endpoints.
+    pub fn from_endpoints(endpoints: Vec<(Url, u32)>) -> Result<Self> {
+        Self::from_endpoints_with_timeouts(endpoints, &Timeouts::default())
+    }
+
+    /// Same as `from_endpoints`, JRPC requests are bounded by the given timeouts.
+    pub fn from_endpoints_with_timeouts(endpoints: Vec<(Url, u32)>, timeouts: &Timeouts) -> Result<Self> {
+        Self::from_endpoints_with_selection(endpoints, timeouts, EndpointSelection::default())
+    }
+
+    /// Same as `from_endpoints_with_timeouts`, requests are spread over the endpoints by the given strategy.
+    pub fn from_endpoints_with_selection(endpoints: Vec<(Url, u32)>, timeouts: &Timeouts, selection: EndpointSelection) -> Result<Self> {
+        Self::from_endpoints_with_connection_pool(endpoints, timeouts, &ConnectionPool::default(), selection)
+    }
+
+    /// Same as `from_endpoints_with_selection`, connections are reused with the given settings.
+    pub fn from_endpoints_with_connection_pool(endpoints: Vec<(Url, u32)>, timeouts: &Timeouts, pool: &ConnectionPool, selection: EndpointSelection) -> Result<Self> {
+        // JrpcClient has no timeout and pool settings, so tuned requests always go through the pool
+        if let ([(url, _)], true) = (endpoints.as_slice(), timeouts.is_unbounded() && pool.is_default()) {
+            validate_endpoints(&endpoints)?;
+            return Self::new(url.clone());
+        }
+        let urls = endpoints.iter().map(|(url, _)| url.clone()).collect();
+        let endpoint_pool = EndpointPool::new(endpoints, timeouts, pool, selection)?;
+        Ok(Self {
+            endpoints: urls,
+            endpoint_pool: Some(endpoint_pool.clone()),
+            http_client: http_client(timeouts, pool)?,
+            ..Self::with_transport(JrpcTransport::new(endpoint_pool))?
+        })
+    }
+
+    /// JRPC endpoints of the resolver.
+    pub fn endpoints(&self) -> &[Url] {
+        &self.endpoints
+    }
+
+    /// IPFS gateway of IPFS records.
+    pub fn ipfs_gateway(&self) -> &str {
+        &self.ipfs_gateway
+    }
+
+    /// Request counters, latency and error rate moving averages of the JRPC endpoints.
+    /// Empty for a resolver created with a single endpoint and no timeouts.
+    pub fn endpoint_stats(&self) -> Vec<EndpointStats> {
+        self.endpoint_pool.as_ref().map(|pool| pool.stats()).unwrap_or_default()
+    }
+
+    fn with_transport(jrpc_transport: JrpcTransport) -> Result<Self> {
         let root_address = MsgAddressInt::AddrStd(MsgAddrStd{
             anycast: None,
             workchain_id: 0,
             address: AccountId::from_string(ROOT_ADDRESS)?,
         });
-        let root_contract = Contract::load(Cursor::new(abi::ROOT_ABI_JSON))?;
-        let domain_contract = Contract::load(Cursor::new(abi::DOMAIN_ABI_JSON))?;
-        let onchain_site_contract = Contract::load(Cursor::new(abi::ONCHAIN_SITE_ABI_JSON))?;
+        let root_abis = vec![VersionedAbi::load(abi::ROOT_ABI_JSON)?];
+        let domain_abis = vec![VersionedAbi::load(abi::DOMAIN_ABI_JSON)?];
+        let onchain_site_abis = vec![VersionedAbi::load(abi::ONCHAIN_SITE_ABI_JSON)?];
         Ok(Self {
             jrpc_transport,
+            endpoints: Vec::new(),
             root_address,
-            root_contract,
-            domain_contract,
-            onchain_site_contract
+            root_abis,
+            domain_abis,
+            onchain_site_abis,
+            state_cache: None,
+            clock: Arc::new(SimpleClock),
+            get_method_budget: DEFAULT_GET_METHOD_BUDGET,
+            get_method_slots: Arc::new(tokio::sync::Semaphore::new(DEFAULT_MAX_CONCURRENT_GET_METHODS)),
+            deployment_wait: None,
+            default_content_type: DEFAULT_CONTENT_TYPE.to_string(),
+            ipfs_gateway: DEFAULT_IPFS_GATEWAY.to_string(),
+            content_records: true,
+            result_cache: None,
+            endpoint_pool: None,
+            in_flight: None,
+            search_indexer: None,
+            http_client: Client::new(),
+            tld: EVERNAME_TLD.to_string(),
+            name: "evername".to_string(),
         })
     }
     
+    #[allow(clippy::should_implement_trait)]
     pub fn default() -> Result<Self> {
         Self::new(EVERSCALE_RPC_ENDPOINT)
     }
+
+    /// Creates builder of a standalone resolver for dapps resolving `.ever` domains only.
+    pub fn builder() -> EvernameResolverBuilder {
+        EvernameResolverBuilder::default()
+    }
+
+    /// Checks the domain against Evername label rules: labels of lowercase latin letters, digits and hyphens
+    /// in the ASCII (punycode) form, up to 63 characters, not starting or ending with a hyphen, and hyphens at
+    /// the 3rd and 4th positions only in `xn--` labels of internationalized names.
+    /// Fails with `ResolverError::InvalidName`, names are checked before querying the chain.
+    pub fn validate_name(domain: &str) -> Result<()> {
+        let invalid = |reason: String| -> anyhow::Error {
+            ResolverError::InvalidName { domain: domain.to_string(), reason }.into()
+        };
+        let name = idna::domain_to_ascii(domain.trim_end_matches('.'))
+            .map_err(|e| invalid(format!("not a valid internationalized name: {}", e)))?;
+        if name.is_empty() {
+            return Err(invalid("name is empty".to_string()));
+        }
+        if name.len() > MAX_EVERNAME_LENGTH {
+            return Err(invalid(format!("name is longer than {} characters", MAX_EVERNAME_LENGTH)));
+        }
+        for label in name.split('.') {
+            if label.is_empty() {
+                return Err(invalid("empty label".to_string()));
+            }
+            if label.len() > MAX_EVERNAME_LABEL_LENGTH {
+                return Err(invalid(format!("label {} is longer than {} characters", label, MAX_EVERNAME_LABEL_LENGTH)));
+            }
+            if let Some(c) = label.chars().find(|c| !matches!(c, 'a'..='z' | '0'..='9' | '-')) {
+                return Err(invalid(format!("label {} has disallowed character {:?}", label, c)));
+            }
+            if label.starts_with('-') || label.ends_with('-') {
+                return Err(invalid(format!("label {} starts or ends with a hyphen", label)));
+            }
+            if label.get(2..4) == Some("--") && !label.starts_with("xn--") {
+                return Err(invalid(format!("label {} has hyphens at the 3rd and 4th positions", label)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets address of the DomainRoot contract for Evername-style deployments other than
+    /// the mainnet one, e.g. testnet `.ever-test` or `.venom`.
+    pub fn with_root_address(self, root_address: &str) -> Result<Self> {
+        let root_address = MsgAddressInt::from_str(root_address)
+            .map_err(|e| anyhow!("Invalid root address {}: {}", root_address, e))?;
+        Ok(Self {
+            root_address,
+            ..self
+        })
+    }
+
+    /// Sets TLD of the deployment, `.ever` by default. Resolvers of other TLDs are reported
+    /// as `evername<tld>` sources, e.g. `evername.venom`.
+    pub fn with_tld(self, tld: &str) -> Self {
+        let tld = format!(".{}", tld.trim_start_matches('.').to_lowercase());
+        let name = if tld == EVERNAME_TLD { "evername".to_string() } else { format!("evername{}", tld) };
+        Self {
+            tld,
+            name,
+            ..self
+        }
+    }
+
+    pub fn tld(&self) -> &str {
+        &self.tld
+    }
+
+    /// Sets indexer of the Evername collection used by `search`. The indexer is queried with
+    /// `GET <url>?prefix=<prefix>&limit=<limit>` and returns a JSON array of domain names
+    /// or of objects with the `name` field.
+    pub fn with_search_indexer(self, indexer: Url) -> Self {
+        Self {
+            search_indexer: Some(indexer),
+            ..self
+        }
+    }
+
+    /// Sets IPFS gateway for IPFS records, `{cid}` is replaced with the content hash,
+    /// otherwise the hash is appended to the gateway path. Default is `https://{cid}.ipfs.w3s.link/`.
+    pub fn with_ipfs_gateway(self, gateway: &str) -> Self {
+        Self {
+            ipfs_gateway: gateway.to_string(),
+            ..self
+        }
+    }
+
+    /// Enables or disables resolving of onchain content records (`1004` and `1005`), so resolution
+    /// never loads site content from Eversite contracts. Enabled by default.
+    pub fn with_content_records(self, content_records: bool) -> Self {
+        Self {
+            content_records,
+            ..self
+        }
+    }
+
+    /// Bounds in-flight JRPC requests by the limit, which may be shared with other resolvers.
+    pub fn with_in_flight_limit(self, in_flight: InFlightLimit) -> Self {
+        Self {
+            in_flight: Some(in_flight),
+            ..self
+        }
+    }
+
+    /// Enables caching of `resolve` results for the given time-to-live. Onchain content is not cached.
+    pub fn with_result_cache(self, ttl: Duration) -> Self {
+        Self {
+            result_cache: Some(Cache::builder().time_to_live(ttl).build()),
+            ..self
+        }
+    }
+
+    /// Removes the domain from the results cache and drops the cached state of its certificate,
+    /// so the next resolution reads the current records.
+    pub fn invalidate(&self, domain: &str) {
+        let key = normalize::cache_key(domain);
+        if let Some(cache) = &self.result_cache {
+            cache.invalidate(&key);
+        }
+        if let Some(state_cache) = &self.state_cache {
+            state_cache.invalidate_certificate(&key);
+        }
+    }
+
+    /// Replaces the cached state of the account with the polled one.
+    pub(crate) fn refresh_state(&self, address: &MsgAddressInt, account: AccountStuff) {
+        if let Some(state_cache) = &self.state_cache {
+            state_cache.insert(&address.to_string(), account);
+        }
+    }
+
+    /// Enables caching of account states.
+    /// States younger than `fresh_for` are used without requests, older ones are revalidated
+    /// by the latest transaction lt (cheap when the account has not changed),
+    /// states older than `max_age` are dropped.
+    pub fn with_state_cache(self, fresh_for: Duration, max_age: Duration) -> Self {
+        Self {
+            state_cache: Some(StateCache::new(fresh_for, max_age)),
+            ..self
+        }
+    }
+
+    /// Sets the clock used for get-methods execution, `SimpleClock` by default.
+    /// E.g. `ConstClock` makes expiry-sensitive contract logic deterministic.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            clock,
+            ..self
+        }
+    }
+
+    /// Sets the wall-time budget of a get-method execution, `DEFAULT_GET_METHOD_BUDGET` by default.
+    /// Huge or malicious contract states exceeding it fail with `ResolverError::GetMethodTimeout`.
+    pub fn with_get_method_budget(self, get_method_budget: Duration) -> Self {
+        Self {
+            get_method_budget,
+            ..self
+        }
+    }
+
+    /// Sets the number of get-methods executed at once, `DEFAULT_MAX_CONCURRENT_GET_METHODS` by default.
+    /// A running TVM can not be interrupted, so executions outliving their budget keep their slot until they finish,
+    /// and hostile contracts resolved repeatedly occupy at most this many blocking threads.
+    /// Waiting for a slot counts against the get-method budget.
+    pub fn with_max_concurrent_get_methods(self, max_concurrent_get_methods: usize) -> Self {
+        Self {
+            get_method_slots: Arc::new(tokio::sync::Semaphore::new(max_concurrent_get_methods)),
+            ..self
+        }
+    }
+
+    /// Retries lookups of accounts that do not exist with backoff for up to `window` before failing:
+    /// certificates of freshly registered domains take a few seconds to appear on public RPC replicas.
+    /// Lookups of unregistered domains take the whole window as well. Disabled by default.
+    pub fn with_deployment_wait(self, window: Duration) -> Self {
+        Self {
+            deployment_wait: Some(window),
+            ..self
+        }
+    }
+
+    /// Sets content type of onchain contract content used when the contract omits it
+    /// and the content type can not be sniffed. Default is `text/html; charset=utf-8`.
+    pub fn with_default_content_type(self, content_type: &str) -> Self {
+        Self {
+            default_content_type: content_type.to_string(),
+            ..self
+        }
+    }
+
+    /// Resolves the domain as it was at the given point in time.
+    ///
+    /// JRPC serves only the latest account states, so the current states are used and get-methods
+    /// are run with the clock set to the requested time. If the domain or content contract has
+    /// transactions after the requested point, its state at that moment is unknown and
+    /// `ResolverError::HistoricalStateUnavailable` is returned.
+    pub async fn resolve_at(&self, domain: &str, at: HistoricalPoint) -> Result<(ResolvedDomainData, AddressTag)> {
+        self.resolve_at_point(domain, Query { at: Some(at), ..Query::default() }, &mut Trace::disabled()).await
+    }
+
+    /// Returns the certificate contract address of the domain, its last transaction lt and the unix time
+    /// storage fees were last paid at, which every transaction does.
+    pub(crate) async fn certificate_state(&self, domain: &str) -> Result<(MsgAddressInt, u64, u32)> {
+        let certificate_address = self.address_contract(domain.to_string(), Query::default()).await?;
+        let state = self.fetch_contract_state(&certificate_address).await?;
+        Ok((certificate_address, state.storage.last_trans_lt, state.storage_stat.last_paid))
+    }
+
+    /// Polls the account for transactions after the given lt.
+    pub(crate) async fn poll_contract_state(&self, address: &MsgAddressInt, last_trans_lt: u64) -> Result<PollContractState> {
+        let _permit = permit(&self.in_flight).await;
+        self.jrpc_transport.poll_contract_state(address, last_trans_lt).await
+    }
+
+    /// Low-level escape hatch: runs a get-method of the domain certificate contract
+    /// and returns its raw output tokens.
+    /// The method must be present in the Domain contract ABI, custom methods can be
+    /// described with `with_domain_abi_json`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # async fn example() -> anyhow::Result<()> {
+    /// use ton_abi::{Token, TokenValue, Uint};
+    /// use web3_resolver::EvernameResolver;
+    ///
+    /// let resolver = EvernameResolver::default()?;
+    /// let tokens = resolver.call_certificate_method(
+    ///     "maksimzubov.ever",
+    ///     "query",
+    ///     &[
+    ///         Token::new("answerId", TokenValue::Uint(Uint::new(0, 32))),
+    ///         Token::new("key", TokenValue::Uint(Uint::new(1001, 32))),
+    ///     ],
+    /// ).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn call_certificate_method(&self, domain: &str, method: &str, tokens: &[Token]) -> Result<Vec<Token>> {
+        self.call_certificate_method_at(domain, method, tokens, Query::default()).await
+    }
+
+    async fn call_certificate_method_at(&self, domain: &str, method: &str, tokens: &[Token], query: Query<'_>) -> Result<Vec<Token>> {
+        let certificate_address = self.address_contract(domain.to_string(), query).await?;
+        let state = self.get_contract_state(&certificate_address, query).await?;
+        self.run_getter(&self.domain_abis, "Domain", method, &state, GetterCall::new(tokens).at(query.at)).await
+    }
+
+    /// Returns the asset manifest of a multi-asset onchain site,
+    /// or `None` if the domain has no manifest record (see `SITE_MANIFEST_RECORD_KEY`).
+    pub async fn site_manifest(&self, domain: &str) -> Result<Option<SiteManifest>> {
+        let certificate_address = self.address_contract(domain.to_string(), Query::default()).await?;
+        let records = self.get_raw_records(&certificate_address, Query::default()).await?;
+        let Some(cell_value) = records.get(&SITE_MANIFEST_RECORD_KEY) else {
+            return Ok(None);
+        };
+        let manifest_json = string_cell_value(cell_value)?;
+        SiteManifest::from_json(&manifest_json)
+            .with_context(|| format!("Malformed site manifest of {}", domain))
+            .map(Some)
+    }
+
+    /// Returns raw and decoded records of the domain in key order, Eversite contract records
+    /// are given by the contract address without loading the content.
+    pub async fn records(&self, domain: &str) -> Result<Vec<SnapshotRecord>> {
+        let certificate_address = self.address_contract(domain.to_string(), Query::default()).await?;
+        self.get_raw_records(&certificate_address, Query::default()).await?
+            .into_iter()
+            .map(|(key, cell_value)| {
+                let value = match AddressTag::from_record_key(key as u128) {
+                    Some(AddressTag::OnchainContract) => address_cell_value(&cell_value).ok(),
+                    _ => string_cell_value(&cell_value).ok(),
+                };
+                Ok(SnapshotRecord::new(key, hex::encode(serialize_toc(&cell_value)?), value))
+            })
+            .collect()
+    }
+
+    /// Returns keys of the populated records of the domain in ascending order, without decoding
+    /// the values, so UIs can show what is configured before fetching content records.
+    pub async fn record_keys(&self, domain: &str) -> Result<Vec<u32>> {
+        let certificate_address = self.address_contract(domain.to_string(), Query::default()).await?;
+        let records = self.get_raw_records(&certificate_address, Query::default()).await?;
+        Ok(records.into_keys().collect())
+    }
+
+    /// Returns TXT-like records of the domain (keys in `TXT_RECORD_KEYS`) ordered by key,
+    /// records that are not strings are skipped.
+    pub async fn txt_records(&self, domain: &str) -> Result<Vec<String>> {
+        let certificate_address = self.address_contract(domain.to_string(), Query::default()).await?;
+        let state = self.get_contract_state(&certificate_address, Query::default()).await?;
+        let records = self.records_of_keys(&state, TXT_RECORD_KEYS, None).await?;
+        Ok(records.iter()
+            .filter_map(|(key, cell_value)| match string_cell_value(cell_value) {
+                Ok(value) => Some(value),
+                Err(e) => {
+                    debug!("Skipping TXT record {} of {}: {}", key, domain, e);
+                    None
+                },
+            })
+            .collect())
+    }
+
+    /// Returns owner and registration period of the domain from its certificate `getDetails`.
+    pub async fn domain_details(&self, domain: &str) -> Result<DomainDetails> {
+        self.domain_details_at(domain, Query::default()).await
+    }
+
+    async fn domain_details_at(&self, domain: &str, query: Query<'_>) -> Result<DomainDetails> {
+        let tokens = self.call_certificate_method_at(domain, "getDetails", &[
+            Token::new("answerId", TokenValue::Uint(Uint::new(0, 32))),
+        ], query).await?;
+        let mut owner = None;
+        let mut registered_at = None;
+        let mut expires_at = None;
+        for token in tokens {
+            match (token.name.as_str(), token.value) {
+                ("owner", TokenValue::Address(address)) => owner = Some(address.to_string()),
+                ("initTime", TokenValue::Uint(time)) => registered_at = time.number.to_u64(),
+                ("expireTime", TokenValue::Uint(time)) => expires_at = time.number.to_u64(),
+                _ => {},
+            }
+        }
+        match (owner, registered_at, expires_at) {
+            (Some(owner), Some(registered_at), Some(expires_at)) => Ok(DomainDetails {
+                domain: domain.to_string(),
+                owner,
+                registered_at,
+                expires_at,
+            }),
+            _ => Err(malformed("domain details", "getDetails returned no owner or registration period")),
+        }
+    }
+
+    /// Returns TLS certificate or public key of the domain (record `1006`), `None` if it is not set.
+    pub async fn tls_record(&self, domain: &str) -> Result<Option<TlsRecord>> {
+        let certificate_address = self.address_contract(domain.to_string(), Query::default()).await?;
+        let records = self.get_records(&certificate_address, Query::default()).await?;
+        let Some(cell_value) = records.get(&AddressTag::TlsCertificate) else {
+            return Ok(None);
+        };
+        let value = string_cell_value(cell_value)?;
+        TlsRecord::from_bytes(value.as_bytes())
+            .with_context(|| format!("Malformed TLS record of {}", domain))
+            .map(Some)
+    }
+
+    /// Registered domains starting with the prefix, at most `limit` of them. Without a search indexer
+    /// (see `with_search_indexer`) only the exact `<prefix>.ever` (or `<prefix><tld>`, see `with_tld`) domain is looked up.
+    pub async fn search(&self, prefix: &str, limit: usize) -> Result<Vec<String>> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        let prefix = prefix.trim().trim_end_matches('.').to_lowercase();
+        let Some(indexer) = &self.search_indexer else {
+            let domain = if prefix.ends_with(&self.tld) { prefix } else { format!("{}{}", prefix, self.tld) };
+            if Self::validate_name(&domain).is_err() {
+                return Ok(Vec::new());
+            }
+            let certificate = self.address_contract(domain.clone(), Query::default()).await?;
+            return match self.get_contract_state(&certificate, Query::default()).await {
+                Ok(_) => Ok(vec![domain]),
+                Err(e) if matches!(e.downcast_ref::<ResolverError>(), Some(ResolverError::AccountNotFound { .. })) => Ok(Vec::new()),
+                Err(e) => Err(e),
+            };
+        };
+        let response = {
+            let _permit = permit(&self.in_flight).await;
+            self.http_client.get(indexer.clone())
+                .query(&[("prefix", prefix.as_str()), ("limit", &limit.to_string())])
+                .send().await?
+                .error_for_status()?
+                .bytes().await?
+        };
+        let found: serde_json::Value = serde_json::from_slice(&response)?;
+        Ok(found.as_array()
+            .map(|names| names.iter()
+                .filter_map(|name| name.as_str().or_else(|| name.get("name").and_then(|name| name.as_str())))
+                .map(|name| name.to_lowercase())
+                .take(limit)
+                .collect())
+            .unwrap_or_default())
+    }
+
+    /// Takes snapshot of the domain: certificate contract with its last transaction lt, all records as raw
+    /// BOCs with decoded values and hashes (Eversite contract content is loaded and hashed), and the resolution result.
+    pub async fn snapshot(&self, domain: &str) -> Result<DomainSnapshot> {
+        let mut snapshot = DomainSnapshot::new(domain, self.name());
+        let certificate_address = self.address_contract(domain.to_string(), Query::default()).await?;
+        let state = self.get_contract_state(&certificate_address, Query::default()).await?;
+        snapshot.account = Some(certificate_address.to_string());
+        snapshot.last_transaction_lt = Some(state.storage.last_trans_lt);
+        for (key, cell_value) in self.get_raw_records(&certificate_address, Query::default()).await? {
+            let raw = hex::encode(serialize_toc(&cell_value)?);
+            let record = match (AddressTag::from_record_key(key as u128), address_cell_value(&cell_value)) {
+                // Eversite contract records are hashed by the content they point to
+                (Some(AddressTag::OnchainContract), Ok(address)) => {
+                    let (content, content_type) = self.load_content_from_contract(&address, Query::default()).await
+                        .with_context(|| format!("Failed to load content of {} from {}", domain, address))?;
+                    SnapshotRecord {
+                        value: Some(address),
+                        content_type: Some(content_type),
+                        ..SnapshotRecord::new(key, raw, Some(content))
+                    }
+                },
+                (Some(AddressTag::OnchainContract), Err(_)) => SnapshotRecord::new(key, raw, None),
+                _ => SnapshotRecord::new(key, raw, string_cell_value(&cell_value).ok()),
+            };
+            snapshot.records.push(record);
+        }
+        snapshot.set_resolution(self.resolve_at_point(domain, Query::default(), &mut Trace::disabled()).await);
+        Ok(snapshot)
+    }
+
+    /// Returns fetchable URL of the domain NFT image from its TIP-4.2 metadata: `preview.source`,
+    /// then the first image file, `None` if the metadata has no image.
+    pub async fn avatar(&self, domain: &str) -> Result<Option<String>> {
+        let certificate_address = self.address_contract(domain.to_string(), Query::default()).await?;
+        let state = self.get_contract_state(&certificate_address, Query::default()).await?;
+        let tokens = self.run_getter(
+            &self.domain_abis,
+            "Domain",
+            "getJson",
+            &state,
+            GetterCall::new(&[
+                Token::new("answerId", TokenValue::Uint(Uint::new(0, 32)))
+            ]).returning("json"),
+        ).await?;
+        let metadata_json = tokens
+            .into_iter()
+            .find(|token| token.name == "json")
+            .and_then(|token| match token.value {
+                TokenValue::String(json) => Some(json),
+                _ => None,
+            })
+            .ok_or_else(|| anyhow!("No metadata of {}", domain))?;
+        let metadata: serde_json::Value = serde_json::from_str(&metadata_json)
+            .with_context(|| format!("Malformed metadata of {}", domain))?;
+        let preview = metadata.get("preview")
+            .and_then(|preview| preview.get("source"))
+            .and_then(|source| source.as_str());
+        let image_file = metadata.get("files")
+            .and_then(|files| files.as_array())
+            .and_then(|files| files.iter().find(|file| file.get("mimetype")
+                .and_then(|mimetype| mimetype.as_str())
+                .is_some_and(|mimetype| mimetype.starts_with("image/"))))
+            .and_then(|file| file.get("source"))
+            .and_then(|source| source.as_str());
+        let Some(image) = preview.or(image_file) else {
+            return Ok(None);
+        };
+        match parse_avatar_uri(image, &self.ipfs_gateway)? {
+            AvatarUri::Link(link) => Ok(Some(link)),
+            AvatarUri::Nft(nft) => Err(anyhow!("Unsupported image {} in metadata of {}", nft, domain)),
+        }
+    }
+
+    /// Loads content of a site manifest asset: Eversite contract content with its content type
+    /// or IPFS gateway link.
+    pub async fn load_site_asset(&self, asset: &SiteAsset) -> Result<ResolvedDomainData> {
+        match asset {
+            SiteAsset::Contract(address) => {
+                let (content, content_type) = self.load_content_from_contract(address, Query::default()).await?;
+                Ok(ResolvedDomainData::OnchainContractData((content, content_type)))
+            },
+            SiteAsset::Ipfs(cid) => Ok(ResolvedDomainData::DomainString(make_gateway_link(&self.ipfs_gateway, cid))),
+        }
+    }
+
+    /// Packages the onchain site of the domain into a UnixFS directory inside a CARv1 archive:
+    /// resolved onchain content becomes the index file, manifest assets stored in Eversite
+    /// contracts are added by their paths. UnixFS has no content types, so the index file is named
+    /// by the content type, e.g. `index.html` or `index.svg`.
+    pub async fn export_site_car(&self, domain: &str) -> Result<SiteArchive> {
+        let mut directory = UnixFsDirectory::default();
+        let mut files = Vec::new();
+        let mut ipfs_assets = BTreeMap::new();
+        let manifest = self.site_manifest(domain).await?.unwrap_or_default();
+        for (path, asset) in &manifest.assets {
+            match asset {
+                SiteAsset::Contract(address) => {
+                    let (content, _) = self.load_content_from_contract(address, Query::default()).await
+                        .with_context(|| format!("Failed to load asset {} of {}", path, domain))?;
+                    directory.add_file(path, content.into_bytes())?;
+                    files.push(path.clone());
+                },
+                SiteAsset::Ipfs(cid) => {
+                    ipfs_assets.insert(path.clone(), cid.clone());
+                },
+            }
+        }
+        let content = match self.resolve_at_point(domain, Query::default(), &mut Trace::disabled()).await {
+            Ok((ResolvedDomainData::OnchainData(content), _)) => {
+                let content_type = self.sniff_content_type(&content);
+                Some((content, content_type))
+            },
+            Ok((ResolvedDomainData::OnchainContractData(content), _)) => Some(content),
+            // sites served from the manifest only may have no content record
+            Ok(_) | Err(_) if !files.is_empty() => None,
+            Ok((data, tag)) => return Err(anyhow!("Domain {} has no onchain site, it resolves to {} with tag {}", domain, data, tag)),
+            Err(e) => return Err(e),
+        };
+        if let Some((content, content_type)) = content {
+            let path = format!("/index.{}", content_extension(&content_type));
+            if !manifest.assets.contains_key(&path) {
+                directory.add_file(&path, content.into_bytes())?;
+                files.push(path);
+            }
+        }
+        let (root_cid, car) = directory.into_car();
+        Ok(SiteArchive { root_cid, car, files, ipfs_assets })
+    }
+
+    /// Loads a byte range of the onchain contract content of the domain, so large media can be served
+    /// with HTTP range requests. `range_header` is the HTTP `Range` header value, the whole content
+    /// is returned if it is `None`. Fails with `ResolverError::RangeNotSatisfiable` for ranges outside of the content.
+    pub async fn content_range(&self, domain: &str, range_header: Option<&str>) -> Result<ContentRange> {
+        let certificate_address = self.address_contract(domain.to_string(), Query::default()).await?;
+        let records = self.get_records(&certificate_address, Query::default()).await?;
+        let cell_value = records.get(&AddressTag::OnchainContract)
+            .ok_or_else(|| anyhow!("Domain {} has no onchain contract record", domain))?;
+        let contract_address = address_cell_value(cell_value)?;
+        self.load_contract_content_range(&contract_address, range_header).await
+    }
+
+    /// Loads a byte range of the site manifest asset stored in an Eversite contract.
+    pub async fn load_site_asset_range(&self, asset: &SiteAsset, range_header: Option<&str>) -> Result<ContentRange> {
+        match asset {
+            SiteAsset::Contract(address) => self.load_contract_content_range(address, range_header).await,
+            SiteAsset::Ipfs(cid) => Err(anyhow!("IPFS asset {} is served by IPFS gateway", cid)),
+        }
+    }
+
+    /// Adds DomainRoot, Domain and Eversite contract ABIs from the given sources,
+    /// so modified contracts can be resolved without recompiling the crate.
+    /// Added ABIs are tried before the bundled ones.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # async fn example() -> anyhow::Result<()> {
+    /// use web3_resolver::{AbiSource, EvernameResolver};
+    ///
+    /// let resolver = EvernameResolver::default()?
+    ///     .with_abis(AbiSource::Bundled, AbiSource::Bundled, "./abi/Eversite.abi.json".into())
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn with_abis(self, root: AbiSource, domain: AbiSource, onchain_site: AbiSource) -> Result<Self> {
+        let http_client = self.http_client.clone();
+        let mut resolver = self;
+        if let Some(abi_json) = root.load(&http_client).await? {
+            resolver = resolver.with_root_abi_json(&abi_json)?;
+        }
+        if let Some(abi_json) = domain.load(&http_client).await? {
+            resolver = resolver.with_domain_abi_json(&abi_json)?;
+        }
+        if let Some(abi_json) = onchain_site.load(&http_client).await? {
+            resolver = resolver.with_onchain_site_abi_json(&abi_json)?;
+        }
+        Ok(resolver)
+    }
+
+    /// Adds DomainRoot contract ABI, it is tried before the bundled one.
+    pub fn with_root_abi_json(mut self, abi_json: &str) -> Result<Self> {
+        self.root_abis.insert(0, VersionedAbi::load(abi_json)?);
+        Ok(self)
+    }
+
+    /// Adds Domain contract ABI, it is tried before the bundled one.
+    pub fn with_domain_abi_json(mut self, abi_json: &str) -> Result<Self> {
+        self.domain_abis.insert(0, VersionedAbi::load(abi_json)?);
+        Ok(self)
+    }
+
+    /// Adds Eversite contract ABI, it is tried before the bundled one.
+    pub fn with_onchain_site_abi_json(mut self, abi_json: &str) -> Result<Self> {
+        self.onchain_site_abis.insert(0, VersionedAbi::load(abi_json)?);
+        Ok(self)
+    }
 }
 
 #[async_trait]
 impl Resolver for EvernameResolver {
     async fn resolve(&self, domain: &str) -> Result<(ResolvedDomainData, AddressTag)> {
-        let resolved_address = self.address_contract(domain.to_string()).await?;
-        let records = self.get_records(&resolved_address).await?;
-        for tag in AddressTag::resolvable() {
+        let Some(cache) = &self.result_cache else {
+            return self.resolve_at_point(domain, Query::default(), &mut Trace::disabled()).await;
+        };
+        let key = normalize::cache_key(domain);
+        if let Some(result) = cache.get(&key) {
+            return Ok(result);
+        }
+        let (data, tag) = self.resolve_at_point(domain, Query::default(), &mut Trace::disabled()).await?;
+        if !tag.is_onchain() {
+            cache.insert(key, (data.clone(), tag));
+        }
+        Ok((data, tag))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn capabilities(&self) -> ResolverCapabilities {
+        ResolverCapabilities {
+            tlds: vec![self.tld.clone()],
+            record_kinds: self.resolvable_tags(),
+            ..ResolverCapabilities::default()
+        }
+    }
+
+    async fn resolve_all(&self, domain: &str) -> Result<Vec<ResolutionCandidate>> {
+        self.resolve_all_traced(domain, &Trace::disabled()).await
+    }
+}
+
+impl EvernameResolver {
+    /// Same as `resolve_all`, the account states cache is not written in dry runs.
+    pub(crate) async fn resolve_all_traced(&self, domain: &str, trace: &Trace) -> Result<Vec<ResolutionCandidate>> {
+        let query = Query { dry_run: trace.is_dry_run(), ..Query::default() };
+        let (_, records) = self.domain_records(domain, query).await?;
+        // records are decoded concurrently, candidates keep the tag priority order
+        let present: Vec<(AddressTag, Cell)> = self.resolvable_tags().into_iter()
+            .filter_map(|tag| records.get(&tag).map(|cell_value| (tag, cell_value.clone())))
+            .collect();
+        let decoded: Vec<_> = stream::iter(present)
+            .map(|(tag, cell_value)| async move { (tag, self.decode_record(&tag, &cell_value, query).await) })
+            .buffered(RESOLVE_ALL_CONCURRENCY)
+            .collect()
+            .await;
+        let mut candidates = Vec::new();
+        for (tag, result) in decoded {
+            match result {
+                Ok(data) => candidates.push(ResolutionCandidate {
+                    source: self.name().to_string(),
+                    data,
+                    tag,
+                }),
+                Err(e) => debug!("Skipping record {} of {}: {}", tag, domain, e),
+            }
+        }
+        Ok(candidates)
+    }
+
+    pub(crate) async fn resolve_traced(&self, domain: &str, trace: &mut Trace) -> Result<(ResolvedDomainData, AddressTag)> {
+        let state_refs = trace.collects_state_refs().then(StateRefs::default);
+        let query = Query { at: None, dry_run: trace.is_dry_run(), state_refs: state_refs.as_ref() };
+        let result = self.resolve_at_point(domain, query, trace).await;
+        if let Some(state_refs) = state_refs {
+            trace.extend_state_refs(state_refs.into_inner());
+        }
+        result
+    }
+
+    async fn resolve_at_point(&self, domain: &str, query: Query<'_>, trace: &mut Trace) -> Result<(ResolvedDomainData, AddressTag)> {
+        if self.state_cache.is_some() {
+            trace.push(|| "Evername: account states cache consulted".to_string());
+        }
+        let (resolved_address, records) = self.domain_records(domain, query).await?;
+        trace.push(|| format!("Evername: certificate contract {}", resolved_address));
+        trace.push(|| {
+            let present: Vec<String> = self.resolvable_tags().iter()
+                .filter(|tag| records.contains_key(tag))
+                .map(|tag| tag.to_string())
+                .collect();
+            format!("Evername: content records present: [{}]", present.join(", "))
+        });
+        if trace.collects_records() {
+            for tag in self.resolvable_tags() {
+                if let Some(cell_value) = records.get(&tag) {
+                    let raw_value = match tag {
+                        AddressTag::OnchainContract => address_cell_value(cell_value),
+                        _ => string_cell_value(cell_value),
+                    };
+                    trace.record(tag.to_string(), raw_value.unwrap_or_else(|e| format!("<undecodable: {}>", e)));
+                }
+            }
+        }
+        for tag in self.resolvable_tags() {
             debug!("Resolving address {} with tag {}", domain, tag);
             if let Some(cell_value) = records.get(&tag) {
-                let domain_data = match tag {
-                    AddressTag::Onchain => {
-                        let cell_value = string_cell_value(cell_value)?;
-                        ResolvedDomainData::OnchainData(cell_value)
-                    },
-                    AddressTag::OnchainContract => {
-                        let contract_address = address_cell_value(cell_value)?;
-                        debug!("Resolving onchain contract {}", contract_address);
-                        let (content, content_type) = self.load_content_from_contract(&contract_address).await?;
-                        ResolvedDomainData::OnchainContractData((content, content_type))
-                    },
-                    AddressTag::Ipfs => {
-                        let cell_value = string_cell_value(cell_value)?;
-                        let ipfs_url = make_ipfs_link(&cell_value);
-                        ResolvedDomainData::DomainString(ipfs_url)
-                    },
-                    _ => {
-                        let cell_value = string_cell_value(cell_value)?;
-                        ResolvedDomainData::DomainString(cell_value)
-                    },
-                };
+                trace.push(|| format!("Evername: tag {} chosen as the highest priority present record", tag));
+                if trace.collects_raw_records() {
+                    trace.raw_record(tag.tag().to_string(), base64(&serialize_toc(cell_value)?));
+                }
+                let domain_data = self.decode_record(&tag, cell_value, query).await?;
                 return Ok((domain_data, tag));
             }
         }
-        Err(anyhow!("No address for requested domain {}", domain))
+        let owner = match query.at {
+            None => self.domain_details_at(domain, query).await.ok().map(|details| details.owner),
+            Some(_) => None,
+        };
+        Err(ResolverError::NoContent { domain: domain.to_string(), owner }.into())
     }
-}
 
-impl EvernameResolver {
-    async fn get_contract_state(&self, address: &MsgAddressInt) -> Result<AccountStuff> {
-        let state = self.jrpc_transport.get_contract_state(address).await?;
-        match state {
-            RawContractState::NotExists { .. } => Err(anyhow!("No account state")),
-            RawContractState::Exists(contract) => Ok(contract.account)
+    /// Certificate address and content records of the domain, fails with `ResolverError::NotFound`
+    /// if the certificate is not deployed, i.e. the domain is not registered.
+    async fn domain_records(&self, domain: &str, query: Query<'_>) -> Result<(MsgAddressInt, HashMap<AddressTag, Cell>)> {
+        let certificate_address = self.address_contract(domain.to_string(), query).await?;
+        match self.get_records(&certificate_address, query).await {
+            Err(e) if matches!(e.downcast_ref::<ResolverError>(), Some(ResolverError::AccountNotFound { .. })) => {
+                Err(ResolverError::NotFound { domain: domain.to_string() }.into())
+            },
+            records => Ok((certificate_address, records?)),
         }
     }
 
-    async fn address_contract(&self, address_url: String) -> Result<MsgAddressInt> {
-        let function = self.root_contract.function("resolve")
-            .context("Failed to load 'resolve' function from contract DomainRoot")?;
-        let state = self.get_contract_state(&self.root_address).await?;
+    fn resolvable_tags(&self) -> Vec<AddressTag> {
+        AddressTag::resolvable()
+            .into_iter()
+            .filter(|tag| self.content_records || !matches!(tag, AddressTag::Onchain | AddressTag::OnchainContract))
+            .collect()
+    }
 
-        let clock = nekoton_utils::SimpleClock{};
-        let result = function.run_local(
-            &clock,
-            state,
-            &[
+    async fn decode_record(&self, tag: &AddressTag, cell_value: &Cell, query: Query<'_>) -> Result<ResolvedDomainData> {
+        let domain_data = match tag {
+            AddressTag::Onchain => {
+                let cell_value = string_cell_value(cell_value)?;
+                ResolvedDomainData::OnchainData(cell_value)
+            },
+            AddressTag::OnchainContract => {
+                let contract_address = address_cell_value(cell_value)?;
+                debug!("Resolving onchain contract {}", contract_address);
+                let (content, content_type) = self.load_content_from_contract(&contract_address, query).await?;
+                ResolvedDomainData::OnchainContractData((content, content_type))
+            },
+            AddressTag::Ipfs => {
+                let cell_value = string_cell_value(cell_value)?;
+                let ipfs_url = make_gateway_links(&self.ipfs_gateway, &cell_value);
+                ResolvedDomainData::DomainString(ipfs_url)
+            },
+            AddressTag::Redirect => {
+                let cell_value = string_cell_value(cell_value)?;
+                ResolvedDomainData::Redirect(Redirect::parse(&cell_value)?)
+            },
+            _ => {
+                let cell_value = string_cell_value(cell_value)?;
+                ResolvedDomainData::DomainString(StructuredTarget::expand(cell_value, tag))
+            },
+        };
+        Ok(domain_data)
+    }
+
+    async fn get_contract_state(&self, address: &MsgAddressInt, query: Query<'_>) -> Result<AccountStuff> {
+        let state = self.load_contract_state(address, query).await?;
+        if let Some(state_refs) = query.state_refs {
+            state_refs.record(address, &state)?;
+        }
+        Ok(state)
+    }
+
+    async fn load_contract_state(&self, address: &MsgAddressInt, query: Query<'_>) -> Result<AccountStuff> {
+        let Some(state_cache) = &self.state_cache else {
+            return self.fetch_contract_state(address).await;
+        };
+        let key = address.to_string();
+        let Some(cached) = state_cache.get(&key) else {
+            let account = self.fetch_contract_state(address).await?;
+            if !query.dry_run {
+                state_cache.insert(&key, account.clone());
+            }
+            return Ok(account);
+        };
+        if state_cache.is_fresh(&cached) {
+            return Ok(cached.account);
+        }
+        match self.poll_contract_state(address, cached.last_trans_lt()).await? {
+            PollContractState::Unchanged { .. } => {
+                debug!("Account {} is unchanged since lt {}", key, cached.last_trans_lt());
+                if !query.dry_run {
+                    state_cache.insert(&key, cached.account.clone());
+                }
+                Ok(cached.account)
+            },
+            PollContractState::Exists(contract) => {
+                if !query.dry_run {
+                    state_cache.insert(&key, contract.account.clone());
+                }
+                Ok(contract.account)
+            },
+            PollContractState::NotExists { .. } => {
+                if !query.dry_run {
+                    state_cache.invalidate(&key);
+                }
+                Err(ResolverError::AccountNotFound { address: key }.into())
+            },
+        }
+    }
+
+    async fn fetch_contract_state(&self, address: &MsgAddressInt) -> Result<AccountStuff> {
+        let deadline = self.deployment_wait.map(|window| tokio::time::Instant::now() + window);
+        let mut backoff = DEPLOYMENT_BACKOFF;
+        loop {
+            let state = {
+                let _permit = permit(&self.in_flight).await;
+                self.jrpc_transport.get_contract_state(address).await?
+            };
+            let now = tokio::time::Instant::now();
+            match (state, deadline) {
+                (RawContractState::Exists(contract), _) => return Ok(contract.account),
+                (RawContractState::NotExists { .. }, Some(deadline)) if now < deadline => {
+                    debug!("Account {} does not exist yet, retrying in {:?}", address, backoff);
+                    tokio::time::sleep(backoff.min(deadline - now)).await;
+                    backoff = (backoff * 2).min(MAX_DEPLOYMENT_BACKOFF);
+                },
+                (RawContractState::NotExists { .. }, _) => return Err(ResolverError::AccountNotFound { address: address.to_string() }.into()),
+            }
+        }
+    }
+
+    async fn address_contract(&self, address_url: String, query: Query<'_>) -> Result<MsgAddressInt> {
+        Self::validate_name(&address_url)?;
+        let domain_key = normalize::cache_key(&address_url);
+        let state = self.get_contract_state(&self.root_address, query).await?;
+        let tokens = self.run_getter(
+            &self.root_abis,
+            "DomainRoot",
+            "resolve",
+            &state,
+            GetterCall::new(&[
                 Token::new("answerId", TokenValue::Uint(Uint::new(0, 32))),
                 Token::new("path", TokenValue::String(address_url)),
-            ],
-            &[]
-        )?;
+            ]).returning("certificate").at(query.at),
+        ).await?;
 
-        let token = result.tokens.ok_or_else(|| anyhow!("empty output"))?
+        let token = tokens
             .into_iter()
             .find(|token| token.name == "certificate")
             .ok_or_else(|| anyhow!("no certificate value"))?;
 
-        match token.value {
-            TokenValue::Address(address) => address.to_msg_addr_int().context("missed certificate address"),
-            _ => Err(anyhow!("wrong certificate value")),
+        let certificate_address = match token.value {
+            TokenValue::Address(address) => address.to_msg_addr_int().ok_or_else(|| malformed("certificate address", "address is not internal"))?,
+            _ => return Err(malformed("certificate address", "resolve returned no address")),
+        };
+        if let (Some(state_cache), false) = (&self.state_cache, query.dry_run) {
+            state_cache.insert_certificate(&domain_key, &certificate_address.to_string());
         }
+        Ok(certificate_address)
     }
 
-    
-
-    async fn get_records(&self, address: &MsgAddressInt) -> Result<HashMap<AddressTag, Cell>> {
-        let function = self.domain_contract.function("getRecords")
-            .context("Failed to load 'getRecords' function from contract Domain")?;
+    /// Returns content records of the certificate by their tags.
+    async fn get_records(&self, address: &MsgAddressInt, query: Query<'_>) -> Result<HashMap<AddressTag, Cell>> {
+        let state = self.get_contract_state(address, query).await?;
+        check_state_at(&state, query.at)?;
+        let keys = AddressTag::all().filter_map(|tag| tag.record_key()).filter_map(|key| u32::try_from(key).ok());
+        let records = self.records_of_keys(&state, keys, query.at).await?;
+        Ok(records.into_iter()
+            .filter_map(|(key, cell)| AddressTag::from_record_key(key as u128).map(|tag| (tag, cell)))
+            .collect())
+    }
 
-        let state = self.get_contract_state(address).await?;
+    /// Returns all records of the certificate by their keys.
+    async fn get_raw_records(&self, address: &MsgAddressInt, query: Query<'_>) -> Result<BTreeMap<u32, Cell>> {
+        let state = self.get_contract_state(address, query).await?;
+        check_state_at(&state, query.at)?;
+        self.records_of_state(&state, query.at).await
+    }
 
-        let clock = nekoton_utils::SimpleClock{};
-        let result = function.run_local(
-            &clock,
+    /// Returns all records of the certificate state with `getRecords`. Records maps exceeding the TVM limits
+    /// or failing to decode are read from the `_records` field of the contract data instead.
+    async fn records_of_state(&self, state: &AccountStuff, at: Option<HistoricalPoint>) -> Result<BTreeMap<u32, Cell>> {
+        let tokens = self.run_getter(
+            &self.domain_abis,
+            "Domain",
+            "getRecords",
             state,
-            &[
+            GetterCall::new(&[
                 Token::new("answerId", TokenValue::Uint(Uint::new(0, 32)))
-            ],
-            &[]
-        )?;
+            ]).returning("records").at(at),
+        ).await;
+        let records = tokens.and_then(|tokens| {
+            let token = tokens
+                .into_iter()
+                .find(|token| token.name == "records")
+                .ok_or_else(|| malformed("records map", "getRecords returned no records"))?;
+            records_map(token.value)
+        });
+        match records {
+            Err(e) if is_size_or_decoding_failure(&e) => {
+                debug!("Records map is not returned by getRecords, reading contract data: {:#}", e);
+                records_map(storage_field(&self.domain_abis, state, "_records")?)
+            },
+            records => records,
+        }
+    }
+
+    /// Returns records of the given keys. Records maps that can not be read at once (see `records_of_state`)
+    /// are assembled from per-key `query` calls instead.
+    async fn records_of_keys(&self, state: &AccountStuff, keys: impl IntoIterator<Item = u32>, at: Option<HistoricalPoint>) -> Result<BTreeMap<u32, Cell>> {
+        let keys: Vec<u32> = keys.into_iter().collect();
+        match self.records_of_state(state, at).await {
+            Ok(records) => Ok(records.into_iter().filter(|(key, _)| keys.contains(key)).collect()),
+            Err(e) if is_size_or_decoding_failure(&e) => {
+                debug!("Records map is not read at once, querying {} keys one by one: {:#}", keys.len(), e);
+                let records: Vec<_> = stream::iter(keys)
+                    .map(|key| self.query_record(state, key, at))
+                    .buffer_unordered(RECORD_QUERY_CONCURRENCY)
+                    .try_collect()
+                    .await?;
+                Ok(records.into_iter().flatten().collect())
+            },
+            Err(e) => Err(e),
+        }
+    }
 
-        let token = result.tokens.ok_or_else(|| anyhow!("empty output"))?
+    /// Returns the record of the key with the `query` get-method, `None` if it is not set.
+    async fn query_record(&self, state: &AccountStuff, key: u32, at: Option<HistoricalPoint>) -> Result<Option<(u32, Cell)>> {
+        let tokens = self.run_getter(
+            &self.domain_abis,
+            "Domain",
+            "query",
+            state,
+            GetterCall::new(&[
+                Token::new("answerId", TokenValue::Uint(Uint::new(0, 32))),
+                Token::new("key", TokenValue::Uint(Uint::new(key as u128, 32))),
+            ]).returning("value").at(at),
+        ).await?;
+        let value = tokens
             .into_iter()
-            .find(|token| token.name == "records")
-            .ok_or_else(|| anyhow!("No value"))?;
-
-        match token.value {
-            TokenValue::Map(ParamType::Uint(32), ParamType::Cell, content) => {
-                let mut result = HashMap::new();
-                for (key, cell) in content {
-                    let key_token = TokenValue::from(key);
-                    match (key_token, cell) {
-                        (TokenValue::Uint(uint), TokenValue::Cell(cell)) => {
-                            let key_u32 = uint.number.to_u32().ok_or_else(|| anyhow!("could not convert map key to uint32: {}", uint.number))?;
-                            if let Ok(tag) = AddressTag::try_from(key_u32) {
-                                result.insert(tag, cell);
-                            }
-                        },
-                        _ => return Err(anyhow!("bad map value"))
-                    }
-                }
-                Ok(result)
+            .find(|token| token.name == "value")
+            .map(|token| token.value);
+        match value {
+            Some(TokenValue::Optional(_, None)) => Ok(None),
+            Some(TokenValue::Optional(_, Some(value))) => match *value {
+                TokenValue::Cell(cell) => Ok(Some((key, cell))),
+                _ => Err(malformed("record", format!("record {} is not a cell", key))),
             },
-            _ => Err(anyhow!("none value")),
+            _ => Err(malformed("record", "query returned no value")),
         }
     }
 
-    async fn load_content_from_contract(&self, address: &str) -> Result<(String, String)> {
-        let function = self.onchain_site_contract.function("getDetails")
-            .context("Failed to load 'getDetails' function from contract Eversite")?;
+    async fn load_content_from_contract(&self, address: &str, query: Query<'_>) -> Result<(String, String)> {
+        let (chunks, content_type, _) = self.load_content_chunks(address, query).await?;
+        let content = chunks.concat();
+        let content_type = content_type.unwrap_or_else(|| self.sniff_content_type(&content));
+        Ok((content, content_type))
+    }
 
+    async fn load_contract_content_range(&self, address: &str, range_header: Option<&str>) -> Result<ContentRange> {
+        let (chunks, content_type, last_paid) = self.load_content_chunks(address, Query::default()).await?;
+        let total_length: u64 = chunks.iter().map(|chunk| chunk.len() as u64).sum();
+        let range = match range_header {
+            Some(range_header) => parse_byte_range(range_header, total_length)?,
+            None => 0..total_length,
+        };
+        let content_type = content_type.unwrap_or_else(|| self.sniff_content_type(&chunks.concat()));
+        Ok(ContentRange {
+            content_type,
+            total_length,
+            data: slice_chunks(&chunks, &range),
+            range,
+            cache: self.cache_headers(&chunks, last_paid),
+        })
+    }
+
+    /// Caching metadata of the contract content: the hash of the content is the entity tag, the last storage
+    /// payment time of the contract is the last modification time, states are reused for the state cache freshness.
+    fn cache_headers(&self, chunks: &[String], last_paid: u32) -> CacheHeaders {
+        let mut hasher = Sha256::new();
+        for chunk in chunks {
+            hasher.update(chunk.as_bytes());
+        }
+        let cache_control = match &self.state_cache {
+            Some(state_cache) => format!("public, max-age={}", state_cache.fresh_for().as_secs()),
+            None => "no-cache".to_string(),
+        };
+        CacheHeaders {
+            cache_control: Some(cache_control),
+            etag: Some(format!("\"{}\"", hex::encode(hasher.finalize()))),
+            last_modified: (last_paid > 0).then_some(last_paid as u64),
+        }
+    }
+
+    fn sniff_content_type(&self, content: &str) -> String {
+        sniff_content_type(content)
+            .map(|content_type| content_type.to_string())
+            .unwrap_or_else(|| self.default_content_type.clone())
+    }
+
+    /// Loads content chunks of Eversite contract in order, together with the content type if the contract has it
+    /// and the last storage payment time of the contract.
+    async fn load_content_chunks(&self, address: &str, query: Query<'_>) -> Result<(Vec<String>, Option<String>, u32)> {
         let msg_address = MsgAddressInt::from_str(address)?;
-        let state = self.get_contract_state(&msg_address).await?;
+        let state = self.get_contract_state(&msg_address, query).await?;
+        check_state_at(&state, query.at)?;
+        let details = self.run_getter(&self.onchain_site_abis, "Eversite", "getDetails", &state, GetterCall::new(&[]).returning("content").at(query.at)).await
+            .and_then(|tokens| {
+                let content = tokens
+                    .iter()
+                    .find(|token| token.name == "content")
+                    .map(|token| &token.value)
+                    .ok_or_else(|| malformed("onchain content", "getDetails returned no content"))?;
+                let content_type = tokens
+                    .iter()
+                    .find(|token| token.name == "contentType")
+                    .map(|token| &token.value)
+                    .and_then(content_type_value);
+                Ok((content_chunks(content)?, content_type))
+            });
+        let (chunks, content_type) = match details {
+            // content maps exceeding the TVM limits are read from the contract data
+            Err(e) if is_size_or_decoding_failure(&e) => {
+                debug!("Content of {} is not returned by getDetails, reading contract data: {:#}", address, e);
+                let content = storage_field(&self.onchain_site_abis, &state, "content")?;
+                let content_type = storage_field(&self.onchain_site_abis, &state, "_contentType").ok()
+                    .and_then(|value| content_type_value(&value));
+                (content_chunks(&content)?, content_type)
+            },
+            details => details?,
+        };
+        Ok((chunks, content_type, state.storage_stat.last_paid()))
+    }
 
-        let clock = nekoton_utils::SimpleClock{};
-        let result = function.run_local(
-            &clock,
-            state,
-            &[],
-            &[]
-        )?;
-        let tokens = result.tokens.ok_or_else(|| anyhow!("empty output"))?;
-        let content = tokens
-            .iter()
-            .find(|token| token.name == "content")
-            .map(|token| &token.value)
-            .ok_or_else(|| anyhow!("No content"))?;
-        let default_content_type = "text/html; charset=utf-8".to_string();
-        let content_type = tokens
-            .iter()
-            .find(|token| token.name == "contentType")
-            .map(|token| &token.value)
-            .and_then(|value| match value {
-                TokenValue::String(ct) => Some(ct),
-                _ => None
-            })
-            .unwrap_or(&default_content_type);
-
-        match content {
-            TokenValue::Map(ParamType::Uint(8), ParamType::Cell, content) => {
-                let mut result = String::new();
-                for (_, cell) in content {
-                    match cell {
-                        TokenValue::Cell(cell) => {
-                            let partial_params = &[Param::new("value0", ParamType::String)];
-                            let data = SliceData::load_cell_ref(cell)?;
-                            match unpack_from_cell(
-                                partial_params, data, false, contract::ABI_VERSION_2_0
-                            )?.get(0).context("malformed cell data")? {
-                                Token { name: _, value: TokenValue::String(s) } => {
-                                    result.push_str(s);
-                                },
-                                _ => return Err(anyhow!("malformed cell data"))
-                            }
+    /// Runs get-method trying ABIs from the newest to the oldest one.
+    /// Fails with `ResolverError::AbiMismatch` if none of them matches the on-chain contract.
+    ///
+    /// TVM runs on the blocking thread pool bounded by the get-method budget, failing with
+    /// `ResolverError::GetMethodTimeout` when it is exceeded. A running TVM can not be interrupted,
+    /// so ABIs left to try are skipped once the budget is exceeded or the resolution is dropped,
+    /// and the execution keeps its get-method slot until it returns.
+    async fn run_getter(
+        &self,
+        abis: &[VersionedAbi],
+        contract_name: &str,
+        function_name: &str,
+        state: &AccountStuff,
+        call: GetterCall<'_>,
+    ) -> Result<Vec<Token>> {
+        let clock: Arc<dyn Clock> = match call.at {
+            Some(HistoricalPoint::Timestamp(timestamp)) => Arc::new(ConstClock::from_secs(timestamp)),
+            _ => self.clock.clone(),
+        };
+        let functions: Vec<_> = abis.iter()
+            .map(|abi| (abi.version.clone(), abi.contract.function(function_name).cloned().map_err(|e| e.to_string())))
+            .collect();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let _cancel_on_drop = CancelOnDrop(cancelled.clone());
+        let slots = self.get_method_slots.clone();
+        let (contract, function) = (contract_name.to_string(), function_name.to_string());
+        let (state, inputs, output) = (state.clone(), call.inputs.to_vec(), call.output.map(str::to_string));
+        let task = async move {
+            let slot = slots.acquire_owned().await?;
+            let (contract_name, function_name) = (contract, function);
+            let execution = tokio::task::spawn_blocking(move || {
+                let _slot = slot;
+                let mut errors = Vec::new();
+                let mut limit_exit_code = None;
+                for (version, function) in functions {
+                    if cancelled.load(Ordering::Relaxed) {
+                        errors.push(format!("ABI {}: cancelled", version));
+                        break;
+                    }
+                    let function = match function {
+                        Ok(function) => function,
+                        Err(e) => {
+                            errors.push(format!("ABI {}: {}", version, e));
+                            continue;
+                        }
+                    };
+                    // TVM and ABI decoding of hostile contracts must not take the resolver down
+                    let result = std::panic::catch_unwind(AssertUnwindSafe(|| function.run_local(clock.as_ref(), state.clone(), &inputs, &[])));
+                    let result = match result {
+                        Ok(result) => result,
+                        Err(panic) => {
+                            errors.push(format!("ABI {}: panicked: {}", version, panic_message(panic.as_ref())));
+                            continue;
+                        }
+                    };
+                    match result {
+                        Ok(result) => match result.tokens {
+                            Some(tokens) if output.as_ref().is_none_or(|output| tokens.iter().any(|token| &token.name == output)) => {
+                                debug!("{}.{} matched ABI {}", contract_name, function_name, version);
+                                return Ok(tokens);
+                            },
+                            _ => {
+                                if TVM_LIMIT_EXIT_CODES.contains(&result.result_code) {
+                                    limit_exit_code = Some(result.result_code);
+                                }
+                                errors.push(format!("ABI {}: unexpected output, exit code {}", version, result.result_code))
+                            },
                         },
-                        _ => return Err(anyhow!("bad cell value in map"))
+                        Err(e) => errors.push(format!("ABI {}: {}", version, e)),
                     }
                 }
-                Ok((result, content_type.to_string()))
+                Err((errors.join("; "), limit_exit_code))
+            });
+            anyhow::Ok(execution.await?)
+        };
+        match tokio::time::timeout(self.get_method_budget, task).await {
+            Ok(Ok(Ok(tokens))) => Ok(tokens),
+            Ok(Ok(Err((reason, Some(exit_code))))) => {
+                debug!("{}.{} exceeded the TVM limits: {}", contract_name, function_name, reason);
+                Err(ResolverError::GetMethodLimitExceeded {
+                    contract: contract_name.to_string(),
+                    function: function_name.to_string(),
+                    exit_code,
+                }.into())
             },
-            _ => Err(anyhow!("wrong getDetails value")),
+            Ok(Ok(Err((reason, None)))) => Err(ResolverError::AbiMismatch {
+                contract: contract_name.to_string(),
+                function: function_name.to_string(),
+                reason,
+            }.into()),
+            Ok(Err(e)) => Err(e.context(format!("{}.{} execution failed", contract_name, function_name))),
+            Err(_) => Err(ResolverError::GetMethodTimeout {
+                contract: contract_name.to_string(),
+                function: function_name.to_string(),
+                budget: self.get_method_budget,
+            }.into()),
         }
     }
 }
 
+/// Sets the flag when dropped, cancelling the get-method execution that is no longer awaited.
+struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Checks that the account has not changed after the requested point in time.
+fn check_state_at(state: &AccountStuff, at: Option<HistoricalPoint>) -> Result<()> {
+    let changed_after = match at {
+        None => false,
+        Some(HistoricalPoint::Lt(lt)) => state.storage.last_trans_lt > lt,
+        // storage fees are paid on every transaction, so last_paid is the time of the last one
+        Some(HistoricalPoint::Timestamp(timestamp)) => state.storage_stat.last_paid as u64 > timestamp,
+    };
+    if changed_after {
+        return Err(ResolverError::HistoricalStateUnavailable {
+            address: state.addr.to_string(),
+            last_trans_lt: state.storage.last_trans_lt,
+        }.into());
+    }
+    Ok(())
+}
+
+/// File extension of the onchain content type.
+fn content_extension(content_type: &str) -> &'static str {
+    let mime = content_type.split(';').next().unwrap_or_default().trim().to_lowercase();
+    match mime.as_str() {
+        "text/html" => "html",
+        "image/svg+xml" => "svg",
+        "application/json" => "json",
+        "text/css" => "css",
+        "text/plain" => "txt",
+        _ => "bin",
+    }
+}
+
 fn string_cell_value(cell: &Cell) -> Result<String> {
-    let partial_params = &[Param::new("value", ParamType::String)];
-    let data = SliceData::load_cell_ref(cell)?;
-    let tokens = unpack_from_cell(partial_params, data, false, contract::ABI_VERSION_2_0)?;
-    let first_token = tokens.get(0).context("malformed cell data")?;
-    match first_token {
-        Token { name: _, value: TokenValue::String(s) } => Ok(s.clone()),
-        _ => Err(anyhow!("malformed cell data"))
+    match cell_value(cell, ParamType::String)? {
+        TokenValue::String(s) => Ok(s),
+        _ => Err(malformed("record cell", "value is not a string")),
     }
 }
 
 fn address_cell_value(cell: &Cell) -> Result<String> {
-    let partial_params = &[Param::new("value", ParamType::Address)];
-    let data = SliceData::load_cell_ref(cell)?;
-    let tokens = unpack_from_cell(partial_params, data, false, contract::ABI_VERSION_2_0)?;
-    let first_token = tokens.get(0).context("malformed cell data")?;
-    match first_token {
-        Token { name: _, value: TokenValue::Address(address) } => Ok(address.to_string()),
-        _ => Err(anyhow!("malformed cell data"))
+    match cell_value(cell, ParamType::Address)? {
+        TokenValue::Address(address) => Ok(address.to_string()),
+        _ => Err(malformed("record cell", "value is not an address")),
+    }
+}
+
+fn cell_value(cell: &Cell, param_type: ParamType) -> Result<TokenValue> {
+    let partial_params = &[Param::new("value", param_type)];
+    let data = SliceData::load_cell_ref(cell).map_err(|e| malformed("record cell", e))?;
+    let tokens = unpack_from_cell(partial_params, data, false, contract::ABI_VERSION_2_0)
+        .map_err(|e| malformed("record cell", e))?;
+    tokens.into_iter()
+        .next()
+        .map(|token| token.value)
+        .ok_or_else(|| malformed("record cell", "no value"))
+}
+
+/// Typed error of undecodable on-chain data.
+fn malformed(what: &str, reason: impl std::fmt::Display) -> anyhow::Error {
+    ResolverError::MalformedData {
+        what: what.to_string(),
+        reason: reason.to_string(),
+    }.into()
+}
+
+/// Records of the certificate from the `records` map of `getRecords` or the contract data.
+fn records_map(value: TokenValue) -> Result<BTreeMap<u32, Cell>> {
+    match value {
+        TokenValue::Map(ParamType::Uint(32), ParamType::Cell, content) => {
+            let mut result = BTreeMap::new();
+            for (key, cell) in content {
+                let key_token = TokenValue::from(key);
+                match (key_token, cell) {
+                    (TokenValue::Uint(uint), TokenValue::Cell(cell)) => {
+                        let key_u32 = uint.number.to_u32().ok_or_else(|| malformed("records map", format!("key {} is not uint32", uint.number)))?;
+                        result.insert(key_u32, cell);
+                    },
+                    _ => return Err(malformed("records map", "value is not a cell"))
+                }
+            }
+            Ok(result)
+        },
+        _ => Err(malformed("records map", "getRecords returned no map")),
+    }
+}
+
+/// Failures of reading a whole map with a get-method that reading the contract data or per-key calls may avoid:
+/// the get-method exceeds the TVM limits or its output does not decode.
+fn is_size_or_decoding_failure(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<ResolverError>(),
+        Some(ResolverError::GetMethodLimitExceeded { .. } | ResolverError::MalformedData { .. })
+    )
+}
+
+/// Decodes the field of the contract data, trying ABIs from the newest to the oldest one.
+fn storage_field(abis: &[VersionedAbi], state: &AccountStuff, name: &str) -> Result<TokenValue> {
+    let AccountState::AccountActive { state_init } = &state.storage.state else {
+        return Err(malformed("contract data", "account is not active"));
+    };
+    let data = state_init.data.as_ref().ok_or_else(|| malformed("contract data", "account has no data"))?;
+    let mut errors = Vec::new();
+    for abi in abis {
+        // data of hostile contracts must not take the resolver down
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            let cursor = SliceData::load_cell_ref(data)?;
+            unpack_from_cell(abi.contract.fields(), cursor, true, *abi.contract.version())
+        }));
+        match result {
+            Ok(Ok(tokens)) => match tokens.into_iter().find(|token| token.name == name) {
+                Some(token) => return Ok(token.value),
+                None => errors.push(format!("ABI {}: no field {}", abi.version, name)),
+            },
+            Ok(Err(e)) => errors.push(format!("ABI {}: {}", abi.version, e)),
+            Err(panic) => errors.push(format!("ABI {}: panicked: {}", abi.version, panic_message(panic.as_ref()))),
+        }
+    }
+    Err(malformed("contract data", errors.join("; ")))
+}
+
+/// Content type of Eversite, `None` if it is not set.
+fn content_type_value(value: &TokenValue) -> Option<String> {
+    match value {
+        TokenValue::String(content_type) if !content_type.trim().is_empty() => Some(content_type.clone()),
+        _ => None,
+    }
+}
+
+/// Content chunks of Eversite from the `content` map of `getDetails` or the contract data, in order.
+fn content_chunks(value: &TokenValue) -> Result<Vec<String>> {
+    match value {
+        TokenValue::Map(ParamType::Uint(8), ParamType::Cell, content) => {
+            let mut chunks = Vec::with_capacity(content.len());
+            for cell in content.values() {
+                match cell {
+                    TokenValue::Cell(cell) => chunks.push(string_cell_value(cell)?),
+                    _ => return Err(malformed("onchain content", "chunk is not a cell"))
+                }
+            }
+            Ok(chunks)
+        },
+        _ => Err(malformed("onchain content", "getDetails returned no content map")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit_exceeded() -> anyhow::Error {
+        ResolverError::GetMethodLimitExceeded {
+            contract: "Domain".to_string(),
+            function: "getRecords".to_string(),
+            exit_code: 13,
+        }.into()
+    }
+
+    #[test]
+    fn falls_back_only_on_size_or_decoding_failures() {
+        assert!(is_size_or_decoding_failure(&limit_exceeded()));
+        assert!(is_size_or_decoding_failure(&malformed("records map", "value is not a cell")));
+        assert!(is_size_or_decoding_failure(&limit_exceeded().context("Domain.getRecords")));
+        let timeout = ResolverError::GetMethodTimeout {
+            contract: "Domain".to_string(),
+            function: "getRecords".to_string(),
+            budget: DEFAULT_GET_METHOD_BUDGET,
+        };
+        assert!(!is_size_or_decoding_failure(&timeout.into()));
+        let mismatch = ResolverError::AbiMismatch {
+            contract: "Domain".to_string(),
+            function: "getRecords".to_string(),
+            reason: "ABI 2.2: unexpected output, exit code 60".to_string(),
+        };
+        assert!(!is_size_or_decoding_failure(&mismatch.into()));
+        assert!(!is_size_or_decoding_failure(&anyhow!("connection refused")));
+    }
+
+    #[test]
+    fn undecodable_maps_fall_back() {
+        let records = records_map(TokenValue::Bool(true)).unwrap_err();
+        assert!(is_size_or_decoding_failure(&records));
+        let content = content_chunks(&TokenValue::String("<html>".to_string())).unwrap_err();
+        assert!(is_size_or_decoding_failure(&content));
+    }
+
+    #[test]
+    fn contract_data_of_inactive_accounts_is_malformed() {
+        let error = storage_field(&[], &AccountStuff::default(), "_records").unwrap_err();
+        assert!(is_size_or_decoding_failure(&error));
+    }
+
+    #[test]
+    fn content_types() {
+        assert_eq!(content_type_value(&TokenValue::String("text/css".to_string())), Some("text/css".to_string()));
+        assert_eq!(content_type_value(&TokenValue::String(" ".to_string())), None);
+        assert_eq!(content_type_value(&TokenValue::Bool(true)), None);
+    }
+
+    #[test]
+    fn name_validation() {
+        let label = "a".repeat(MAX_EVERNAME_LABEL_LENGTH);
+        let long_name = format!("{}.{}.{}.{}", label, label, label, "a".repeat(61));
+        assert_eq!(long_name.len(), MAX_EVERNAME_LENGTH);
+        let valid = ["foo.ever", "foo.ever.", "a-b.ever", "xn--80ak6aa92e.ever", "Foo.EVER", "пример.ever", "0.ever"];
+        for name in valid.iter().copied().chain([label.as_str(), long_name.as_str()]) {
+            assert!(EvernameResolver::validate_name(name).is_ok(), "{}", name);
+        }
+        let too_long_label = format!("{}a.ever", label);
+        let too_long_name = format!("a{}", long_name);
+        let invalid = ["", ".", "foo..ever", ".foo.ever", "-foo.ever", "foo-.ever", "ab--c.ever", "foo_bar.ever", "foo bar.ever"];
+        for name in invalid.iter().copied().chain([too_long_label.as_str(), too_long_name.as_str()]) {
+            let error = EvernameResolver::validate_name(name).unwrap_err();
+            assert!(matches!(error.downcast_ref(), Some(ResolverError::InvalidName { .. })), "{}: {}", name, error);
+        }
     }
 }
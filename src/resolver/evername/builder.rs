@@ -0,0 +1,312 @@
+use std::sync::Arc;
+use std::time::Duration;
+use anyhow::Result;
+use nekoton_utils::Clock;
+use url::Url;
+use crate::models::{ConnectionPool, EndpointSelection, Timeouts};
+use crate::resolver::endpoints::validate_endpoints;
+use crate::resolver::limit::InFlightLimit;
+use crate::resolver::evername::{EvernameResolver, EVERSCALE_RPC_ENDPOINT};
+
+/// Builder of a standalone `EvernameResolver`, also used by `DomainResolverBuilder`.
+///
+/// # Example
+/// ```
+/// # fn example() -> anyhow::Result<()> {
+/// use web3_resolver::{EvernameResolver, Url};
+///
+/// let evername_resolver = EvernameResolver::builder()
+///     .with_everscale_endpoints(vec![Url::parse("https://jrpc.everwallet.net/rpc")?])
+///     .cache_ttl_seconds(60)
+///     .with_ipfs_gateway("https://{cid}.ipfs.dweb.link/")
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct EvernameResolverBuilder {
+    endpoints: Vec<(Url, u32)>,
+    state_cache_seconds: Option<(u64, u64)>,
+    clock: Option<Arc<dyn Clock>>,
+    get_method_budget: Option<Duration>,
+    max_concurrent_get_methods: Option<usize>,
+    deployment_wait: Option<Duration>,
+    root_abi_json: Option<String>,
+    domain_abi_json: Option<String>,
+    onchain_site_abi_json: Option<String>,
+    default_content_type: Option<String>,
+    ipfs_gateway: Option<String>,
+    content_records: bool,
+    cache_ttl_seconds: Option<u64>,
+    timeouts: Timeouts,
+    connection_pool: ConnectionPool,
+    selection: EndpointSelection,
+    in_flight: Option<InFlightLimit>,
+    search_indexer: Option<Url>,
+    root_address: Option<String>,
+    tld: Option<String>,
+}
+
+impl Default for EvernameResolverBuilder {
+    fn default() -> Self {
+        Self {
+            endpoints: vec![(Url::parse(EVERSCALE_RPC_ENDPOINT).expect("Default endpoint is a valid URL"), 1)],
+            state_cache_seconds: None,
+            clock: None,
+            get_method_budget: None,
+            max_concurrent_get_methods: None,
+            deployment_wait: None,
+            root_abi_json: None,
+            domain_abi_json: None,
+            onchain_site_abi_json: None,
+            default_content_type: None,
+            ipfs_gateway: None,
+            content_records: true,
+            cache_ttl_seconds: None,
+            timeouts: Timeouts::default(),
+            connection_pool: ConnectionPool::default(),
+            selection: EndpointSelection::default(),
+            in_flight: None,
+            search_indexer: None,
+            root_address: None,
+            tld: None,
+        }
+    }
+}
+
+impl EvernameResolverBuilder {
+    /// Sets Everscale JRPC endpoints, requests are spread over them evenly
+    /// and retried on the other endpoints on failures.
+    pub fn with_everscale_endpoints(self, endpoints: Vec<Url>) -> Self {
+        self.with_weighted_everscale_endpoints(endpoints.into_iter().map(|url| (url, 1)).collect())
+    }
+
+    /// Sets Everscale JRPC endpoints with weights, each endpoint gets a share of requests
+    /// proportional to its weight. Endpoints are validated on build.
+    pub fn with_weighted_everscale_endpoints(self, endpoints: Vec<(Url, u32)>) -> Self {
+        Self {
+            endpoints,
+            ..self
+        }
+    }
+
+    /// Enables Everscale account states caching: states are used as is for `fresh_seconds`,
+    /// then revalidated by the latest account transaction, and dropped after `max_age_seconds`.
+    pub fn cache_account_states(self, fresh_seconds: u64, max_age_seconds: u64) -> Self {
+        Self {
+            state_cache_seconds: Some((fresh_seconds, max_age_seconds)),
+            ..self
+        }
+    }
+
+    /// Enables caching of `resolve` results for the given time-to-live. Disabled by default.
+    pub fn cache_ttl_seconds(self, ttl: u64) -> Self {
+        Self {
+            cache_ttl_seconds: Some(ttl),
+            ..self
+        }
+    }
+
+    /// Sets the clock used for Everscale get-methods execution.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            clock: Some(clock),
+            ..self
+        }
+    }
+
+    /// Sets the wall-time budget of a get-method execution, see `EvernameResolver::with_get_method_budget`.
+    pub fn with_get_method_budget(self, budget: Duration) -> Self {
+        Self {
+            get_method_budget: Some(budget),
+            ..self
+        }
+    }
+
+    /// Sets the number of get-methods executed at once, see `EvernameResolver::with_max_concurrent_get_methods`.
+    pub fn with_max_concurrent_get_methods(self, max_concurrent_get_methods: usize) -> Self {
+        Self {
+            max_concurrent_get_methods: Some(max_concurrent_get_methods),
+            ..self
+        }
+    }
+
+    /// Retries lookups of accounts not deployed yet for up to `window`, see `EvernameResolver::with_deployment_wait`.
+    pub fn with_deployment_wait(self, window: Duration) -> Self {
+        Self {
+            deployment_wait: Some(window),
+            ..self
+        }
+    }
+
+    /// Sets DomainRoot contract ABI to be tried before the bundled one.
+    pub fn with_root_abi_json(self, abi_json: &str) -> Self {
+        Self {
+            root_abi_json: Some(abi_json.to_string()),
+            ..self
+        }
+    }
+
+    /// Sets Domain contract ABI to be tried before the bundled one.
+    pub fn with_domain_abi_json(self, abi_json: &str) -> Self {
+        Self {
+            domain_abi_json: Some(abi_json.to_string()),
+            ..self
+        }
+    }
+
+    /// Sets Eversite contract ABI to be tried before the bundled one.
+    pub fn with_onchain_site_abi_json(self, abi_json: &str) -> Self {
+        Self {
+            onchain_site_abi_json: Some(abi_json.to_string()),
+            ..self
+        }
+    }
+
+    /// Sets content type of onchain contract content used when the Eversite contract omits it
+    /// and the content is not recognized as HTML, SVG, JSON or CSS.
+    pub fn with_default_content_type(self, content_type: &str) -> Self {
+        Self {
+            default_content_type: Some(content_type.to_string()),
+            ..self
+        }
+    }
+
+    /// Sets IPFS gateway for IPFS records, see `EvernameResolver::with_ipfs_gateway`.
+    pub fn with_ipfs_gateway(self, gateway: &str) -> Self {
+        Self {
+            ipfs_gateway: Some(gateway.to_string()),
+            ..self
+        }
+    }
+
+    /// Enables or disables resolving of onchain content records, see `EvernameResolver::with_content_records`.
+    pub fn content_records(self, content_records: bool) -> Self {
+        Self {
+            content_records,
+            ..self
+        }
+    }
+
+    /// Sets connect, read and total timeouts of JRPC requests. Unbounded by default.
+    pub fn with_timeouts(self, timeouts: Timeouts) -> Self {
+        Self {
+            timeouts,
+            ..self
+        }
+    }
+
+    /// Sets keep-alive and idle connection settings of the JRPC client, see `ConnectionPool`.
+    pub fn with_connection_pool(self, connection_pool: ConnectionPool) -> Self {
+        Self {
+            connection_pool,
+            ..self
+        }
+    }
+
+    /// Sets how requests are spread over the endpoints, weighted round-robin by default.
+    pub fn endpoint_selection(self, selection: EndpointSelection) -> Self {
+        Self {
+            selection,
+            ..self
+        }
+    }
+
+    /// Bounds in-flight JRPC requests by the limit, which may be shared with other resolvers.
+    pub fn with_in_flight_limit(self, in_flight: InFlightLimit) -> Self {
+        Self {
+            in_flight: Some(in_flight),
+            ..self
+        }
+    }
+
+    /// Sets indexer of the Evername collection used by `search`, see `EvernameResolver::with_search_indexer`.
+    pub fn with_search_indexer(self, indexer: Url) -> Self {
+        Self {
+            search_indexer: Some(indexer),
+            ..self
+        }
+    }
+
+    /// Sets DomainRoot contract address of the deployment, see `EvernameResolver::with_root_address`.
+    pub fn with_root_address(self, root_address: &str) -> Self {
+        Self {
+            root_address: Some(root_address.to_string()),
+            ..self
+        }
+    }
+
+    /// Sets TLD of the deployment, `.ever` by default.
+    pub fn with_tld(self, tld: &str) -> Self {
+        Self {
+            tld: Some(tld.to_string()),
+            ..self
+        }
+    }
+
+    /// Configured endpoints with their weights.
+    pub(crate) fn endpoints(&self) -> &[(Url, u32)] {
+        &self.endpoints
+    }
+
+    /// Checks configured endpoints without creating the resolver.
+    pub(crate) fn validate(&self) -> Result<()> {
+        validate_endpoints(&self.endpoints)
+    }
+
+    pub fn build(&self) -> Result<EvernameResolver> {
+        let mut evername_resolver = EvernameResolver::from_endpoints_with_connection_pool(self.endpoints.clone(), &self.timeouts, &self.connection_pool, self.selection)?;
+        if let Some((fresh_seconds, max_age_seconds)) = self.state_cache_seconds {
+            evername_resolver = evername_resolver.with_state_cache(
+                Duration::from_secs(fresh_seconds),
+                Duration::from_secs(max_age_seconds),
+            );
+        }
+        if let Some(clock) = &self.clock {
+            evername_resolver = evername_resolver.with_clock(clock.clone());
+        }
+        if let Some(budget) = self.get_method_budget {
+            evername_resolver = evername_resolver.with_get_method_budget(budget);
+        }
+        if let Some(max_concurrent_get_methods) = self.max_concurrent_get_methods {
+            evername_resolver = evername_resolver.with_max_concurrent_get_methods(max_concurrent_get_methods);
+        }
+        if let Some(window) = self.deployment_wait {
+            evername_resolver = evername_resolver.with_deployment_wait(window);
+        }
+        if let Some(root_address) = &self.root_address {
+            evername_resolver = evername_resolver.with_root_address(root_address)?;
+        }
+        if let Some(tld) = &self.tld {
+            evername_resolver = evername_resolver.with_tld(tld);
+        }
+        if let Some(abi_json) = &self.root_abi_json {
+            evername_resolver = evername_resolver.with_root_abi_json(abi_json)?;
+        }
+        if let Some(abi_json) = &self.domain_abi_json {
+            evername_resolver = evername_resolver.with_domain_abi_json(abi_json)?;
+        }
+        if let Some(abi_json) = &self.onchain_site_abi_json {
+            evername_resolver = evername_resolver.with_onchain_site_abi_json(abi_json)?;
+        }
+        if let Some(content_type) = &self.default_content_type {
+            evername_resolver = evername_resolver.with_default_content_type(content_type);
+        }
+        if let Some(gateway) = &self.ipfs_gateway {
+            evername_resolver = evername_resolver.with_ipfs_gateway(gateway);
+        }
+        if !self.content_records {
+            evername_resolver = evername_resolver.with_content_records(false);
+        }
+        if let Some(in_flight) = &self.in_flight {
+            evername_resolver = evername_resolver.with_in_flight_limit(in_flight.clone());
+        }
+        if let Some(indexer) = &self.search_indexer {
+            evername_resolver = evername_resolver.with_search_indexer(indexer.clone());
+        }
+        if let Some(ttl) = self.cache_ttl_seconds.filter(|ttl| *ttl > 0) {
+            evername_resolver = evername_resolver.with_result_cache(Duration::from_secs(ttl));
+        }
+        Ok(evername_resolver)
+    }
+}
@@ -0,0 +1,41 @@
+//! Entry points of the `fuzz/` targets: on-chain cell decoders fed with arbitrary BOCs.
+//! Not a stable API.
+
+use std::collections::BTreeMap;
+use anyhow::{anyhow, Result};
+use ton_abi::{contract, Param, ParamType};
+use nekoton_abi::unpack_from_cell;
+use ton_types::{deserialize_tree_of_cells, Cell, SliceData};
+
+fn root_cell(boc: &[u8]) -> Result<Cell> {
+    deserialize_tree_of_cells(&mut &boc[..])
+}
+
+/// Decodes the BOC as a string record value.
+pub fn string_cell_value(boc: &[u8]) -> Result<String> {
+    super::string_cell_value(&root_cell(boc)?)
+}
+
+/// Decodes the BOC as an address record value.
+pub fn address_cell_value(boc: &[u8]) -> Result<String> {
+    super::address_cell_value(&root_cell(boc)?)
+}
+
+/// Decodes the BOC as the `records` map of a certificate and every record value as a string.
+pub fn records_map(boc: &[u8]) -> Result<BTreeMap<u32, Result<String>>> {
+    let params = &[Param::new("records", ParamType::Map(Box::new(ParamType::Uint(32)), Box::new(ParamType::Cell)))];
+    let tokens = unpack_from_cell(params, SliceData::load_cell(root_cell(boc)?)?, true, contract::ABI_VERSION_2_0)?;
+    let records = tokens.into_iter().next().ok_or_else(|| anyhow!("No records"))?;
+    let records = super::records_map(records.value)?;
+    Ok(records.into_iter()
+        .map(|(key, cell)| (key, super::string_cell_value(&cell)))
+        .collect())
+}
+
+/// Decodes the BOC as the `content` map of an Eversite contract.
+pub fn onchain_content(boc: &[u8]) -> Result<String> {
+    let params = &[Param::new("content", ParamType::Map(Box::new(ParamType::Uint(8)), Box::new(ParamType::Cell)))];
+    let tokens = unpack_from_cell(params, SliceData::load_cell(root_cell(boc)?)?, true, contract::ABI_VERSION_2_0)?;
+    let content = tokens.first().map(|token| &token.value).ok_or_else(|| anyhow!("No content"))?;
+    Ok(super::content_chunks(content)?.concat())
+}
@@ -0,0 +1,123 @@
+use anyhow::{anyhow, Result};
+use nekoton::transport::Transport;
+use nekoton::transport::models::RawContractState;
+use nekoton_abi::num_traits::ToPrimitive;
+use ton_abi::{Token, TokenValue, Uint};
+use ton_types::serialize_toc;
+use crate::models::{RegistrationQuote, RegistrationRequest};
+use crate::resolver::limit::permit;
+use crate::resolver::normalize;
+use crate::resolver::evername::{malformed, EvernameResolver, GetterCall, Query};
+
+/// Registration duration of one year in seconds.
+pub const YEAR_SECONDS: u32 = 365 * 24 * 60 * 60;
+
+impl EvernameResolver {
+    /// Checks whether the domain can be registered and returns its price and the amount
+    /// registering it for `duration` seconds, for "claim this name" flows.
+    pub async fn registration_quote(&self, domain: &str, duration: u32) -> Result<RegistrationQuote> {
+        let (domain, name) = registration_name(domain, &self.tld)?;
+        let available = !self.is_registered(&domain).await?;
+        let tokens = self.run_root_getter("expectedPrice", &[Token::new("name", TokenValue::String(name.clone()))], "price").await?;
+        let price = uint_output(&tokens, "price")?;
+        let needs_zero_auction = tokens.iter()
+            .find(|token| token.name == "needZeroAuction")
+            .is_some_and(|token| token.value == TokenValue::Bool(true));
+        let amount = self.registration_amount(&name, duration).await?;
+        Ok(RegistrationQuote {
+            domain,
+            available,
+            price,
+            needs_zero_auction,
+            duration,
+            amount,
+        })
+    }
+
+    /// Builds the registration of an available domain for `duration` seconds: the payload and amount
+    /// of the WEVER transfer to the DomainRoot, to be signed and sent by the buyer wallet.
+    pub async fn registration_request(&self, domain: &str, duration: u32) -> Result<RegistrationRequest> {
+        let (domain, name) = registration_name(domain, &self.tld)?;
+        if self.is_registered(&domain).await? {
+            return Err(anyhow!("Domain {} is already registered", domain));
+        }
+        let amount = self.registration_amount(&name, duration).await?;
+        let tokens = self.run_root_getter("buildRegisterPayload", &[Token::new("name", TokenValue::String(name))], "payload").await?;
+        let payload = match tokens.into_iter().find(|token| token.name == "payload").map(|token| token.value) {
+            Some(TokenValue::Cell(cell)) => serialize_toc(&cell)?,
+            _ => return Err(malformed("register payload", "buildRegisterPayload returned no cell")),
+        };
+        Ok(RegistrationRequest {
+            domain,
+            root_address: self.root_address.to_string(),
+            amount,
+            payload,
+        })
+    }
+
+    /// Domain is registered if its certificate contract is deployed.
+    async fn is_registered(&self, domain: &str) -> Result<bool> {
+        let certificate_address = self.address_contract(domain.to_string(), Query::default()).await?;
+        let state = {
+            let _permit = permit(&self.in_flight).await;
+            self.jrpc_transport.get_contract_state(&certificate_address).await?
+        };
+        Ok(matches!(state, RawContractState::Exists(_)))
+    }
+
+    async fn registration_amount(&self, name: &str, duration: u32) -> Result<u128> {
+        let tokens = self.run_root_getter(
+            "expectedRegisterAmount",
+            &[
+                Token::new("name", TokenValue::String(name.to_string())),
+                Token::new("duration", TokenValue::Uint(Uint::new(duration as u128, 32))),
+            ],
+            "amount",
+        ).await?;
+        uint_output(&tokens, "amount")
+    }
+
+    async fn run_root_getter(&self, function_name: &str, inputs: &[Token], output: &str) -> Result<Vec<Token>> {
+        let state = self.get_contract_state(&self.root_address, Query::default()).await?;
+        let mut tokens = vec![Token::new("answerId", TokenValue::Uint(Uint::new(0, 32)))];
+        tokens.extend_from_slice(inputs);
+        self.run_getter(&self.root_abis, "DomainRoot", function_name, &state, GetterCall::new(&tokens).returning(output)).await
+    }
+}
+
+/// Normalized ASCII form of the domain and the name registered under the DomainRoot,
+/// the domain without the TLD of the deployment, e.g. `foo` of `Foo.EVER`.
+fn registration_name(domain: &str, tld: &str) -> Result<(String, String)> {
+    let domain = normalize::cache_key(domain);
+    EvernameResolver::validate_name(&domain)?;
+    match domain.strip_suffix(tld) {
+        Some(name) if !name.is_empty() && !name.contains('.') => {
+            let name = name.to_string();
+            Ok((domain, name))
+        },
+        _ => Err(anyhow!("Only second-level {} domains can be registered, got {}", tld, domain)),
+    }
+}
+
+fn uint_output(tokens: &[Token], name: &str) -> Result<u128> {
+    match tokens.iter().find(|token| token.name == name).map(|token| &token.value) {
+        Some(TokenValue::Uint(uint)) => uint.number.to_u128().ok_or_else(|| malformed(name, "value is not uint128")),
+        _ => Err(malformed(name, "no value")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registration_names() {
+        assert_eq!(registration_name("Foo.EVER", ".ever").unwrap(), ("foo.ever".to_string(), "foo".to_string()));
+        assert_eq!(registration_name("site.ever.", ".ever").unwrap().1, "site");
+        assert_eq!(registration_name("Пример.ever", ".ever").unwrap(), ("xn--e1afmkfd.ever".to_string(), "xn--e1afmkfd".to_string()));
+        assert_eq!(registration_name("site.venom", ".venom").unwrap().1, "site");
+        for domain in ["sub.site.ever", ".ever", "site.venom", "-site.ever", "si_te.ever"] {
+            assert!(registration_name(domain, ".ever").is_err(), "{}", domain);
+        }
+    }
+}
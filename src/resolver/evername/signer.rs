@@ -0,0 +1,236 @@
+//! Record updates of Evername domains for registrar tools. Domain contracts accept updates
+//! from their owner only, so `setRecord` and `deleteRecord` calls are sent through the owner
+//! wallet (EverWallet or Multisig2) by external messages signed with the wallet key.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+use anyhow::{anyhow, Context, Result};
+use ed25519_dalek::{Signer, SigningKey};
+use log::debug;
+use nekoton::transport::Transport;
+use nekoton::transport::models::PollContractState;
+use nekoton_abi::pack_into_cell;
+use ton_abi::{contract, Function, Token, TokenValue, Uint};
+use ton_block::{AccountStuff, ExternalInboundMessageHeader, Message, MsgAddress, MsgAddressInt, Serializable};
+use ton_types::{Cell, SliceData};
+use crate::resolver::abi::{self, VersionedAbi};
+use crate::resolver::limit::permit;
+use crate::resolver::evername::{EvernameResolver, GetterCall, Query};
+
+pub use crate::resolver::abi::WALLET_ABI_VERSION;
+
+/// Value attached to record updates by default, 0.1 EVER in nano EVERs.
+pub const DEFAULT_ATTACHED_VALUE: u64 = 100_000_000;
+
+/// Default lifetime of the signed external messages in seconds.
+pub const DEFAULT_MESSAGE_TIMEOUT: u32 = 60;
+
+/// How long `send_record_message` waits for the domain certificate to process the update.
+const PROCESSING_TIMEOUT: Duration = Duration::from_secs(DEFAULT_MESSAGE_TIMEOUT as u64 + 30);
+const PROCESSING_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Owner wallet of Evername domains signing record updates with its key.
+///
+/// # Example
+/// ```no_run
+/// # async fn example(secret: [u8; 32]) -> anyhow::Result<()> {
+/// use web3_resolver::{EvernameResolver, OwnerWallet, RecordValue, SigningKey};
+///
+/// let evername_resolver = EvernameResolver::default()?;
+/// let wallet = OwnerWallet::new("0:2d5b...", SigningKey::from_bytes(&secret))?;
+/// let message_hash = evername_resolver.set_record(&wallet, "maksimzubov.ever", 1003, RecordValue::String("https://chums.chat".to_string())).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct OwnerWallet {
+    address: MsgAddressInt,
+    signing_key: SigningKey,
+    attached_value: u64,
+    timeout: u32,
+}
+
+impl OwnerWallet {
+    pub fn new(address: &str, signing_key: SigningKey) -> Result<Self> {
+        let address = MsgAddressInt::from_str(address).map_err(|e| anyhow!("Invalid wallet address {}: {}", address, e))?;
+        Ok(Self {
+            address,
+            signing_key,
+            attached_value: DEFAULT_ATTACHED_VALUE,
+            timeout: DEFAULT_MESSAGE_TIMEOUT,
+        })
+    }
+
+    /// Sets value in nano EVERs attached to each update to pay the domain contract fees.
+    pub fn with_attached_value(self, attached_value: u64) -> Self {
+        Self {
+            attached_value,
+            ..self
+        }
+    }
+
+    /// Sets lifetime of the signed external messages, expired messages are rejected by the wallet.
+    pub fn with_timeout(self, timeout: u32) -> Self {
+        Self {
+            timeout,
+            ..self
+        }
+    }
+
+    pub fn address(&self) -> String {
+        self.address.to_string()
+    }
+}
+
+/// Value of a domain record, encoded the way the resolver decodes it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RecordValue {
+    /// Text records: onion addresses, IPFS CIDs, URLs, onchain content
+    String(String),
+    /// Address records, e.g. of the Eversite contract
+    Address(String),
+}
+
+impl RecordValue {
+    fn into_cell(self) -> Result<Cell> {
+        let value = match self {
+            RecordValue::String(value) => TokenValue::String(value),
+            RecordValue::Address(address) => {
+                let address = MsgAddressInt::from_str(&address).map_err(|e| anyhow!("Invalid address {}: {}", address, e))?;
+                TokenValue::Address(msg_address(address))
+            },
+        };
+        pack_into_cell(&[Token::new("value", value)], contract::ABI_VERSION_2_0)?.into_cell()
+    }
+}
+
+impl EvernameResolver {
+    /// Sets the record of the domain from the owner wallet, returns hash of the sent message
+    /// once it is processed by the wallet and the domain contract, see `send_record_message`.
+    pub async fn set_record(&self, wallet: &OwnerWallet, domain: &str, key: u32, value: RecordValue) -> Result<String> {
+        let message = self.build_set_record_message(wallet, domain, key, value).await?;
+        self.send_record_message(domain, &message).await
+    }
+
+    /// Deletes the record of the domain from the owner wallet, returns hash of the sent message.
+    pub async fn delete_record(&self, wallet: &OwnerWallet, domain: &str, key: u32) -> Result<String> {
+        let message = self.build_delete_record_message(wallet, domain, key).await?;
+        self.send_record_message(domain, &message).await
+    }
+
+    /// Builds signed external message of the owner wallet setting the record, without sending it.
+    pub async fn build_set_record_message(&self, wallet: &OwnerWallet, domain: &str, key: u32, value: RecordValue) -> Result<Message> {
+        let tokens = [
+            Token::new("key", TokenValue::Uint(Uint::new(key as u128, 32))),
+            Token::new("value", TokenValue::Cell(value.into_cell()?)),
+        ];
+        self.build_owner_message(wallet, domain, "setRecord", &tokens).await
+    }
+
+    /// Builds signed external message of the owner wallet deleting the record, without sending it.
+    pub async fn build_delete_record_message(&self, wallet: &OwnerWallet, domain: &str, key: u32) -> Result<Message> {
+        let tokens = [Token::new("key", TokenValue::Uint(Uint::new(key as u128, 32)))];
+        self.build_owner_message(wallet, domain, "deleteRecord", &tokens).await
+    }
+
+    /// Sends the message built by `build_*_message`, waits until the domain certificate gets a new transaction
+    /// and drops cached results of the domain then, replacing the cached certificate state with the updated one,
+    /// so they are not refilled with the records before the update.
+    /// Fails if the certificate does not get a transaction in time, e.g. the message expired.
+    pub async fn send_record_message(&self, domain: &str, message: &Message) -> Result<String> {
+        let hash = message.serialize()?.repr_hash().to_hex_string();
+        let certificate_address = self.address_contract(domain.to_string(), Query::default()).await?;
+        let last_trans_lt = self.fetch_contract_state(&certificate_address).await?.storage.last_trans_lt;
+        {
+            let _permit = permit(&self.in_flight).await;
+            self.jrpc_transport.send_message(message).await?;
+        }
+        debug!("Record update of {} is sent in message {}", domain, hash);
+        let account = tokio::time::timeout(PROCESSING_TIMEOUT, self.wait_transaction(&certificate_address, last_trans_lt)).await
+            .map_err(|_| anyhow!("Record update of {} in message {} is not processed in {:?}", domain, hash, PROCESSING_TIMEOUT))??;
+        debug!("Record update of {} in message {} is processed", domain, hash);
+        self.invalidate(domain);
+        self.refresh_state(&certificate_address, account);
+        Ok(hash)
+    }
+
+    /// Polls the account until it gets a transaction after the given lt, returns the new state.
+    async fn wait_transaction(&self, address: &MsgAddressInt, last_trans_lt: u64) -> Result<AccountStuff> {
+        loop {
+            match self.poll_contract_state(address, last_trans_lt).await? {
+                PollContractState::Unchanged { .. } => tokio::time::sleep(PROCESSING_POLL_INTERVAL).await,
+                PollContractState::Exists(contract) => return Ok(contract.account),
+                PollContractState::NotExists { .. } => return Err(anyhow!("Account {} does not exist", address)),
+            }
+        }
+    }
+
+    /// Builds external message calling the Domain contract function through the owner wallet.
+    async fn build_owner_message(&self, wallet: &OwnerWallet, domain: &str, function_name: &str, tokens: &[Token]) -> Result<Message> {
+        let certificate_address = self.address_contract(domain.to_string(), Query::default()).await?;
+        self.check_owner(&certificate_address, wallet).await?;
+        let function = self.domain_function(function_name)?;
+        let payload = function.encode_internal_input(tokens)?.into_cell()?;
+        let wallet_abi = VersionedAbi::load(abi::WALLET_ABI_JSON)?;
+        let send_transaction = wallet_abi.contract.function("sendTransaction")?;
+        let inputs = [
+            Token::new("dest", TokenValue::Address(msg_address(certificate_address))),
+            Token::new("value", TokenValue::Uint(Uint::new(wallet.attached_value as u128, 128))),
+            Token::new("bounce", TokenValue::Bool(true)),
+            // pay fees separately, ignore errors of the action phase
+            Token::new("flags", TokenValue::Uint(Uint::new(3, 8))),
+            Token::new("payload", TokenValue::Cell(payload)),
+        ];
+        let now_ms = self.clock.now_ms_u64();
+        let header = HashMap::from([
+            ("time".to_string(), TokenValue::Time(now_ms)),
+            ("expire".to_string(), TokenValue::Expire((now_ms / 1000) as u32 + wallet.timeout)),
+        ]);
+        // without the pubkey header the wallet checks the signature against its own key
+        let (unsigned, hash) = send_transaction.create_unsigned_call(&header, &inputs, false, true, Some(wallet.address.clone()))?;
+        let signature = wallet.signing_key.sign(&hash).to_bytes();
+        let body = Function::add_sign_to_encoded_input(wallet_abi.contract.version(), &signature, None, SliceData::load_builder(unsigned)?)?;
+        let mut message = Message::with_ext_in_header(ExternalInboundMessageHeader {
+            dst: wallet.address.clone(),
+            ..Default::default()
+        });
+        message.set_body(SliceData::load_builder(body)?);
+        Ok(message)
+    }
+
+    /// Fails unless the wallet owns the domain certificate, so updates are not lost on failed transactions.
+    async fn check_owner(&self, certificate_address: &MsgAddressInt, wallet: &OwnerWallet) -> Result<()> {
+        let state = self.fetch_contract_state(certificate_address).await?;
+        let tokens = self.run_getter(
+            &self.domain_abis,
+            "Domain",
+            "getDetails",
+            &state,
+            GetterCall::new(&[Token::new("answerId", TokenValue::Uint(Uint::new(0, 32)))]).returning("owner"),
+        ).await?;
+        let owner = tokens.into_iter()
+            .find(|token| token.name == "owner")
+            .and_then(|token| match token.value {
+                TokenValue::Address(address) => address.to_msg_addr_int(),
+                _ => None,
+            })
+            .context("getDetails returned no owner")?;
+        if owner != wallet.address {
+            return Err(anyhow!("Domain is owned by {}, not by wallet {}", owner, wallet.address));
+        }
+        Ok(())
+    }
+
+    fn domain_function(&self, function_name: &str) -> Result<&Function> {
+        self.domain_abis.iter()
+            .find_map(|abi| abi.contract.function(function_name).ok())
+            .ok_or_else(|| anyhow!("Domain ABI has no {} function", function_name))
+    }
+}
+
+fn msg_address(address: MsgAddressInt) -> MsgAddress {
+    match address {
+        MsgAddressInt::AddrStd(address) => MsgAddress::AddrStd(address),
+        MsgAddressInt::AddrVar(address) => MsgAddress::AddrVar(address),
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, Instant};
+use mini_moka::sync::Cache;
+
+/// Expiry of cache entries protecting upstreams from cache stampedes: TTL jitter spreads expiration
+/// of entries inserted at the same moment, and probabilistic early refresh lets a few lookups refresh
+/// a popular entry shortly before it expires, while the others keep getting the cached result.
+///
+/// # Example
+/// ```
+/// use web3_resolver::CacheExpiry;
+///
+/// // entries live 90-100% of the TTL and are refreshed early within the last 10% of their lifetime
+/// let expiry = CacheExpiry::new().with_jitter(0.1).with_early_refresh(0.1);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CacheExpiry {
+    jitter: f64,
+    early_refresh: f64,
+}
+
+impl CacheExpiry {
+    /// Entries live exactly the TTL and are never refreshed early.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Shortens lifetime of each entry by a random share of the TTL up to `fraction` (0.0 to 1.0).
+    pub fn with_jitter(self, fraction: f64) -> Self {
+        Self {
+            jitter: fraction.clamp(0.0, 1.0),
+            ..self
+        }
+    }
+
+    /// Within the last `fraction` (0.0 to 1.0) of entry lifetime, lookups refresh the entry early with
+    /// probability growing linearly from 0 to 1 at the expiration. If the early refresh fails, the cached
+    /// result is served.
+    pub fn with_early_refresh(self, fraction: f64) -> Self {
+        Self {
+            early_refresh: fraction.clamp(0.0, 1.0),
+            ..self
+        }
+    }
+
+    fn deadline(&self, ttl: Duration) -> Instant {
+        Instant::now() + ttl.mul_f64(1.0 - self.jitter * random_unit())
+    }
+
+    fn lookup<V>(&self, value: V, inserted: Instant, deadline: Instant) -> Lookup<V> {
+        let now = Instant::now();
+        if now >= deadline {
+            return Lookup::Miss;
+        }
+        let window = deadline.duration_since(inserted).mul_f64(self.early_refresh);
+        let remaining = deadline.duration_since(now);
+        if remaining < window && random_unit() >= remaining.as_secs_f64() / window.as_secs_f64() {
+            return Lookup::Refresh(value);
+        }
+        Lookup::Hit(value)
+    }
+}
+
+/// Result of a cache lookup.
+pub(crate) enum Lookup<V> {
+    Hit(V),
+    /// Entry is alive, but the lookup is chosen to refresh it early
+    Refresh(V),
+    Miss,
+}
+
+/// Cache with a TTL and `CacheExpiry` of the entries.
+#[derive(Clone)]
+pub(crate) struct ExpiringCache<V> {
+    // values with their insertion time and deadline
+    entries: Cache<String, (V, Instant, Instant)>,
+    ttl: Duration,
+    expiry: CacheExpiry,
+}
+
+impl<V: Clone + Send + Sync + 'static> ExpiringCache<V> {
+    pub fn new(ttl: Duration, expiry: CacheExpiry) -> Self {
+        Self {
+            entries: Cache::builder().time_to_live(ttl).build(),
+            ttl,
+            expiry,
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    pub fn lookup(&self, key: &String) -> Lookup<V> {
+        match self.entries.get(key) {
+            Some((value, inserted, deadline)) => self.expiry.lookup(value, inserted, deadline),
+            None => Lookup::Miss,
+        }
+    }
+
+    /// Alive entry regardless of early refresh.
+    pub fn get(&self, key: &String) -> Option<V> {
+        self.entries.get(key)
+            .filter(|(_, _, deadline)| Instant::now() < *deadline)
+            .map(|(value, _, _)| value)
+    }
+
+    pub fn insert(&self, key: String, value: V) {
+        self.entries.insert(key, (value, Instant::now(), self.expiry.deadline(self.ttl)));
+    }
+
+    /// Inserts the value expiring after the given time, bounded by the TTL of the cache.
+    pub fn insert_for(&self, key: String, value: V, ttl: Duration) {
+        let now = Instant::now();
+        self.entries.insert(key, (value, now, now + ttl.min(self.ttl)));
+    }
+
+    /// Alive entries with the time left until their deadline.
+    pub fn alive_entries(&self) -> Vec<(String, V, Duration)> {
+        let now = Instant::now();
+        self.entries.iter()
+            .filter(|entry| now < entry.value().2)
+            .map(|entry| (entry.key().clone(), entry.value().0.clone(), entry.value().2 - now))
+            .collect()
+    }
+
+    pub fn invalidate(&self, key: &String) {
+        self.entries.invalidate(key);
+    }
+
+    pub fn contains_key(&self, key: &String) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Time since the entry was cached.
+    #[cfg(feature = "evername")]
+    pub fn age(&self, key: &String) -> Option<Duration> {
+        self.entries.get(key).map(|(_, inserted, _)| inserted.elapsed())
+    }
+
+    #[cfg(feature = "evername")]
+    pub fn keys(&self) -> Vec<String> {
+        self.entries.iter().map(|entry| entry.key().clone()).collect()
+    }
+}
+
+/// Random number in `[0, 1)`, randomly seeded `RandomState` is good enough to spread expiration.
+fn random_unit() -> f64 {
+    let hash = RandomState::new().build_hasher().finish();
+    (hash >> 11) as f64 / (1u64 << 53) as f64
+}
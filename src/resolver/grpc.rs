@@ -0,0 +1,225 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use anyhow::Result;
+use tonic::{Request, Response, Status};
+use crate::error::ResolverError;
+use crate::models::{AddressTag, ResolvedDomainData};
+use crate::Web3DomainResolver;
+use self::proto::web3_resolver_server::{Web3Resolver, Web3ResolverServer};
+
+/// Messages of `proto/web3_resolver.proto` and the generated `Web3Resolver` service.
+pub mod proto {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ResolveRequest {
+        #[prost(string, tag = "1")]
+        pub domain: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Target {
+        /// `domain-string`, `onchain-data`, `onchain-contract-data` or `dns-records`
+        #[prost(string, tag = "1")]
+        pub kind: String,
+        /// Address, onchain content, or comma separated IP addresses of `dns-records`
+        #[prost(string, tag = "2")]
+        pub value: String,
+        #[prost(string, tag = "3")]
+        pub content_type: String,
+        #[prost(string, tag = "4")]
+        pub tag: String,
+        #[prost(string, tag = "5")]
+        pub source: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ResolveResponse {
+        #[prost(message, optional, tag = "1")]
+        pub target: Option<Target>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ResolveAllResponse {
+        #[prost(message, repeated, tag = "1")]
+        pub targets: Vec<Target>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct RecordsRequest {
+        #[prost(string, tag = "1")]
+        pub domain: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Record {
+        #[prost(string, tag = "1")]
+        pub key: String,
+        #[prost(string, tag = "2")]
+        pub raw: String,
+        #[prost(string, optional, tag = "3")]
+        pub value: Option<String>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct RecordsResponse {
+        #[prost(string, tag = "1")]
+        pub source: String,
+        #[prost(message, repeated, tag = "2")]
+        pub records: Vec<Record>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct HealthRequest {}
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct HealthResponse {
+        #[prost(bool, tag = "1")]
+        pub serving: bool,
+        #[prost(uint64, tag = "2")]
+        pub requests: u64,
+        #[prost(uint64, tag = "3")]
+        pub errors: u64,
+    }
+
+    include!(concat!(env!("OUT_DIR"), "/web3resolver.v1.Web3Resolver.rs"));
+}
+
+/// gRPC service of `proto/web3_resolver.proto` on top of `Web3DomainResolver`, so non-Rust services
+/// can resolve domains over the network.
+///
+/// # Example
+/// ```no_run
+/// # async fn example() -> anyhow::Result<()> {
+/// use std::sync::Arc;
+/// use web3_resolver::DomainResolverBuilder;
+/// use web3_resolver::grpc::GrpcService;
+///
+/// let resolver = Arc::new(DomainResolverBuilder::gateway_defaults().build().await?);
+/// tonic::transport::Server::builder()
+///     .add_service(GrpcService::new(resolver).into_server())
+///     .serve("127.0.0.1:50051".parse()?)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct GrpcService {
+    resolver: Arc<Web3DomainResolver>,
+}
+
+impl GrpcService {
+    pub fn new(resolver: Arc<Web3DomainResolver>) -> Self {
+        Self { resolver }
+    }
+
+    /// Wraps the service into a tonic server to be added to a `tonic::transport::Server`.
+    pub fn into_server(self) -> Web3ResolverServer<Self> {
+        Web3ResolverServer::new(self)
+    }
+}
+
+/// Serves the resolver over gRPC on the address until the server fails.
+pub async fn serve(resolver: Arc<Web3DomainResolver>, address: SocketAddr) -> Result<()> {
+    tonic::transport::Server::builder()
+        .add_service(GrpcService::new(resolver).into_server())
+        .serve(address)
+        .await?;
+    Ok(())
+}
+
+#[tonic::async_trait]
+impl Web3Resolver for GrpcService {
+    async fn resolve(&self, request: Request<proto::ResolveRequest>) -> Result<Response<proto::ResolveResponse>, Status> {
+        let report = self.resolver.resolve_report(&request.into_inner().domain).await.map_err(status)?;
+        Ok(Response::new(proto::ResolveResponse {
+            target: Some(target(&report.data, &report.tag, &report.source)),
+        }))
+    }
+
+    async fn resolve_all(&self, request: Request<proto::ResolveRequest>) -> Result<Response<proto::ResolveAllResponse>, Status> {
+        let candidates = self.resolver.resolve_all(&request.into_inner().domain).await.map_err(status)?;
+        Ok(Response::new(proto::ResolveAllResponse {
+            targets: candidates.iter()
+                .map(|candidate| target(&candidate.data, &candidate.tag, &candidate.source))
+                .collect(),
+        }))
+    }
+
+    async fn records(&self, request: Request<proto::RecordsRequest>) -> Result<Response<proto::RecordsResponse>, Status> {
+        let records = self.resolver.records(&request.into_inner().domain).await.map_err(status)?;
+        Ok(Response::new(proto::RecordsResponse {
+            source: records.source,
+            records: records.records.into_iter()
+                .map(|record| proto::Record { key: record.key, raw: record.raw, value: record.value })
+                .collect(),
+        }))
+    }
+
+    async fn health(&self, _request: Request<proto::HealthRequest>) -> Result<Response<proto::HealthResponse>, Status> {
+        let stats = self.resolver.stats();
+        Ok(Response::new(proto::HealthResponse {
+            serving: !self.resolver.is_shut_down(),
+            requests: stats.requests,
+            errors: stats.errors,
+        }))
+    }
+}
+
+fn target(data: &ResolvedDomainData, tag: &AddressTag, source: &str) -> proto::Target {
+    proto::Target {
+        kind: data.kind().to_string(),
+        value: data.value_string(),
+        content_type: data.content_type().unwrap_or_default().to_string(),
+        tag: tag.as_str().to_string(),
+        source: source.to_string(),
+    }
+}
+
+/// Maps resolution errors to gRPC statuses. Only unregistered domains are reported as not found,
+/// so clients do not cache outages as missing domains.
+fn status(error: anyhow::Error) -> Status {
+    let message = format!("{:#}", error);
+    match error.downcast_ref::<ResolverError>() {
+        Some(ResolverError::NotFound { .. } | ResolverError::AccountNotFound { .. }) => Status::not_found(message),
+        Some(ResolverError::Blocked { .. }) => Status::permission_denied(message),
+        Some(ResolverError::AmbiguousDomain { .. } | ResolverError::NoContent { .. }) => Status::failed_precondition(message),
+        Some(ResolverError::InvalidName { .. } | ResolverError::UnsupportedTld { .. }) => Status::invalid_argument(message),
+        Some(ResolverError::RateLimited { .. }) => Status::resource_exhausted(message),
+        Some(ResolverError::GetMethodTimeout { .. }) => Status::deadline_exceeded(message),
+        Some(
+            ResolverError::QuorumMismatch { .. }
+            | ResolverError::UpstreamUnavailable { .. }
+            | ResolverError::InitializationFailed { .. }
+        ) => Status::unavailable(message),
+        Some(_) => Status::internal(message),
+        // transport and other untyped failures
+        None => Status::unknown(message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tonic::Code;
+    use super::*;
+
+    #[test]
+    fn error_statuses() {
+        let not_found = ResolverError::NotFound { domain: "site.ever".to_string() };
+        assert_eq!(status(not_found.into()).code(), Code::NotFound);
+        let timeout = ResolverError::GetMethodTimeout {
+            contract: "Domain".to_string(),
+            function: "getRecords".to_string(),
+            budget: std::time::Duration::from_secs(5),
+        };
+        assert_eq!(status(timeout.into()).code(), Code::DeadlineExceeded);
+        let initialization = ResolverError::InitializationFailed {
+            resolver: "ud".to_string(),
+            source: anyhow::anyhow!("connection refused"),
+        };
+        assert_eq!(status(initialization.into()).code(), Code::Unavailable);
+        let malformed = ResolverError::MalformedData { what: "record cell".to_string(), reason: "no value".to_string() };
+        assert_eq!(status(malformed.into()).code(), Code::Internal);
+        let panicked = ResolverError::ResolverPanicked { resolver: "custom".to_string(), message: "oops".to_string() };
+        assert_eq!(status(panicked.into()).code(), Code::Internal);
+        assert_eq!(status(anyhow::anyhow!("error sending request")).code(), Code::Unknown);
+    }
+}
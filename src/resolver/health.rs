@@ -0,0 +1,147 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use crate::models::{EndpointSelection, EndpointStats};
+use crate::resolver::targets::splitmix64;
+
+/// Weight of the latest sample in latency and error rate moving averages.
+const EWMA_ALPHA: f64 = 0.2;
+/// Cost multiplier of an endpoint failing all requests, so flaky endpoints lose to slower healthy ones.
+const ERROR_PENALTY: f64 = 10.0;
+/// Latency failed requests are charged at least, unless the total request timeout is set.
+pub(crate) const DEFAULT_FAILURE_LATENCY: Duration = Duration::from_secs(10);
+
+/// Rolling health of an endpoint.
+#[derive(Clone, Copy, Default)]
+struct EndpointHealth {
+    requests: u64,
+    errors: u64,
+    // seconds
+    latency_ewma: f64,
+    error_rate_ewma: f64,
+}
+
+impl EndpointHealth {
+    fn record(&mut self, latency: Duration, success: bool) {
+        let error = if success { 0.0 } else { 1.0 };
+        if self.requests == 0 {
+            self.latency_ewma = latency.as_secs_f64();
+            self.error_rate_ewma = error;
+        } else {
+            self.latency_ewma += EWMA_ALPHA * (latency.as_secs_f64() - self.latency_ewma);
+            self.error_rate_ewma += EWMA_ALPHA * (error - self.error_rate_ewma);
+        }
+        self.requests += 1;
+        if !success {
+            self.errors += 1;
+        }
+    }
+
+    /// Expected cost of a request, endpoints without requests are the cheapest so they get explored.
+    fn cost(&self, weight: u32) -> f64 {
+        self.latency_ewma * (1.0 + ERROR_PENALTY * self.error_rate_ewma) / weight as f64
+    }
+}
+
+/// Latency and error rate moving averages of a fixed list of endpoints (JRPC endpoints or IPFS gateways).
+pub(crate) struct HealthTracker {
+    health: Mutex<Vec<EndpointHealth>>,
+    // failures are charged at least this latency, so endpoints refusing connections at once are not the fastest
+    failure_latency: Duration,
+}
+
+impl HealthTracker {
+    pub fn new(count: usize, failure_latency: Duration) -> Self {
+        Self {
+            health: Mutex::new(vec![EndpointHealth::default(); count]),
+            failure_latency,
+        }
+    }
+
+    pub fn record(&self, index: usize, latency: Duration, success: bool) {
+        let latency = if success { latency } else { latency.max(self.failure_latency) };
+        if let Ok(mut health) = self.health.lock() {
+            if let Some(health) = health.get_mut(index) {
+                health.record(latency, success);
+            }
+        }
+    }
+
+    /// Costs of the endpoints with the given weights.
+    pub fn costs<I: IntoIterator<Item = u32>>(&self, weights: I) -> Vec<f64> {
+        let health = self.health.lock().map(|health| health.clone()).unwrap_or_default();
+        weights.into_iter()
+            .enumerate()
+            .map(|(index, weight)| health.get(index).map(|health| health.cost(weight)).unwrap_or_default())
+            .collect()
+    }
+
+    /// Request counters, latency and error rate moving averages of the endpoints with the given names.
+    pub fn stats<I: IntoIterator<Item = String>>(&self, names: I) -> Vec<EndpointStats> {
+        let health = self.health.lock().map(|health| health.clone()).unwrap_or_default();
+        names.into_iter()
+            .zip(health)
+            .map(|(endpoint, health)| EndpointStats {
+                endpoint,
+                requests: health.requests,
+                errors: health.errors,
+                latency_ewma: Duration::from_secs_f64(health.latency_ewma),
+                error_rate_ewma: health.error_rate_ewma,
+            })
+            .collect()
+    }
+}
+
+/// Power of two choices: the cheaper of two pseudo-randomly picked endpoints.
+pub(crate) fn select_p2c(tick: u64, costs: &[f64]) -> usize {
+    let count = costs.len() as u64;
+    if count <= 1 {
+        return 0;
+    }
+    let random = splitmix64(tick);
+    let first = (random % count) as usize;
+    let second = ((first as u64 + 1 + (random >> 32) % (count - 1)) % count) as usize;
+    if costs[second] < costs[first] { second } else { first }
+}
+
+/// The selected endpoint followed by the others from the cheapest one, the failover order.
+pub(crate) fn failover_order(selected: usize, costs: &[f64]) -> Vec<usize> {
+    let mut others: Vec<usize> = (0..costs.len()).filter(|index| *index != selected).collect();
+    others.sort_by(|a, b| costs[*a].total_cmp(&costs[*b]));
+    std::iter::once(selected).chain(others).collect()
+}
+
+/// Health of the IPFS gateways set by `DomainResolverBuilder::with_ipfs_gateways`, recorded by `fetch_with_failover`.
+pub(crate) struct GatewayHealth {
+    selection: EndpointSelection,
+    counter: AtomicU64,
+    health: HealthTracker,
+}
+
+impl GatewayHealth {
+    pub fn new(count: usize, selection: EndpointSelection) -> Self {
+        Self {
+            selection,
+            counter: AtomicU64::new(0),
+            health: HealthTracker::new(count, DEFAULT_FAILURE_LATENCY),
+        }
+    }
+
+    /// Gateway indexes from the best one with latency aware selection, none keeps the configured order.
+    pub fn order(&self, count: usize) -> Option<Vec<usize>> {
+        if self.selection != EndpointSelection::LatencyAware || count < 2 {
+            return None;
+        }
+        let costs = self.health.costs(std::iter::repeat_n(1, count));
+        let tick = self.counter.fetch_add(1, Ordering::Relaxed);
+        Some(failover_order(select_p2c(tick, &costs), &costs))
+    }
+
+    pub fn record(&self, index: usize, latency: Duration, success: bool) {
+        self.health.record(index, latency, success);
+    }
+
+    pub fn stats(&self, gateways: &[String]) -> Vec<EndpointStats> {
+        self.health.stats(gateways.iter().cloned())
+    }
+}
@@ -0,0 +1,27 @@
+use anyhow::Result;
+use reqwest::Client;
+use crate::models::{ConnectionPool, Timeouts};
+
+/// Creates HTTP client with the given timeouts and connection reuse settings.
+pub(crate) fn http_client(timeouts: &Timeouts, pool: &ConnectionPool) -> Result<Client> {
+    let mut builder = Client::builder();
+    if let Some(connect) = timeouts.connect {
+        builder = builder.connect_timeout(connect);
+    }
+    if let Some(read) = timeouts.read {
+        builder = builder.read_timeout(read);
+    }
+    if let Some(total) = timeouts.total {
+        builder = builder.timeout(total);
+    }
+    if let Some(tcp_keepalive) = pool.tcp_keepalive {
+        builder = builder.tcp_keepalive(tcp_keepalive);
+    }
+    if let Some(idle_timeout) = pool.idle_timeout {
+        builder = builder.pool_idle_timeout(idle_timeout);
+    }
+    if let Some(max_idle_per_host) = pool.max_idle_per_host {
+        builder = builder.pool_max_idle_per_host(max_idle_per_host);
+    }
+    Ok(builder.build()?)
+}
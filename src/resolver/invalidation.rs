@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use std::sync::Weak;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use futures::{stream, StreamExt};
+use log::debug;
+use nekoton::transport::models::PollContractState;
+use ton_block::MsgAddressInt;
+use tokio_util::sync::CancellationToken;
+use crate::resolver::{normalize, Web3DomainResolver};
+
+/// Certificates polled at once in one scan.
+const SCAN_CONCURRENCY: usize = 8;
+/// Certificate transactions made up to this long before a result was cached may be missing from it,
+/// JRPC endpoints lag behind the chain.
+const ENDPOINT_LAG: Duration = Duration::from_secs(10);
+
+/// Certificate contract of a cached domain and its last seen transaction lt.
+struct TrackedCertificate {
+    address: MsgAddressInt,
+    last_trans_lt: u64,
+}
+
+/// Task invalidating cached Evername domains (of every deployment, see `DomainResolverBuilder::with_evername_network`)
+/// as soon as their certificate contracts get new transactions (record updates, transfers, renewals).
+/// The task stops when the resolver is dropped or shut down.
+pub(crate) async fn run(resolver: Weak<Web3DomainResolver>, interval: Duration, cancellation: CancellationToken) {
+    let mut tracked: HashMap<String, TrackedCertificate> = HashMap::new();
+    loop {
+        tokio::select! {
+            _ = cancellation.cancelled() => {
+                debug!("Resolver is shut down, stopping cache invalidation");
+                return;
+            },
+            _ = tokio::time::sleep(interval) => {},
+        }
+        let Some(resolver) = resolver.upgrade() else {
+            debug!("Resolver is dropped, stopping cache invalidation");
+            return;
+        };
+        let Some(cache) = &resolver.dns_cache else {
+            return;
+        };
+        // certificates of domains no longer cached are dropped
+        let mut previous = std::mem::take(&mut tracked);
+        let scans = cache.keys()
+            .into_iter()
+            .filter(|domain| resolver.evername_of(domain).is_some())
+            .map(|domain| {
+                let certificate = previous.remove(&domain);
+                let resolver = &resolver;
+                async move {
+                    let certificate = scan_domain(resolver, &domain, certificate).await;
+                    (domain, certificate)
+                }
+            })
+            .collect::<Vec<_>>();
+        tracked = stream::iter(scans)
+            .buffer_unordered(SCAN_CONCURRENCY)
+            .filter_map(|(domain, certificate)| async move { Some((domain, certificate?)) })
+            .collect()
+            .await;
+    }
+}
+
+/// Polls the certificate of the cached domain, invalidating the domain if it has new transactions.
+/// Returns the certificate to track further, `None` if it is gone or not found yet.
+async fn scan_domain(resolver: &Web3DomainResolver, domain: &String, certificate: Option<TrackedCertificate>) -> Option<TrackedCertificate> {
+    let evername = resolver.evername_of(domain)?;
+    let Some(mut certificate) = certificate else {
+        return match evername.certificate_state(&normalize::domain_name(domain)).await {
+            Ok((address, last_trans_lt, last_paid)) => {
+                // the certificate may have changed after the result was read, between caching and this scan
+                let age = resolver.dns_cache.as_ref().and_then(|cache| cache.age(domain)).unwrap_or_default();
+                let cached_at = SystemTime::now().checked_sub(age + ENDPOINT_LAG).unwrap_or(UNIX_EPOCH);
+                if UNIX_EPOCH + Duration::from_secs(last_paid as u64) >= cached_at {
+                    debug!("Certificate of {} has transactions since the result was cached, invalidating cache", domain);
+                    resolver.invalidate(domain);
+                }
+                Some(TrackedCertificate { address, last_trans_lt })
+            },
+            Err(e) => {
+                debug!("Failed to track certificate of {}: {:#}", domain, e);
+                None
+            },
+        };
+    };
+    match evername.poll_contract_state(&certificate.address, certificate.last_trans_lt).await {
+        Ok(PollContractState::Unchanged { .. }) => Some(certificate),
+        Ok(PollContractState::Exists(contract)) => {
+            debug!("Certificate of {} has new transactions, invalidating cache", domain);
+            certificate.last_trans_lt = contract.account.storage.last_trans_lt;
+            resolver.invalidate(domain);
+            // the cached certificate state may still be fresh, the next resolution reads the polled one
+            evername.refresh_state(&certificate.address, contract.account);
+            Some(certificate)
+        },
+        Ok(PollContractState::NotExists { .. }) => {
+            debug!("Certificate of {} is gone, invalidating cache", domain);
+            resolver.invalidate(domain);
+            None
+        },
+        Err(e) => {
+            debug!("Failed to poll certificate of {}: {:#}", domain, e);
+            Some(certificate)
+        },
+    }
+}
@@ -1,4 +1,96 @@
-pub fn make_ipfs_link(content_hash_string: &str) -> String {
+use crate::models::WeightedTarget;
+
+/// Default IPFS gateway, `{cid}` is replaced with the content hash.
+#[cfg(any(feature = "evername", feature = "ud", feature = "ens"))]
+pub const DEFAULT_IPFS_GATEWAY: &str = "https://{cid}.ipfs.w3s.link/";
+
+/// Makes link to the content on the gateway: subdomain gateways have `{cid}` placeholder,
+/// e.g. `https://{cid}.ipfs.dweb.link/`, path gateways get the hash appended, e.g. `https://ipfs.io/ipfs/`.
+pub fn make_gateway_link(gateway: &str, content_hash_string: &str) -> String {
     let content_hash_fixed = content_hash_string.trim_start_matches("ipfs://").trim_start_matches("/ipfs/");
-    format!("https://{}.ipfs.w3s.link/", content_hash_fixed)
-}
\ No newline at end of file
+    if gateway.contains("{cid}") {
+        gateway.replace("{cid}", content_hash_fixed)
+    } else {
+        format!("{}/{}", gateway.trim_end_matches('/'), content_hash_fixed)
+    }
+}
+
+/// Makes links of every target of a record publishing several targets, see `WeightedTarget`.
+#[cfg(any(feature = "evername", feature = "ud"))]
+pub(crate) fn make_gateway_links(gateway: &str, value: &str) -> String {
+    map_targets(value, |content_hash| make_gateway_link(gateway, content_hash))
+}
+
+/// Applies the mapping to the URL of every target of the record value,
+/// values that are not target lists are mapped as a whole.
+pub(crate) fn map_targets<F: Fn(&str) -> String>(value: &str, map: F) -> String {
+    match WeightedTarget::parse_list(value) {
+        Ok(targets) if !targets.is_empty() => {
+            let targets: Vec<WeightedTarget> = targets.into_iter()
+                .map(|target| WeightedTarget { url: map(&target.url), ..target })
+                .collect();
+            WeightedTarget::join(&targets)
+        },
+        _ => map(value),
+    }
+}
+
+/// Moves IPFS URI or gateway link to another gateway keeping the path, `None` if it is not an IPFS link.
+pub(crate) fn move_to_gateway(gateway: &str, link: &str) -> Option<String> {
+    match split_gateway_link(link)? {
+        (cid, "" | "/") => Some(make_gateway_link(gateway, cid)),
+        (cid, path) => Some(format!("{}{}", make_gateway_link(gateway, cid).trim_end_matches('/'), path)),
+    }
+}
+
+/// Splits IPFS URI or gateway link into the content hash and the path after it, e.g.
+/// `https://{cid}.ipfs.w3s.link/index.html` or `https://ipfs.io/ipfs/{cid}/index.html`.
+pub(crate) fn split_gateway_link(link: &str) -> Option<(&str, &str)> {
+    if let Some(rest) = link.strip_prefix("ipfs://") {
+        return Some(rest.split_at(rest.find('/').unwrap_or(rest.len())));
+    }
+    let rest = link.split_once("://").map_or(link, |(_, rest)| rest);
+    let (host, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    if let Some((cid, _)) = host.split_once(".ipfs.") {
+        return Some((cid, path));
+    }
+    let rest = &path[path.find("/ipfs/")? + "/ipfs/".len()..];
+    Some(rest.split_at(rest.find('/').unwrap_or(rest.len())))
+}
+
+/// RFC 4648 base32 in lowercase without padding, as used by multibase `b` prefix.
+#[cfg(any(feature = "evername", feature = "ens"))]
+pub(crate) fn base32_lower(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let mut buffer = 0u16;
+    let mut bits = 0;
+    for byte in bytes {
+        buffer = (buffer << 8) | *byte as u16;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        encoded.push(ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    encoded
+}
+
+/// Default IPNS gateway, `{cid}` is replaced with the IPNS name.
+#[cfg(feature = "ens")]
+pub const DEFAULT_IPNS_GATEWAY: &str = "https://{cid}.ipns.dweb.link/";
+
+/// Makes link to the IPNS name on the gateway. On subdomain gateways DNSLink names are inlined
+/// into a single DNS label, e.g. `app.uniswap.org` becomes `app-uniswap-org`.
+#[cfg(feature = "ens")]
+pub fn make_ipns_gateway_link(gateway: &str, name: &str) -> String {
+    let name = name.trim_start_matches("ipns://").trim_start_matches("/ipns/");
+    if gateway.contains("{cid}") {
+        gateway.replace("{cid}", &name.replace('-', "--").replace('.', "-"))
+    } else {
+        format!("{}/{}", gateway.trim_end_matches('/'), name)
+    }
+}
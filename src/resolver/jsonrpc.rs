@@ -0,0 +1,323 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use anyhow::{anyhow, Result};
+use http_body_util::{BodyExt, Full, LengthLimitError, Limited};
+use hyper::body::{Bytes, Incoming};
+use hyper::header::{HeaderValue, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN, CONTENT_TYPE, ORIGIN};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::{TokioIo, TokioTimer};
+use log::debug;
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+use tokio::sync::Semaphore;
+use crate::error::ResolverError;
+use crate::models::{AddressTag, ResolvedDomainData};
+use crate::Web3DomainResolver;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// Resolution failed
+const RESOLUTION_ERROR: i64 = -32000;
+/// Domain is blocked by an override or policy
+const BLOCKED_ERROR: i64 = -32001;
+/// Upstream of the resolver is rate limited or unavailable, the call may be retried
+const RETRYABLE_ERROR: i64 = -32002;
+/// Domain is not registered
+const NOT_FOUND_ERROR: i64 = -32003;
+/// Domain is registered but has no content records
+const NO_CONTENT_ERROR: i64 = -32004;
+
+const MAX_BODY_SIZE: usize = 64 * 1024;
+/// Time a client has to send the request headers, and then the body.
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_MAX_CONNECTIONS: usize = 256;
+const DEFAULT_MAX_BATCH_SIZE: usize = 32;
+
+/// JSON-RPC 2.0 server of `Web3DomainResolver` for running the resolver as a local sidecar daemon
+/// queried by browsers and extensions. Requests are HTTP POSTs with a JSON-RPC request or batch body.
+///
+/// Browser requests carry an `Origin` header and are served only if it is the allowed origin,
+/// so web pages cannot call the daemon. Requests without `Origin` come from non-browser clients and are served.
+///
+/// Methods, params are `{"domain": "..."}` or `["..."]`:
+/// - `resolve`: the best target, `{kind, value, contentType, tag, source, fromCache, stale, warnings}`
+/// - `resolveAll`: all matching targets in resolver priority order
+/// - `stats`: resolution counters and latencies, no params
+/// - `invalidate`: drops the domain from the resolution cache
+///
+/// # Example
+/// ```no_run
+/// # async fn example() -> anyhow::Result<()> {
+/// use std::sync::Arc;
+/// use web3_resolver::{DomainResolverBuilder, JsonRpcServer};
+///
+/// let resolver = Arc::new(DomainResolverBuilder::default().build().await?);
+/// JsonRpcServer::new(resolver)
+///     .with_allowed_origin("chrome-extension://abcdefghijklmnopabcdefghijklmnop")?
+///     .serve("127.0.0.1:8545".parse()?)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct JsonRpcServer {
+    resolver: Arc<Web3DomainResolver>,
+    allowed_origin: Option<HeaderValue>,
+    request_timeout: Duration,
+    max_connections: usize,
+    max_batch_size: usize,
+}
+
+impl JsonRpcServer {
+    pub fn new(resolver: Arc<Web3DomainResolver>) -> Self {
+        Self {
+            resolver,
+            allowed_origin: None,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+        }
+    }
+
+    /// Sets the only web origin (e.g. the extension) allowed to call the server from a browser,
+    /// responses to it get CORS headers. By default browser requests are rejected and no CORS headers are sent.
+    pub fn with_allowed_origin(self, origin: &str) -> Result<Self> {
+        let origin = HeaderValue::from_str(origin).map_err(|e| anyhow!("Invalid origin {}: {}", origin, e))?;
+        Ok(Self {
+            allowed_origin: Some(origin),
+            ..self
+        })
+    }
+
+    /// Sets the time a client has to send the request headers, and then the body. Default is 10 seconds.
+    pub fn with_request_timeout(self, request_timeout: Duration) -> Self {
+        Self {
+            request_timeout,
+            ..self
+        }
+    }
+
+    /// Sets the number of connections served at once, further ones wait in the listen backlog. Default is 256.
+    pub fn with_max_connections(self, max_connections: usize) -> Self {
+        Self {
+            max_connections,
+            ..self
+        }
+    }
+
+    /// Sets the max number of calls in a batch, larger batches are rejected. Default is 32.
+    pub fn with_max_batch_size(self, max_batch_size: usize) -> Self {
+        Self {
+            max_batch_size,
+            ..self
+        }
+    }
+
+    /// Handles JSON-RPC request or batch body and returns the response body,
+    /// `None` if there is nothing to respond, e.g. for notifications.
+    /// Useful to mount the server into an existing HTTP service.
+    pub async fn handle(&self, body: &[u8]) -> Option<Value> {
+        let request: Value = match serde_json::from_slice(body) {
+            Ok(request) => request,
+            Err(e) => return Some(error_response(Value::Null, PARSE_ERROR, &e.to_string())),
+        };
+        match request {
+            Value::Array(requests) if requests.is_empty() => {
+                Some(error_response(Value::Null, INVALID_REQUEST, "Empty batch"))
+            },
+            Value::Array(requests) if requests.len() > self.max_batch_size => {
+                Some(error_response(Value::Null, INVALID_REQUEST, &format!("Batch of {} calls exceeds the limit of {}", requests.len(), self.max_batch_size)))
+            },
+            Value::Array(requests) => {
+                let responses: Vec<Value> = futures::future::join_all(requests.into_iter().map(|request| self.handle_call(request)))
+                    .await
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                (!responses.is_empty()).then_some(Value::Array(responses))
+            },
+            request => self.handle_call(request).await,
+        }
+    }
+
+    /// Accepts HTTP connections on the address until the listener fails or the resolver is shut down,
+    /// one request per connection.
+    pub async fn serve(self, address: SocketAddr) -> Result<()> {
+        let listener = TcpListener::bind(address).await?;
+        debug!("JSON-RPC server is listening on {}", address);
+        let cancellation = self.resolver.cancellation_token();
+        let connections = Arc::new(Semaphore::new(self.max_connections));
+        let server = Arc::new(self);
+        loop {
+            let (permit, (stream, peer)) = tokio::select! {
+                _ = cancellation.cancelled() => break,
+                accepted = async { Ok::<_, anyhow::Error>((connections.clone().acquire_owned().await?, listener.accept().await?)) } => accepted?,
+            };
+            let server = server.clone();
+            tokio::spawn(async move {
+                let request_timeout = server.request_timeout;
+                let service = service_fn(move |request| {
+                    let server = server.clone();
+                    async move { Ok::<_, Infallible>(server.handle_request(request).await) }
+                });
+                let connection = http1::Builder::new()
+                    .timer(TokioTimer::new())
+                    .header_read_timeout(request_timeout)
+                    .keep_alive(false)
+                    .serve_connection(TokioIo::new(stream), service);
+                if let Err(e) = connection.await {
+                    debug!("JSON-RPC connection from {} failed: {}", peer, e);
+                }
+                drop(permit);
+            });
+        }
+        debug!("JSON-RPC server on {} is stopped", address);
+        Ok(())
+    }
+
+    async fn handle_call(&self, request: Value) -> Option<Value> {
+        let Some(method) = request.get("method").and_then(|method| method.as_str()) else {
+            return Some(error_response(Value::Null, INVALID_REQUEST, "Method is missing"));
+        };
+        // requests without id are notifications and get no response
+        let id = request.get("id").cloned()?;
+        if request.get("jsonrpc").and_then(|version| version.as_str()) != Some("2.0") {
+            return Some(error_response(id, INVALID_REQUEST, "Only JSON-RPC 2.0 is supported"));
+        }
+        let params = request.get("params").cloned().unwrap_or(Value::Null);
+        let result = match method {
+            "resolve" => self.resolve(&params).await,
+            "resolveAll" => self.resolve_all(&params).await,
+            "stats" => Ok(self.stats()),
+            "invalidate" => domain_param(&params).map(|domain| {
+                self.resolver.invalidate(&domain);
+                Value::Bool(true)
+            }),
+            _ => return Some(error_response(id, METHOD_NOT_FOUND, &format!("Method {} is not found", method))),
+        };
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(e) => {
+                let code = match e.downcast_ref::<ResolverError>() {
+                    Some(ResolverError::Blocked { .. }) => BLOCKED_ERROR,
+                    Some(ResolverError::InvalidName { .. } | ResolverError::UnsupportedTld { .. }) => INVALID_PARAMS,
+                    Some(ResolverError::NotFound { .. }) => NOT_FOUND_ERROR,
+                    Some(ResolverError::NoContent { .. }) => NO_CONTENT_ERROR,
+                    Some(error) if error.is_retryable() => RETRYABLE_ERROR,
+                    _ if e.is::<InvalidParams>() => INVALID_PARAMS,
+                    _ => RESOLUTION_ERROR,
+                };
+                error_response(id, code, &format!("{:#}", e))
+            },
+        })
+    }
+
+    async fn resolve(&self, params: &Value) -> Result<Value> {
+        let report = self.resolver.resolve_report(&domain_param(params)?).await?;
+        let mut target = target(&report.data, &report.tag, &report.source);
+        target["fromCache"] = Value::Bool(report.from_cache);
+        target["stale"] = Value::Bool(report.stale);
+        target["warnings"] = report.warnings.iter().map(|warning| json!(warning.to_string())).collect();
+        Ok(target)
+    }
+
+    async fn resolve_all(&self, params: &Value) -> Result<Value> {
+        let candidates = self.resolver.resolve_all(&domain_param(params)?).await?;
+        Ok(candidates.iter()
+            .map(|candidate| target(&candidate.data, &candidate.tag, &candidate.source))
+            .collect())
+    }
+
+    fn stats(&self) -> Value {
+        let stats = self.resolver.stats();
+        json!({
+            "requests": stats.requests,
+            "errors": stats.errors,
+            "topDomains": stats.top_domains.iter()
+                .map(|(domain, count)| json!({ "domain": domain, "count": count }))
+                .collect::<Vec<_>>(),
+            "sources": stats.sources.iter()
+                .map(|source| json!({ "source": source.source, "requests": source.requests, "errors": source.errors }))
+                .collect::<Vec<_>>(),
+            "latencyP50Ms": stats.latency_p50.as_millis() as u64,
+            "latencyP99Ms": stats.latency_p99.as_millis() as u64,
+            "namehashCacheClears": stats.namehash_cache_clears,
+        })
+    }
+
+    async fn handle_request(&self, request: Request<Incoming>) -> Response<Full<Bytes>> {
+        // browsers send `Origin` with cross-origin requests, only the allowed one is served
+        if let Some(origin) = request.headers().get(ORIGIN) {
+            if self.allowed_origin.as_ref() != Some(origin) {
+                return self.response(StatusCode::FORBIDDEN, None);
+            }
+        }
+        match *request.method() {
+            // CORS preflight of browser requests
+            Method::OPTIONS => return self.response(StatusCode::NO_CONTENT, None),
+            Method::POST => {},
+            _ => return self.response(StatusCode::METHOD_NOT_ALLOWED, None),
+        }
+        let body = Limited::new(request.into_body(), MAX_BODY_SIZE);
+        let body = match tokio::time::timeout(self.request_timeout, body.collect()).await {
+            Ok(Ok(body)) => body.to_bytes(),
+            Ok(Err(e)) if e.is::<LengthLimitError>() => return self.response(StatusCode::PAYLOAD_TOO_LARGE, None),
+            Ok(Err(_)) => return self.response(StatusCode::BAD_REQUEST, None),
+            Err(_) => return self.response(StatusCode::REQUEST_TIMEOUT, None),
+        };
+        match self.handle(&body).await {
+            Some(response) => self.response(StatusCode::OK, Some(response.to_string())),
+            None => self.response(StatusCode::NO_CONTENT, None),
+        }
+    }
+
+    fn response(&self, status: StatusCode, body: Option<String>) -> Response<Full<Bytes>> {
+        let json = body.is_some();
+        let mut response = Response::new(Full::new(Bytes::from(body.unwrap_or_default())));
+        *response.status_mut() = status;
+        let headers = response.headers_mut();
+        if json {
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        }
+        if let Some(origin) = &self.allowed_origin {
+            headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, origin.clone());
+            headers.insert(ACCESS_CONTROL_ALLOW_METHODS, HeaderValue::from_static("POST, OPTIONS"));
+            headers.insert(ACCESS_CONTROL_ALLOW_HEADERS, HeaderValue::from_static("Content-Type"));
+        }
+        response
+    }
+}
+
+/// Invalid params of a call, reported with the `-32602` code.
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+struct InvalidParams(String);
+
+/// Domain from `{"domain": "..."}` or `["..."]` params.
+fn domain_param(params: &Value) -> Result<String> {
+    params.get("domain")
+        .or_else(|| params.get(0))
+        .and_then(|domain| domain.as_str())
+        .map(|domain| domain.to_string())
+        .ok_or_else(|| InvalidParams("Domain param is missing".to_string()).into())
+}
+
+fn target(data: &ResolvedDomainData, tag: &AddressTag, source: &str) -> Value {
+    json!({
+        "kind": data.kind(),
+        "value": data.value_string(),
+        "contentType": data.content_type(),
+        "tag": tag.as_str(),
+        "source": source,
+    })
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
@@ -0,0 +1,423 @@
+//! Tower-like layers adding cross-cutting behavior (caching, retries, metrics, policies)
+//! to any `Resolver`, e.g. to resolvers registered with `DomainResolverBuilder::with_resolver`.
+//!
+//! ```
+//! use std::time::Duration;
+//! use web3_resolver::{CacheLayer, MetricsLayer, Resolver, ResolverExt, RetryLayer};
+//! use web3_resolver::models::{AddressTag, ResolvedDomainData};
+//!
+//! struct MyResolver;
+//!
+//! #[async_trait::async_trait]
+//! impl Resolver for MyResolver {
+//!     async fn resolve(&self, domain: &str) -> anyhow::Result<(ResolvedDomainData, AddressTag)> {
+//!         Ok((ResolvedDomainData::DomainString(domain.to_string()), AddressTag::NonWeb3))
+//!     }
+//! }
+//!
+//! let resolver = MyResolver
+//!     .layer(RetryLayer::new(3, Duration::from_millis(200)))
+//!     .layer(CacheLayer::new(Duration::from_secs(60)))
+//!     .layer(MetricsLayer);
+//! ```
+//! Layers wrap in the order they are applied, so the last layer is the outermost one.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use anyhow::Result;
+use async_trait::async_trait;
+use log::debug;
+use crate::error::ResolverError;
+use crate::models::{AddressTag, CoinType, ResolutionCandidate, ResolvedDomainData, ResolverCapabilities, ResolverStats};
+use crate::resolver::expiry::{CacheExpiry, ExpiringCache, Lookup};
+use crate::resolver::normalize;
+use crate::resolver::policy::{self, ResolutionPolicy};
+use crate::resolver::stats::StatsCollector;
+use crate::resolver::trace::Trace;
+use crate::resolver::Resolver;
+
+/// Wraps a resolver into another one, like `tower::Layer`.
+pub trait ResolverLayer<R> {
+    type Resolver: Resolver;
+
+    fn layer(&self, inner: R) -> Self::Resolver;
+}
+
+/// Composes layers on any resolver: `resolver.layer(CacheLayer::new(ttl))`.
+pub trait ResolverExt: Resolver + Sized {
+    fn layer<L: ResolverLayer<Self>>(self, layer: L) -> L::Resolver {
+        layer.layer(self)
+    }
+}
+
+impl<R: Resolver + Sized> ResolverExt for R {}
+
+/// Caches successful resolutions for the given time-to-live. Onchain content is not cached.
+#[derive(Clone, Copy)]
+pub struct CacheLayer {
+    ttl: Duration,
+    expiry: CacheExpiry,
+}
+
+impl CacheLayer {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            expiry: CacheExpiry::default(),
+        }
+    }
+
+    /// Sets TTL jitter and early refresh of the entries, see `CacheExpiry`.
+    pub fn with_expiry(self, expiry: CacheExpiry) -> Self {
+        Self {
+            expiry,
+            ..self
+        }
+    }
+}
+
+impl<R: Resolver + Send + Sync> ResolverLayer<R> for CacheLayer {
+    type Resolver = CachedResolver<R>;
+
+    fn layer(&self, inner: R) -> Self::Resolver {
+        CachedResolver {
+            inner,
+            cache: ExpiringCache::new(self.ttl, self.expiry),
+        }
+    }
+}
+
+pub struct CachedResolver<R> {
+    inner: R,
+    cache: ExpiringCache<(ResolvedDomainData, AddressTag)>,
+}
+
+impl<R> CachedResolver<R> {
+    /// Removes the domain from the cache.
+    pub fn invalidate(&self, domain: &str) {
+        self.cache.invalidate(&normalize::cache_key(domain));
+    }
+}
+
+#[async_trait]
+impl<R: Resolver + Send + Sync> Resolver for CachedResolver<R> {
+    async fn resolve(&self, domain: &str) -> Result<(ResolvedDomainData, AddressTag)> {
+        let key = normalize::cache_key(domain);
+        let cached = match self.cache.lookup(&key) {
+            Lookup::Hit(result) => {
+                debug!("Host {} is taken from {} cache", domain, self.inner.name());
+                return Ok(result);
+            },
+            Lookup::Refresh(result) => Some(result),
+            Lookup::Miss => None,
+        };
+        let (data, tag) = match (self.inner.resolve(domain).await, cached) {
+            (Ok(result), _) => result,
+            (Err(e), Some(result)) => {
+                debug!("Early refresh of host {} failed: {}, {} cache is used", domain, e, self.inner.name());
+                return Ok(result);
+            },
+            (Err(e), None) => return Err(e),
+        };
+        if !tag.is_onchain() {
+            self.cache.insert(key, (data.clone(), tag));
+        }
+        Ok((data, tag))
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn capabilities(&self) -> ResolverCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn resolve_address(&self, domain: &str, coin: CoinType) -> Result<Option<String>> {
+        self.inner.resolve_address(domain, coin).await
+    }
+
+    async fn reverse(&self, address: &str, chain_hint: Option<CoinType>) -> Result<Option<String>> {
+        self.inner.reverse(address, chain_hint).await
+    }
+
+    async fn resolve_all(&self, domain: &str) -> Result<Vec<ResolutionCandidate>> {
+        self.inner.resolve_all(domain).await
+    }
+}
+
+/// Retries failed resolutions with linearly growing backoff.
+/// Typed `ResolverError`s (blocked domains, ABI mismatches etc.) are not retried unless they are retryable,
+/// rate limited attempts wait at least the `Retry-After` of the upstream.
+#[derive(Clone, Copy)]
+pub struct RetryLayer {
+    retries: u32,
+    backoff: Duration,
+}
+
+impl RetryLayer {
+    pub fn new(retries: u32, backoff: Duration) -> Self {
+        Self { retries, backoff }
+    }
+}
+
+impl<R: Resolver + Send + Sync> ResolverLayer<R> for RetryLayer {
+    type Resolver = RetryResolver<R>;
+
+    fn layer(&self, inner: R) -> Self::Resolver {
+        RetryResolver {
+            inner,
+            retries: self.retries,
+            backoff: self.backoff,
+        }
+    }
+}
+
+pub struct RetryResolver<R> {
+    inner: R,
+    retries: u32,
+    backoff: Duration,
+}
+
+impl<R: Resolver + Send + Sync> RetryResolver<R> {
+    /// Waits before the next attempt if the error is worth retrying.
+    async fn should_retry(&self, domain: &str, attempt: u32, error: &anyhow::Error) -> bool {
+        let typed = error.downcast_ref::<ResolverError>();
+        if attempt >= self.retries || typed.is_some_and(|typed| !typed.is_retryable()) {
+            return false;
+        }
+        debug!("Attempt {} to resolve {} with {} failed: {}", attempt + 1, domain, self.inner.name(), error);
+        let backoff = match typed {
+            Some(ResolverError::RateLimited { retry_after: Some(retry_after), .. }) => (*retry_after).max(self.backoff * (attempt + 1)),
+            _ => self.backoff * (attempt + 1),
+        };
+        tokio::time::sleep(backoff).await;
+        true
+    }
+}
+
+#[async_trait]
+impl<R: Resolver + Send + Sync> Resolver for RetryResolver<R> {
+    async fn resolve(&self, domain: &str) -> Result<(ResolvedDomainData, AddressTag)> {
+        let mut attempt = 0;
+        loop {
+            match self.inner.resolve(domain).await {
+                Err(e) if self.should_retry(domain, attempt, &e).await => attempt += 1,
+                result => return result,
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn capabilities(&self) -> ResolverCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn resolve_address(&self, domain: &str, coin: CoinType) -> Result<Option<String>> {
+        self.inner.resolve_address(domain, coin).await
+    }
+
+    async fn reverse(&self, address: &str, chain_hint: Option<CoinType>) -> Result<Option<String>> {
+        self.inner.reverse(address, chain_hint).await
+    }
+
+    async fn resolve_all(&self, domain: &str) -> Result<Vec<ResolutionCandidate>> {
+        let mut attempt = 0;
+        loop {
+            match self.inner.resolve_all(domain).await {
+                Err(e) if self.should_retry(domain, attempt, &e).await => attempt += 1,
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Collects the same rolling statistics as `Web3DomainResolver::stats` for the wrapped resolver.
+#[derive(Clone, Copy, Default)]
+pub struct MetricsLayer;
+
+impl<R: Resolver + Send + Sync> ResolverLayer<R> for MetricsLayer {
+    type Resolver = MetricsResolver<R>;
+
+    fn layer(&self, inner: R) -> Self::Resolver {
+        MetricsResolver {
+            inner,
+            stats: StatsCollector::default(),
+        }
+    }
+}
+
+pub struct MetricsResolver<R> {
+    inner: R,
+    stats: StatsCollector,
+}
+
+impl<R> MetricsResolver<R> {
+    pub fn stats(&self) -> ResolverStats {
+        self.stats.snapshot()
+    }
+}
+
+#[async_trait]
+impl<R: Resolver + Send + Sync> Resolver for MetricsResolver<R> {
+    async fn resolve(&self, domain: &str) -> Result<(ResolvedDomainData, AddressTag)> {
+        let started = Instant::now();
+        let result = self.inner.resolve(domain).await;
+        self.stats.record(&normalize::domain_name(domain), self.inner.name(), result.is_ok(), started.elapsed());
+        result
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn capabilities(&self) -> ResolverCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn resolve_address(&self, domain: &str, coin: CoinType) -> Result<Option<String>> {
+        self.inner.resolve_address(domain, coin).await
+    }
+
+    async fn reverse(&self, address: &str, chain_hint: Option<CoinType>) -> Result<Option<String>> {
+        self.inner.reverse(address, chain_hint).await
+    }
+
+    async fn resolve_all(&self, domain: &str) -> Result<Vec<ResolutionCandidate>> {
+        let started = Instant::now();
+        let result = self.inner.resolve_all(domain).await;
+        self.stats.record(&normalize::domain_name(domain), self.inner.name(), result.is_ok(), started.elapsed());
+        result
+    }
+}
+
+/// Applies resolution policies before and after the wrapped resolver,
+/// the same way `DomainResolverBuilder::with_policy` does for `Web3DomainResolver`.
+#[derive(Clone, Default)]
+pub struct PolicyLayer {
+    policies: Vec<Arc<dyn ResolutionPolicy>>,
+}
+
+impl PolicyLayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_policy<P: ResolutionPolicy + 'static>(mut self, policy: P) -> Self {
+        self.policies.push(Arc::new(policy));
+        self
+    }
+}
+
+impl<R: Resolver + Send + Sync> ResolverLayer<R> for PolicyLayer {
+    type Resolver = PolicyResolver<R>;
+
+    fn layer(&self, inner: R) -> Self::Resolver {
+        PolicyResolver {
+            inner,
+            policies: self.policies.clone(),
+        }
+    }
+}
+
+pub struct PolicyResolver<R> {
+    inner: R,
+    policies: Vec<Arc<dyn ResolutionPolicy>>,
+}
+
+#[async_trait]
+impl<R: Resolver + Send + Sync> Resolver for PolicyResolver<R> {
+    async fn resolve(&self, domain: &str) -> Result<(ResolvedDomainData, AddressTag)> {
+        let trace = &mut Trace::disabled();
+        if let Some(result) = policy::apply_before(&self.policies, domain, trace)? {
+            return Ok(result);
+        }
+        let (data, tag) = self.inner.resolve(domain).await?;
+        policy::apply_after(&self.policies, domain, data, tag, trace)
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn capabilities(&self) -> ResolverCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn resolve_address(&self, domain: &str, coin: CoinType) -> Result<Option<String>> {
+        if !policy::allows_lookup(&self.policies, domain)? {
+            return Ok(None);
+        }
+        self.inner.resolve_address(domain, coin).await
+    }
+
+    async fn reverse(&self, address: &str, chain_hint: Option<CoinType>) -> Result<Option<String>> {
+        // domains blocked or rewritten by a policy are not reported
+        let domain = self.inner.reverse(address, chain_hint).await?;
+        Ok(domain.filter(|domain| matches!(policy::allows_lookup(&self.policies, domain), Ok(true))))
+    }
+
+    async fn resolve_all(&self, domain: &str) -> Result<Vec<ResolutionCandidate>> {
+        let trace = &mut Trace::disabled();
+        if let Some((data, tag)) = policy::apply_before(&self.policies, domain, trace)? {
+            return Ok(vec![ResolutionCandidate { source: "policy".to_string(), data, tag }]);
+        }
+        // candidates blocked by a policy are dropped, the domain is blocked only if none is left
+        let mut allowed = Vec::new();
+        let mut blocked = None;
+        for candidate in self.inner.resolve_all(domain).await? {
+            match policy::apply_after(&self.policies, domain, candidate.data, candidate.tag, trace) {
+                Ok((data, tag)) => allowed.push(ResolutionCandidate { source: candidate.source, data, tag }),
+                Err(e) => blocked = Some(e),
+            }
+        }
+        match (allowed.is_empty(), blocked) {
+            (true, Some(e)) => Err(e),
+            _ => Ok(allowed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolver::policy::{ListPolicy, PolicyVerdict};
+
+    struct ReverseResolver(&'static str);
+
+    #[async_trait]
+    impl Resolver for ReverseResolver {
+        async fn resolve(&self, domain: &str) -> Result<(ResolvedDomainData, AddressTag)> {
+            Ok((ResolvedDomainData::DomainString(domain.to_string()), AddressTag::NonWeb3))
+        }
+
+        async fn reverse(&self, _address: &str, _chain_hint: Option<CoinType>) -> Result<Option<String>> {
+            Ok(Some(self.0.to_string()))
+        }
+    }
+
+    struct RewritePolicy;
+
+    impl ResolutionPolicy for RewritePolicy {
+        fn before_resolve(&self, domain: &str) -> PolicyVerdict {
+            match domain {
+                "parked.eth" => PolicyVerdict::Rewrite(ResolvedDomainData::DomainString("https://parking.example/".to_string()), AddressTag::Web2),
+                _ => PolicyVerdict::Allow,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn reverse_skips_blocked_and_rewritten_domains() {
+        let layer = PolicyLayer::new()
+            .with_policy(ListPolicy::new().block_domain("*.scam.eth"))
+            .with_policy(RewritePolicy);
+        let reverse = |domain| layer.layer(ReverseResolver(domain));
+        assert_eq!(reverse("vitalik.eth").reverse("0xd8da", None).await.unwrap().as_deref(), Some("vitalik.eth"));
+        assert_eq!(reverse("wallet.scam.eth").reverse("0xd8da", None).await.unwrap(), None);
+        assert_eq!(reverse("parked.eth").reverse("0xd8da", None).await.unwrap(), None);
+    }
+}
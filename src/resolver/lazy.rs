@@ -0,0 +1,47 @@
+use anyhow::{anyhow, Result};
+use futures::future::BoxFuture;
+use tokio::sync::OnceCell;
+
+type InitFn<T> = Box<dyn Fn() -> BoxFuture<'static, Result<T>> + Send + Sync>;
+
+/// Value initialized on first use. Failed initialization is retried on the next use.
+pub(crate) struct Lazy<T> {
+    cell: OnceCell<T>,
+    init: Option<InitFn<T>>,
+}
+
+impl<T> Lazy<T> {
+    /// Already initialized value.
+    pub fn ready(value: T) -> Self {
+        Self {
+            cell: OnceCell::new_with(Some(value)),
+            init: None,
+        }
+    }
+
+    /// Value initialized by the given function on first use.
+    pub fn new<F>(init: F) -> Self
+    where
+        F: Fn() -> BoxFuture<'static, Result<T>> + Send + Sync + 'static,
+    {
+        Self {
+            cell: OnceCell::new(),
+            init: Some(Box::new(init)),
+        }
+    }
+
+    /// Returns the value if it is initialized.
+    pub fn get(&self) -> Option<&T> {
+        self.cell.get()
+    }
+
+    /// Returns the value, initializing it if needed.
+    pub async fn force(&self) -> Result<&T> {
+        self.cell.get_or_try_init(|| async {
+            match &self.init {
+                Some(init) => init().await,
+                None => Err(anyhow!("Lazy value has no initializer")),
+            }
+        }).await
+    }
+}
@@ -0,0 +1,45 @@
+use std::sync::Arc;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// Bound on in-flight upstream calls: Everscale JRPC, Unstoppable Domains HTTP and Ethereum JSON-RPC requests.
+/// Clones share the permits, so one limit can cover all resolvers of a process and a burst of cache misses
+/// waits for permits instead of opening thousands of simultaneous connections.
+#[derive(Clone, Debug)]
+pub struct InFlightLimit {
+    semaphore: Arc<Semaphore>,
+    max_in_flight: usize,
+}
+
+impl InFlightLimit {
+    /// Creates limit of the given number of simultaneous calls, at least one.
+    pub fn new(max_in_flight: usize) -> Self {
+        let max_in_flight = max_in_flight.max(1);
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_in_flight)),
+            max_in_flight,
+        }
+    }
+
+    pub fn max_in_flight(&self) -> usize {
+        self.max_in_flight
+    }
+
+    /// Number of calls holding permits right now.
+    pub fn in_flight(&self) -> usize {
+        self.max_in_flight - self.semaphore.available_permits()
+    }
+
+    /// Waits for a permit, the call is in flight until the permit is dropped.
+    pub(crate) async fn acquire(&self) -> Option<SemaphorePermit<'_>> {
+        // the semaphore is never closed
+        self.semaphore.acquire().await.ok()
+    }
+}
+
+/// Waits for a permit of the limit if it is set.
+pub(crate) async fn permit(limit: &Option<InFlightLimit>) -> Option<SemaphorePermit<'_>> {
+    match limit {
+        Some(limit) => limit.acquire().await,
+        None => None,
+    }
+}
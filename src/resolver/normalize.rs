@@ -0,0 +1,56 @@
+/// Cache key of the domain: lowercase ASCII (punycode) form without trailing dot,
+/// so `Site.ever`, `site.ever.` and `site.ever` share a single cache slot.
+pub(crate) fn cache_key(domain: &str) -> String {
+    let domain = domain.trim().trim_end_matches('.');
+    idna::domain_to_ascii(domain).unwrap_or_else(|_| domain.to_lowercase())
+}
+
+/// Name of the domain passed to resolvers: lowercase unicode form without trailing dot.
+pub(crate) fn domain_name(domain: &str) -> String {
+    let key = cache_key(domain);
+    match idna::domain_to_unicode(&key) {
+        (name, Ok(())) => name,
+        (_, Err(_)) => key,
+    }
+}
+
+/// Domain name of the record value if it is a bare domain, e.g. `site.crypto`,
+/// `None` for URLs, IP addresses and other values.
+pub(crate) fn alias_domain(value: &str) -> Option<String> {
+    let value = value.trim().trim_end_matches('.');
+    if !value.contains('.') || value.contains(|c: char| c.is_whitespace() || matches!(c, '/' | ':' | '@' | '?' | '#')) {
+        return None;
+    }
+    if value.parse::<std::net::IpAddr>().is_ok() {
+        return None;
+    }
+    Some(domain_name(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_keys_share_slots() {
+        assert_eq!(cache_key("Site.ever"), "site.ever");
+        assert_eq!(cache_key(" site.ever. "), "site.ever");
+        assert_eq!(cache_key("пример.ever"), "xn--e1afmkfd.ever");
+        assert_eq!(cache_key("ПРИМЕР.ever"), cache_key("пример.ever"));
+    }
+
+    #[test]
+    fn domain_names_are_unicode() {
+        assert_eq!(domain_name("xn--e1afmkfd.ever"), "пример.ever");
+        assert_eq!(domain_name("Site.EVER."), "site.ever");
+    }
+
+    #[test]
+    fn alias_domains() {
+        assert_eq!(alias_domain("Site.Crypto."), Some("site.crypto".to_string()));
+        assert_eq!(alias_domain("https://site.com"), None);
+        assert_eq!(alias_domain("127.0.0.1"), None);
+        assert_eq!(alias_domain("localhost"), None);
+        assert_eq!(alias_domain("user@site.com"), None);
+    }
+}
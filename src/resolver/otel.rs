@@ -0,0 +1,55 @@
+use std::time::SystemTime;
+use anyhow::Result;
+use opentelemetry::trace::{Span, Status, Tracer};
+use opentelemetry::{global, KeyValue};
+use crate::models::ResolutionReport;
+
+/// Instrumentation scope of the spans.
+pub const SCOPE: &str = "web3-resolver";
+/// Name of the resolution span.
+pub const RESOLVE_SPAN: &str = "web3.resolve";
+/// Name of the event of a resolution trace step.
+pub const STEP_EVENT: &str = "web3.resolution.step";
+
+/// Domain being resolved, in its lowercase unicode form.
+pub const DOMAIN: &str = "web3.domain";
+/// Name of the resolver produced the result, e.g. `evername` or `unstoppable-domains`.
+pub const RESOLVER: &str = "web3.resolver";
+/// Address tag of the result, e.g. `ipfs` or `web2-url`.
+pub const TAG: &str = "web3.tag";
+/// Kind of the result, e.g. `domain-string` or `dns-records`.
+pub const KIND: &str = "web3.kind";
+/// Result was taken from a cache.
+pub const FROM_CACHE: &str = "web3.from_cache";
+/// Result is a stale one served because of the exceeded resolution budget.
+pub const STALE: &str = "web3.stale";
+/// Message of a trace step event.
+pub const STEP: &str = "web3.step";
+
+/// Exports the resolution as a span of the globally installed tracer provider, e.g. an OTLP one,
+/// with trace steps as events. Without an installed provider the span is a no-op.
+pub(crate) fn export_resolution(domain: &str, source: &str, started: SystemTime, result: &Result<ResolutionReport>) {
+    let tracer = global::tracer(SCOPE);
+    let mut span = tracer.span_builder(RESOLVE_SPAN)
+        .with_start_time(started)
+        .with_attributes(vec![
+            KeyValue::new(DOMAIN, domain.to_string()),
+            KeyValue::new(RESOLVER, source.to_string()),
+        ])
+        .start(&tracer);
+    match result {
+        Ok(report) => {
+            span.set_attributes([
+                KeyValue::new(TAG, report.tag.to_string()),
+                KeyValue::new(KIND, report.data.kind()),
+                KeyValue::new(FROM_CACHE, report.from_cache),
+                KeyValue::new(STALE, report.stale),
+            ]);
+            for step in &report.trace {
+                span.add_event(STEP_EVENT, vec![KeyValue::new(STEP, step.clone())]);
+            }
+        },
+        Err(e) => span.set_status(Status::error(format!("{:#}", e))),
+    }
+    span.end();
+}
@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use crate::models::{AddressTag, ResolvedDomainData};
+use crate::resolver::normalize;
+
+/// Local override of a domain resolution.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OverrideEntry {
+    /// Domain always resolves into the given data and tag
+    Pin(ResolvedDomainData, AddressTag),
+    /// Domain resolution is refused with the given reason
+    Block(String),
+}
+
+/// Hosts-file like table of per-domain overrides consulted before any resolver,
+/// e.g. to pin staging sites or block abusive domains without touching registries.
+///
+/// Can be loaded from TOML:
+/// ```toml
+/// [[pin]]
+/// domain = "staging.chums.ever"
+/// target = "https://staging.chums.chat"
+/// tag = "web2" # tor, ipfs, web2, onchain or non-web3; web2 by default
+///
+/// [[block]]
+/// domain = "scam.ever"
+/// reason = "phishing"
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct DomainOverrides {
+    entries: HashMap<String, OverrideEntry>,
+}
+
+#[derive(Deserialize)]
+struct OverridesFile {
+    #[serde(default)]
+    pin: Vec<PinEntry>,
+    #[serde(default)]
+    block: Vec<BlockEntry>,
+}
+
+#[derive(Deserialize)]
+struct PinEntry {
+    domain: String,
+    target: String,
+    tag: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct BlockEntry {
+    domain: String,
+    reason: Option<String>,
+}
+
+impl DomainOverrides {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses overrides table from TOML string. Later entries of a domain replace earlier ones,
+    /// blocks replace pins of the same domain.
+    pub fn from_toml(toml: &str) -> Result<Self> {
+        let file: OverridesFile = toml::from_str(toml).context("Malformed overrides TOML")?;
+        let mut overrides = Self::new();
+        for pin in file.pin {
+            let tag = match pin.tag {
+                Some(tag) => AddressTag::from_str(&tag)
+                    .with_context(|| format!("Invalid tag of pinned domain {}", pin.domain))?,
+                None => AddressTag::Web2,
+            };
+            let data = match tag {
+                AddressTag::Onchain => ResolvedDomainData::OnchainData(pin.target),
+                _ => ResolvedDomainData::DomainString(pin.target),
+            };
+            overrides = overrides.pin(&pin.domain, data, tag);
+        }
+        for block in file.block {
+            overrides = overrides.block(&block.domain, &block.reason.unwrap_or_else(|| "blocked by operator".to_string()));
+        }
+        Ok(overrides)
+    }
+
+    /// Loads overrides table from TOML file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let toml = std::fs::read_to_string(path.as_ref())
+            .with_context(|| format!("Failed to read overrides file {}", path.as_ref().display()))?;
+        Self::from_toml(&toml)
+    }
+
+    pub fn pin(mut self, domain: &str, data: ResolvedDomainData, tag: AddressTag) -> Self {
+        self.entries.insert(normalize::cache_key(domain), OverrideEntry::Pin(data, tag));
+        self
+    }
+
+    pub fn block(mut self, domain: &str, reason: &str) -> Self {
+        self.entries.insert(normalize::cache_key(domain), OverrideEntry::Block(reason.to_string()));
+        self
+    }
+
+    /// Adds all entries of other table, replacing existing ones.
+    pub fn extend(&mut self, other: DomainOverrides) {
+        self.entries.extend(other.entries);
+    }
+
+    pub fn get(&self, domain: &str) -> Option<&OverrideEntry> {
+        self.entries.get(&normalize::cache_key(domain))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toml_entries() {
+        let overrides = DomainOverrides::from_toml(r#"
+            [[pin]]
+            domain = "staging.chums.ever"
+            target = "https://staging.chums.chat"
+
+            [[pin]]
+            domain = "docs.chums.ever"
+            target = "bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi"
+            tag = "ipfs"
+
+            [[pin]]
+            domain = "hello.ever"
+            target = "<h1>Hello</h1>"
+            tag = "onchain"
+
+            [[block]]
+            domain = "scam.ever"
+            reason = "phishing"
+
+            [[block]]
+            domain = "spam.ever"
+        "#).unwrap();
+        assert_eq!(overrides.get("staging.chums.ever"), Some(&OverrideEntry::Pin(
+            ResolvedDomainData::DomainString("https://staging.chums.chat".to_string()), AddressTag::Web2)));
+        assert!(matches!(overrides.get("docs.chums.ever"), Some(OverrideEntry::Pin(ResolvedDomainData::DomainString(_), AddressTag::Ipfs))));
+        assert_eq!(overrides.get("hello.ever"), Some(&OverrideEntry::Pin(
+            ResolvedDomainData::OnchainData("<h1>Hello</h1>".to_string()), AddressTag::Onchain)));
+        assert_eq!(overrides.get("scam.ever"), Some(&OverrideEntry::Block("phishing".to_string())));
+        assert_eq!(overrides.get("spam.ever"), Some(&OverrideEntry::Block("blocked by operator".to_string())));
+        assert_eq!(overrides.get("chums.ever"), None);
+    }
+
+    #[test]
+    fn malformed_toml() {
+        let bad_tag = DomainOverrides::from_toml("[[pin]]\ndomain = \"a.ever\"\ntarget = \"x\"\ntag = \"bogus\"").unwrap_err();
+        assert!(format!("{:#}", bad_tag).contains("a.ever"));
+        assert!(DomainOverrides::from_toml("[[pin]]\ndomain = \"a.ever\"").is_err());
+        assert!(DomainOverrides::from_toml("[[block]]\nreason = \"spam\"").is_err());
+        assert!(DomainOverrides::from_toml("pin = 1").is_err());
+        assert!(DomainOverrides::from_toml("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn duplicate_domains() {
+        let overrides = DomainOverrides::from_toml(r#"
+            [[block]]
+            domain = "site.ever"
+
+            [[pin]]
+            domain = "site.ever"
+            target = "https://a.example"
+
+            [[pin]]
+            domain = "other.ever"
+            target = "https://a.example"
+
+            [[pin]]
+            domain = "Other.EVER"
+            target = "https://b.example"
+        "#).unwrap();
+        assert!(matches!(overrides.get("site.ever"), Some(OverrideEntry::Block(_))));
+        assert_eq!(overrides.get("other.ever"), Some(&OverrideEntry::Pin(
+            ResolvedDomainData::DomainString("https://b.example".to_string()), AddressTag::Web2)));
+        let mut merged = DomainOverrides::new().block("other.ever", "spam");
+        merged.extend(overrides);
+        assert!(matches!(merged.get("other.ever"), Some(OverrideEntry::Pin(..))));
+    }
+
+    #[test]
+    fn cache_key_normalization() {
+        let overrides = DomainOverrides::new()
+            .block("Scam.EVER.", "phishing")
+            .pin("пример.ever", ResolvedDomainData::DomainString("https://example.com".to_string()), AddressTag::Web2);
+        for domain in ["scam.ever", "SCAM.ever", "scam.ever.", " scam.ever "] {
+            assert!(matches!(overrides.get(domain), Some(OverrideEntry::Block(_))), "{}", domain);
+        }
+        assert!(overrides.get("xn--e1afmkfd.ever").is_some());
+        assert!(overrides.get("ПРИМЕР.ever").is_some());
+    }
+}
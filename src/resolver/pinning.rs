@@ -0,0 +1,89 @@
+use std::time::{Duration, Instant};
+use mini_moka::sync::Cache;
+use crate::models::{AddressTag, ResolvedDomainData};
+use crate::resolver::ipfs::split_gateway_link;
+
+/// Results of domains resolved into immutable targets (IPFS CIDs, onion addresses), kept beyond
+/// the resolution cache TTL. The content behind such targets never changes, so the domain only has to be
+/// re-resolved once per recheck interval to detect the record re-pointing to another target.
+pub(crate) struct PinnedTargets {
+    recheck: Duration,
+    // results with their sources and the time of the last re-pointing check by cache key
+    results: Cache<String, (ResolvedDomainData, AddressTag, String, Instant)>,
+}
+
+impl PinnedTargets {
+    pub fn new(max_pin: Duration, recheck: Duration) -> Self {
+        Self {
+            recheck,
+            results: Cache::builder().time_to_live(max_pin).build(),
+        }
+    }
+
+    /// Pinned result, `None` if there is none or it is due for the re-pointing check.
+    pub fn get(&self, key: &String) -> Option<(ResolvedDomainData, AddressTag, String)> {
+        self.results.get(key)
+            .filter(|(_, _, _, checked_at)| checked_at.elapsed() < self.recheck)
+            .map(|(data, tag, source, _)| (data, tag, source))
+    }
+
+    /// Pins the result if its target is immutable, otherwise unpins the domain.
+    /// Returns `true` if the domain was pinned to a different target before, i.e. its record is re-pointed.
+    pub fn update(&self, key: String, data: &ResolvedDomainData, tag: AddressTag, source: &str) -> bool {
+        let repointed = self.results.get(&key).is_some_and(|(pinned, pinned_tag, _, _)| pinned != *data || pinned_tag != tag);
+        if is_immutable(data, &tag) {
+            self.results.insert(key, (data.clone(), tag, source.to_string(), Instant::now()));
+        } else {
+            self.results.invalidate(&key);
+        }
+        repointed
+    }
+
+    pub fn invalidate(&self, key: &String) {
+        self.results.invalidate(key);
+    }
+}
+
+/// Checks whether the target is content addressed: IPFS targets whose path or subdomain is a CID
+/// and Tor targets on a v3 onion address. IPNS names and targets merely looking like gateway links are not.
+pub(crate) fn is_immutable(data: &ResolvedDomainData, tag: &AddressTag) -> bool {
+    let ResolvedDomainData::DomainString(target) = data else {
+        return false;
+    };
+    match tag {
+        AddressTag::Ipfs => is_cid(split_gateway_link(target).map_or(target.trim_end_matches('/'), |(cid, _)| cid)),
+        AddressTag::Tor => {
+            let host = url::Url::parse(target).ok()
+                .and_then(|url| url.host_str().map(|host| host.to_string()))
+                .unwrap_or_else(|| target.trim_end_matches('/').to_string());
+            host.strip_suffix(".onion")
+                .map(|host| host.rsplit('.').next().unwrap_or(host))
+                .is_some_and(is_onion_v3)
+        },
+        _ => false,
+    }
+}
+
+/// Checks whether the value is a CIDv0 (base58btc multihash) or a CIDv1 in base32, base58btc or base16 multibase.
+fn is_cid(value: &str) -> bool {
+    const BASE58: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+    let in_alphabet = |value: &str, alphabet: &str| value.chars().all(|char| alphabet.contains(char));
+    if value.len() == 46 && value.starts_with("Qm") {
+        return in_alphabet(value, BASE58);
+    }
+    // CIDv1 with a 32 bytes multihash is at least 36 bytes long
+    match value.split_at_checked(1) {
+        Some(("b", rest)) => rest.len() >= 58 && in_alphabet(rest, "abcdefghijklmnopqrstuvwxyz234567"),
+        Some(("B", rest)) => rest.len() >= 58 && in_alphabet(rest, "ABCDEFGHIJKLMNOPQRSTUVWXYZ234567"),
+        Some(("z", rest)) => rest.len() >= 49 && in_alphabet(rest, BASE58),
+        Some(("f", rest)) => rest.len() >= 72 && rest.chars().all(|char| char.is_ascii_hexdigit()),
+        _ => false,
+    }
+}
+
+/// Checks whether the label is a v3 onion address: 56 base32 characters of the key, checksum and version 3.
+fn is_onion_v3(label: &str) -> bool {
+    label.len() == 56
+        && label.ends_with('d')
+        && label.chars().all(|char| matches!(char, 'a'..='z' | '2'..='7'))
+}
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use anyhow::Result;
+use async_trait::async_trait;
+use crate::models::{AddressTag, ResolvedDomainData};
+use crate::resolver::trace::Trace;
+
+/// Built-in stages of the resolution pipeline of `Web3DomainResolver::resolve`, run in this order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Stage {
+    /// Normalizes the domain name: lowercase, no trailing dot
+    Normalize,
+    /// Applies overrides and policies, selects resolvers by the TLD of the domain
+    Dispatch,
+    /// Resolves the domain with the selected resolvers, which decode their records,
+    /// and checks the decoded result against the policies
+    Resolve,
+    /// Runs result rewriters
+    PostProcess,
+    /// Stores the result in the resolution cache
+    Cache,
+}
+
+impl Display for Stage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Stage::Normalize => "normalize",
+            Stage::Dispatch => "dispatch",
+            Stage::Resolve => "resolve",
+            Stage::PostProcess => "post-process",
+            Stage::Cache => "cache",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// State of a resolution passed through the pipeline stages.
+#[derive(Clone, Debug)]
+pub struct PipelineState {
+    /// Domain being resolved, normalized by the `Normalize` stage
+    pub domain: String,
+    /// Source names of the resolvers selected by the `Dispatch` stage, in precedence order
+    pub sources: Vec<String>,
+    /// Result with the source name of its resolver, set by the `Resolve` stage.
+    /// Stages running before it may set the result to skip the resolution.
+    pub result: Option<(ResolvedDomainData, AddressTag, String)>,
+    /// Domains followed by redirects and aliases to reach the result, starting with the requested domain,
+    /// set by the `Resolve` stage
+    pub chain: Vec<String>,
+}
+
+impl PipelineState {
+    pub(crate) fn new(domain: &str) -> Self {
+        Self {
+            domain: domain.to_string(),
+            sources: Vec::new(),
+            result: None,
+            chain: Vec::new(),
+        }
+    }
+}
+
+/// Custom stage of the resolution pipeline, inserted after a built-in stage or replacing it,
+/// see `DomainResolverBuilder::with_pipeline_stage`.
+///
+/// # Example
+/// ```no_run
+/// # async fn example(blocked_domains: std::collections::HashSet<String>) -> anyhow::Result<()> {
+/// use std::collections::HashSet;
+/// use anyhow::{anyhow, Result};
+/// use async_trait::async_trait;
+/// use web3_resolver::{DomainResolverBuilder, PipelineStage, PipelineState, Stage};
+///
+/// struct Blocklist(HashSet<String>);
+///
+/// #[async_trait]
+/// impl PipelineStage for Blocklist {
+///     fn name(&self) -> &str {
+///         "blocklist"
+///     }
+///
+///     async fn run(&self, state: &mut PipelineState) -> Result<()> {
+///         if self.0.contains(&state.domain) {
+///             return Err(anyhow!("{} is blocked", state.domain));
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// let domain_resolver = DomainResolverBuilder::default()
+///     .with_pipeline_stage(Stage::Normalize, Blocklist(blocked_domains))
+///     .build().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[async_trait]
+pub trait PipelineStage: Send + Sync {
+    /// Name of the stage, reported in explain traces.
+    fn name(&self) -> &str {
+        "stage"
+    }
+
+    /// Runs the stage on the resolution state, an error aborts the resolution.
+    async fn run(&self, state: &mut PipelineState) -> Result<()>;
+}
+
+/// Custom stages of the pipeline.
+#[derive(Clone, Default)]
+pub(crate) struct Pipeline {
+    replaced: HashMap<Stage, Arc<dyn PipelineStage>>,
+    // custom stages with the built-in stage they run after, in registration order
+    inserted: Vec<(Stage, Arc<dyn PipelineStage>)>,
+}
+
+impl Pipeline {
+    pub(crate) fn insert_after(&mut self, stage: Stage, custom: Arc<dyn PipelineStage>) {
+        self.inserted.push((stage, custom));
+    }
+
+    pub(crate) fn replace(&mut self, stage: Stage, custom: Arc<dyn PipelineStage>) {
+        self.replaced.insert(stage, custom);
+    }
+
+    pub(crate) fn replacement(&self, stage: Stage) -> Option<&Arc<dyn PipelineStage>> {
+        self.replaced.get(&stage)
+    }
+
+    /// Runs the custom stages inserted after the built-in one.
+    pub(crate) async fn run_inserted(&self, stage: Stage, state: &mut PipelineState, trace: &mut Trace) -> Result<()> {
+        for (_, custom) in self.inserted.iter().filter(|(after, _)| *after == stage) {
+            trace.push(|| format!("Pipeline: stage {} runs after {}", custom.name(), stage));
+            custom.run(state).await?;
+        }
+        Ok(())
+    }
+}
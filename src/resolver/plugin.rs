@@ -0,0 +1,212 @@
+use std::ffi::{c_char, CStr, CString};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use libloading::Library;
+use log::debug;
+use crate::models::{AddressTag, ResolvedDomainData, ResolverCapabilities};
+use crate::resolver::Resolver;
+
+/// Version of the plugin C ABI this crate understands.
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+type AbiVersionFn = unsafe extern "C" fn() -> u32;
+type TldsFn = unsafe extern "C" fn() -> *const c_char;
+type ResolveFn = unsafe extern "C" fn(domain: *const c_char, tag: *mut u32) -> *mut c_char;
+type FreeFn = unsafe extern "C" fn(value: *mut c_char);
+
+/// Resolver implementation loaded at runtime from a dynamic library.
+///
+/// A plugin library must export the following C symbols:
+/// ```c
+/// // Must return PLUGIN_ABI_VERSION
+/// uint32_t web3_resolver_plugin_abi_version(void);
+/// // Comma-separated list of handled TLDs, e.g. ".sol,.bit". Must have static lifetime.
+/// const char *web3_resolver_plugin_tlds(void);
+/// // Resolves domain, writes the record key of the address into `tag`: 1001 (Tor), 1002 (IPFS),
+/// // 1003 (web2), 1004 (onchain content) or 1007 (redirect).
+/// // Returns plugin-owned string or NULL if domain can not be resolved.
+/// char *web3_resolver_plugin_resolve(const char *domain, uint32_t *tag);
+/// // Frees string returned from `web3_resolver_plugin_resolve`
+/// void web3_resolver_plugin_free(char *value);
+/// ```
+/// Resolving is called on the blocking thread pool, so plugins may do blocking I/O.
+///
+/// Loading a plugin runs native code with the privileges of the process, only load trusted libraries.
+pub struct PluginResolver {
+    name: String,
+    path: PathBuf,
+    tlds: Vec<String>,
+    resolve_fn: ResolveFn,
+    free_fn: FreeFn,
+    // must outlive the function pointers above, also held by running resolutions
+    library: Arc<Library>,
+}
+
+impl PluginResolver {
+    /// Loads plugin from the dynamic library at the given path.
+    ///
+    /// # Safety
+    /// The library initializers and exported functions are called without any checks. The library
+    /// must be trusted, export the functions with the signatures above and be safe to call from any thread.
+    pub unsafe fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        // SAFETY: the caller guarantees the library is a trusted plugin
+        let library = unsafe { Library::new(&path) }
+            .with_context(|| format!("Failed to load plugin {}", path.display()))?;
+        let (abi_version, tlds_fn, resolve_fn, free_fn) = unsafe {
+            (
+                *library.get::<AbiVersionFn>(b"web3_resolver_plugin_abi_version\0")?,
+                *library.get::<TldsFn>(b"web3_resolver_plugin_tlds\0")?,
+                *library.get::<ResolveFn>(b"web3_resolver_plugin_resolve\0")?,
+                *library.get::<FreeFn>(b"web3_resolver_plugin_free\0")?,
+            )
+        };
+        let version = unsafe { abi_version() };
+        if version != PLUGIN_ABI_VERSION {
+            return Err(anyhow!("Plugin {} has unsupported ABI version {}, expected {}",
+                path.display(), version, PLUGIN_ABI_VERSION));
+        }
+        let tlds_ptr = unsafe { tlds_fn() };
+        if tlds_ptr.is_null() {
+            return Err(anyhow!("Plugin {} returned no TLDs", path.display()));
+        }
+        let tlds = parse_tlds(unsafe { CStr::from_ptr(tlds_ptr) }.to_str()?);
+        debug!("Loaded plugin {} with TLDs: {:?}", path.display(), tlds);
+        let name = path.file_stem()
+            .map(|stem| format!("plugin:{}", stem.to_string_lossy()))
+            .unwrap_or_else(|| "plugin".to_string());
+        Ok(Self {
+            name,
+            path,
+            tlds,
+            resolve_fn,
+            free_fn,
+            library: Arc::new(library),
+        })
+    }
+
+    /// Loads all plugins (`.so`, `.dylib`, `.dll` files) found in the given directory.
+    ///
+    /// # Safety
+    /// Every library in the directory is loaded, see `PluginResolver::load`.
+    pub unsafe fn discover<P: AsRef<Path>>(dir: P) -> Result<Vec<Self>> {
+        let mut plugins = Vec::new();
+        for entry in std::fs::read_dir(dir.as_ref())? {
+            let path = entry?.path();
+            let is_library = path.extension()
+                .and_then(|ext| ext.to_str())
+                .map(|ext| matches!(ext, "so" | "dylib" | "dll"))
+                .unwrap_or_default();
+            if path.is_file() && is_library {
+                plugins.push(Self::load(&path)?);
+            }
+        }
+        Ok(plugins)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn get_tlds(&self) -> Vec<String> {
+        self.tlds.clone()
+    }
+}
+
+#[async_trait]
+impl Resolver for PluginResolver {
+    async fn resolve(&self, domain: &str) -> Result<(ResolvedDomainData, AddressTag)> {
+        let domain_c = CString::new(domain)?;
+        let (resolve_fn, free_fn, library) = (self.resolve_fn, self.free_fn, self.library.clone());
+        // native code may block, so it does not run on the async worker threads
+        let resolved = tokio::task::spawn_blocking(move || {
+            let _library = library;
+            let mut tag_value: u32 = 0;
+            let value_ptr = unsafe { resolve_fn(domain_c.as_ptr(), &mut tag_value) };
+            if value_ptr.is_null() {
+                return None;
+            }
+            let value = unsafe { CStr::from_ptr(value_ptr) }.to_string_lossy().into_owned();
+            unsafe { free_fn(value_ptr) };
+            Some((value, tag_value))
+        }).await?;
+        let Some((value, tag_value)) = resolved else {
+            return Err(anyhow!("Plugin {} could not resolve domain {}", self.path.display(), domain));
+        };
+        domain_data(value, tag_value)
+            .with_context(|| format!("Plugin {} returned invalid result for domain {}", self.path.display(), domain))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn capabilities(&self) -> ResolverCapabilities {
+        ResolverCapabilities {
+            tlds: self.get_tlds(),
+            record_kinds: AddressTag::resolvable(),
+            ..ResolverCapabilities::default()
+        }
+    }
+}
+
+/// Splits the comma-separated TLDs of a plugin, adding missing leading dots.
+fn parse_tlds(tlds: &str) -> Vec<String> {
+    tlds.split(',')
+        .map(|tld| tld.trim())
+        .filter(|tld| !tld.is_empty())
+        .map(|tld| if tld.starts_with('.') { tld.to_string() } else { format!(".{}", tld) })
+        .collect()
+}
+
+/// Converts the value returned by a plugin with the record key it wrote into the result.
+fn domain_data(value: String, tag_value: u32) -> Result<(ResolvedDomainData, AddressTag)> {
+    let tag = AddressTag::from_record_key(tag_value as u128)
+        .ok_or_else(|| anyhow!("Unknown address tag {}", tag_value))?;
+    let domain_data = match tag {
+        AddressTag::Onchain => ResolvedDomainData::OnchainData(value),
+        AddressTag::OnchainContract => return Err(anyhow!("Plugins can not return onchain contract data")),
+        AddressTag::TlsCertificate => return Err(anyhow!("Plugins can not return TLS certificates")),
+        _ => ResolvedDomainData::DomainString(value),
+    };
+    Ok((domain_data, tag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tlds() {
+        assert_eq!(parse_tlds(".sol, bit,,.x "), vec![".sol", ".bit", ".x"]);
+        assert!(parse_tlds(" , ").is_empty());
+    }
+
+    #[test]
+    fn record_keys() {
+        let (data, tag) = domain_data("abc.onion".to_string(), 1001).unwrap();
+        assert_eq!((data, tag), (ResolvedDomainData::DomainString("abc.onion".to_string()), AddressTag::Tor));
+        let (data, tag) = domain_data("<h1>hi</h1>".to_string(), 1004).unwrap();
+        assert_eq!((data, tag), (ResolvedDomainData::OnchainData("<h1>hi</h1>".to_string()), AddressTag::Onchain));
+        assert_eq!(domain_data("other.ever".to_string(), 1007).unwrap().1, AddressTag::Redirect);
+        for tag_value in [0, 1005, 1006, 1008, AddressTag::NON_WEB3_ADDRESS_TAG as u32] {
+            assert!(domain_data("value".to_string(), tag_value).is_err(), "{}", tag_value);
+        }
+    }
+
+    #[test]
+    fn invalid_libraries() {
+        let dir = std::env::temp_dir().join(format!("web3-resolver-plugins-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("README.txt"), "not a plugin").unwrap();
+        // SAFETY: the directory holds no libraries, nothing is loaded
+        assert!(unsafe { PluginResolver::discover(&dir) }.unwrap().is_empty());
+        std::fs::write(dir.join("broken.so"), "not a library").unwrap();
+        // SAFETY: the file is not a library, loading fails before any code runs
+        assert!(unsafe { PluginResolver::discover(&dir) }.is_err());
+        assert!(unsafe { PluginResolver::load(dir.join("missing.so")) }.is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,312 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::Arc;
+use anyhow::Result;
+use url::{Host, Url};
+use crate::error::ResolverError;
+use crate::models::{AddressTag, ResolvedDomainData};
+use crate::resolver::trace::Trace;
+
+/// Decision of a resolution policy.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PolicyVerdict {
+    /// Resolution continues unchanged
+    Allow,
+    /// Resolution is refused with `ResolverError::Blocked` and the given reason
+    Block(String),
+    /// Result is replaced with the given data and tag
+    Rewrite(ResolvedDomainData, AddressTag),
+}
+
+/// Hook invoked before and after every resolution, so abuse and compliance policies
+/// can be enforced centrally in the resolver.
+///
+/// Policies run in registration order, the first non-`Allow` verdict before resolution
+/// short-circuits it. After resolution each policy sees the result rewritten by the previous ones.
+pub trait ResolutionPolicy: Send + Sync {
+    /// Name of the policy, reported in explain traces.
+    fn name(&self) -> &str {
+        "policy"
+    }
+
+    /// Called with the requested domain before any resolver is consulted.
+    fn before_resolve(&self, _domain: &str) -> PolicyVerdict {
+        PolicyVerdict::Allow
+    }
+
+    /// Called with the resolved data and tag of the domain.
+    fn after_resolve(&self, _domain: &str, _data: &ResolvedDomainData, _tag: &AddressTag) -> PolicyVerdict {
+        PolicyVerdict::Allow
+    }
+}
+
+/// Policy blocking domains by name patterns and results by target patterns.
+///
+/// Domain patterns are either exact names or `*.suffix` wildcards matching all subdomains,
+/// e.g. `*.scam.ever`. Target patterns match if the resolved address (URL, IPFS link, onion
+/// address) contains them, onchain content is never matched.
+/// If any allowed domain pattern is set, all other domains are blocked.
+#[derive(Clone, Default)]
+pub struct ListPolicy {
+    blocked_domains: Vec<String>,
+    allowed_domains: Vec<String>,
+    blocked_targets: Vec<String>,
+}
+
+impl ListPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn block_domain(mut self, pattern: &str) -> Self {
+        self.blocked_domains.push(pattern.to_lowercase());
+        self
+    }
+
+    pub fn allow_domain(mut self, pattern: &str) -> Self {
+        self.allowed_domains.push(pattern.to_lowercase());
+        self
+    }
+
+    pub fn block_target(mut self, pattern: &str) -> Self {
+        self.blocked_targets.push(pattern.to_string());
+        self
+    }
+}
+
+fn matches_domain(pattern: &str, domain: &str) -> bool {
+    match pattern.strip_prefix("*.") {
+        Some(suffix) => domain.len() > suffix.len()
+            && domain.ends_with(suffix)
+            && domain[..domain.len() - suffix.len()].ends_with('.'),
+        None => pattern == domain,
+    }
+}
+
+impl ResolutionPolicy for ListPolicy {
+    fn name(&self) -> &str {
+        "list"
+    }
+
+    fn before_resolve(&self, domain: &str) -> PolicyVerdict {
+        let domain = domain.to_lowercase();
+        if let Some(pattern) = self.blocked_domains.iter().find(|pattern| matches_domain(pattern, &domain)) {
+            return PolicyVerdict::Block(format!("domain matches blocked pattern {}", pattern));
+        }
+        if !self.allowed_domains.is_empty() && !self.allowed_domains.iter().any(|pattern| matches_domain(pattern, &domain)) {
+            return PolicyVerdict::Block("domain is not in the allowlist".to_string());
+        }
+        PolicyVerdict::Allow
+    }
+
+    fn after_resolve(&self, _domain: &str, data: &ResolvedDomainData, _tag: &AddressTag) -> PolicyVerdict {
+        let ResolvedDomainData::DomainString(target) = data else {
+            return PolicyVerdict::Allow;
+        };
+        match self.blocked_targets.iter().find(|pattern| target.contains(pattern.as_str())) {
+            Some(pattern) => PolicyVerdict::Block(format!("target matches blocked pattern {}", pattern)),
+            None => PolicyVerdict::Allow,
+        }
+    }
+}
+
+/// Policy protecting gateways from server-side request forgery by literal addresses: targets and redirect
+/// targets of any tag with loopback, private, link-local or otherwise internal IP literals (including IPv4-mapped
+/// IPv6 ones) or `localhost`, `*.localhost`, `*.local`, `*.internal` hosts are blocked, as well as URLs with schemes
+/// other than HTTP(S) and DNS fallback records with internal addresses. Only content-addressed IPFS and Tor
+/// targets are not checked.
+///
+/// Host names are not resolved, so a public name pointing to an internal address passes: gateways fetching
+/// targets must still check the addresses they connect to.
+#[derive(Clone, Copy, Default)]
+pub struct LiteralPrivateNetworkPolicy;
+
+impl LiteralPrivateNetworkPolicy {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl ResolutionPolicy for LiteralPrivateNetworkPolicy {
+    fn name(&self) -> &str {
+        "literal-private-network"
+    }
+
+    fn after_resolve(&self, _domain: &str, data: &ResolvedDomainData, tag: &AddressTag) -> PolicyVerdict {
+        if let ResolvedDomainData::DnsRecords(records) = data {
+            return match records.addresses.iter().find(|address| is_internal_ip(**address)) {
+                Some(address) => PolicyVerdict::Block(format!("{} resolves to internal address {}", records.name, address)),
+                None => PolicyVerdict::Allow,
+            };
+        }
+        if matches!(tag, AddressTag::Ipfs | AddressTag::Tor) {
+            return PolicyVerdict::Allow;
+        }
+        match data {
+            ResolvedDomainData::DomainString(target) => check_target(target),
+            ResolvedDomainData::Redirect(redirect) => check_target(&redirect.target),
+            _ => PolicyVerdict::Allow,
+        }
+    }
+}
+
+/// Blocks URLs and bare hosts with internal IP literals or names, or with schemes other than HTTP(S).
+fn check_target(target: &str) -> PolicyVerdict {
+    // targets are either URLs or bare hosts, optionally with port and path
+    let url = match target.contains("://") {
+        true => Url::parse(target),
+        false => Url::parse(&format!("http://{}", target)),
+    };
+    let Ok(url) = url else {
+        return PolicyVerdict::Block(format!("target {} is not a valid URL or host", target));
+    };
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return PolicyVerdict::Block(format!("scheme {} is not allowed", url.scheme()));
+    }
+    let internal = match url.host() {
+        Some(Host::Ipv4(ip)) => is_internal_ip(IpAddr::V4(ip)),
+        Some(Host::Ipv6(ip)) => is_internal_ip(IpAddr::V6(ip)),
+        Some(Host::Domain(host)) => is_internal_host(host),
+        None => true,
+    };
+    if internal {
+        return PolicyVerdict::Block(format!("target {} points to an internal address", target));
+    }
+    PolicyVerdict::Allow
+}
+
+fn is_internal_host(host: &str) -> bool {
+    let host = host.trim_end_matches('.').to_lowercase();
+    host == "localhost"
+        || [".localhost", ".local", ".internal"].iter().any(|suffix| host.ends_with(suffix))
+        || host.parse::<IpAddr>().is_ok_and(is_internal_ip)
+}
+
+fn is_internal_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_internal_ipv4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_internal_ipv4(ip),
+            None => is_internal_ipv6(ip),
+        },
+    }
+}
+
+fn is_internal_ipv4(ip: Ipv4Addr) -> bool {
+    let [first, second, ..] = ip.octets();
+    ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        // 0.0.0.0/8 and shared address space 100.64.0.0/10
+        || first == 0
+        || (first == 100 && (64..128).contains(&second))
+}
+
+fn is_internal_ipv6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        // unique local fc00::/7 and link-local fe80::/10
+        || (first & 0xfe00) == 0xfc00
+        || (first & 0xffc0) == 0xfe80
+}
+
+/// Runs policies before the resolution, returns rewritten result or blocking error if any.
+pub(crate) fn apply_before(policies: &[Arc<dyn ResolutionPolicy>], domain: &str, trace: &mut Trace) -> Result<Option<(ResolvedDomainData, AddressTag)>> {
+    for policy in policies {
+        match policy.before_resolve(domain) {
+            PolicyVerdict::Allow => {},
+            PolicyVerdict::Block(reason) => {
+                trace.push(|| format!("Policy {}: domain is blocked: {}", policy.name(), reason));
+                return Err(ResolverError::Blocked { domain: domain.to_string(), reason }.into());
+            },
+            PolicyVerdict::Rewrite(data, tag) => {
+                trace.push(|| format!("Policy {}: domain is rewritten into {} with tag {}", policy.name(), data, tag));
+                return Ok(Some((data, tag)));
+            },
+        }
+    }
+    Ok(None)
+}
+
+/// Runs policies before lookups other than resolution (addresses, TXT records, avatars): blocking error if the domain
+/// is blocked, `false` if a policy rewrites the domain, so its records are not looked up.
+pub(crate) fn allows_lookup(policies: &[Arc<dyn ResolutionPolicy>], domain: &str) -> Result<bool> {
+    Ok(apply_before(policies, domain, &mut Trace::disabled())?.is_none())
+}
+
+/// Runs policies on the resolved result, returns the result rewritten by them or blocking error.
+pub(crate) fn apply_after(policies: &[Arc<dyn ResolutionPolicy>], domain: &str, data: ResolvedDomainData, tag: AddressTag, trace: &mut Trace) -> Result<(ResolvedDomainData, AddressTag)> {
+    let mut result = (data, tag);
+    for policy in policies {
+        match policy.after_resolve(domain, &result.0, &result.1) {
+            PolicyVerdict::Allow => {},
+            PolicyVerdict::Block(reason) => {
+                trace.push(|| format!("Policy {}: result {} is blocked: {}", policy.name(), result.0, reason));
+                return Err(ResolverError::Blocked { domain: domain.to_string(), reason }.into());
+            },
+            PolicyVerdict::Rewrite(data, tag) => {
+                trace.push(|| format!("Policy {}: result is rewritten into {} with tag {}", policy.name(), data, tag));
+                result = (data, tag);
+            },
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DnsRecords;
+
+    fn verdict(target: &str, tag: AddressTag) -> PolicyVerdict {
+        LiteralPrivateNetworkPolicy.after_resolve("site.ever", &ResolvedDomainData::DomainString(target.to_string()), &tag)
+    }
+
+    fn is_blocked(target: &str) -> bool {
+        matches!(verdict(target, AddressTag::Web2), PolicyVerdict::Block(_))
+    }
+
+    #[test]
+    fn ipv4_literals() {
+        for target in ["http://10.0.0.1/", "169.254.169.254", "https://127.0.0.1:8080/admin", "192.168.1.1", "100.64.0.1", "0.0.0.0", "http://[::ffff:10.0.0.1]/"] {
+            assert!(is_blocked(target), "{}", target);
+        }
+        for target in ["https://1.1.1.1/", "8.8.8.8:53", "100.128.0.1"] {
+            assert!(!is_blocked(target), "{}", target);
+        }
+    }
+
+    #[test]
+    fn ipv6_literals() {
+        for target in ["http://[::1]/", "[fc00::1]", "https://[fe80::1]:443/", "http://[ff02::1]/", "http://[::ffff:127.0.0.1]/"] {
+            assert!(is_blocked(target), "{}", target);
+        }
+        for target in ["http://[2606:4700:4700::1111]/", "http://[::ffff:1.1.1.1]/"] {
+            assert!(!is_blocked(target), "{}", target);
+        }
+    }
+
+    #[test]
+    fn host_names() {
+        for target in ["localhost", "http://api.localhost/", "printer.local", "https://metadata.internal/", "LOCALHOST."] {
+            assert!(is_blocked(target), "{}", target);
+        }
+        // names are not resolved, only literal addresses are checked
+        assert!(!is_blocked("https://evil.example/"));
+        assert!(!is_blocked("chums.chat"));
+    }
+
+    #[test]
+    fn schemes_and_tags() {
+        assert!(is_blocked("ftp://example.com/"));
+        assert!(is_blocked("file:///etc/passwd"));
+        assert_eq!(verdict("http://127.0.0.1/", AddressTag::Ipfs), PolicyVerdict::Allow);
+        let records = DnsRecords { name: "example.com".to_string(), addresses: vec!["10.0.0.1".parse().unwrap()], ttl: Some(60) };
+        let verdict = LiteralPrivateNetworkPolicy.after_resolve("example.com", &ResolvedDomainData::DnsRecords(records), &AddressTag::NonWeb3);
+        assert!(matches!(verdict, PolicyVerdict::Block(_)));
+    }
+}
@@ -0,0 +1,113 @@
+use std::ops::Range;
+use anyhow::{anyhow, Result};
+use crate::error::ResolverError;
+
+/// Parses HTTP `Range` header value (`bytes=0-499`, `bytes=500-`, `bytes=-500`) into a byte range
+/// of the content with the given length. Only the first range of a multi-range request is used.
+pub(crate) fn parse_byte_range(header: &str, total_length: u64) -> Result<Range<u64>> {
+    let header = header.trim();
+    let spec = header.get(..6)
+        .filter(|unit| unit.eq_ignore_ascii_case("bytes="))
+        .map(|_| &header[6..])
+        .ok_or_else(|| anyhow!("Unsupported range header: {}", header))?;
+    let first = spec.split(',').next().unwrap_or_default().trim();
+    let (start, end) = first.split_once('-').ok_or_else(|| anyhow!("Malformed range: {}", first))?;
+    let not_satisfiable = || ResolverError::RangeNotSatisfiable { total_length };
+    let range = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().map_err(|_| anyhow!("Malformed range: {}", first))?;
+            if suffix == 0 || total_length == 0 {
+                return Err(not_satisfiable().into());
+            }
+            total_length.saturating_sub(suffix)..total_length
+        },
+        (start, end) => {
+            let start: u64 = start.parse().map_err(|_| anyhow!("Malformed range: {}", first))?;
+            let end = match end {
+                "" => total_length,
+                end => {
+                    let end: u64 = end.parse().map_err(|_| anyhow!("Malformed range: {}", first))?;
+                    if end < start {
+                        return Err(anyhow!("Malformed range: {}", first));
+                    }
+                    end.saturating_add(1).min(total_length)
+                },
+            };
+            if start >= total_length {
+                return Err(not_satisfiable().into());
+            }
+            start..end
+        },
+    };
+    Ok(range)
+}
+
+/// Copies the byte range of the content split into chunks, only chunks overlapping the range are touched.
+pub(crate) fn slice_chunks(chunks: &[String], range: &Range<u64>) -> Vec<u8> {
+    let mut data = Vec::with_capacity((range.end - range.start) as usize);
+    let mut offset = 0u64;
+    for chunk in chunks {
+        let chunk_end = offset + chunk.len() as u64;
+        if chunk_end > range.start && offset < range.end {
+            let from = range.start.saturating_sub(offset) as usize;
+            let to = (range.end.min(chunk_end) - offset) as usize;
+            data.extend_from_slice(&chunk.as_bytes()[from..to]);
+        }
+        if chunk_end >= range.end {
+            break;
+        }
+        offset = chunk_end;
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn not_satisfiable(result: Result<Range<u64>>) -> bool {
+        matches!(result.unwrap_err().downcast_ref::<ResolverError>(), Some(ResolverError::RangeNotSatisfiable { .. }))
+    }
+
+    #[test]
+    fn parses_ranges() {
+        assert_eq!(parse_byte_range("bytes=0-499", 1000).unwrap(), 0..500);
+        assert_eq!(parse_byte_range("bytes=500-", 1000).unwrap(), 500..1000);
+        assert_eq!(parse_byte_range("Bytes=10-19, 30-39", 1000).unwrap(), 10..20);
+        assert!(parse_byte_range("items=0-1", 1000).is_err());
+        assert!(parse_byte_range("bytes=5-1", 1000).is_err());
+        assert!(parse_byte_range("bytes=a-1", 1000).is_err());
+    }
+
+    #[test]
+    fn parses_suffix_ranges() {
+        assert_eq!(parse_byte_range("bytes=-500", 1000).unwrap(), 500..1000);
+        assert_eq!(parse_byte_range("bytes=-5000", 1000).unwrap(), 0..1000);
+        assert!(not_satisfiable(parse_byte_range("bytes=-0", 1000)));
+    }
+
+    #[test]
+    fn clamps_end_to_length() {
+        assert_eq!(parse_byte_range("bytes=0-999", 1000).unwrap(), 0..1000);
+        assert_eq!(parse_byte_range("bytes=900-5000", 1000).unwrap(), 900..1000);
+        assert!(not_satisfiable(parse_byte_range("bytes=1000-", 1000)));
+    }
+
+    #[test]
+    fn empty_content_is_not_satisfiable() {
+        assert!(not_satisfiable(parse_byte_range("bytes=0-", 0)));
+        assert!(not_satisfiable(parse_byte_range("bytes=-10", 0)));
+    }
+
+    #[test]
+    fn slices_across_chunk_boundaries() {
+        let chunks = ["abcd", "efgh", "ijkl"].map(String::from);
+        assert_eq!(slice_chunks(&chunks, &(0..12)), b"abcdefghijkl");
+        assert_eq!(slice_chunks(&chunks, &(2..6)), b"cdef");
+        assert_eq!(slice_chunks(&chunks, &(4..8)), b"efgh");
+        assert_eq!(slice_chunks(&chunks, &(3..9)), b"defghi");
+        assert_eq!(slice_chunks(&chunks, &(11..12)), b"l");
+        assert!(slice_chunks(&chunks, &(5..5)).is_empty());
+        assert!(slice_chunks(&[], &(0..0)).is_empty());
+    }
+}
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use std::sync::Weak;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use log::debug;
+use tokio_util::sync::CancellationToken;
+use crate::models::ExpiryEvent;
+use crate::resolver::evername::EvernameResolver;
+use crate::resolver::{normalize, Web3DomainResolver};
+
+/// Last known expiry date of a watched domain and whether it was reported.
+struct WatchedDomain {
+    expires_at: u64,
+    notified: Notified,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Notified {
+    Nothing,
+    Expiring,
+    Expired,
+}
+
+/// Expiry dates of the watched domains, read from certificate `getDetails` on every scan.
+pub(crate) struct ExpiryTracker {
+    domains: Vec<String>,
+    warn_before: Duration,
+    watched: HashMap<String, WatchedDomain>,
+}
+
+impl ExpiryTracker {
+    pub fn new(domains: &[&str], warn_before: Duration) -> Self {
+        Self {
+            domains: domains.iter().map(|domain| normalize::domain_name(domain)).collect(),
+            warn_before,
+            watched: HashMap::new(),
+        }
+    }
+
+    /// Reads expiry dates of the domains, returns events of the changes since the previous scan.
+    /// Domains failed to be read are skipped until the next scan.
+    pub async fn scan(&mut self, evername: &EvernameResolver) -> Vec<ExpiryEvent> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let mut events = Vec::new();
+        for domain in &self.domains {
+            let expires_at = match evername.domain_details(domain).await {
+                Ok(details) => details.expires_at,
+                Err(e) => {
+                    debug!("Failed to read expiry of {}: {:#}", domain, e);
+                    continue;
+                },
+            };
+            let watched = self.watched.entry(domain.clone()).or_insert(WatchedDomain { expires_at, notified: Notified::Nothing });
+            if expires_at > watched.expires_at {
+                events.push(ExpiryEvent::Renewed { domain: domain.clone(), previous_expires_at: watched.expires_at, expires_at });
+                *watched = WatchedDomain { expires_at, notified: Notified::Nothing };
+            }
+            if now >= expires_at {
+                if watched.notified != Notified::Expired {
+                    events.push(ExpiryEvent::Expired { domain: domain.clone(), expires_at });
+                    watched.notified = Notified::Expired;
+                }
+            } else if expires_at - now <= self.warn_before.as_secs() && watched.notified == Notified::Nothing {
+                events.push(ExpiryEvent::Expiring { domain: domain.clone(), expires_at });
+                watched.notified = Notified::Expiring;
+            }
+        }
+        events
+    }
+}
+
+/// Task scanning expiry dates of the domains every `interval` and passing the events to the callback.
+/// The task stops when the resolver is dropped or shut down.
+pub(crate) async fn run(
+    resolver: Weak<Web3DomainResolver>,
+    mut tracker: ExpiryTracker,
+    interval: Duration,
+    on_event: impl Fn(ExpiryEvent),
+    cancellation: CancellationToken,
+) {
+    loop {
+        {
+            let Some(resolver) = resolver.upgrade() else {
+                debug!("Resolver is dropped, stopping expiry watch");
+                return;
+            };
+            match resolver.evername().await {
+                Ok(evername) => tracker.scan(evername).await.into_iter().for_each(&on_event),
+                Err(e) => debug!("Expiry watch skipped the scan: {:#}", e),
+            }
+        }
+        tokio::select! {
+            _ = cancellation.cancelled() => {
+                debug!("Resolver is shut down, stopping expiry watch");
+                return;
+            },
+            _ = tokio::time::sleep(interval) => {},
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use std::sync::Arc;
+use url::Url;
+use crate::models::{AddressTag, ResolvedDomainData};
+use crate::resolver::trace::Trace;
+
+/// Hook transforming resolved targets before they are cached and returned, e.g. to rewrite
+/// the IPFS gateway host, append query parameters or map onion addresses to a tor2web proxy.
+///
+/// Unlike `ResolutionPolicy` rewriters never fail and see every result, including overrides
+/// and results rewritten by policies. Rewriters run in registration order, each one sees
+/// the result of the previous ones.
+pub trait ResultRewriter: Send + Sync {
+    /// Name of the rewriter, reported in explain traces.
+    fn name(&self) -> &str {
+        "rewriter"
+    }
+
+    /// Returns the result replacing the resolved one, `None` keeps it unchanged.
+    fn rewrite(&self, domain: &str, data: &ResolvedDomainData, tag: &AddressTag) -> Option<(ResolvedDomainData, AddressTag)>;
+}
+
+/// Rewriter turning web2 records into well-formed URLs: bare hosts (optionally with port and path)
+/// get the default scheme, `https` unless set otherwise, explicit schemes, ports and paths are kept.
+/// Values that are not a single URL or host, e.g. multi-target records, are kept as is.
+/// See `DomainResolverBuilder::normalize_web2_urls`.
+#[derive(Clone, Debug)]
+pub struct Web2UrlNormalizer {
+    default_scheme: String,
+}
+
+impl Default for Web2UrlNormalizer {
+    fn default() -> Self {
+        Self {
+            default_scheme: "https".to_string(),
+        }
+    }
+}
+
+impl Web2UrlNormalizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets scheme of bare hosts, e.g. `http` for records of plain HTTP sites.
+    pub fn default_scheme(self, scheme: &str) -> Self {
+        Self {
+            default_scheme: scheme.trim_end_matches("://").to_lowercase(),
+        }
+    }
+
+    /// Well-formed URL of the target, `None` if it is not a single URL or host.
+    pub fn normalize(&self, target: &str) -> Option<String> {
+        let target = target.trim();
+        if target.is_empty() || target.contains(char::is_whitespace) {
+            return None;
+        }
+        // bare hosts with a port, e.g. `example.com:8080`, parse as URLs with the host as scheme and no host
+        let url = match Url::parse(target) {
+            Ok(url) if url.has_host() => url,
+            _ => Url::parse(&format!("{}://{}", self.default_scheme, target)).ok().filter(Url::has_host)?,
+        };
+        Some(url.to_string())
+    }
+}
+
+impl ResultRewriter for Web2UrlNormalizer {
+    fn name(&self) -> &str {
+        "web2-url"
+    }
+
+    fn rewrite(&self, _domain: &str, data: &ResolvedDomainData, tag: &AddressTag) -> Option<(ResolvedDomainData, AddressTag)> {
+        let ResolvedDomainData::DomainString(target) = data else {
+            return None;
+        };
+        if *tag != AddressTag::Web2 {
+            return None;
+        }
+        self.normalize(target)
+            .filter(|url| url != target)
+            .map(|url| (ResolvedDomainData::DomainString(url), *tag))
+    }
+}
+
+/// Runs rewriters on the result, returns the rewritten result.
+pub(crate) fn apply(rewriters: &[Arc<dyn ResultRewriter>], domain: &str, data: ResolvedDomainData, tag: AddressTag, trace: &mut Trace) -> (ResolvedDomainData, AddressTag) {
+    let mut result = (data, tag);
+    for rewriter in rewriters {
+        if let Some((data, tag)) = rewriter.rewrite(domain, &result.0, &result.1) {
+            trace.push(|| format!("Rewriter {}: result is rewritten into {} with tag {}", rewriter.name(), data, tag));
+            result = (data, tag);
+        }
+    }
+    result
+}
@@ -0,0 +1,68 @@
+pub(crate) const DEFAULT_CONTENT_TYPE: &str = "text/html; charset=utf-8";
+
+/// Guesses content type of onchain content: HTML, SVG, JSON or CSS.
+/// Returns `None` if the content matches none of them.
+pub(crate) fn sniff_content_type(content: &str) -> Option<&'static str> {
+    let content = content.trim_start_matches('\u{feff}').trim_start();
+    let head: String = content.chars().take(512).collect::<String>().to_lowercase();
+    if head.starts_with("<svg") || (head.starts_with("<?xml") && head.contains("<svg")) {
+        return Some("image/svg+xml");
+    }
+    if head.starts_with('<') {
+        return Some(DEFAULT_CONTENT_TYPE);
+    }
+    if (content.starts_with('{') || content.starts_with('['))
+        && serde_json::from_str::<serde_json::Value>(content).is_ok() {
+        return Some("application/json");
+    }
+    if looks_like_css(content) {
+        return Some("text/css; charset=utf-8");
+    }
+    None
+}
+
+/// Content starts with a CSS at-rule or a `selector { declarations }` rule.
+fn looks_like_css(content: &str) -> bool {
+    if ["@charset", "@import", "@media", "@font-face"].iter().any(|rule| content.starts_with(rule)) {
+        return true;
+    }
+    let Some(open) = content.find('{') else {
+        return false;
+    };
+    let selector = content[..open].trim();
+    let body_end = content[open..].find('}').map(|end| open + end);
+    let Some(body_end) = body_end else {
+        return false;
+    };
+    let declarations = &content[open + 1..body_end];
+    !selector.is_empty()
+        && !selector.contains(['<', ';'])
+        && (declarations.trim().is_empty() || declarations.contains(':'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniffs_markup() {
+        assert_eq!(sniff_content_type("<!DOCTYPE html><html></html>"), Some(DEFAULT_CONTENT_TYPE));
+        assert_eq!(sniff_content_type("\u{feff}  <svg xmlns=\"http://www.w3.org/2000/svg\"/>"), Some("image/svg+xml"));
+        assert_eq!(sniff_content_type("<?xml version=\"1.0\"?><svg/>"), Some("image/svg+xml"));
+    }
+
+    #[test]
+    fn sniffs_json_and_css() {
+        assert_eq!(sniff_content_type("{\"name\": \"site\"}"), Some("application/json"));
+        assert_eq!(sniff_content_type("[1, 2]"), Some("application/json"));
+        assert_eq!(sniff_content_type("body { color: red; }"), Some("text/css; charset=utf-8"));
+        assert_eq!(sniff_content_type("@media print { body { color: black; } }"), Some("text/css; charset=utf-8"));
+    }
+
+    #[test]
+    fn unknown_content() {
+        assert_eq!(sniff_content_type("plain text"), None);
+        assert_eq!(sniff_content_type("{ not json"), None);
+        assert_eq!(sniff_content_type(""), None);
+    }
+}
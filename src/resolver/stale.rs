@@ -0,0 +1,98 @@
+use std::collections::HashSet;
+use std::sync::{Mutex, Weak};
+use std::time::Duration;
+use log::debug;
+use mini_moka::sync::Cache;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use crate::models::{AddressTag, ResolvedDomainData};
+use crate::resolver::Web3DomainResolver;
+
+/// Last known results served when the upstream resolution exceeds the budget,
+/// together with the queue of domains to refresh in background.
+pub(crate) struct StaleFallback {
+    budget: Duration,
+    // results with their sources by cache key, kept much longer than in the resolution cache
+    results: Cache<String, (ResolvedDomainData, AddressTag, String)>,
+    pending: Mutex<HashSet<String>>,
+    refresh_sender: mpsc::UnboundedSender<String>,
+    refresh_receiver: Mutex<Option<mpsc::UnboundedReceiver<String>>>,
+}
+
+impl StaleFallback {
+    pub fn new(budget: Duration, max_stale: Duration) -> Self {
+        let (refresh_sender, refresh_receiver) = mpsc::unbounded_channel();
+        Self {
+            budget,
+            results: Cache::builder().time_to_live(max_stale).build(),
+            pending: Mutex::new(HashSet::new()),
+            refresh_sender,
+            refresh_receiver: Mutex::new(Some(refresh_receiver)),
+        }
+    }
+
+    pub fn budget(&self) -> Duration {
+        self.budget
+    }
+
+    pub fn get(&self, key: &String) -> Option<(ResolvedDomainData, AddressTag, String)> {
+        self.results.get(key)
+    }
+
+    pub fn insert(&self, key: String, data: ResolvedDomainData, tag: AddressTag, source: String) {
+        self.results.insert(key, (data, tag, source));
+    }
+
+    pub fn invalidate(&self, key: &String) {
+        self.results.invalidate(key);
+    }
+
+    /// Queues the domain for background refresh unless it is already queued.
+    pub fn request_refresh(&self, domain: &str) {
+        let Ok(mut pending) = self.pending.lock() else {
+            return;
+        };
+        if pending.insert(domain.to_string()) && self.refresh_sender.send(domain.to_string()).is_err() {
+            pending.remove(domain);
+        }
+    }
+
+    fn refreshed(&self, domain: &str) {
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.remove(domain);
+        }
+    }
+
+    /// Receiver of the refresh queue, can be taken once.
+    pub fn take_receiver(&self) -> Option<mpsc::UnboundedReceiver<String>> {
+        self.refresh_receiver.lock().ok().and_then(|mut receiver| receiver.take())
+    }
+}
+
+/// Task refreshing domains whose stale results were served because of the exceeded budget.
+/// The task stops when the resolver is dropped or shut down.
+pub(crate) async fn run(resolver: Weak<Web3DomainResolver>, mut receiver: mpsc::UnboundedReceiver<String>, cancellation: CancellationToken) {
+    loop {
+        let domain = tokio::select! {
+            _ = cancellation.cancelled() => {
+                debug!("Resolver is shut down, stopping stale results refresh");
+                return;
+            },
+            domain = receiver.recv() => match domain {
+                Some(domain) => domain,
+                None => return,
+            },
+        };
+        let Some(resolver) = resolver.upgrade() else {
+            debug!("Resolver is dropped, stopping stale results refresh");
+            return;
+        };
+        match resolver.refresh(&domain).await {
+            Ok(()) => debug!("Stale result of {} is refreshed", domain),
+            Err(e) => debug!("Failed to refresh stale result of {}: {:#}", domain, e),
+        }
+        if let Some(fallback) = &resolver.stale_fallback {
+            fallback.refreshed(&domain);
+        }
+    }
+}
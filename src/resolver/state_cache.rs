@@ -0,0 +1,90 @@
+use std::time::{Duration, Instant};
+use mini_moka::sync::Cache;
+use ton_block::AccountStuff;
+
+#[derive(Clone)]
+pub(crate) struct CachedState {
+    pub account: AccountStuff,
+    pub checked_at: Instant,
+}
+
+impl CachedState {
+    pub fn last_trans_lt(&self) -> u64 {
+        self.account.storage.last_trans_lt
+    }
+}
+
+/// Cache of account states keyed by address.
+/// States younger than `fresh_for` are used as is, older ones are revalidated
+/// against the latest transaction lt of the account.
+pub(crate) struct StateCache {
+    states: Cache<String, CachedState>,
+    // certificate addresses by domain cache keys, to drop the certificate state when the domain is invalidated
+    certificates: Cache<String, String>,
+    fresh_for: Duration,
+}
+
+impl StateCache {
+    pub fn new(fresh_for: Duration, max_age: Duration) -> Self {
+        let states = Cache::builder()
+            .time_to_live(max_age)
+            .build();
+        let certificates = Cache::builder()
+            .time_to_live(max_age)
+            .build();
+        Self { states, certificates, fresh_for }
+    }
+
+    pub fn get(&self, address: &str) -> Option<CachedState> {
+        self.states.get(&address.to_string())
+    }
+
+    /// How long states are used without requests.
+    pub fn fresh_for(&self) -> Duration {
+        self.fresh_for
+    }
+
+    pub fn is_fresh(&self, state: &CachedState) -> bool {
+        state.checked_at.elapsed() < self.fresh_for
+    }
+
+    pub fn insert(&self, address: &str, account: AccountStuff) {
+        self.states.insert(address.to_string(), CachedState { account, checked_at: Instant::now() });
+    }
+
+    pub fn invalidate(&self, address: &str) {
+        self.states.invalidate(&address.to_string());
+    }
+
+    /// Remembers the certificate contract of the domain.
+    pub fn insert_certificate(&self, domain: &str, address: &str) {
+        self.certificates.insert(domain.to_string(), address.to_string());
+    }
+
+    /// Drops the state of the domain certificate, so the next resolution reads the current records.
+    pub fn invalidate_certificate(&self, domain: &str) {
+        if let Some(address) = self.certificates.get(&domain.to_string()) {
+            self.invalidate(&address);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalidated_certificate_is_not_served_fresh() {
+        let cache = StateCache::new(Duration::from_secs(30), Duration::from_secs(300));
+        cache.insert_certificate("site.ever", "0:01");
+        cache.insert("0:01", AccountStuff::default());
+        cache.insert("0:02", AccountStuff::default());
+        assert!(cache.get("0:01").is_some_and(|state| cache.is_fresh(&state)));
+        cache.invalidate_certificate("site.ever");
+        assert!(cache.get("0:01").is_none());
+        assert!(cache.get("0:02").is_some());
+        // unknown domains keep the states
+        cache.invalidate_certificate("other.ever");
+        assert!(cache.get("0:02").is_some());
+    }
+}
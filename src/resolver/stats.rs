@@ -0,0 +1,95 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+use crate::models::{ResolverStats, SourceStats};
+
+/// Number of latency samples percentiles are computed over.
+const LATENCY_WINDOW: usize = 1024;
+/// Number of distinct domains counted before counters are decayed.
+const MAX_TRACKED_DOMAINS: usize = 10_000;
+/// Number of domains reported in `ResolverStats::top_domains`.
+const TOP_DOMAINS: usize = 10;
+
+/// Rolling resolution counters of `Web3DomainResolver`.
+#[derive(Default)]
+pub(crate) struct StatsCollector {
+    state: Mutex<StatsState>,
+}
+
+#[derive(Default)]
+struct StatsState {
+    requests: u64,
+    errors: u64,
+    domains: HashMap<String, u64>,
+    sources: HashMap<String, (u64, u64)>,
+    latencies: VecDeque<Duration>,
+}
+
+impl StatsCollector {
+    pub fn record(&self, domain: &str, source: &str, success: bool, latency: Duration) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        state.requests += 1;
+        if !success {
+            state.errors += 1;
+        }
+        let source_counters = state.sources.entry(source.to_string()).or_default();
+        source_counters.0 += 1;
+        if !success {
+            source_counters.1 += 1;
+        }
+        if success {
+            *state.domains.entry(domain.to_string()).or_default() += 1;
+            if state.domains.len() > MAX_TRACKED_DOMAINS {
+                // decay counters, so rarely requested domains are forgotten
+                state.domains.retain(|_, count| {
+                    *count /= 2;
+                    *count > 0
+                });
+            }
+        }
+        if state.latencies.len() == LATENCY_WINDOW {
+            state.latencies.pop_front();
+        }
+        state.latencies.push_back(latency);
+    }
+
+    pub fn snapshot(&self) -> ResolverStats {
+        let Ok(state) = self.state.lock() else {
+            return ResolverStats::default();
+        };
+        let mut top_domains: Vec<(String, u64)> = state.domains.iter()
+            .map(|(domain, count)| (domain.clone(), *count))
+            .collect();
+        top_domains.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top_domains.truncate(TOP_DOMAINS);
+        let mut sources: Vec<SourceStats> = state.sources.iter()
+            .map(|(source, (requests, errors))| SourceStats {
+                source: source.clone(),
+                requests: *requests,
+                errors: *errors,
+            })
+            .collect();
+        sources.sort_by(|a, b| a.source.cmp(&b.source));
+        let mut latencies: Vec<Duration> = state.latencies.iter().copied().collect();
+        latencies.sort();
+        ResolverStats {
+            requests: state.requests,
+            errors: state.errors,
+            top_domains,
+            sources,
+            latency_p50: percentile(&latencies, 50),
+            latency_p99: percentile(&latencies, 99),
+            namehash_cache_clears: 0,
+        }
+    }
+}
+
+fn percentile(sorted: &[Duration], percent: usize) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let index = (sorted.len() * percent).div_ceil(100).saturating_sub(1);
+    sorted[index.min(sorted.len() - 1)]
+}
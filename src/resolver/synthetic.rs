@@ -0,0 +1,177 @@
+use std::fmt::{Display, Formatter};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use sha2::{Digest, Sha256};
+use crate::models::{AddressTag, ResolvedDomainData};
+
+/// Default TTL of synthetic records in seconds.
+pub const SYNTHETIC_RECORD_TTL: u32 = 300;
+/// Max length of a single TXT character string.
+const TXT_STRING_LENGTH: usize = 255;
+
+/// Data of a synthetic DNS record.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SyntheticRecordData {
+    A(Ipv4Addr),
+    Aaaa(Ipv6Addr),
+    /// Character strings of the record, each up to 255 bytes
+    Txt(Vec<String>),
+}
+
+/// DNS record synthesized from a resolution result.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyntheticRecord {
+    /// Fully qualified name with the trailing dot
+    pub name: String,
+    pub ttl: u32,
+    pub data: SyntheticRecordData,
+}
+
+impl SyntheticRecord {
+    /// Record type mnemonic: `A`, `AAAA` or `TXT`.
+    pub fn record_type(&self) -> &'static str {
+        match self.data {
+            SyntheticRecordData::A(_) => "A",
+            SyntheticRecordData::Aaaa(_) => "AAAA",
+            SyntheticRecordData::Txt(_) => "TXT",
+        }
+    }
+}
+
+/// Zone file line, e.g. `alice.ever. 300 IN A 192.0.2.1`.
+impl Display for SyntheticRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} IN {} ", self.name, self.ttl, self.record_type())?;
+        match &self.data {
+            SyntheticRecordData::A(address) => write!(f, "{}", address),
+            SyntheticRecordData::Aaaa(address) => write!(f, "{}", address),
+            SyntheticRecordData::Txt(strings) => {
+                let quoted: Vec<String> = strings.iter()
+                    .map(|string| format!("\"{}\"", string.replace('\\', "\\\\").replace('"', "\\\"")))
+                    .collect();
+                write!(f, "{}", quoted.join(" "))
+            },
+        }
+    }
+}
+
+/// Converts resolution results into synthetic DNS records, so the crate can back an authoritative
+/// DNS front for web3 TLDs: A and AAAA records point at the gateway serving the sites, and a TXT record
+/// describes the resolved target (`web3-tag=...`, `web3-target=...`, `web3-content-type=...`,
+/// `web3-content-sha256=...`).
+/// Non-web3 domains resolved by the DNS fallback keep their own addresses and TTL.
+///
+/// # Example
+/// ```no_run
+/// # async fn example() -> anyhow::Result<()> {
+/// use web3_resolver::{Resolver, SyntheticDns, Web3DomainResolver};
+///
+/// let resolver = Web3DomainResolver::default().await?;
+/// let synthetic = SyntheticDns::new(vec!["192.0.2.1".parse()?]).with_ttl(60);
+/// let (data, tag) = resolver.resolve("alice.ever").await?;
+/// for record in synthetic.records("alice.ever", &data, &tag) {
+///     println!("{}", record);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct SyntheticDns {
+    gateway_addresses: Vec<IpAddr>,
+    ttl: u32,
+    txt_metadata: bool,
+}
+
+impl SyntheticDns {
+    /// Creates generator of records pointing at the given gateway addresses, IPv4 ones become A
+    /// and IPv6 ones AAAA records.
+    pub fn new(gateway_addresses: Vec<IpAddr>) -> Self {
+        Self {
+            gateway_addresses,
+            ttl: SYNTHETIC_RECORD_TTL,
+            txt_metadata: true,
+        }
+    }
+
+    /// Sets TTL of the records in seconds, 300 by default.
+    pub fn with_ttl(self, ttl: u32) -> Self {
+        Self {
+            ttl,
+            ..self
+        }
+    }
+
+    /// Enables or disables the TXT record with target metadata. Enabled by default.
+    pub fn with_txt_metadata(self, txt_metadata: bool) -> Self {
+        Self {
+            txt_metadata,
+            ..self
+        }
+    }
+
+    /// Records of the domain for the resolution result.
+    pub fn records(&self, domain: &str, data: &ResolvedDomainData, tag: &AddressTag) -> Vec<SyntheticRecord> {
+        let name = fqdn(domain);
+        let (addresses, ttl) = match data {
+            ResolvedDomainData::DnsRecords(records) => (records.addresses.clone(), records.ttl.unwrap_or(self.ttl)),
+            _ => (self.gateway_addresses.clone(), self.ttl),
+        };
+        let mut records: Vec<SyntheticRecord> = addresses.into_iter()
+            .map(|address| SyntheticRecord {
+                name: name.clone(),
+                ttl,
+                data: match address {
+                    IpAddr::V4(address) => SyntheticRecordData::A(address),
+                    IpAddr::V6(address) => SyntheticRecordData::Aaaa(address),
+                },
+            })
+            .collect();
+        if self.txt_metadata {
+            records.push(SyntheticRecord {
+                name,
+                ttl,
+                data: SyntheticRecordData::Txt(txt_metadata(data, tag)),
+            });
+        }
+        records
+    }
+}
+
+fn fqdn(domain: &str) -> String {
+    format!("{}.", domain.trim_end_matches('.').to_lowercase())
+}
+
+/// Metadata strings of the target, long values are split into 255 bytes strings.
+fn txt_metadata(data: &ResolvedDomainData, tag: &AddressTag) -> Vec<String> {
+    let mut entries = vec![format!("web3-tag={}", tag)];
+    match data {
+        ResolvedDomainData::DomainString(target) => entries.push(format!("web3-target={}", target)),
+        ResolvedDomainData::DnsRecords(records) => entries.push(format!("web3-target={}", records.name)),
+        ResolvedDomainData::Redirect(redirect) => {
+            entries.push(format!("web3-target={}", redirect.target));
+            entries.push(format!("web3-redirect-status={}", redirect.status));
+        },
+        // onchain content is served by the gateway, only its digest is published
+        ResolvedDomainData::OnchainData(content) => {
+            entries.push(format!("web3-content-sha256={}", hex::encode(Sha256::digest(content.as_bytes()))));
+        },
+        ResolvedDomainData::OnchainContractData((content, content_type)) => {
+            entries.push(format!("web3-content-type={}", content_type));
+            entries.push(format!("web3-content-sha256={}", hex::encode(Sha256::digest(content.as_bytes()))));
+        },
+    }
+    entries.iter().flat_map(|entry| split_txt(entry)).collect()
+}
+
+/// Splits the string into chunks of at most 255 bytes on char boundaries.
+fn split_txt(value: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut current = String::new();
+    for c in value.chars() {
+        if current.len() + c.len_utf8() > TXT_STRING_LENGTH {
+            strings.push(std::mem::take(&mut current));
+        }
+        current.push(c);
+    }
+    strings.push(current);
+    strings
+}
@@ -0,0 +1,126 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use async_trait::async_trait;
+use crate::models::WeightedTarget;
+use crate::resolver::ipfs::move_to_gateway;
+
+/// Hook choosing among several candidate targets of a domain: targets published by a multi-target
+/// record (see `WeightedTarget`) and IPFS targets on every gateway set by `DomainResolverBuilder::with_ipfs_gateways`.
+/// Lets deployments implement geo-aware or RTT-probing selection, the default is `WeightedSelector`.
+///
+/// # Example
+/// ```no_run
+/// # async fn example(measured_rtts: std::collections::HashMap<String, std::time::Duration>) -> anyhow::Result<()> {
+/// use std::collections::HashMap;
+/// use std::time::Duration;
+/// use async_trait::async_trait;
+/// use web3_resolver::{DomainResolverBuilder, TargetSelector};
+/// use web3_resolver::models::WeightedTarget;
+///
+/// struct NearestFirst(HashMap<String, Duration>);
+///
+/// #[async_trait]
+/// impl TargetSelector for NearestFirst {
+///     async fn select(&self, _domain: &str, mut targets: Vec<WeightedTarget>) -> Vec<WeightedTarget> {
+///         targets.sort_by_key(|target| self.0.get(&target.url).copied().unwrap_or(Duration::MAX));
+///         targets
+///     }
+/// }
+///
+/// let domain_resolver = DomainResolverBuilder::default()
+///     .with_target_selector(NearestFirst(measured_rtts))
+///     .build().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[async_trait]
+pub trait TargetSelector: Send + Sync {
+    /// Name of the selector, reported in explain traces.
+    fn name(&self) -> &str {
+        "selector"
+    }
+
+    /// Orders the candidate targets of the domain, the first one is returned by `resolve` and the rest are
+    /// tried in order by `fetch_with_failover`. Targets left out are not used, an empty list keeps the record as is.
+    async fn select(&self, domain: &str, targets: Vec<WeightedTarget>) -> Vec<WeightedTarget>;
+}
+
+/// Default target selector: orders targets by priority, then weighted random within the same priority,
+/// so targets share requests by weight.
+#[derive(Debug, Default)]
+pub struct WeightedSelector {
+    tick: AtomicU64,
+}
+
+impl WeightedSelector {
+    /// Weighted random index of the target, targets with zero weight are picked last.
+    fn pick_weighted(&self, targets: &[WeightedTarget]) -> usize {
+        let total_weight: u64 = targets.iter().map(|target| target.weight as u64).sum();
+        if total_weight == 0 {
+            return 0;
+        }
+        let mut point = splitmix64(self.tick.fetch_add(1, Ordering::Relaxed)) % total_weight;
+        for (index, target) in targets.iter().enumerate() {
+            if point < target.weight as u64 {
+                return index;
+            }
+            point -= target.weight as u64;
+        }
+        0
+    }
+}
+
+#[async_trait]
+impl TargetSelector for WeightedSelector {
+    fn name(&self) -> &str {
+        "weighted"
+    }
+
+    async fn select(&self, _domain: &str, mut targets: Vec<WeightedTarget>) -> Vec<WeightedTarget> {
+        targets.sort_by_key(|target| target.priority);
+        let mut ordered = Vec::with_capacity(targets.len());
+        while !targets.is_empty() {
+            let priority = targets[0].priority;
+            let same_priority = targets.iter().take_while(|target| target.priority == priority).count();
+            let index = self.pick_weighted(&targets[..same_priority]);
+            ordered.push(targets.remove(index));
+        }
+        ordered
+    }
+}
+
+/// IPFS targets on every gateway, in gateway order after each target, other targets are kept as is.
+/// With a gateway health order only the link on the best gateway keeps the target weight, links on the others
+/// get zero weight so they are tried last in health order.
+pub(crate) fn on_gateways(targets: Vec<WeightedTarget>, gateways: &[String], order: Option<&[usize]>) -> Vec<WeightedTarget> {
+    if gateways.len() < 2 {
+        return targets;
+    }
+    let ranked = order.is_some();
+    let order: Vec<usize> = order.map_or_else(|| (0..gateways.len()).collect(), <[usize]>::to_vec);
+    targets.into_iter()
+        .flat_map(|target| {
+            let links: Vec<String> = order.iter()
+                .filter_map(|index| move_to_gateway(gateways.get(*index)?, &target.url))
+                .collect();
+            match links.is_empty() {
+                true => vec![target],
+                false => links.into_iter()
+                    .enumerate()
+                    .map(|(rank, url)| WeightedTarget {
+                        url,
+                        weight: if ranked && rank > 0 { 0 } else { target.weight },
+                        ..target.clone()
+                    })
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// Cheap deterministic mixing of a counter into a pseudo-random number.
+pub(crate) fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
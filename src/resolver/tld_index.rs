@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use mini_moka::sync::Cache;
+
+/// Time suffixes matching no TLD are remembered for. The index is rebuilt with an empty cache
+/// whenever TLDs change, so the TTL only bounds how long rarely seen suffixes are kept.
+const MISS_TTL: Duration = Duration::from_secs(5);
+const MISS_CAPACITY: u64 = 10_000;
+
+/// Resolver handling a TLD.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TldTarget {
+    #[cfg(feature = "evername")]
+    Evername,
+    #[cfg(feature = "ud")]
+    UnstoppableDomains,
+    #[cfg(feature = "ens")]
+    Ens,
+    /// Index in the custom resolvers list
+    Custom(usize),
+}
+
+/// Index of all registered TLDs keyed on their labels, e.g. `crypto` or `eth.link`,
+/// so dispatch costs a few hash lookups regardless of the number of TLDs.
+/// Suffixes not handled by any resolver are cached, so non-web3 traffic costs a single lookup.
+pub(crate) struct TldIndex {
+    // resolvers claiming the TLD in registration order
+    targets: HashMap<String, Vec<TldTarget>>,
+    // number of labels in the longest registered TLD
+    max_labels: usize,
+    // last `max_labels` labels of domains matching no TLD
+    misses: Cache<String, ()>,
+}
+
+impl Default for TldIndex {
+    fn default() -> Self {
+        Self::with_miss_ttl(MISS_TTL)
+    }
+}
+
+impl TldIndex {
+    fn with_miss_ttl(miss_ttl: Duration) -> Self {
+        Self {
+            targets: HashMap::new(),
+            max_labels: 0,
+            misses: Cache::builder().max_capacity(MISS_CAPACITY).time_to_live(miss_ttl).build(),
+        }
+    }
+
+    /// Registers TLD given with or without leading dot. A TLD claimed by several resolvers
+    /// keeps all of them in registration order. Cached misses are dropped.
+    pub fn insert(&mut self, tld: &str, target: TldTarget) {
+        let tld = tld.trim_start_matches('.');
+        if tld.is_empty() {
+            return;
+        }
+        self.misses.invalidate_all();
+        self.max_labels = self.max_labels.max(tld.split('.').count());
+        let targets = self.targets.entry(tld.to_string()).or_default();
+        if !targets.contains(&target) {
+            targets.push(target);
+        }
+    }
+
+    /// Reorders resolvers of every TLD, e.g. by configured precedence. Sort is stable.
+    pub fn sort_targets_by_key<K: Ord, F: FnMut(&TldTarget) -> K>(&mut self, mut key: F) {
+        for targets in self.targets.values_mut() {
+            targets.sort_by_key(&mut key);
+        }
+    }
+
+    /// Registered TLDs without leading dot with their resolvers.
+    pub fn entries(&self) -> impl Iterator<Item = (&String, &Vec<TldTarget>)> {
+        self.targets.iter()
+    }
+
+    /// Finds the resolvers of the domain by its longest registered TLD, returns the TLD with leading dot.
+    pub fn lookup(&self, domain: &str) -> Option<(String, Vec<TldTarget>)> {
+        // suffixes starting after each of the last `max_labels` dots, the shortest first
+        let suffixes: Vec<&str> = domain.rmatch_indices('.')
+            .take(self.max_labels)
+            .map(|(index, _)| &domain[index + 1..])
+            .collect();
+        // the longest suffix is all the lookup depends on
+        let longest = suffixes.last()?.to_string();
+        if self.misses.contains_key(&longest) {
+            return None;
+        }
+        let found = suffixes.into_iter()
+            .rev()
+            .find_map(|suffix| self.targets.get(suffix).map(|targets| (format!(".{}", suffix), targets.clone())));
+        if found.is_none() {
+            self.misses.insert(longest, ());
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(tlds: &[(&str, usize)]) -> TldIndex {
+        let mut index = TldIndex::default();
+        for (tld, custom) in tlds {
+            index.insert(tld, TldTarget::Custom(*custom));
+        }
+        index
+    }
+
+    #[test]
+    fn longest_suffix_wins() {
+        let index = index(&[(".link", 0), (".eth.link", 1), ("crypto", 2)]);
+        assert_eq!(index.lookup("vitalik.eth.link"), Some((".eth.link".to_string(), vec![TldTarget::Custom(1)])));
+        assert_eq!(index.lookup("site.link"), Some((".link".to_string(), vec![TldTarget::Custom(0)])));
+        assert_eq!(index.lookup("a.b.site.crypto"), Some((".crypto".to_string(), vec![TldTarget::Custom(2)])));
+        // the whole domain is not a TLD suffix of itself
+        assert_eq!(index.lookup("link"), None);
+        assert_eq!(index.lookup("example.com"), None);
+    }
+
+    #[test]
+    fn multi_label_tlds() {
+        let index = index(&[("eth.link", 0), ("a.b.c", 1)]);
+        assert_eq!(index.lookup("site.a.b.c"), Some((".a.b.c".to_string(), vec![TldTarget::Custom(1)])));
+        assert_eq!(index.lookup("site.b.c"), None);
+        assert_eq!(index.lookup("site.link"), None);
+        assert_eq!(index.lookup("site.eth.link").map(|(tld, _)| tld), Some(".eth.link".to_string()));
+    }
+
+    #[test]
+    fn shared_tlds_keep_registration_order() {
+        let mut index = index(&[(".x", 2), ("x", 0), (".x", 2), (".y", 1)]);
+        assert_eq!(index.lookup("site.x").unwrap().1, vec![TldTarget::Custom(2), TldTarget::Custom(0)]);
+        index.sort_targets_by_key(|target| match target {
+            TldTarget::Custom(index) => *index,
+            #[allow(unreachable_patterns)]
+            _ => usize::MAX,
+        });
+        assert_eq!(index.lookup("site.x").unwrap().1, vec![TldTarget::Custom(0), TldTarget::Custom(2)]);
+        assert_eq!(index.entries().count(), 2);
+    }
+
+    #[test]
+    fn misses_expire() {
+        let mut index = TldIndex::with_miss_ttl(Duration::from_millis(50));
+        index.insert(".x", TldTarget::Custom(0));
+        assert_eq!(index.lookup("example.com"), None);
+        assert!(index.misses.contains_key(&"com".to_string()));
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(!index.misses.contains_key(&"com".to_string()));
+    }
+
+    #[test]
+    fn new_tlds_drop_misses() {
+        // TLDs refreshed by `refresh_tlds` are matched right away, not after the miss TTL
+        let mut index = index(&[(".x", 0)]);
+        assert_eq!(index.lookup("site.new"), None);
+        assert!(index.misses.contains_key(&"new".to_string()));
+        index.insert(".new", TldTarget::Custom(1));
+        assert_eq!(index.lookup("site.new"), Some((".new".to_string(), vec![TldTarget::Custom(1)])));
+    }
+}
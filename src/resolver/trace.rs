@@ -0,0 +1,155 @@
+// dry run helpers are used only by the Evername resolver
+#![cfg_attr(not(feature = "evername"), allow(dead_code))]
+
+use crate::models::{RawRecord, ResolutionReport, StateRef};
+
+/// Decision trail of a resolution, steps are collected only in explain mode.
+pub(crate) struct Trace {
+    steps: Option<Vec<String>>,
+    records: Option<Vec<(String, String)>>,
+    state_refs: Option<Vec<StateRef>>,
+    raw_records: Option<Vec<RawRecord>>,
+    // domains followed by redirects and aliases, starting with the requested one
+    chain: Vec<String>,
+    dry_run: bool,
+}
+
+impl Trace {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            steps: enabled.then(Vec::new),
+            records: None,
+            state_refs: None,
+            raw_records: None,
+            chain: Vec::new(),
+            dry_run: false,
+        }
+    }
+
+    /// Enables collecting of the raw records the result is decoded from.
+    pub fn with_raw_records(self, enabled: bool) -> Self {
+        Self {
+            raw_records: enabled.then(Vec::new),
+            ..self
+        }
+    }
+
+    /// Enables collecting of the chain states read during the resolution.
+    pub fn with_state_refs(self, enabled: bool) -> Self {
+        Self {
+            state_refs: enabled.then(Vec::new),
+            ..self
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Self::new(false)
+    }
+
+    /// Trace of a dry run: collects steps and raw record values, caches must not be written.
+    pub fn dry_run() -> Self {
+        Self {
+            steps: Some(Vec::new()),
+            records: Some(Vec::new()),
+            state_refs: None,
+            raw_records: None,
+            chain: Vec::new(),
+            dry_run: true,
+        }
+    }
+
+    /// Trace for a side resolution: collects nothing, but keeps the dry run flag.
+    pub fn fork(&self) -> Self {
+        Self {
+            steps: None,
+            records: None,
+            state_refs: None,
+            raw_records: None,
+            chain: Vec::new(),
+            dry_run: self.dry_run,
+        }
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Adds a step, message is built only if tracing is enabled.
+    pub fn push<F: FnOnce() -> String>(&mut self, step: F) {
+        if let Some(steps) = &mut self.steps {
+            steps.push(step());
+        }
+    }
+
+    pub fn collects_records(&self) -> bool {
+        self.records.is_some()
+    }
+
+    /// Adds raw value of a record seen during the resolution.
+    pub fn record(&mut self, key: String, value: String) {
+        if let Some(records) = &mut self.records {
+            records.push((key, value));
+        }
+    }
+
+    pub fn collects_state_refs(&self) -> bool {
+        self.state_refs.is_some()
+    }
+
+    /// Adds references of the chain states read during the resolution.
+    pub fn extend_state_refs(&mut self, state_refs: Vec<StateRef>) {
+        if let Some(collected) = &mut self.state_refs {
+            collected.extend(state_refs);
+        }
+    }
+
+    pub fn take_state_refs(&mut self) -> Vec<StateRef> {
+        self.state_refs.take().unwrap_or_default()
+    }
+
+    pub fn collects_raw_records(&self) -> bool {
+        self.raw_records.is_some()
+    }
+
+    /// Adds the raw record the result is decoded from.
+    pub fn raw_record(&mut self, key: String, boc: String) {
+        if let Some(raw_records) = &mut self.raw_records {
+            raw_records.push(RawRecord { key, boc });
+        }
+    }
+
+    pub fn take_raw_records(&mut self) -> Vec<RawRecord> {
+        self.raw_records.take().unwrap_or_default()
+    }
+
+    /// Sets the chain of domains followed to reach the result.
+    pub fn set_chain(&mut self, chain: Vec<String>) {
+        self.chain = chain;
+    }
+
+    pub fn take_chain(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.chain)
+    }
+
+    pub fn into_parts(self) -> (Vec<String>, Vec<(String, String)>) {
+        (self.steps.unwrap_or_default(), self.records.unwrap_or_default())
+    }
+}
+
+impl ResolutionReport {
+    /// Moves the steps, records, state references, raw records and chain collected by the trace into the report.
+    pub(crate) fn with_trace(self, mut trace: Trace) -> Self {
+        let state_refs = trace.take_state_refs();
+        let raw_records = trace.take_raw_records();
+        let chain = trace.take_chain();
+        let (steps, records) = trace.into_parts();
+        Self {
+            trace: steps,
+            records,
+            state_refs,
+            raw_records,
+            chain,
+            ..self
+        }
+    }
+}
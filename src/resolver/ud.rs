@@ -1,77 +1,518 @@
-use crate::models::{AddressTag, ResolvedDomainData};
-use crate::resolver::ipfs::make_ipfs_link;
-use crate::resolver::Resolver;
+use crate::error::ResolverError;
+use crate::models::{ConnectionPool, Timeouts, AddressTag, CoinType, DomainSnapshot, Redirect, ResolutionCandidate, ResolvedDomainData, ResolverCapabilities, SnapshotRecord, StructuredTarget};
+use crate::resolver::avatar::{parse_avatar_uri, AvatarUri};
+use crate::resolver::http::http_client;
+use crate::resolver::ipfs::{make_gateway_links, DEFAULT_IPFS_GATEWAY};
+use crate::resolver::limit::{permit, InFlightLimit};
+use crate::resolver::{normalize, Resolver};
+use crate::resolver::trace::Trace;
 use anyhow::{anyhow, Result};
+use arc_swap::ArcSwap;
 use async_trait::async_trait;
 use log::debug;
-use reqwest::{Client, IntoUrl};
+use mini_moka::sync::Cache;
+use reqwest::{Client, IntoUrl, Response, StatusCode};
+use reqwest::header::RETRY_AFTER;
+use std::collections::BTreeMap;
 use std::sync::Arc;
+use std::time::Duration;
 use url::Url;
 
+mod builder;
+
+pub use builder::UnstoppableDomainsResolverBuilder;
+
 pub const UD_BASE_URL: &str = "https://api.unstoppabledomains.com";
+/// Unstoppable Domains sandbox API with testnet domains, see `UnstoppableDomainsResolverBuilder::sandbox`.
+pub const UD_SANDBOX_BASE_URL: &str = "https://api.ud-sandbox.com";
+/// Unstoppable Domains search page, `{domain}` is replaced with the domain, see `registration_link`.
+pub const UD_REGISTRATION_URL: &str = "https://unstoppabledomains.com/search?searchTerm={domain}";
+/// Default time-to-live of cached domain profiles.
+pub const PROFILE_CACHE_SECONDS: u64 = 30;
+
+/// Metadata of a TLD from the Unstoppable Domains supported TLDs, see `UnstoppableDomainsResolver::tld_metadata`.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct TldMetadata {
+    /// TLD with the leading dot, e.g. `.crypto`
+    pub tld: String,
+    /// Naming service of the TLD, e.g. `UNS`, `ZNS` or `DNS`
+    pub naming_service: String,
+    /// Blockchain the TLD is registered on, e.g. `ETH`, `MATIC` or `ZIL`, `None` if not reported
+    pub network: Option<String>,
+    pub registerable: bool,
+    pub renewable: bool,
+}
+
+impl TldMetadata {
+    /// Domains of the TLD are resolved by Unstoppable Domains, DNS TLDs are not.
+    pub fn is_resolvable(&self) -> bool {
+        self.naming_service != "DNS"
+    }
+
+    /// TLD of the Zilliqa Name Service, e.g. `.zil`, resolved by the resolution endpoint
+    /// rather than the profile one.
+    pub fn is_zns(&self) -> bool {
+        self.naming_service == "ZNS"
+    }
+}
 
 pub struct UnstoppableDomainsResolver {
+    base_url: Url,
     tlds_url: Url,
     profile_url: Url,
+    // records of ZNS domains, which have no public profiles
+    records_url: Url,
+    search_url: Url,
     http_client: Arc<Client>,
-    tlds: Vec<String>,
+    // swapped as a whole by `update_tlds`, so a shared resolver can refresh them
+    tld_metadata: ArcSwap<Vec<TldMetadata>>,
+    // API is the sandbox one, only TLDs it lists are resolved
+    sandbox: bool,
+    // parsed profiles, shared by resolve, resolve_all and resolve_records of the same domain
+    profile_cache: Option<Cache<String, Arc<serde_json::Value>>>,
+    ipfs_gateway: String,
+    // results of standalone `resolve` calls, `Web3DomainResolver` has its own cache
+    result_cache: Option<Cache<String, (ResolvedDomainData, AddressTag)>>,
+    in_flight: Option<InFlightLimit>,
 }
 
 impl UnstoppableDomainsResolver {
     pub async fn new<U: IntoUrl>(base_url: U) -> Result<Self> {
+        Self::new_with_timeouts(base_url, &Timeouts::default()).await
+    }
+
+    /// Same as `new`, HTTP requests are bounded by the given timeouts.
+    pub async fn new_with_timeouts<U: IntoUrl>(base_url: U, timeouts: &Timeouts) -> Result<Self> {
+        Self::new_with_connection_pool(base_url, timeouts, &ConnectionPool::default()).await
+    }
+
+    /// Same as `new_with_timeouts`, connections are reused with the given settings.
+    pub async fn new_with_connection_pool<U: IntoUrl>(base_url: U, timeouts: &Timeouts, pool: &ConnectionPool) -> Result<Self> {
+        Self::connect(base_url, timeouts, pool, false, None).await
+    }
+
+    /// Connects to the API, supported TLDs are fetched unless they are given.
+    pub(crate) async fn connect<U: IntoUrl>(base_url: U, timeouts: &Timeouts, pool: &ConnectionPool, sandbox: bool, tld_metadata: Option<Vec<TldMetadata>>) -> Result<Self> {
         let base_url = base_url.into_url()?;
         let tlds_url = base_url.join("/resolve/supported_tlds")?;
         let profile_url = base_url.join("/profile/public/")?;
-        let http_client = Arc::new(Client::new());
-        let tlds = fetch_tlds(&http_client, tlds_url.clone()).await?;
-        debug!("TLDs: {:?}", tlds);
+        let records_url = base_url.join("/resolve/domains/")?;
+        let search_url = base_url.join("/profile/search")?;
+        let http_client = Arc::new(http_client(timeouts, pool)?);
+        let tld_metadata = match tld_metadata {
+            Some(tld_metadata) => tld_metadata,
+            None => fetch_tld_metadata(&http_client, tlds_url.clone(), sandbox).await?,
+        };
+        debug!("TLDs: {:?}", tld_metadata);
         Ok(Self {
+            base_url,
             tlds_url,
             profile_url,
+            records_url,
+            search_url,
             http_client,
-            tlds,
-        })
+            tld_metadata: ArcSwap::from_pointee(tld_metadata),
+            sandbox,
+            profile_cache: None,
+            ipfs_gateway: DEFAULT_IPFS_GATEWAY.to_string(),
+            result_cache: None,
+            in_flight: None,
+        }.with_profile_cache_ttl(Some(Duration::from_secs(PROFILE_CACHE_SECONDS))))
+    }
+
+    /// Sets time-to-live of cached domain profiles, `None` disables the cache.
+    pub fn with_profile_cache_ttl(self, ttl: Option<Duration>) -> Self {
+        Self {
+            profile_cache: ttl
+                .filter(|ttl| !ttl.is_zero())
+                .map(|ttl| Cache::builder().time_to_live(ttl).build()),
+            ..self
+        }
+    }
+
+    /// Removes the domain profile from the cache.
+    pub fn invalidate_profile(&self, domain: &str) {
+        if let Some(cache) = &self.profile_cache {
+            cache.invalidate(&normalize::cache_key(domain));
+        }
+    }
+
+    /// Removes the domain from the results and profiles caches.
+    pub fn invalidate(&self, domain: &str) {
+        if let Some(cache) = &self.result_cache {
+            cache.invalidate(&normalize::cache_key(domain));
+        }
+        self.invalidate_profile(domain);
     }
     
     pub async fn default() -> Result<Self> {
         Self::new(UD_BASE_URL).await
     }
 
+    /// Creates builder of a standalone resolver for dapps resolving Unstoppable Domains only.
+    pub fn builder() -> UnstoppableDomainsResolverBuilder {
+        UnstoppableDomainsResolverBuilder::default()
+    }
+
+    /// Sets IPFS gateway for IPFS records, `{cid}` is replaced with the content hash,
+    /// otherwise the hash is appended to the gateway path. Default is `https://{cid}.ipfs.w3s.link/`.
+    pub fn with_ipfs_gateway(self, gateway: &str) -> Self {
+        Self {
+            ipfs_gateway: gateway.to_string(),
+            ..self
+        }
+    }
+
+    /// Enables caching of `resolve` results for the given time-to-live.
+    pub fn with_result_cache(self, ttl: Duration) -> Self {
+        Self {
+            result_cache: Some(Cache::builder().time_to_live(ttl).build()),
+            ..self
+        }
+    }
+
+    /// Bounds in-flight HTTP requests by the limit, which may be shared with other resolvers.
+    pub fn with_in_flight_limit(self, in_flight: InFlightLimit) -> Self {
+        Self {
+            in_flight: Some(in_flight),
+            ..self
+        }
+    }
+
+    /// TLDs resolved by Unstoppable Domains with the leading dot, DNS TLDs are left out.
     pub fn get_tlds(&self) -> Vec<String> {
-        self.tlds.clone()
+        self.tld_metadata.load().iter()
+            .filter(|metadata| metadata.is_resolvable())
+            .map(|metadata| metadata.tld.clone())
+            .collect()
     }
-    
-    pub async fn update_tlds(&mut self) -> Result<()> {
-        let tlds = fetch_tlds(&self.http_client, self.tlds_url.clone()).await?;
-        debug!("TLDs: {:?}", tlds);
-        self.tlds = tlds;
+
+    /// Base URL of the Unstoppable Domains API.
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    /// IPFS gateway of IPFS records.
+    pub fn ipfs_gateway(&self) -> &str {
+        &self.ipfs_gateway
+    }
+
+    /// Resolver uses the sandbox API with testnet domains.
+    pub fn is_sandbox(&self) -> bool {
+        self.sandbox
+    }
+
+    /// Full metadata of the supported TLDs, including DNS ones: naming service, network and registration flags.
+    pub fn tld_metadata(&self) -> Arc<Vec<TldMetadata>> {
+        self.tld_metadata.load_full()
+    }
+
+    /// Fetches the supported TLDs again, lookups in progress keep using the previous ones.
+    pub async fn update_tlds(&self) -> Result<()> {
+        let tld_metadata = {
+            let _permit = permit(&self.in_flight).await;
+            fetch_tld_metadata(&self.http_client, self.tlds_url.clone(), self.sandbox).await?
+        };
+        debug!("TLDs: {:?}", tld_metadata);
+        self.tld_metadata.store(Arc::new(tld_metadata));
         Ok(())
     }
+
+    /// Metadata of the TLD of the domain, `None` if it is not supported.
+    fn metadata_of(&self, domain: &str) -> Option<TldMetadata> {
+        let domain = domain.trim().trim_end_matches('.').to_lowercase();
+        self.tld_metadata.load().iter().find(|metadata| domain.ends_with(&metadata.tld)).cloned()
+    }
 }
 
 #[async_trait]
 impl Resolver for UnstoppableDomainsResolver {
     async fn resolve(&self, domain: &str) -> Result<(ResolvedDomainData, AddressTag)> {
-        let url = self.profile_url.join(domain)?;
-        let response = self.http_client.get(url).send().await?;
-        let body = response.bytes().await?;
-        let profile: serde_json::Value = serde_json::from_slice(&body)?;
+        let Some(cache) = &self.result_cache else {
+            return self.resolve_traced(domain, &mut Trace::disabled()).await;
+        };
+        let key = normalize::cache_key(domain);
+        if let Some(result) = cache.get(&key) {
+            return Ok(result);
+        }
+        let result = self.resolve_traced(domain, &mut Trace::disabled()).await?;
+        cache.insert(key, result.clone());
+        Ok(result)
+    }
+
+    fn name(&self) -> &str {
+        "unstoppable-domains"
+    }
+
+    fn capabilities(&self) -> ResolverCapabilities {
+        ResolverCapabilities {
+            tlds: self.get_tlds(),
+            record_kinds: vec![AddressTag::UnstoppableDomain, AddressTag::Redirect],
+            addresses: true,
+            reverse: false,
+        }
+    }
+
+    async fn resolve_address(&self, domain: &str, coin: CoinType) -> Result<Option<String>> {
+        self.address(domain, coin).await
+    }
+
+    async fn resolve_all(&self, domain: &str) -> Result<Vec<ResolutionCandidate>> {
+        self.resolve_all_traced(domain, &Trace::disabled()).await
+    }
+}
+
+impl UnstoppableDomainsResolver {
+    /// Same as `resolve_all`, the profile cache is bypassed in dry runs.
+    pub(crate) async fn resolve_all_traced(&self, domain: &str, trace: &Trace) -> Result<Vec<ResolutionCandidate>> {
+        let profile = self.fetch_profile(domain, trace.is_dry_run()).await?;
+        Ok(self.profile_candidates(&profile))
+    }
+
+    /// Returns all records of the domain profile, e.g. `crypto.ETH.address` or `ipfs.html.value`.
+    pub async fn resolve_records(&self, domain: &str) -> Result<BTreeMap<String, String>> {
+        let profile = self.fetch_profile(domain, false).await?;
+        let records = profile.get("records")
+            .and_then(|r| r.as_object())
+            .map(|records| records.iter()
+                .map(|(key, value)| (key.clone(), value.as_str().map(|v| v.to_string()).unwrap_or_else(|| value.to_string())))
+                .collect())
+            .unwrap_or_default();
+        Ok(records)
+    }
+
+    /// Returns keys of the populated records of the domain profile, without their values.
+    pub async fn record_keys(&self, domain: &str) -> Result<Vec<String>> {
+        let profile = self.fetch_profile(domain, false).await?;
+        Ok(profile.get("records")
+            .and_then(|r| r.as_object())
+            .map(|records| records.iter()
+                .filter(|(_, value)| !value.is_null() && value.as_str() != Some(""))
+                .map(|(key, _)| key.clone())
+                .collect())
+            .unwrap_or_default())
+    }
+
+    /// Registered domains matching the prefix found by the profile search endpoint, at most `limit` of them.
+    pub async fn search(&self, prefix: &str, limit: usize) -> Result<Vec<String>> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        let prefix = prefix.trim().trim_end_matches('.').to_lowercase();
+        let body = {
+            let _permit = permit(&self.in_flight).await;
+            self.http_client.get(self.search_url.clone())
+                .query(&[("name", prefix.as_str())])
+                .send().await?
+                .error_for_status()?
+                .bytes().await?
+        };
+        let found: serde_json::Value = serde_json::from_slice(&body)?;
+        Ok(found.as_array()
+            .map(|profiles| profiles.iter()
+                .filter_map(|profile| profile.get("name").and_then(|name| name.as_str()))
+                .map(|name| name.to_lowercase())
+                .take(limit)
+                .collect())
+            .unwrap_or_default())
+    }
+
+    /// Deep link to the Unstoppable Domains search page where the domain can be bought,
+    /// for "claim this name" flows. Only domains of the supported TLDs get a link.
+    pub fn registration_link(&self, domain: &str) -> Option<Url> {
+        let domain = domain.trim().trim_end_matches('.').to_lowercase();
+        let supported = self.metadata_of(&domain).is_some_and(|metadata| metadata.is_resolvable());
+        Url::parse(&UD_REGISTRATION_URL.replace("{domain}", &domain)).ok().filter(|_| supported)
+    }
+
+    /// Returns address of the domain for the coin (`crypto.<TICKER>.address` record), `None` if it is not set.
+    pub async fn address(&self, domain: &str, coin: CoinType) -> Result<Option<String>> {
+        let ticker = coin.ticker().ok_or(anyhow!("No Unstoppable Domains ticker of {}", coin))?;
+        let profile = self.fetch_profile(domain, false).await?;
+        Ok(profile.get("records")
+            .and_then(|r| r.get(format!("crypto.{}.address", ticker)))
+            .and_then(|v| v.as_str())
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty()))
+    }
+
+    /// Returns TXT records of the domain (`dns.TXT` record, a JSON array of strings or a single string),
+    /// empty if it is not set.
+    pub async fn txt_records(&self, domain: &str) -> Result<Vec<String>> {
+        let profile = self.fetch_profile(domain, false).await?;
+        let Some(value) = profile.get("records").and_then(|r| r.get("dns.TXT")) else {
+            return Ok(Vec::new());
+        };
+        let value = match value.as_str() {
+            Some(value) => serde_json::from_str(value).unwrap_or_else(|_| serde_json::Value::String(value.to_string())),
+            None => value.clone(),
+        };
+        let records: Vec<String> = match value {
+            serde_json::Value::Array(values) => values.into_iter()
+                .filter_map(|v| v.as_str().map(|v| v.to_string()))
+                .collect(),
+            serde_json::Value::String(value) => vec![value],
+            _ => Vec::new(),
+        };
+        Ok(records.into_iter().filter(|record| !record.trim().is_empty()).collect())
+    }
+
+    /// Takes snapshot of the domain: raw profile JSON fetched bypassing the profile cache, all records
+    /// with their hashes, and the resolution result of that profile. The profile cache is left as is.
+    pub async fn snapshot(&self, domain: &str) -> Result<DomainSnapshot> {
+        let mut snapshot = DomainSnapshot::new(domain, self.name());
+        let profile = self.fetch_profile(domain, true).await?;
+        snapshot.profile = Some(profile.to_string());
+        snapshot.records = profile_records(&profile);
+        snapshot.set_resolution(self.profile_result(domain, &profile));
+        Ok(snapshot)
+    }
+
+    /// Returns raw and decoded records of the domain profile, the cached profile is used if fresh.
+    pub async fn records(&self, domain: &str) -> Result<Vec<SnapshotRecord>> {
+        let profile = self.fetch_profile(domain, false).await?;
+        Ok(profile_records(&profile))
+    }
+
+    /// Returns fetchable URL of the profile picture (`social.picture.value` record), `None` if it is not set.
+    /// Pictures set to NFTs fail here, `Web3DomainResolver::resolve_avatar` looks them up with the `ens` feature.
+    pub async fn avatar(&self, domain: &str) -> Result<Option<String>> {
+        match self.avatar_uri(domain).await? {
+            Some(AvatarUri::Link(link)) => Ok(Some(link)),
+            Some(AvatarUri::Nft(nft)) => Err(anyhow!("Profile picture of {} is NFT {}", domain, nft)),
+            None => Ok(None),
+        }
+    }
+
+    pub(crate) async fn avatar_uri(&self, domain: &str) -> Result<Option<AvatarUri>> {
+        let profile = self.fetch_profile(domain, false).await?;
+        profile.get("records")
+            .and_then(|r| r.get("social.picture.value"))
+            .and_then(|v| v.as_str())
+            .filter(|v| !v.trim().is_empty())
+            .map(|value| parse_avatar_uri(value, &self.ipfs_gateway))
+            .transpose()
+    }
+
+    pub(crate) async fn resolve_traced(&self, domain: &str, trace: &mut Trace) -> Result<(ResolvedDomainData, AddressTag)> {
+        let profile = self.fetch_profile(domain, trace.is_dry_run()).await?;
+        if trace.collects_records() {
+            if let Some(records) = profile.get("records").and_then(|r| r.as_object()) {
+                for (key, value) in records {
+                    trace.record(format!("records.{}", key), value.as_str().map(|v| v.to_string()).unwrap_or_else(|| value.to_string()));
+                }
+            }
+            if let Some(web2_url) = profile.get("profile").and_then(|p| p.get("web2Url")) {
+                trace.record("profile.web2Url".to_string(), web2_url.to_string());
+            }
+        }
+        trace.push(|| format!("Unstoppable Domains: {} content targets in profile, Web2Url has precedence over IPFS and redirect",
+            self.profile_candidates(&profile).len()));
+        self.profile_result(domain, &profile)
+    }
+
+    /// Resolution result of the profile, its first content target.
+    fn profile_result(&self, domain: &str, profile: &serde_json::Value) -> Result<(ResolvedDomainData, AddressTag)> {
+        let candidate = self.profile_candidates(profile)
+            .into_iter()
+            .next()
+            .ok_or(anyhow!("Profile for domain {} does not contain IPFS hash, Web2Url or redirect URL", domain))?;
+        Ok((candidate.data, candidate.tag))
+    }
+
+    /// Fetches the profile of the domain through the profile cache, dry runs neither read nor fill it.
+    async fn fetch_profile(&self, domain: &str, dry_run: bool) -> Result<Arc<serde_json::Value>> {
+        let key = normalize::cache_key(domain);
+        let profile_cache = self.profile_cache.as_ref().filter(|_| !dry_run);
+        if let Some(profile) = profile_cache.and_then(|cache| cache.get(&key)) {
+            debug!("Profile of {} is taken from cache", domain);
+            return Ok(profile);
+        }
+        // ZNS domains have no public profiles, their records are read from the resolution endpoint,
+        // UNS domains of any network, L2-only ones included, from the profile endpoint
+        let url = match self.metadata_of(domain) {
+            Some(metadata) if metadata.is_zns() => self.records_url.join(domain)?,
+            _ => self.profile_url.join(domain)?,
+        };
+        let body = {
+            let _permit = permit(&self.in_flight).await;
+            check_status(self.http_client.get(url).send().await?, domain)?.bytes().await?
+        };
+        let profile: Arc<serde_json::Value> = Arc::new(serde_json::from_slice(&body)?);
+        if let Some(cache) = profile_cache {
+            cache.insert(key, profile.clone());
+        }
+        Ok(profile)
+    }
+
+    fn profile_candidates(&self, profile: &serde_json::Value) -> Vec<ResolutionCandidate> {
         let ipfs_url = profile.get("records")
             .and_then(|p| p.get("ipfs.html.value"))
             .and_then(|h| h.as_str())
-            .map(|cid| make_ipfs_link(cid));
+            .map(|cid| make_gateway_links(&self.ipfs_gateway, cid));
         let web2_url = profile.get("profile")
             .and_then(|p| p.get("web2Url"))
             .and_then(|u| u.as_str())
-            .map(|u| u.to_string());
-        let result = web2_url
-            .or(ipfs_url)
-            .ok_or(anyhow!("Profile for domain {} does not contain IPFS hash or Web2Url", domain))?;
-        Ok((ResolvedDomainData::DomainString(result), AddressTag::UnstoppableDomain))
+            .map(|u| StructuredTarget::expand(u.to_string(), &AddressTag::Web2));
+        let redirect = profile.get("records")
+            .and_then(|r| r.get("browser.redirect_url"))
+            .and_then(|u| u.as_str())
+            .filter(|u| !u.trim().is_empty())
+            .and_then(|u| Redirect::parse(u).ok());
+        // Web2Url has precedence over IPFS hash, redirect is the last resort
+        let mut candidates: Vec<ResolutionCandidate> = web2_url.into_iter()
+            .chain(ipfs_url)
+            .map(|url| ResolutionCandidate {
+                source: self.name().to_string(),
+                data: ResolvedDomainData::DomainString(url),
+                tag: AddressTag::UnstoppableDomain,
+            })
+            .collect();
+        candidates.extend(redirect.map(|redirect| ResolutionCandidate {
+            source: self.name().to_string(),
+            data: ResolvedDomainData::Redirect(redirect),
+            tag: AddressTag::Redirect,
+        }));
+        candidates
     }
 }
 
-async fn fetch_tlds(http_client: &Client, tlds_url: Url) -> Result<Vec<String>> {
+/// Records of the profile with their JSON as the raw value, string values are decoded.
+fn profile_records(profile: &serde_json::Value) -> Vec<SnapshotRecord> {
+    profile.get("records")
+        .and_then(|r| r.as_object())
+        .map(|records| records.iter()
+            .map(|(key, value)| SnapshotRecord::new(key, value.to_string(), value.as_str().map(|v| v.to_string())))
+            .collect())
+        .unwrap_or_default()
+}
+
+/// Maps error statuses of the API to typed errors: 404 to `NotFound`, 429 to `RateLimited` with the `Retry-After`
+/// seconds and 5xx to `UpstreamUnavailable`, so not found domains are told apart from retryable failures.
+fn check_status(response: Response, domain: &str) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let resolver = "unstoppable-domains".to_string();
+    let error = match status {
+        StatusCode::NOT_FOUND => ResolverError::NotFound { domain: domain.to_string() },
+        StatusCode::TOO_MANY_REQUESTS => {
+            let retry_after = response.headers().get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse().ok())
+                .map(Duration::from_secs);
+            ResolverError::RateLimited { resolver, retry_after }
+        },
+        status if status.is_server_error() => ResolverError::UpstreamUnavailable { resolver, status: status.as_u16() },
+        status => return Err(anyhow!("Unstoppable Domains API returned {} for {}", status, domain)),
+    };
+    Err(error.into())
+}
+
+/// Metadata of the supported TLDs. The sandbox describes TLDs it has no domains of as well,
+/// so only the ones in its `tlds` list are kept there.
+async fn fetch_tld_metadata(http_client: &Client, tlds_url: Url, sandbox: bool) -> Result<Vec<TldMetadata>> {
     let response = http_client.get(tlds_url).send().await?;
     let body = response.bytes().await?;
     let tlds_value: serde_json::Value = serde_json::from_slice(&body)?;
@@ -79,15 +520,22 @@ async fn fetch_tlds(http_client: &Client, tlds_url: Url) -> Result<Vec<String>>
         .and_then(|m| m.as_object())
         .cloned()
         .unwrap_or_default();
-    let tlds = meta_objects.iter()
-        .filter_map(|(key, value)| {
-            let naming_service = value.get("namingService").and_then(|ns| ns.as_str());
-            return if naming_service.unwrap_or_default() != "DNS" {
-                Some(format!(".{}", key))
-            } else {
-                None
-            }
+    let listed: Option<Vec<String>> = tlds_value.get("tlds")
+        .and_then(|t| t.as_array())
+        .map(|tlds| tlds.iter().filter_map(|tld| tld.as_str()).map(|tld| tld.to_lowercase()).collect());
+    let string_field = |value: &serde_json::Value, field: &str| value.get(field).and_then(|v| v.as_str()).map(|v| v.to_string());
+    let tld_metadata = meta_objects.iter()
+        .filter(|(key, _)| match (&listed, sandbox) {
+            (Some(listed), true) => listed.contains(&key.to_lowercase()),
+            _ => true,
+        })
+        .map(|(key, value)| TldMetadata {
+            tld: format!(".{}", key.to_lowercase()),
+            naming_service: string_field(value, "namingService").unwrap_or_default(),
+            network: string_field(value, "network").or_else(|| string_field(value, "blockchain")),
+            registerable: value.get("registerable").and_then(|v| v.as_bool()).unwrap_or_default(),
+            renewable: value.get("renewable").and_then(|v| v.as_bool()).unwrap_or_default(),
         })
         .collect();
-    Ok(tlds)
+    Ok(tld_metadata)
 }
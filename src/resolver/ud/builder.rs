@@ -0,0 +1,152 @@
+use std::time::Duration;
+use anyhow::Result;
+use crate::models::{ConnectionPool, Timeouts};
+use crate::resolver::limit::InFlightLimit;
+use crate::resolver::ud::{TldMetadata, UnstoppableDomainsResolver, PROFILE_CACHE_SECONDS, UD_BASE_URL, UD_SANDBOX_BASE_URL};
+
+/// Builder of a standalone `UnstoppableDomainsResolver`, also used by `DomainResolverBuilder`.
+///
+/// # Example
+/// ```no_run
+/// # async fn example() -> anyhow::Result<()> {
+/// use web3_resolver::UnstoppableDomainsResolver;
+///
+/// let ud_resolver = UnstoppableDomainsResolver::builder()
+///     .cache_ttl_seconds(60)
+///     .with_ipfs_gateway("https://ipfs.io/ipfs/")
+///     .build().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct UnstoppableDomainsResolverBuilder {
+    base_url: Option<String>,
+    sandbox: bool,
+    profile_cache_seconds: u64,
+    ipfs_gateway: Option<String>,
+    cache_ttl_seconds: Option<u64>,
+    timeouts: Timeouts,
+    connection_pool: ConnectionPool,
+    in_flight: Option<InFlightLimit>,
+    tld_metadata: Option<Vec<TldMetadata>>,
+}
+
+impl Default for UnstoppableDomainsResolverBuilder {
+    fn default() -> Self {
+        Self {
+            base_url: None,
+            sandbox: false,
+            profile_cache_seconds: PROFILE_CACHE_SECONDS,
+            ipfs_gateway: None,
+            cache_ttl_seconds: None,
+            timeouts: Timeouts::default(),
+            connection_pool: ConnectionPool::default(),
+            in_flight: None,
+            tld_metadata: None,
+        }
+    }
+}
+
+impl UnstoppableDomainsResolverBuilder {
+    /// Sets the Unstoppable Domains API base URL.
+    pub fn with_base_url(self, base_url: &str) -> Self {
+        Self {
+            base_url: Some(base_url.to_string()),
+            ..self
+        }
+    }
+
+    /// Uses the sandbox API with testnet domains (`UD_SANDBOX_BASE_URL` unless the base URL is set),
+    /// so staging environments never call the production API. Only TLDs the sandbox lists are resolved.
+    pub fn sandbox(self, sandbox: bool) -> Self {
+        Self {
+            sandbox,
+            ..self
+        }
+    }
+
+    /// Sets time-to-live of cached domain profiles, `0` disables the cache. Default is 30 seconds.
+    pub fn profile_cache_seconds(self, profile_cache_seconds: u64) -> Self {
+        Self {
+            profile_cache_seconds,
+            ..self
+        }
+    }
+
+    /// Enables caching of `resolve` results for the given time-to-live. Disabled by default.
+    pub fn cache_ttl_seconds(self, ttl: u64) -> Self {
+        Self {
+            cache_ttl_seconds: Some(ttl),
+            ..self
+        }
+    }
+
+    /// Sets IPFS gateway for IPFS records, see `UnstoppableDomainsResolver::with_ipfs_gateway`.
+    pub fn with_ipfs_gateway(self, gateway: &str) -> Self {
+        Self {
+            ipfs_gateway: Some(gateway.to_string()),
+            ..self
+        }
+    }
+
+    /// Sets connect, read and total timeouts of HTTP requests. Unbounded by default.
+    pub fn with_timeouts(self, timeouts: Timeouts) -> Self {
+        Self {
+            timeouts,
+            ..self
+        }
+    }
+
+    /// Sets keep-alive and idle connection settings of the HTTP client, see `ConnectionPool`.
+    pub fn with_connection_pool(self, connection_pool: ConnectionPool) -> Self {
+        Self {
+            connection_pool,
+            ..self
+        }
+    }
+
+    /// Bounds in-flight HTTP requests by the limit, which may be shared with other resolvers.
+    pub fn with_in_flight_limit(self, in_flight: InFlightLimit) -> Self {
+        Self {
+            in_flight: Some(in_flight),
+            ..self
+        }
+    }
+
+    /// Sets the supported TLDs, e.g. exported from another instance, so they are not fetched on build.
+    pub fn with_tld_metadata(self, tld_metadata: Vec<TldMetadata>) -> Self {
+        Self {
+            tld_metadata: Some(tld_metadata),
+            ..self
+        }
+    }
+
+    /// Base URL the resolver is created for, the sandbox or production API unless it is set.
+    pub(crate) fn base_url(&self) -> &str {
+        match (&self.base_url, self.sandbox) {
+            (Some(base_url), _) => base_url.as_str(),
+            (None, true) => UD_SANDBOX_BASE_URL,
+            (None, false) => UD_BASE_URL,
+        }
+    }
+
+    pub(crate) fn is_sandbox(&self) -> bool {
+        self.sandbox
+    }
+
+    /// Creates the resolver fetching supported TLDs unless they are set.
+    pub async fn build(&self) -> Result<UnstoppableDomainsResolver> {
+        let mut ud_resolver = UnstoppableDomainsResolver::connect(self.base_url(), &self.timeouts, &self.connection_pool, self.sandbox, self.tld_metadata.clone()).await?
+            .with_profile_cache_ttl(Some(Duration::from_secs(self.profile_cache_seconds)));
+        if let Some(gateway) = &self.ipfs_gateway {
+            ud_resolver = ud_resolver.with_ipfs_gateway(gateway);
+        }
+        if let Some(in_flight) = &self.in_flight {
+            ud_resolver = ud_resolver.with_in_flight_limit(in_flight.clone());
+        }
+        if let Some(ttl) = self.cache_ttl_seconds.filter(|ttl| *ttl > 0) {
+            ud_resolver = ud_resolver.with_result_cache(Duration::from_secs(ttl));
+        }
+        Ok(ud_resolver)
+    }
+}